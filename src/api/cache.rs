@@ -0,0 +1,91 @@
+//! On-disk cache for API responses
+//!
+//! Responses are stored as raw text under `dirs::cache_dir()/mapto3d/<namespace>/`,
+//! in a file named by a stable hash of the request. Entries older than the
+//! caller's TTL are treated as missing.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Default cache root: `<platform cache dir>/mapto3d`
+pub fn default_cache_root() -> Option<PathBuf> {
+    dirs::cache_dir().map(|d| d.join("mapto3d"))
+}
+
+/// Path of the cache file for `request` within `dir`
+pub fn cache_path(dir: &Path, request: &str) -> PathBuf {
+    dir.join(format!("{:016x}.json", fnv1a_64(request.as_bytes())))
+}
+
+/// Read a cached response if it exists and is younger than `ttl`
+pub fn load(dir: &Path, request: &str, ttl: Duration) -> Option<String> {
+    let path = cache_path(dir, request);
+    let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+    let age = SystemTime::now()
+        .duration_since(modified)
+        .unwrap_or(Duration::ZERO);
+    if age > ttl {
+        return None;
+    }
+    fs::read_to_string(&path).ok()
+}
+
+/// Store a response in the cache
+///
+/// Failures are reported as warnings only; a broken cache should never fail a run.
+pub fn store(dir: &Path, request: &str, body: &str) {
+    let path = cache_path(dir, request);
+    let result = fs::create_dir_all(dir).and_then(|_| fs::write(&path, body));
+    if let Err(e) = result {
+        eprintln!(
+            "Warning: Failed to write cache file {}: {}",
+            path.display(),
+            e
+        );
+    }
+}
+
+/// 64-bit FNV-1a hash; stable across Rust versions, unlike `DefaultHasher`
+fn fnv1a_64(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_fnv1a_known_values() {
+        assert_eq!(fnv1a_64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a_64(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = tempdir().unwrap();
+        let ttl = Duration::from_secs(60);
+
+        assert!(load(dir.path(), "query", ttl).is_none());
+        store(dir.path(), "query", r#"{"elements":[]}"#);
+        assert_eq!(
+            load(dir.path(), "query", ttl).as_deref(),
+            Some(r#"{"elements":[]}"#)
+        );
+        assert!(load(dir.path(), "other query", ttl).is_none());
+    }
+
+    #[test]
+    fn test_cache_expired() {
+        let dir = tempdir().unwrap();
+        store(dir.path(), "query", "{}");
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(load(dir.path(), "query", Duration::from_millis(1)).is_none());
+    }
+}
@@ -0,0 +1,69 @@
+//! Shared HTTP clients
+//!
+//! One blocking client is built per User-Agent and proxy and handed out as a
+//! cheap clone, so a batch run reuses connections instead of reconnecting
+//! for every Overpass or Nominatim request. Timeouts are set per request.
+//!
+//! Without an explicit proxy, reqwest follows the `HTTPS_PROXY`,
+//! `HTTP_PROXY`, `ALL_PROXY` and `NO_PROXY` environment variables.
+
+use anyhow::{Context, Result};
+use reqwest::Proxy;
+use reqwest::blocking::Client;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+type ClientKey = (String, Option<String>);
+
+static CLIENTS: OnceLock<Mutex<HashMap<ClientKey, Client>>> = OnceLock::new();
+
+/// Client identifying itself as `user_agent`, built on first use
+///
+/// `proxy` is an `http://`, `https://`, `socks5://` or `socks5h://` URL that
+/// all requests go through, overriding the proxy environment variables.
+pub fn client(user_agent: &str, proxy: Option<&str>) -> Result<Client> {
+    let mut clients = CLIENTS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let key = (user_agent.to_string(), proxy.map(str::to_string));
+    if let Some(client) = clients.get(&key) {
+        return Ok(client.clone());
+    }
+    let mut builder = Client::builder().user_agent(user_agent);
+    if let Some(url) = proxy {
+        builder =
+            builder.proxy(Proxy::all(url).with_context(|| format!("Invalid proxy URL '{}'", url))?);
+    }
+    let client = builder.build().context("Failed to create HTTP client")?;
+    clients.insert(key, client.clone());
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_client_built_once_per_user_agent() {
+        let agents = ["mapto3d-test/a", "mapto3d-test/b"];
+        for _ in 0..3 {
+            for agent in agents {
+                client(agent, None).unwrap();
+            }
+        }
+        client(agents[0], Some("socks5h://127.0.0.1:1080")).unwrap();
+        let clients = CLIENTS.get().unwrap().lock().unwrap();
+        let built = clients
+            .keys()
+            .filter(|(agent, _)| agents.contains(&agent.as_str()))
+            .count();
+        assert_eq!(built, agents.len() + 1);
+    }
+
+    #[test]
+    fn test_invalid_proxy_url() {
+        let err = client("mapto3d-test/c", Some("not a url")).unwrap_err();
+        assert!(err.to_string().contains("Invalid proxy URL"));
+    }
+}
@@ -1,5 +1,14 @@
+pub mod cache;
+pub mod http;
 pub mod nominatim;
 pub mod overpass;
 
-pub use nominatim::geocode_city;
-pub use overpass::{OverpassResponse, RoadDepth, fetch_parks, fetch_roads_with_depth, fetch_water};
+pub use nominatim::{
+    GeocodeResult, geocode_area, geocode_area_cached, geocode_city, geocode_city_cached,
+    geocode_place, reverse_geocode, reverse_geocode_cached,
+};
+pub use overpass::{
+    BBOX_TOKEN, OverpassResponse, RoadDepth, calculate_bbox, fetch_aeroways, fetch_boundary,
+    fetch_buildings, fetch_custom, fetch_parks, fetch_railways, fetch_roads_with_depth,
+    fetch_water,
+};
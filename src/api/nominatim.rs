@@ -1,10 +1,55 @@
 use anyhow::{Context, Result, bail};
-use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+use super::{cache, http};
+use crate::config::NominatimConfig;
+use crate::geometry::bbox::BBox;
 
 const NOMINATIM_URL: &str = "https://nominatim.openstreetmap.org/search";
-const USER_AGENT: &str = "mapto3d/0.1.0 (https://github.com/shantanugoel/mapto3d)";
+const NOMINATIM_REVERSE_URL: &str = "https://nominatim.openstreetmap.org/reverse";
+
+/// Shortest gap between requests allowed by the Nominatim usage policy
+const NOMINATIM_MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+/// File under the cache root holding past lookups, each a [`GeocodeResult`]
+/// keyed by `"city,country"`, `"city,state,country"` or `"area:name"`
+const GEOCODE_CACHE_FILE: &str = "geocode.json";
+
+/// File under the cache root holding reverse lookups as `{"lat,lon": name}`
+const REVERSE_GEOCODE_CACHE_FILE: &str = "reverse_geocode.json";
+
+/// Results scanned for a boundary relation when looking up an area; the
+/// best match for a name is often its center node, ranked above the
+/// boundary
+const AREA_SEARCH_LIMIT: u8 = 10;
+
+/// Nominatim zoom for reverse lookups: 10 resolves to a city or town
+const REVERSE_GEOCODE_ZOOM: u8 = 10;
+
+/// Address fields tried in order for a reverse lookup's place name
+const PLACE_ADDRESS_FIELDS: [&str; 7] = [
+    "city",
+    "town",
+    "village",
+    "municipality",
+    "hamlet",
+    "county",
+    "state",
+];
+
+/// Geocoded places keyed by normalized "city,country"
+type GeocodeCache = BTreeMap<String, GeocodeResult>;
+
+/// Reverse lookups keyed by rounded "lat,lon"; `None` when Nominatim had
+/// no place there
+type ReverseGeocodeCache = BTreeMap<String, Option<String>>;
 
 #[derive(Debug, Deserialize)]
 struct NominatimResult {
@@ -12,38 +57,285 @@ struct NominatimResult {
     lon: String,
     #[allow(dead_code)]
     display_name: String,
+    /// Extent of the place as [south, north, west, east]
+    #[serde(default)]
+    boundingbox: Option<[String; 4]>,
+    /// "node", "way" or "relation"
+    #[serde(default)]
+    osm_type: Option<String>,
+    #[serde(default)]
+    osm_id: Option<u64>,
+}
+
+impl NominatimResult {
+    fn to_geocode(&self) -> Result<GeocodeResult> {
+        let lat: f64 = self
+            .lat
+            .parse()
+            .context("Failed to parse latitude from Nominatim response")?;
+        let lon: f64 = self
+            .lon
+            .parse()
+            .context("Failed to parse longitude from Nominatim response")?;
+
+        Ok(GeocodeResult {
+            lat,
+            lon,
+            bbox: self.boundingbox.as_ref().and_then(parse_bounding_box),
+            relation: self
+                .osm_id
+                .filter(|_| self.osm_type.as_deref() == Some("relation")),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct NominatimReverseResult {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    address: BTreeMap<String, String>,
+}
+
+impl NominatimReverseResult {
+    /// The settlement the point lies in, else whatever Nominatim named
+    fn place_name(&self) -> Option<String> {
+        PLACE_ADDRESS_FIELDS
+            .iter()
+            .find_map(|field| self.address.get(*field))
+            .or(self.name.as_ref())
+            .filter(|name| !name.trim().is_empty())
+            .cloned()
+    }
+}
+
+/// A geocoded place: its center and, if Nominatim knows it, its extent
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GeocodeResult {
+    pub lat: f64,
+    pub lon: f64,
+    /// Bounding box as (south, west, north, east)
+    pub bbox: Option<BBox>,
+    /// OSM relation outlining the place, when the match is a boundary
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relation: Option<u64>,
+}
+
+impl GeocodeResult {
+    pub fn center(&self) -> (f64, f64) {
+        (self.lat, self.lon)
+    }
 }
 
 /// Geocode a city name to latitude/longitude coordinates.
 ///
 /// Uses the Nominatim API to convert "{city}, {country}" to (lat, lon).
-/// Includes a 1 second delay for rate limiting (Nominatim ToS).
+/// Requests are spaced at least one second apart (Nominatim ToS).
 ///
 /// # Arguments
 /// * `city` - City name (e.g., "San Francisco")
 /// * `country` - Country name (e.g., "USA")
 ///
 /// # Returns
-/// * `Ok((lat, lon))` - Coordinates as f64 tuple
+/// * `Ok(GeocodeResult)` - Center coordinates and the place's extent
 /// * `Err` - If city not found or API error
-pub fn geocode_city(city: &str, country: &str) -> Result<(f64, f64)> {
-    // Rate limiting - Nominatim requires max 1 request per second
-    thread::sleep(Duration::from_secs(1));
+pub fn geocode_city(city: &str, country: &str) -> Result<GeocodeResult> {
+    geocode_place(city, None, country, &NominatimConfig::default())
+}
 
-    let query = format!("{}, {}", city, country);
+/// Geocode a city, narrowed to a state or region when one is given
+///
+/// With a state the lookup uses Nominatim's structured `city`/`state`/
+/// `country` parameters, which tells apart same-named cities far better
+/// than the free-form query.
+pub fn geocode_place(
+    city: &str,
+    state: Option<&str>,
+    country: &str,
+    config: &NominatimConfig,
+) -> Result<GeocodeResult> {
+    let results = search(&search_params(city, state, country), config)?;
 
-    let client = reqwest::blocking::Client::builder()
-        .user_agent(USER_AGENT)
-        .timeout(Duration::from_secs(30))
-        .build()
-        .context("Failed to create HTTP client")?;
+    let result = results
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("City not found: {}", place_name(city, state, country)))?;
 
-    let response = client
+    result.to_geocode()
+}
+
+/// Look up a named area, such as a city or district, by its boundary
+///
+/// Takes the best-ranked match that is an OSM relation, so the result
+/// carries the relation id for fetching the outline.
+pub fn geocode_area(name: &str, config: &NominatimConfig) -> Result<GeocodeResult> {
+    let params = [
+        ("q", name.to_string()),
+        ("format", "json".to_string()),
+        ("limit", AREA_SEARCH_LIMIT.to_string()),
+    ];
+    let results = search(&params, config)?;
+    if results.is_empty() {
+        bail!("Area not found: {}", name);
+    }
+
+    let Some(result) = boundary_result(results) else {
+        bail!(
+            "{} has no boundary in OpenStreetMap; use --city for a place without one",
+            name
+        );
+    };
+    result.to_geocode()
+}
+
+/// First result that is a relation, the form OSM boundaries take
+fn boundary_result(results: Vec<NominatimResult>) -> Option<NominatimResult> {
+    results
+        .into_iter()
+        .find(|r| r.osm_type.as_deref() == Some("relation") && r.osm_id.is_some())
+}
+
+/// Run a Nominatim search, spaced from the previous request
+fn search(params: &[(&str, String)], config: &NominatimConfig) -> Result<Vec<NominatimResult>> {
+    wait_for_rate_limit();
+
+    let response = http::client(config.user_agent(), config.proxy.as_deref())?
         .get(NOMINATIM_URL)
+        .timeout(Duration::from_secs(30))
+        .query(params)
+        .send()
+        .context("Failed to send request to Nominatim API")?;
+
+    if !response.status().is_success() {
+        bail!("Nominatim API returned error status: {}", response.status());
+    }
+
+    response
+        .json()
+        .context("Failed to parse Nominatim JSON response")
+}
+
+/// Sleep until a second has passed since the previous Nominatim request
+///
+/// Nominatim allows at most one request per second; tracking the last one
+/// keeps a batch of lookups within that without delaying the first.
+fn wait_for_rate_limit() {
+    static LAST_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+    let mut last = LAST_REQUEST
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some(previous) = *last {
+        let wait = NOMINATIM_MIN_INTERVAL.saturating_sub(previous.elapsed());
+        thread::sleep(wait);
+    }
+    *last = Some(Instant::now());
+}
+
+/// Nominatim's `[south, north, west, east]` strings as a (south, west,
+/// north, east) box; `None` if any value is malformed
+fn parse_bounding_box(values: &[String; 4]) -> Option<BBox> {
+    let [south, north, west, east] = values
+        .iter()
+        .map(|v| v.parse::<f64>().ok())
+        .collect::<Option<Vec<_>>>()?[..]
+    else {
+        return None;
+    };
+    Some((south, west, north, east))
+}
+
+/// Query parameters for a search: free-form `q` unless a state is given
+fn search_params<'a>(
+    city: &'a str,
+    state: Option<&'a str>,
+    country: &'a str,
+) -> Vec<(&'static str, String)> {
+    let mut params = match state {
+        Some(state) => vec![
+            ("city", city.to_string()),
+            ("state", state.to_string()),
+            ("country", country.to_string()),
+        ],
+        None => vec![("q", format!("{}, {}", city, country))],
+    };
+    params.push(("format", "json".to_string()));
+    params.push(("limit", "1".to_string()));
+    params
+}
+
+/// "City, State, Country" for messages
+fn place_name(city: &str, state: Option<&str>, country: &str) -> String {
+    match state {
+        Some(state) => format!("{}, {}, {}", city, state, country),
+        None => format!("{}, {}", city, country),
+    }
+}
+
+/// Geocode a city, reusing the result of an earlier lookup if there is one
+///
+/// Lookups are remembered in `<cache root>/geocode.json`, so regenerating
+/// the same city skips both the rate-limit delay and the network call.
+/// `refresh` forces a fresh lookup, which then replaces the cached entry.
+pub fn geocode_city_cached(
+    city: &str,
+    state: Option<&str>,
+    country: &str,
+    refresh: bool,
+    config: &NominatimConfig,
+) -> Result<GeocodeResult> {
+    let Some(path) = geocode_cache_path() else {
+        return geocode_place(city, state, country, config);
+    };
+    let key = geocode_key(city, state, country);
+    let mut entries: GeocodeCache = load_geocode_cache(&path);
+    if !refresh && let Some(&place) = entries.get(&key) {
+        return Ok(place);
+    }
+
+    let place = geocode_place(city, state, country, config)?;
+    entries.insert(key, place);
+    store_geocode_cache(&path, &entries);
+    Ok(place)
+}
+
+/// Look up an area, reusing an earlier lookup of the same name
+///
+/// Shares `<cache root>/geocode.json` with city lookups, under an `area:`
+/// key.
+pub fn geocode_area_cached(
+    name: &str,
+    refresh: bool,
+    config: &NominatimConfig,
+) -> Result<GeocodeResult> {
+    let Some(path) = geocode_cache_path() else {
+        return geocode_area(name, config);
+    };
+    let key = format!("area:{}", name.trim().to_lowercase());
+    let mut entries: GeocodeCache = load_geocode_cache(&path);
+    if !refresh && let Some(&place) = entries.get(&key) {
+        return Ok(place);
+    }
+
+    let place = geocode_area(name, config)?;
+    entries.insert(key, place);
+    store_geocode_cache(&path, &entries);
+    Ok(place)
+}
+
+/// Name of the city or town around `center`, if Nominatim knows one
+///
+/// Shares the one-request-per-second limit with forward lookups.
+pub fn reverse_geocode(center: (f64, f64), config: &NominatimConfig) -> Result<Option<String>> {
+    wait_for_rate_limit();
+
+    let response = http::client(config.user_agent(), config.proxy.as_deref())?
+        .get(NOMINATIM_REVERSE_URL)
+        .timeout(Duration::from_secs(30))
         .query(&[
-            ("q", &query),
-            ("format", &"json".to_string()),
-            ("limit", &"1".to_string()),
+            ("lat", center.0.to_string()),
+            ("lon", center.1.to_string()),
+            ("zoom", REVERSE_GEOCODE_ZOOM.to_string()),
+            ("format", "jsonv2".to_string()),
         ])
         .send()
         .context("Failed to send request to Nominatim API")?;
@@ -52,25 +344,83 @@ pub fn geocode_city(city: &str, country: &str) -> Result<(f64, f64)> {
         bail!("Nominatim API returned error status: {}", response.status());
     }
 
-    let results: Vec<NominatimResult> = response
+    // Points in the sea come back as `{"error": "Unable to geocode"}`
+    let body: serde_json::Value = response
         .json()
         .context("Failed to parse Nominatim JSON response")?;
+    if body.get("error").is_some() {
+        return Ok(None);
+    }
+    let result: NominatimReverseResult =
+        serde_json::from_value(body).context("Failed to parse Nominatim reverse response")?;
+    Ok(result.place_name())
+}
 
-    let result = results
-        .into_iter()
-        .next()
-        .ok_or_else(|| anyhow::anyhow!("City not found: {}, {}", city, country))?;
+/// Reverse geocode `center`, reusing an earlier lookup of about the same
+/// point from `<cache root>/reverse_geocode.json`
+pub fn reverse_geocode_cached(
+    center: (f64, f64),
+    refresh: bool,
+    config: &NominatimConfig,
+) -> Result<Option<String>> {
+    let Some(path) = cache::default_cache_root().map(|root| root.join(REVERSE_GEOCODE_CACHE_FILE))
+    else {
+        return reverse_geocode(center, config);
+    };
+    let key = reverse_geocode_key(center);
+    let mut entries: ReverseGeocodeCache = load_geocode_cache(&path);
+    if !refresh && let Some(name) = entries.get(&key) {
+        return Ok(name.clone());
+    }
+
+    let name = reverse_geocode(center, config)?;
+    entries.insert(key, name.clone());
+    store_geocode_cache(&path, &entries);
+    Ok(name)
+}
 
-    let lat: f64 = result
-        .lat
-        .parse()
-        .context("Failed to parse latitude from Nominatim response")?;
-    let lon: f64 = result
-        .lon
-        .parse()
-        .context("Failed to parse longitude from Nominatim response")?;
+/// Cache key for a reverse lookup, rounded to about 10m
+fn reverse_geocode_key((lat, lon): (f64, f64)) -> String {
+    format!("{:.4},{:.4}", lat, lon)
+}
 
-    Ok((lat, lon))
+fn geocode_cache_path() -> Option<PathBuf> {
+    cache::default_cache_root().map(|root| root.join(GEOCODE_CACHE_FILE))
+}
+
+/// Cache key for a lookup; case and surrounding spaces don't matter
+fn geocode_key(city: &str, state: Option<&str>, country: &str) -> String {
+    match state {
+        Some(state) => format!("{},{},{}", city.trim(), state.trim(), country.trim()),
+        None => format!("{},{}", city.trim(), country.trim()),
+    }
+    .to_lowercase()
+}
+
+/// Read a cache, treating a missing or corrupt file as empty
+fn load_geocode_cache<T: DeserializeOwned + Default>(path: &Path) -> T {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Write a cache; failures are only warnings, like the response cache
+fn store_geocode_cache<T: Serialize>(path: &Path, entries: &T) {
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| {
+            let json = serde_json::to_string_pretty(entries).map_err(std::io::Error::other)?;
+            fs::write(path, json)
+        });
+    if let Err(e) = result {
+        eprintln!(
+            "Warning: Failed to write geocode cache {}: {}",
+            path.display(),
+            e
+        );
+    }
 }
 
 #[cfg(test)]
@@ -86,5 +436,99 @@ mod tests {
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].lat, "37.7790262");
         assert_eq!(results[0].lon, "-122.4199061");
+        assert!(results[0].boundingbox.is_none());
+    }
+
+    #[test]
+    fn test_parse_bounding_box() {
+        let json = r#"[{"lat":"48.8566","lon":"2.3522","display_name":"Paris","boundingbox":["48.8155755","48.9021560","2.2241220","2.4697602"]}]"#;
+        let results: Vec<NominatimResult> = serde_json::from_str(json).unwrap();
+        let bbox = results[0].boundingbox.as_ref().and_then(parse_bounding_box);
+        assert_eq!(bbox, Some((48.8155755, 2.224122, 48.902156, 2.4697602)));
+
+        let bad = [
+            "1".to_string(),
+            "x".to_string(),
+            "2".to_string(),
+            "3".to_string(),
+        ];
+        assert!(parse_bounding_box(&bad).is_none());
+    }
+
+    #[test]
+    fn test_boundary_result() {
+        // The town's center node ranks above its boundary
+        let json = r#"[
+            {"lat":"40.7896","lon":"-73.9598","display_name":"Manhattan","osm_type":"node","osm_id":61785451},
+            {"lat":"40.7834","lon":"-73.9663","display_name":"Manhattan","osm_type":"relation","osm_id":8398124,
+             "boundingbox":["40.6803","40.8820","-74.0479","-73.9067"]}
+        ]"#;
+        let results: Vec<NominatimResult> = serde_json::from_str(json).unwrap();
+        assert_eq!(results[0].to_geocode().unwrap().relation, None);
+
+        let place = boundary_result(results).unwrap().to_geocode().unwrap();
+        assert_eq!(place.relation, Some(8398124));
+        assert_eq!(place.bbox, Some((40.6803, -74.0479, 40.882, -73.9067)));
+
+        let json = r#"[{"lat":"1","lon":"2","display_name":"Spot","osm_type":"way","osm_id":5}]"#;
+        let results: Vec<NominatimResult> = serde_json::from_str(json).unwrap();
+        assert!(boundary_result(results).is_none());
+    }
+
+    #[test]
+    fn test_search_params() {
+        let free_form = search_params("Paris", None, "France");
+        assert_eq!(free_form[0], ("q", "Paris, France".to_string()));
+        assert!(!free_form.iter().any(|(k, _)| *k == "city"));
+
+        let structured = search_params("Springfield", Some("Illinois"), "USA");
+        assert!(structured.contains(&("city", "Springfield".to_string())));
+        assert!(structured.contains(&("state", "Illinois".to_string())));
+        assert!(structured.contains(&("country", "USA".to_string())));
+        assert!(!structured.iter().any(|(k, _)| *k == "q"));
+
+        assert_ne!(
+            geocode_key("Springfield", Some("Illinois"), "USA"),
+            geocode_key("Springfield", Some("Missouri"), "USA")
+        );
+    }
+
+    #[test]
+    fn test_geocode_cache_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join(GEOCODE_CACHE_FILE);
+        assert!(load_geocode_cache::<GeocodeCache>(&path).is_empty());
+
+        let mut entries = GeocodeCache::new();
+        let paris = GeocodeResult {
+            lat: 48.8566,
+            lon: 2.3522,
+            bbox: Some((48.81, 2.22, 48.90, 2.47)),
+            relation: None,
+        };
+        entries.insert(geocode_key(" Paris", None, "France "), paris);
+        store_geocode_cache(&path, &entries);
+
+        let loaded: GeocodeCache = load_geocode_cache(&path);
+        assert_eq!(loaded.get("paris,france"), Some(&paris));
+
+        fs::write(&path, "not json").unwrap();
+        assert!(load_geocode_cache::<GeocodeCache>(&path).is_empty());
+    }
+
+    #[test]
+    fn test_reverse_geocode_place_name() {
+        let json = r#"{"name":"Rue de Rivoli","address":{"road":"Rue de Rivoli","city":"Paris","country":"France"}}"#;
+        let result: NominatimReverseResult = serde_json::from_str(json).unwrap();
+        assert_eq!(result.place_name().as_deref(), Some("Paris"));
+
+        let json = r#"{"name":"Mont Blanc","address":{"country":"France"}}"#;
+        let result: NominatimReverseResult = serde_json::from_str(json).unwrap();
+        assert_eq!(result.place_name().as_deref(), Some("Mont Blanc"));
+
+        let result: NominatimReverseResult = serde_json::from_str(r#"{"name":""}"#).unwrap();
+        assert_eq!(result.place_name(), None);
+
+        assert_eq!(reverse_geocode_key((48.85661, 2.35222)), "48.8566,2.3522");
     }
 }
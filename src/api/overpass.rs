@@ -1,43 +1,67 @@
 use anyhow::{Context, Result, bail};
-use serde::Deserialize;
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
 use std::time::Duration;
 
+use super::{cache, http};
 use crate::config::OverpassConfig;
+use crate::geometry::bbox::{BBox, crosses_antimeridian, split_antimeridian, wrap_lon};
+use crate::geometry::lon_scale;
 
-const USER_AGENT: &str = "mapto3d/0.1.0 (https://github.com/shantanugoel/mapto3d)";
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OverpassResponse {
     pub elements: Vec<Element>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Element {
     #[serde(rename = "type")]
     pub type_: String,
     pub id: u64,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub nodes: Option<Vec<u64>>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub members: Option<Vec<Member>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub tags: Option<HashMap<String, String>>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub lat: Option<f64>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub lon: Option<f64>,
 }
 
-fn calculate_bbox(center: (f64, f64), radius_m: u32) -> (f64, f64, f64, f64) {
+/// Relation member reference (only present on relation elements)
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Member {
+    #[serde(rename = "type")]
+    pub type_: String,
+    #[serde(rename = "ref")]
+    pub ref_: u64,
+    #[serde(default)]
+    pub role: String,
+}
+
+/// Bounding box (south, west, north, east) in degrees around a center point
+pub fn calculate_bbox(center: (f64, f64), radius_m: u32) -> BBox {
     let (lat, lon) = center;
     let radius_km = radius_m as f64 / 1000.0;
 
     let lat_delta = radius_km / 111.0;
-    let lon_delta = radius_km / (111.0 * lat.to_radians().cos());
+    let lon_delta = radius_km / (111.0 * lon_scale(lat));
 
-    let south = lat - lat_delta;
-    let north = lat + lat_delta;
-    let west = lon - lon_delta;
-    let east = lon + lon_delta;
+    let south = (lat - lat_delta).max(-90.0);
+    let north = (lat + lat_delta).min(90.0);
+    if lon_delta >= 180.0 {
+        return (south, -180.0, north, 180.0);
+    }
+    // Near the antimeridian the edges wrap, leaving west > east
+    let west = wrap_lon(lon - lon_delta);
+    let east = wrap_lon(lon + lon_delta);
 
     (south, west, north, east)
 }
@@ -90,6 +114,10 @@ impl RoadDepth {
     }
 }
 
+/// Footways, paths, pedestrian streets and steps, fetched on top of the
+/// road depth when paths are asked for
+pub const PATH_FILTER: &str = r#"["highway"~"^(footway|path|pedestrian|steps)$"]"#;
+
 /// Fetch road data from Overpass API
 ///
 /// # Arguments
@@ -102,38 +130,30 @@ impl RoadDepth {
 #[allow(dead_code)]
 pub fn fetch_roads(center: (f64, f64), radius_m: u32) -> Result<OverpassResponse> {
     fetch_roads_with_depth(
-        center,
-        radius_m,
+        calculate_bbox(center, radius_m),
         RoadDepth::default(),
+        false,
         &OverpassConfig::default(),
     )
 }
 
-/// Fetch road data with configurable depth
+/// Fetch road data with configurable depth, plus footways and other paths
+/// if `paths` is set
 pub fn fetch_roads_with_depth(
-    center: (f64, f64),
-    radius_m: u32,
+    bbox: BBox,
     depth: RoadDepth,
+    paths: bool,
     config: &OverpassConfig,
 ) -> Result<OverpassResponse> {
-    let (south, west, north, east) = calculate_bbox(center, radius_m);
-
     // Overpass QL query for highways with depth filter
     // Use 180s timeout to match OSMnx's default - 60s is often too short for larger areas
-    let query = format!(
-        r#"[out:json][timeout:180];
-(
-  way{filter}({south},{west},{north},{east});
-);
-out body;
->;
-out skel qt;"#,
-        filter = depth.highway_filter(),
-        south = south,
-        west = west,
-        north = north,
-        east = east
-    );
+    let mut statements = vec![format!("way{}", depth.highway_filter())];
+    // Every highway is in the query already at depth all
+    if paths && depth != RoadDepth::All {
+        statements.push(format!("way{}", PATH_FILTER));
+    }
+    let statements: Vec<&str> = statements.iter().map(String::as_str).collect();
+    let query = union_query(&statements, bbox);
 
     execute_overpass_query(&query, config)
 }
@@ -147,30 +167,17 @@ out skel qt;"#,
 /// - water=* (generic water tag)
 /// - landuse=reservoir/basin (man-made water storage)
 /// - natural=wetland (swamps, marshes)
-pub fn fetch_water(
-    center: (f64, f64),
-    radius_m: u32,
-    config: &OverpassConfig,
-) -> Result<OverpassResponse> {
-    let (south, west, north, east) = calculate_bbox(center, radius_m);
-
-    let query = format!(
-        r#"[out:json][timeout:180];
-(
-  way["natural"="water"]({south},{west},{north},{east});
-  way["natural"="coastline"]({south},{west},{north},{east});
-  way["waterway"="riverbank"]({south},{west},{north},{east});
-  way["waterway"="river"]({south},{west},{north},{east});
-  way["water"]({south},{west},{north},{east});
-  way["landuse"="reservoir"]({south},{west},{north},{east});
-);
-out body;
->;
-out skel qt;"#,
-        south = south,
-        west = west,
-        north = north,
-        east = east
+pub fn fetch_water(bbox: BBox, config: &OverpassConfig) -> Result<OverpassResponse> {
+    let query = union_query(
+        &[
+            r#"way["natural"="water"]"#,
+            r#"way["natural"="coastline"]"#,
+            r#"way["waterway"="riverbank"]"#,
+            r#"way["waterway"="river"]"#,
+            r#"way["water"]"#,
+            r#"way["landuse"="reservoir"]"#,
+        ],
+        bbox,
     );
 
     execute_overpass_query(&query, config)
@@ -182,43 +189,167 @@ out skel qt;"#,
 /// - leisure=park/garden/nature_reserve/recreation_ground
 /// - landuse=grass/meadow/forest
 /// - natural=wood/grassland (natural vegetation)
-pub fn fetch_parks(
-    center: (f64, f64),
-    radius_m: u32,
-    config: &OverpassConfig,
-) -> Result<OverpassResponse> {
-    let (south, west, north, east) = calculate_bbox(center, radius_m);
+pub fn fetch_parks(bbox: BBox, config: &OverpassConfig) -> Result<OverpassResponse> {
+    let query = union_query(
+        &[
+            r#"way["leisure"="park"]"#,
+            r#"way["leisure"="garden"]"#,
+            r#"way["leisure"="nature_reserve"]"#,
+            r#"way["landuse"="grass"]"#,
+            r#"way["landuse"="meadow"]"#,
+            r#"way["landuse"="forest"]"#,
+            r#"way["natural"="wood"]"#,
+            r#"relation["type"="multipolygon"]["leisure"~"^(park|garden|nature_reserve)$"]"#,
+            r#"relation["type"="multipolygon"]["landuse"~"^(grass|meadow|forest)$"]"#,
+            r#"relation["type"="multipolygon"]["natural"="wood"]"#,
+        ],
+        bbox,
+    );
+
+    execute_overpass_query(&query, config)
+}
+
+/// Fetch rail lines from Overpass API
+///
+/// Fetches railway=rail/light_rail/subway/tram. Underground segments are
+/// included here and filtered during parsing.
+pub fn fetch_railways(bbox: BBox, config: &OverpassConfig) -> Result<OverpassResponse> {
+    let query = union_query(
+        &[r#"way["railway"~"^(rail|light_rail|subway|tram)$"]"#],
+        bbox,
+    );
+
+    execute_overpass_query(&query, config)
+}
+
+/// Fetch airfield features from Overpass API
+///
+/// Fetches aeroway=runway/taxiway lines and aeroway=apron areas, including
+/// apron multipolygons.
+pub fn fetch_aeroways(bbox: BBox, config: &OverpassConfig) -> Result<OverpassResponse> {
+    let query = union_query(
+        &[
+            r#"way["aeroway"~"^(runway|taxiway|apron)$"]"#,
+            r#"relation["type"="multipolygon"]["aeroway"="apron"]"#,
+        ],
+        bbox,
+    );
 
+    execute_overpass_query(&query, config)
+}
+
+/// Fetch building footprints from Overpass API
+///
+/// Fetches every way tagged `building=*` (houses, offices, churches, ...).
+/// Dense city centers can return tens of thousands of footprints, so prefer
+/// smaller radii when buildings are enabled.
+pub fn fetch_buildings(bbox: BBox, config: &OverpassConfig) -> Result<OverpassResponse> {
+    let query = union_query(&[r#"way["building"]"#], bbox);
+
+    execute_overpass_query(&query, config)
+}
+
+/// Fetch the outline of a boundary relation from Overpass API
+///
+/// Returns the relation with its member ways and their nodes, ready for
+/// `parse_boundary`.
+pub fn fetch_boundary(relation_id: u64, config: &OverpassConfig) -> Result<OverpassResponse> {
     let query = format!(
-        r#"[out:json][timeout:180];
-(
-  way["leisure"="park"]({south},{west},{north},{east});
-  way["leisure"="garden"]({south},{west},{north},{east});
-  way["leisure"="nature_reserve"]({south},{west},{north},{east});
-  way["landuse"="grass"]({south},{west},{north},{east});
-  way["landuse"="meadow"]({south},{west},{north},{east});
-  way["landuse"="forest"]({south},{west},{north},{east});
-  way["natural"="wood"]({south},{west},{north},{east});
-);
-out body;
->;
-out skel qt;"#,
-        south = south,
-        west = west,
-        north = north,
-        east = east
+        "[out:json][timeout:180];\nrelation({});\nout body;\n>;\nout skel qt;",
+        relation_id
     );
 
     execute_overpass_query(&query, config)
 }
 
-/// Execute an Overpass API query with retry logic and URL fallback
+/// Token in a custom query replaced by the map's `south,west,north,east`
+pub const BBOX_TOKEN: &str = "{{bbox}}";
+
+/// Run a user-written Overpass QL query with [`BBOX_TOKEN`] filled in
+///
+/// The query must ask for JSON output (`[out:json]`) and recurse down to the
+/// nodes of its ways (`>;`), just like the built-in queries.
+pub fn fetch_custom(
+    template: &str,
+    bbox: BBox,
+    config: &OverpassConfig,
+) -> Result<OverpassResponse> {
+    let query = substitute_bbox(template, bbox)?;
+    execute_overpass_query(&query, config)
+}
+
+/// Replace every [`BBOX_TOKEN`] in `template` with `bbox`
+pub fn substitute_bbox(template: &str, bbox: BBox) -> Result<String> {
+    if !template.contains(BBOX_TOKEN) {
+        bail!(
+            "Custom Overpass query must contain {} to limit it to the map",
+            BBOX_TOKEN
+        );
+    }
+    // One token can only hold one box, and Overpass needs west < east
+    if crosses_antimeridian(bbox) {
+        bail!("Custom Overpass queries are not supported for maps crossing the antimeridian");
+    }
+    let (south, west, north, east) = bbox;
+    Ok(template.replace(
+        BBOX_TOKEN,
+        &format!("{},{},{},{}", south, west, north, east),
+    ))
+}
+
+/// Overpass QL union of `selectors` within `bbox`, with the ways' nodes
+///
+/// A box crossing the antimeridian is split in two, since Overpass only
+/// accepts boxes with west < east.
+fn union_query(selectors: &[&str], bbox: BBox) -> String {
+    let mut query = String::from("[out:json][timeout:180];\n(\n");
+    for selector in selectors {
+        for (south, west, north, east) in split_antimeridian(bbox) {
+            query.push_str(&format!(
+                "  {}({},{},{},{});\n",
+                selector, south, west, north, east
+            ));
+        }
+    }
+    query.push_str(");\nout body;\n>;\nout skel qt;");
+    query
+}
+
+/// Execute an Overpass API query, serving it from the on-disk cache when possible
 fn execute_overpass_query(query: &str, config: &OverpassConfig) -> Result<OverpassResponse> {
-    let client = reqwest::blocking::Client::builder()
-        .user_agent(USER_AGENT)
-        .timeout(Duration::from_secs(config.timeout_secs))
-        .build()
-        .context("Failed to create HTTP client")?;
+    let cache_dir = config
+        .cache
+        .then(cache::default_cache_root)
+        .flatten()
+        .map(|root| root.join("overpass"));
+    let ttl = Duration::from_secs(config.cache_ttl_secs);
+
+    if let Some(dir) = &cache_dir
+        && let Some(body) = cache::load(dir, query, ttl)
+    {
+        match serde_json::from_str(&body) {
+            Ok(response) => return Ok(response),
+            Err(e) => eprintln!("Warning: Ignoring corrupt Overpass cache entry: {}", e),
+        }
+    }
+
+    let body = fetch_overpass_body(query, config)?;
+    let response: OverpassResponse =
+        serde_json::from_str(&body).context("Failed to parse Overpass JSON response")?;
+
+    if let Some(dir) = &cache_dir {
+        cache::store(dir, query, &body);
+    }
+
+    Ok(response)
+}
+
+/// Send an Overpass query with retry logic and URL fallback, returning the raw JSON body
+///
+/// With `race_mirrors` the first two mirrors get the query at once and the
+/// first to answer wins; the rest are only tried if both fail.
+fn fetch_overpass_body(query: &str, config: &OverpassConfig) -> Result<String> {
+    let client = http::client(config.user_agent(), config.proxy.as_deref())?;
 
     let urls = if config.urls.is_empty() {
         // Fallback to defaults if somehow empty
@@ -232,68 +363,31 @@ fn execute_overpass_query(query: &str, config: &OverpassConfig) -> Result<Overpa
     };
 
     let mut all_errors: Vec<String> = Vec::new();
+    let mut remaining = &urls[..];
+    if config.race_mirrors && urls.len() >= 2 {
+        match race_mirrors(&client, &urls[..2], query, config) {
+            Ok(body) => return Ok(body),
+            Err(errors) => all_errors.extend(errors),
+        }
+        remaining = &urls[2..];
+        if !remaining.is_empty() {
+            eprintln!("Both raced Overpass mirrors failed, trying fallback mirror...");
+        }
+    }
 
     // Try each URL in sequence
-    for (url_idx, url) in urls.iter().enumerate() {
-        let mut last_error = None;
-
-        // Retry logic for each URL
-        for attempt in 0..config.max_retries {
-            if attempt > 0 {
-                // Wait before retry - Overpass recommends waiting when overloaded
-                let wait_secs = 30 * attempt as u64;
-                eprintln!(
-                    "Overpass API timeout on {}, retrying in {} seconds (attempt {}/{})",
-                    url,
-                    wait_secs,
-                    attempt + 1,
-                    config.max_retries
-                );
-                std::thread::sleep(Duration::from_secs(wait_secs));
-            }
-
-            // IMPORTANT: Overpass API expects form-encoded POST data, not raw body
-            // The query must be sent as: data=<query>
-            let response = match client.post(url).form(&[("data", query)]).send() {
-                Ok(resp) => resp,
-                Err(e) => {
-                    last_error = Some(format!("Request failed: {}", e));
-                    continue;
-                }
-            };
-
-            match response.status().as_u16() {
-                200 => {
-                    let result: OverpassResponse = response
-                        .json()
-                        .context("Failed to parse Overpass JSON response")?;
-                    return Ok(result);
-                }
-                429 | 504 => {
-                    // 429 = Too Many Requests, 504 = Gateway Timeout
-                    // These are retriable errors
-                    last_error = Some(format!(
-                        "Overpass API returned status {} (attempt {})",
-                        response.status(),
-                        attempt + 1
-                    ));
-                    continue;
-                }
-                status => {
-                    // Non-retriable error for this URL, try next URL
-                    last_error = Some(format!("Overpass API returned error status: {}", status));
-                    break;
+    let never = AtomicBool::new(false);
+    for (url_idx, url) in remaining.iter().enumerate() {
+        match query_mirror(&client, url, query, config, &never) {
+            Ok(body) => return Ok(body),
+            Err(err) => {
+                // Record error for this URL and try next
+                all_errors.push(format!("{}: {}", url, err));
+                if url_idx + 1 < remaining.len() {
+                    eprintln!("Overpass API {} failed, trying fallback mirror...", url);
                 }
             }
         }
-
-        // Record error for this URL and try next
-        if let Some(err) = last_error {
-            all_errors.push(format!("{}: {}", url, err));
-            if url_idx + 1 < urls.len() {
-                eprintln!("Overpass API {} failed, trying fallback mirror...", url);
-            }
-        }
     }
 
     bail!(
@@ -302,10 +396,245 @@ fn execute_overpass_query(query: &str, config: &OverpassConfig) -> Result<Overpa
     )
 }
 
+/// Query `urls` at the same time and return the first successful body, or
+/// every mirror's error if none succeeds
+///
+/// Once one mirror answers the others stop retrying. A request already in
+/// flight cannot be aborted with the blocking client, so it is abandoned and
+/// its answer dropped.
+fn race_mirrors(
+    client: &Client,
+    urls: &[String],
+    query: &str,
+    config: &OverpassConfig,
+) -> std::result::Result<String, Vec<String>> {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = mpsc::channel();
+    for url in urls {
+        let client = client.clone();
+        let url = url.clone();
+        let query = query.to_string();
+        let config = config.clone();
+        let cancelled = Arc::clone(&cancelled);
+        let sender = sender.clone();
+        std::thread::spawn(move || {
+            let result = query_mirror(&client, &url, &query, &config, &cancelled);
+            // The receiver is gone once another mirror has won
+            let _ = sender.send((url, result));
+        });
+    }
+    drop(sender);
+
+    let mut errors = Vec::new();
+    for (url, result) in receiver {
+        match result {
+            Ok(body) => {
+                cancelled.store(true, Ordering::Relaxed);
+                return Ok(body);
+            }
+            Err(err) => errors.push(format!("{}: {}", url, err)),
+        }
+    }
+    Err(errors)
+}
+
+/// Wait before retry `attempt` (1 for the first retry)
+///
+/// A server's `retry_after` is taken as given; otherwise the wait is
+/// `retry_base_secs` doubled per earlier retry and scaled by up to a quarter
+/// either way by `jitter` in -1..=1. Both are capped at `retry_max_secs`.
+fn retry_delay(
+    attempt: u32,
+    config: &OverpassConfig,
+    retry_after: Option<Duration>,
+    jitter: f64,
+) -> Duration {
+    let cap = Duration::from_secs(config.retry_max_secs);
+    let wait = retry_after.unwrap_or_else(|| {
+        // Capped before it becomes a Duration, which cannot hold the
+        // doubling after enough retries
+        let doubled = config.retry_base_secs as f64 * 2f64.powf(attempt.saturating_sub(1) as f64);
+        let secs = (doubled * (1.0 + 0.25 * jitter.clamp(-1.0, 1.0))).min(cap.as_secs_f64());
+        Duration::try_from_secs_f64(secs).unwrap_or(cap)
+    });
+    wait.min(cap)
+}
+
+/// Random number in -1..=1 for retry jitter, from the randomly seeded
+/// hasher std uses for `HashMap`
+fn random_unit() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+}
+
+/// Send the query to one mirror, retrying while it is overloaded, until it
+/// answers, fails for good or `cancelled` is set
+fn query_mirror(
+    client: &Client,
+    url: &str,
+    query: &str,
+    config: &OverpassConfig,
+    cancelled: &AtomicBool,
+) -> std::result::Result<String, String> {
+    let timeout = Duration::from_secs(config.timeout_secs);
+    let mut last_error = "No attempts made (max_retries is 0)".to_string();
+    let mut retry_after = None;
+
+    // Retry logic for each URL
+    for attempt in 0..config.max_retries {
+        if attempt > 0 {
+            // Overpass recommends waiting when overloaded; the jitter keeps
+            // clients that failed together from retrying together
+            let wait = retry_delay(attempt, config, retry_after.take(), random_unit());
+            eprintln!(
+                "Overpass API busy on {}, retrying in {:.0} seconds (attempt {}/{})",
+                url,
+                wait.as_secs_f64(),
+                attempt + 1,
+                config.max_retries
+            );
+            std::thread::sleep(wait);
+        }
+        if cancelled.load(Ordering::Relaxed) {
+            return Err("Cancelled after another mirror answered".to_string());
+        }
+
+        // IMPORTANT: Overpass API expects form-encoded POST data, not raw body
+        // The query must be sent as: data=<query>
+        let response = match client
+            .post(url)
+            .timeout(timeout)
+            .form(&[("data", query)])
+            .send()
+        {
+            Ok(resp) => resp,
+            Err(e) => {
+                last_error = format!("Request failed: {}", e);
+                continue;
+            }
+        };
+
+        match response.status().as_u16() {
+            200 => {
+                return response
+                    .text()
+                    .map_err(|e| format!("Failed to read Overpass response body: {}", e));
+            }
+            429 | 504 => {
+                // 429 = Too Many Requests, 504 = Gateway Timeout
+                // These are retriable errors
+                retry_after = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.trim().parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                last_error = format!(
+                    "Overpass API returned status {} (attempt {})",
+                    response.status(),
+                    attempt + 1
+                );
+                continue;
+            }
+            status => {
+                // Non-retriable error for this URL, try next URL
+                return Err(format!("Overpass API returned error status: {}", status));
+            }
+        }
+    }
+    Err(last_error)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Serve one Overpass request on a local port after `delay`
+    fn mirror(delay: Duration) -> String {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/interpreter", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let Ok((mut stream, _)) = listener.accept() else {
+                return;
+            };
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+            // Read the headers and the form body before answering
+            while let Ok(n) = stream.read(&mut buffer) {
+                request.extend_from_slice(&buffer[..n]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text[..end]
+                        .lines()
+                        .find_map(|line| {
+                            let (name, value) = line.split_once(':')?;
+                            name.eq_ignore_ascii_case("content-length")
+                                .then(|| value.trim().parse::<usize>().ok())?
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length {
+                        break;
+                    }
+                }
+                if n == 0 {
+                    break;
+                }
+            }
+            std::thread::sleep(delay);
+            let body = r#"{"elements":[]}"#;
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+        });
+        url
+    }
+
+    #[test]
+    fn test_race_mirrors_takes_first_answer() {
+        let slow = mirror(Duration::from_secs(20));
+        let fast = mirror(Duration::ZERO);
+        let config = OverpassConfig {
+            urls: vec![slow, fast],
+            timeout_secs: 30,
+            max_retries: 1,
+            race_mirrors: true,
+            ..OverpassConfig::default()
+        };
+        let start = std::time::Instant::now();
+        let body = fetch_overpass_body("[out:json];", &config).unwrap();
+        assert_eq!(body, r#"{"elements":[]}"#);
+        assert!(start.elapsed() < Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        let config = OverpassConfig {
+            retry_base_secs: 10,
+            retry_max_secs: 60,
+            ..OverpassConfig::default()
+        };
+        let secs = |attempt, retry_after, jitter| {
+            retry_delay(attempt, &config, retry_after, jitter).as_secs_f64()
+        };
+        assert_eq!(secs(1, None, 0.0), 10.0);
+        assert_eq!(secs(2, None, 0.0), 20.0);
+        assert_eq!(secs(3, None, 1.0), 50.0);
+        assert_eq!(secs(3, None, -1.0), 30.0);
+        assert_eq!(secs(5, None, 0.0), 60.0);
+        // Far past the point where the doubling overflows a Duration
+        assert_eq!(secs(100, None, 1.0), 60.0);
+        assert_eq!(secs(u32::MAX, None, 0.0), 60.0);
+        // Retry-After wins over the backoff, within the cap
+        assert_eq!(secs(1, Some(Duration::from_secs(45)), 1.0), 45.0);
+        assert_eq!(secs(1, Some(Duration::from_secs(600)), 0.0), 60.0);
+        assert!((-1.0..=1.0).contains(&random_unit()));
+    }
+
     #[test]
     fn test_calculate_bbox() {
         // San Francisco: (37.7749, -122.4194)
@@ -317,6 +646,58 @@ mod tests {
         assert!(east - west > north - south);
     }
 
+    #[test]
+    fn test_calculate_bbox_high_latitude() {
+        // Longyearbyen, Svalbard
+        let (south, west, north, east) = calculate_bbox((78.22, 15.65), 10000);
+        assert!(west.is_finite() && east.is_finite());
+        assert!(north < 90.0 && south > 78.0 - 0.1);
+        // About 4.7x wider in degrees than tall at cos(78.22) = 0.204
+        let ratio = (east - west) / (north - south);
+        assert!((ratio - 1.0 / 0.204).abs() < 0.1, "{}", ratio);
+
+        // At the pole the box is capped instead of spanning infinity
+        let (_, west, _, east) = calculate_bbox((89.99, 0.0), 10000);
+        assert!(east - west < 20.0);
+    }
+
+    #[test]
+    fn test_calculate_bbox_across_antimeridian() {
+        let bbox = calculate_bbox((-17.7, 179.9), 50000);
+        let (south, west, north, east) = bbox;
+        assert!(west > 179.0 && west < 179.9);
+        assert!(east > -180.0 && east < -179.0);
+
+        // Split into one clause on each side of the antimeridian
+        let query = union_query(&["way[\"building\"]"], bbox);
+        assert!(query.contains(&format!("({},{},{},180);", south, west, north)));
+        assert!(query.contains(&format!("({},-180,{},{});", south, north, east)));
+    }
+
+    #[test]
+    fn test_substitute_bbox() {
+        let template = "[out:json];way[amenity=parking]({{bbox}});out body;>;out skel qt;";
+        let query = substitute_bbox(template, (1.0, 2.0, 3.0, 4.5)).unwrap();
+        assert_eq!(
+            query,
+            "[out:json];way[amenity=parking](1,2,3,4.5);out body;>;out skel qt;"
+        );
+
+        assert!(
+            substitute_bbox("[out:json];way[amenity=parking];out;", (1.0, 2.0, 3.0, 4.0)).is_err()
+        );
+        assert!(substitute_bbox(template, (-18.0, 179.5, -17.0, -179.5)).is_err());
+    }
+
+    #[test]
+    fn test_union_query_format() {
+        let query = union_query(&["way[\"building\"]"], (1.0, 2.0, 3.0, 4.0));
+        assert_eq!(
+            query,
+            "[out:json][timeout:180];\n(\n  way[\"building\"](1,2,3,4);\n);\nout body;\n>;\nout skel qt;"
+        );
+    }
+
     #[test]
     fn test_parse_overpass_response() {
         let json = r#"{
@@ -1,7 +1,15 @@
-use serde::Deserialize;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Deserializer};
 use std::path::PathBuf;
 
 use crate::api::RoadDepth;
+use crate::geometry::{MarginSide, SimplifyAlgorithm, parse_latitude, parse_longitude};
+use crate::layers::{TunnelMode, WaterStyle};
+use crate::mesh::RibbonProfile;
+
+pub mod theme;
+
+pub use theme::{Theme, ThemePreset};
 
 /// Central height constants for 3D printing layer alignment.
 /// All heights in mm, aligned to 0.2mm layer height for FDM printing.
@@ -43,63 +51,305 @@ pub mod heights {
     pub const ROAD_Z_BOTTOM: f32 = 0.0;
     pub const ROAD_Z_TOP: f32 = BASE_Z_TOP + ROAD_HEIGHT;
 
+    // Bridges: 0.4mm above the road or rail they carry
+    pub const BRIDGE_RAISE: f32 = 0.4;
+
     // Text: 2.4mm above base top = 4.4mm absolute (tallest feature)
     pub const TEXT_HEIGHT: f32 = 2.4;
     pub const TEXT_Z_BOTTOM: f32 = 0.0;
     pub const TEXT_Z_TOP: f32 = BASE_Z_TOP + TEXT_HEIGHT;
+
+    // Buildings: variable height above base top, scaled from OSM height tags
+    pub const BUILDING_MIN_HEIGHT: f32 = 0.4;
+    pub const BUILDING_MAX_HEIGHT: f32 = 6.0;
+}
+
+/// Feature heights from the `[heights]` config section
+///
+/// Each offset is the feature's top in mm above the base plate. Features
+/// without one stack one step above the feature below them, where a step is
+/// `FEATURE_INCREMENT` rounded to whole layers of `layer_height`.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct HeightOverrides {
+    /// Printer layer height in mm [default: 0.2]
+    pub layer_height: Option<f32>,
+    /// With recessed water, the pocket depth below the base top instead
+    pub water: Option<f32>,
+    pub parks: Option<f32>,
+    pub aeroways: Option<f32>,
+    pub railways: Option<f32>,
+    pub roads: Option<f32>,
+    pub text: Option<f32>,
+}
+
+impl HeightOverrides {
+    /// Fill each unset value from `fallback`
+    pub fn or(self, fallback: HeightOverrides) -> Self {
+        Self {
+            layer_height: self.layer_height.or(fallback.layer_height),
+            water: self.water.or(fallback.water),
+            parks: self.parks.or(fallback.parks),
+            aeroways: self.aeroways.or(fallback.aeroways),
+            railways: self.railways.or(fallback.railways),
+            roads: self.roads.or(fallback.roads),
+            text: self.text.or(fallback.text),
+        }
+    }
+
+    /// Reject zero or negative heights, naming the offending key
+    pub fn validate(&self) -> Result<()> {
+        let values = [
+            ("layer_height", self.layer_height),
+            ("water", self.water),
+            ("parks", self.parks),
+            ("aeroways", self.aeroways),
+            ("railways", self.railways),
+            ("roads", self.roads),
+            ("text", self.text),
+        ];
+        for (key, value) in values {
+            if let Some(v) = value
+                && v <= 0.0
+            {
+                bail!("[heights] {} must be greater than 0, got {}", key, v);
+            }
+        }
+        Ok(())
+    }
 }
 
 /// Dynamic height calculation based on which features are enabled
 #[derive(Debug, Clone, Copy)]
 pub struct FeatureHeights {
+    /// Layer height the heights are counted in for the color change guide
+    pub layer_height: f32,
     pub base_height: f32,
     pub water_enabled: bool,
+    pub water_style: WaterStyle,
     pub parks_enabled: bool,
+    pub aeroways_enabled: bool,
+    pub buildings_enabled: bool,
+    pub railways_enabled: bool,
+    /// Bottom of the water columns, which recessed water fills the base's
+    /// pockets from
+    pub water_z_bottom: f32,
+    /// Below `base_height` when the water is recessed
     pub water_z_top: f32,
     pub park_z_top: f32,
+    pub aeroway_z_top: f32,
+    /// Tallest possible building top; actual heights vary per building
+    pub building_z_top: f32,
+    pub rail_z_top: f32,
     pub road_z_top: f32,
+    /// Top of each road class, least important first; `road_z_top` unless
+    /// the `[roads]` section gives the class its own height
+    pub road_class_z_tops: [(&'static str, f32); 5],
     pub text_z_top: f32,
 }
 
 impl FeatureHeights {
-    pub fn new(base_height: f32, water_enabled: bool, parks_enabled: bool) -> Self {
+    pub fn new(
+        base_height: f32,
+        water: Option<WaterStyle>,
+        parks_enabled: bool,
+        aeroways_enabled: bool,
+        buildings_enabled: bool,
+        railways_enabled: bool,
+        overrides: &HeightOverrides,
+    ) -> Self {
+        let layer_height = overrides.layer_height.unwrap_or(heights::LAYER_HEIGHT);
+        let step = (heights::FEATURE_INCREMENT / layer_height).round().max(1.0) * layer_height;
         let mut current_z = base_height;
-
-        let water_z_top = if water_enabled {
-            current_z += heights::FEATURE_INCREMENT;
+        let mut next_top = |offset: Option<f32>| {
+            current_z = offset.map_or(current_z + step, |h| base_height + h);
             current_z
+        };
+
+        // Recessed water sits in the base, so the bands above start there
+        let water_z_top = match water {
+            None => 0.0,
+            Some(WaterStyle::Raised) => next_top(overrides.water),
+            Some(WaterStyle::Recessed) => base_height - overrides.water.unwrap_or(step),
+        };
+
+        let park_z_top = if parks_enabled {
+            next_top(overrides.parks)
         } else {
             0.0
         };
 
-        let park_z_top = if parks_enabled {
-            current_z += heights::FEATURE_INCREMENT;
-            current_z
+        let aeroway_z_top = if aeroways_enabled {
+            next_top(overrides.aeroways)
         } else {
             0.0
         };
 
-        current_z += heights::FEATURE_INCREMENT;
-        let road_z_top = current_z;
+        let rail_z_top = if railways_enabled {
+            next_top(overrides.railways)
+        } else {
+            0.0
+        };
 
-        current_z += heights::FEATURE_INCREMENT;
-        let text_z_top = current_z;
+        let road_z_top = next_top(overrides.roads);
+        let text_z_top = next_top(overrides.text);
+
+        // Buildings take their height from OSM tags rather than a fixed band
+        let building_z_top = if buildings_enabled {
+            base_height + heights::BUILDING_MAX_HEIGHT
+        } else {
+            0.0
+        };
 
         Self {
+            layer_height,
             base_height,
-            water_enabled,
+            water_enabled: water.is_some(),
+            water_style: water.unwrap_or_default(),
             parks_enabled,
+            aeroways_enabled,
+            buildings_enabled,
+            railways_enabled,
+            water_z_bottom: heights::WATER_Z_BOTTOM,
             water_z_top,
             park_z_top,
+            aeroway_z_top,
+            building_z_top,
+            rail_z_top,
             road_z_top,
+            road_class_z_tops: RoadOverrides::default()
+                .class_heights()
+                .map(|(name, _)| (name, road_z_top)),
             text_z_top,
         }
     }
+
+    /// Raise or lower road classes to the heights set in the `[roads]`
+    /// section, lifting the text to stay above the tallest of them
+    pub fn with_road_overrides(mut self, roads: &RoadOverrides) -> Self {
+        let (road_z_top, base_height) = (self.road_z_top, self.base_height);
+        self.road_class_z_tops = roads
+            .class_heights()
+            .map(|(name, height)| (name, height.map_or(road_z_top, |h| base_height + h)));
+        let highest = self.road_bands().last().map_or(road_z_top, |band| band.0);
+        if self.text_z_top <= highest {
+            self.text_z_top = highest + (self.text_z_top - road_z_top).max(self.layer_height);
+        }
+        self
+    }
+
+    /// Distinct road tops from lowest to highest, each with the classes
+    /// that reach it
+    pub fn road_bands(&self) -> Vec<(f32, Vec<&'static str>)> {
+        let mut bands: Vec<(f32, Vec<&'static str>)> = Vec::new();
+        let mut classes = self.road_class_z_tops;
+        classes.sort_by(|a, b| a.1.total_cmp(&b.1));
+        for (name, z_top) in classes {
+            match bands.last_mut() {
+                Some(band) if (band.0 - z_top).abs() < 1e-4 => band.1.push(name),
+                _ => bands.push((z_top, vec![name])),
+            }
+        }
+        bands
+    }
+
+    /// Check that the enabled bands still rise from water up to text, which
+    /// the color change schedule relies on
+    pub fn check_order(&self) -> Result<()> {
+        let recessed = self.water_enabled && self.water_style == WaterStyle::Recessed;
+        if recessed && self.water_z_top < self.layer_height - 1e-4 {
+            bail!(
+                "[heights] water recess ({:.2}mm) must leave at least one layer of the {:.2}mm base under it",
+                self.base_height - self.water_z_top,
+                self.base_height
+            );
+        }
+        // Road classes may span several heights; the whole span sits
+        // between the bands below and the text
+        let road_bands = self.road_bands();
+        let lowest_road = road_bands.first().map_or(self.road_z_top, |band| band.0);
+        let highest_road = road_bands.last().map_or(self.road_z_top, |band| band.0);
+        let bands = [
+            (
+                "water",
+                self.water_enabled && !recessed,
+                self.water_z_top,
+                self.water_z_top,
+            ),
+            (
+                "parks",
+                self.parks_enabled,
+                self.park_z_top,
+                self.park_z_top,
+            ),
+            (
+                "aeroways",
+                self.aeroways_enabled,
+                self.aeroway_z_top,
+                self.aeroway_z_top,
+            ),
+            (
+                "railways",
+                self.railways_enabled,
+                self.rail_z_top,
+                self.rail_z_top,
+            ),
+            ("roads", true, lowest_road, highest_road),
+            ("text", true, self.text_z_top, self.text_z_top),
+        ];
+        let mut below = ("base", self.base_height);
+        for (name, enabled, z_bottom, z_top) in bands {
+            if !enabled {
+                continue;
+            }
+            if z_bottom <= below.1 {
+                bail!(
+                    "[heights] {} ({:.2}mm) must be above {} ({:.2}mm)",
+                    name,
+                    z_bottom - self.base_height,
+                    below.0,
+                    below.1 - self.base_height
+                );
+            }
+            below = (name, z_top);
+        }
+        Ok(())
+    }
+}
+
+/// A coordinate written as a number or as text for `parse_latitude` and
+/// `parse_longitude`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CoordinateValue {
+    Degrees(f64),
+    Text(String),
+}
+
+fn deserialize_coordinate<'de, D: Deserializer<'de>>(
+    deserializer: D,
+    parse: fn(&str) -> Result<f64>,
+) -> std::result::Result<Option<f64>, D::Error> {
+    match Option::<CoordinateValue>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(CoordinateValue::Degrees(degrees)) => Ok(Some(degrees)),
+        Some(CoordinateValue::Text(text)) => parse(&text)
+            .map(Some)
+            .map_err(|e| serde::de::Error::custom(format!("{:#}", e))),
+    }
 }
 
-fn default_radius() -> u32 {
-    10000
+fn deserialize_latitude<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<f64>, D::Error> {
+    deserialize_coordinate(deserializer, parse_latitude)
 }
+
+fn deserialize_longitude<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<Option<f64>, D::Error> {
+    deserialize_coordinate(deserializer, parse_longitude)
+}
+
 fn default_size() -> f32 {
     220.0
 }
@@ -112,6 +362,9 @@ fn default_road_scale() -> f32 {
 fn default_road_depth() -> RoadDepth {
     RoadDepth::Primary
 }
+fn default_line_spacing() -> f32 {
+    1.2
+}
 fn default_simplify() -> u8 {
     0
 }
@@ -119,24 +372,42 @@ fn default_verbose() -> bool {
     false
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Deserialize, Default, Clone)]
 pub struct FileConfig {
     #[serde(default)]
     pub city: Option<String>,
     #[serde(default)]
     pub country: Option<String>,
-    #[serde(default)]
+    /// State or region narrowing the city lookup
+    #[serde(default, alias = "region")]
+    pub state: Option<String>,
+    /// Decimal degrees, or a string such as `"37°46'30\"N"`
+    #[serde(default, deserialize_with = "deserialize_latitude")]
     pub lat: Option<f64>,
-    #[serde(default)]
+    #[serde(default, deserialize_with = "deserialize_longitude")]
     pub lon: Option<f64>,
-    #[serde(default = "default_radius")]
-    pub radius: u32,
+    /// Map radius in meters; fitted to the geocoded city when unset
+    #[serde(default)]
+    pub radius: Option<u32>,
+    /// Explicit `[south, west, north, east]` box; overrides city, coordinates and radius
+    #[serde(default)]
+    pub bbox: Option<[f64; 4]>,
+    /// Named area printed to its boundary; overrides city, coordinates, radius and bbox
+    #[serde(default)]
+    pub area: Option<String>,
     #[serde(default)]
     pub output: Option<PathBuf>,
     #[serde(default = "default_size")]
     pub size: f32,
+    #[serde(default)]
+    pub width: Option<f32>,
+    #[serde(default)]
+    pub height: Option<f32>,
     #[serde(default = "default_base_height")]
     pub base_height: f32,
+    /// Radius of the rounded base plate corners in mm (0 = sharp)
+    #[serde(default)]
+    pub corner_radius: f32,
     #[serde(default = "default_road_scale")]
     pub road_scale: f32,
     #[serde(default = "default_road_depth")]
@@ -145,14 +416,173 @@ pub struct FileConfig {
     pub primary_text: Option<String>,
     #[serde(default)]
     pub secondary_text: Option<String>,
+    /// Fixed cap heights in mm for the labels, shared by every `[[map]]`
+    #[serde(default)]
+    pub primary_text_height_mm: Option<f32>,
+    #[serde(default)]
+    pub secondary_text_height_mm: Option<f32>,
+    /// Message inlaid into the underside of the base
+    #[serde(default)]
+    pub back_text: Option<String>,
+    /// Line spacing for multi-line labels, as a multiple of the line height
+    #[serde(default = "default_line_spacing")]
+    pub line_spacing: f32,
     #[serde(default = "default_verbose")]
     pub verbose: bool,
     #[serde(default = "default_simplify")]
     pub simplify: u8,
+    /// Road simplification algorithm: douglas-peucker (default) or visvalingam
+    #[serde(default)]
+    pub simplify_algorithm: SimplifyAlgorithm,
+    /// Finish road ends with half circles instead of flat cuts
+    #[serde(default)]
+    pub round_caps: bool,
+    /// Merge crossing roads into one solid instead of overlapping ribbons
+    #[serde(default)]
+    pub union_roads: bool,
+    /// Label maps without a city after the place at their center
+    #[serde(default)]
+    pub reverse_geocode: bool,
+    /// Leave out the labels and their margin
+    #[serde(default)]
+    pub no_text: bool,
+    /// Printer bed `[width, height]` in mm the plate is checked against
+    #[serde(default)]
+    pub bed: Option<[f32; 2]>,
+    /// Shrink a plate too large for `bed` until it fits
+    #[serde(default)]
+    pub auto_fit_bed: bool,
+    /// Edge the labels sit along: bottom or top
+    #[serde(default)]
+    pub text_position: Option<MarginSide>,
+    /// Elevation model to raise the map into terrain
+    #[serde(default)]
+    pub dem: Option<PathBuf>,
+    /// Vertical exaggeration of the terrain
+    #[serde(default)]
+    pub terrain_scale: Option<f32>,
+    /// Contour line interval in meters, drawn instead of the relief
+    #[serde(default)]
+    pub contours: Option<f32>,
+    /// Road cross-section: flat (default), trapezoid or rounded
+    #[serde(default)]
+    pub road_profile: RibbonProfile,
+    /// Tunnels: show (default), hide or engrave
+    #[serde(default)]
+    pub tunnels: TunnelMode,
+    /// Water: raised (default) or recessed into the base
+    #[serde(default)]
+    pub water_style: WaterStyle,
     #[serde(default)]
     pub overpass: Option<OverpassConfig>,
+    #[serde(default)]
+    pub nominatim: Option<NominatimConfig>,
+    #[serde(default)]
+    pub roads: Option<RoadOverrides>,
+    #[serde(default)]
+    pub heights: Option<HeightOverrides>,
+    /// Preset of layers, heights and colors: classic, night, earth or ocean
+    #[serde(default)]
+    pub theme: Option<Theme>,
+    /// Maps generated in one run by `--all`, from `[[map]]` tables
+    #[serde(default, rename = "map")]
+    pub maps: Vec<MapEntry>,
+}
+
+/// One `[[map]]` table: where a batch map is and where it is written
+///
+/// Everything else comes from the rest of the config file. `country` and
+/// `radius` fall back to the shared values when left out.
+#[derive(Debug, Deserialize, Default, Clone)]
+pub struct MapEntry {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    #[serde(alias = "region")]
+    pub state: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_latitude")]
+    pub lat: Option<f64>,
+    #[serde(default, deserialize_with = "deserialize_longitude")]
+    pub lon: Option<f64>,
+    pub radius: Option<u32>,
+    pub bbox: Option<[f64; 4]>,
+    pub area: Option<String>,
+    pub output: Option<PathBuf>,
+    pub primary_text: Option<String>,
+    pub secondary_text: Option<String>,
+}
+
+impl MapEntry {
+    /// Name for progress and error messages
+    pub fn label(&self, index: usize) -> String {
+        match (
+            self.city.as_ref().or(self.area.as_ref()),
+            self.output.as_ref(),
+        ) {
+            (Some(name), _) => name.clone(),
+            (None, Some(output)) => output.display().to_string(),
+            (None, None) => format!("map {}", index + 1),
+        }
+    }
+}
+
+/// Per-class road dimensions from the `[roads]` config section
+///
+/// Widths are in mm before `road_scale` and the map-size scaling; heights
+/// are in mm above the base plate. Unset values keep the built-in defaults.
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct RoadOverrides {
+    pub motorway_width: Option<f32>,
+    pub motorway_height: Option<f32>,
+    pub primary_width: Option<f32>,
+    pub primary_height: Option<f32>,
+    pub secondary_width: Option<f32>,
+    pub secondary_height: Option<f32>,
+    pub tertiary_width: Option<f32>,
+    pub tertiary_height: Option<f32>,
+    pub residential_width: Option<f32>,
+    pub residential_height: Option<f32>,
 }
 
+impl RoadOverrides {
+    /// Per-class heights by class name, least important class first
+    pub fn class_heights(&self) -> [(&'static str, Option<f32>); 5] {
+        [
+            ("residential", self.residential_height),
+            ("tertiary", self.tertiary_height),
+            ("secondary", self.secondary_height),
+            ("primary", self.primary_height),
+            ("motorway", self.motorway_height),
+        ]
+    }
+
+    /// Reject zero or negative dimensions, naming the offending key
+    pub fn validate(&self) -> Result<()> {
+        let values = [
+            ("motorway_width", self.motorway_width),
+            ("motorway_height", self.motorway_height),
+            ("primary_width", self.primary_width),
+            ("primary_height", self.primary_height),
+            ("secondary_width", self.secondary_width),
+            ("secondary_height", self.secondary_height),
+            ("tertiary_width", self.tertiary_width),
+            ("tertiary_height", self.tertiary_height),
+            ("residential_width", self.residential_width),
+            ("residential_height", self.residential_height),
+        ];
+        for (key, value) in values {
+            if let Some(v) = value
+                && v <= 0.0
+            {
+                bail!("[roads] {} must be greater than 0, got {}", key, v);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Identifies mapto3d to Overpass and Nominatim unless configured otherwise
+pub const DEFAULT_USER_AGENT: &str = "mapto3d/0.1.0 (https://github.com/shantanugoel/mapto3d)";
+
 fn default_overpass_urls() -> Vec<String> {
     vec![
         "https://overpass.private.coffee/api/interpreter".to_string(),
@@ -169,6 +599,22 @@ fn default_max_retries() -> u32 {
     3
 }
 
+fn default_retry_base_secs() -> u64 {
+    15
+}
+
+fn default_retry_max_secs() -> u64 {
+    120
+}
+
+fn default_cache_ttl_secs() -> u64 {
+    7 * 24 * 60 * 60
+}
+
+fn default_cache_enabled() -> bool {
+    true
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct OverpassConfig {
     #[serde(default = "default_overpass_urls")]
@@ -177,6 +623,31 @@ pub struct OverpassConfig {
     pub timeout_secs: u64,
     #[serde(default = "default_max_retries")]
     pub max_retries: u32,
+    /// Wait before the first retry in seconds; each later retry doubles it,
+    /// give or take a random quarter
+    #[serde(default = "default_retry_base_secs")]
+    pub retry_base_secs: u64,
+    /// Longest wait between retries in seconds, also for a server's
+    /// `Retry-After`
+    #[serde(default = "default_retry_max_secs")]
+    pub retry_max_secs: u64,
+    /// How long cached Overpass responses stay valid, in seconds
+    #[serde(default = "default_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+    /// Read and write the on-disk response cache (disabled by `--no-cache`)
+    #[serde(default = "default_cache_enabled")]
+    pub cache: bool,
+    /// User-Agent sent to Overpass; defaults to `DEFAULT_USER_AGENT`
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Send each query to the first two mirrors at once and keep the first
+    /// answer, instead of trying mirrors one after another
+    #[serde(default)]
+    pub race_mirrors: bool,
+    /// Proxy URL for Overpass requests (`http://`, `https://` or
+    /// `socks5://`); unset follows the `HTTPS_PROXY`/`ALL_PROXY` variables
+    #[serde(default)]
+    pub proxy: Option<String>,
 }
 
 impl Default for OverpassConfig {
@@ -185,11 +656,67 @@ impl Default for OverpassConfig {
             urls: default_overpass_urls(),
             timeout_secs: default_timeout_secs(),
             max_retries: default_max_retries(),
+            retry_base_secs: default_retry_base_secs(),
+            retry_max_secs: default_retry_max_secs(),
+            cache_ttl_secs: default_cache_ttl_secs(),
+            cache: default_cache_enabled(),
+            user_agent: None,
+            race_mirrors: false,
+            proxy: None,
         }
     }
 }
 
+impl OverpassConfig {
+    pub fn user_agent(&self) -> &str {
+        self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT)
+    }
+}
+
+/// Settings for Nominatim geocoding
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct NominatimConfig {
+    /// User-Agent sent to Nominatim, which its usage policy asks to
+    /// identify the application; defaults to `DEFAULT_USER_AGENT`
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    /// Proxy URL for Nominatim requests, like `OverpassConfig::proxy`
+    #[serde(default)]
+    pub proxy: Option<String>,
+}
+
+impl NominatimConfig {
+    pub fn user_agent(&self) -> &str {
+        self.user_agent.as_deref().unwrap_or(DEFAULT_USER_AGENT)
+    }
+}
+
 impl FileConfig {
+    /// The shared settings with the location, labels and output of `entry`
+    ///
+    /// An entry without a city, area or output writes `map-<n>.stl`, so batch maps
+    /// never overwrite each other through the shared `output`.
+    pub fn for_map(&self, entry: &MapEntry, index: usize) -> FileConfig {
+        FileConfig {
+            city: entry.city.clone(),
+            country: entry.country.clone().or_else(|| self.country.clone()),
+            state: entry.state.clone(),
+            lat: entry.lat,
+            lon: entry.lon,
+            radius: entry.radius.or(self.radius),
+            bbox: entry.bbox,
+            area: entry.area.clone(),
+            output: entry.output.clone().or_else(|| {
+                (entry.city.is_none() && entry.area.is_none())
+                    .then(|| PathBuf::from(format!("map-{}.stl", index + 1)))
+            }),
+            primary_text: entry.primary_text.clone(),
+            secondary_text: entry.secondary_text.clone(),
+            maps: Vec::new(),
+            ..self.clone()
+        }
+    }
+
     pub fn load() -> Option<Self> {
         let config_paths = get_config_paths();
 
@@ -227,3 +754,203 @@ fn get_config_paths() -> Vec<PathBuf> {
 
     paths
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_entries() {
+        let config: FileConfig = toml::from_str(
+            r#"
+            country = "Italy"
+            radius = 3000
+            output = "shared.stl"
+
+            [[map]]
+            city = "Venice"
+            primary_text = "VENEZIA"
+
+            [[map]]
+            lat = 43.77
+            lon = 11.25
+            radius = 5000
+
+            [[map]]
+            lat = "45°26'N"
+            lon = "12.33E"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.maps.len(), 3);
+        assert!((config.maps[2].lat.unwrap() - (45.0 + 26.0 / 60.0)).abs() < 1e-9);
+        assert_eq!(config.maps[2].lon, Some(12.33));
+        let err = toml::from_str::<FileConfig>("lat = \"45N\"\nlon = \"12.33S\"").unwrap_err();
+        assert!(err.to_string().contains("Invalid longitude"));
+
+        let venice = config.for_map(&config.maps[0], 0);
+        assert_eq!(venice.city.as_deref(), Some("Venice"));
+        assert_eq!(venice.country.as_deref(), Some("Italy"));
+        assert_eq!(venice.radius, Some(3000));
+        assert_eq!(venice.output, None);
+        assert_eq!(venice.primary_text.as_deref(), Some("VENEZIA"));
+
+        let florence = config.for_map(&config.maps[1], 1);
+        assert_eq!(florence.radius, Some(5000));
+        assert_eq!(florence.output, Some(PathBuf::from("map-2.stl")));
+        assert_eq!(config.maps[1].label(1), "map 2");
+        assert!(florence.maps.is_empty());
+    }
+
+    #[test]
+    fn test_feature_heights_default_stack() {
+        let h = FeatureHeights::new(
+            2.0,
+            Some(WaterStyle::Raised),
+            true,
+            false,
+            false,
+            false,
+            &HeightOverrides::default(),
+        );
+        assert!((h.water_z_top - 2.6).abs() < 1e-5);
+        assert!((h.park_z_top - 3.2).abs() < 1e-5);
+        assert!((h.road_z_top - 3.8).abs() < 1e-5);
+        assert!((h.text_z_top - 4.4).abs() < 1e-5);
+        assert!(h.check_order().is_ok());
+
+        // Aeroways stack between parks and roads
+        let h = FeatureHeights::new(
+            2.0,
+            Some(WaterStyle::Raised),
+            true,
+            true,
+            false,
+            false,
+            &HeightOverrides::default(),
+        );
+        assert!((h.aeroway_z_top - 3.8).abs() < 1e-5);
+        assert!((h.road_z_top - 4.4).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_feature_heights_road_classes() {
+        let h = FeatureHeights::new(
+            2.0,
+            None,
+            false,
+            false,
+            false,
+            false,
+            &HeightOverrides::default(),
+        );
+        assert_eq!(h.road_bands().len(), 1);
+
+        // Motorways above the text band lift the text over them
+        let roads = RoadOverrides {
+            motorway_height: Some(2.4),
+            primary_height: Some(2.4),
+            residential_height: Some(0.4),
+            ..RoadOverrides::default()
+        };
+        let h = h.with_road_overrides(&roads);
+        let bands = h.road_bands();
+        assert_eq!(bands.len(), 3);
+        assert_eq!(bands[0].1, vec!["residential"]);
+        assert_eq!(bands[2].1, vec!["primary", "motorway"]);
+        assert!((bands[2].0 - 4.4).abs() < 1e-5);
+        assert!((h.text_z_top - 5.0).abs() < 1e-5);
+        assert!(h.check_order().is_ok());
+
+        // Residential streets sunk below the parks band break the order
+        let h = FeatureHeights::new(
+            2.0,
+            None,
+            true,
+            false,
+            false,
+            false,
+            &HeightOverrides::default(),
+        )
+        .with_road_overrides(&roads);
+        assert!(h.check_order().is_err());
+    }
+
+    #[test]
+    fn test_feature_heights_overrides() {
+        // 0.28mm layers: the default step becomes two layers
+        let coarse = HeightOverrides {
+            layer_height: Some(0.28),
+            ..HeightOverrides::default()
+        };
+        let h = FeatureHeights::new(
+            2.0,
+            Some(WaterStyle::Raised),
+            false,
+            false,
+            false,
+            false,
+            &coarse,
+        );
+        assert!((h.water_z_top - 2.56).abs() < 1e-5);
+        assert!((h.road_z_top - 3.12).abs() < 1e-5);
+
+        // An explicit offset is kept and later bands stack above it
+        let tall_roads = HeightOverrides {
+            roads: Some(3.0),
+            ..HeightOverrides::default()
+        };
+        let h = FeatureHeights::new(2.0, None, false, false, false, false, &tall_roads);
+        assert!((h.road_z_top - 5.0).abs() < 1e-5);
+        assert!((h.text_z_top - 5.6).abs() < 1e-5);
+
+        let inverted = HeightOverrides {
+            water: Some(2.0),
+            roads: Some(1.0),
+            ..HeightOverrides::default()
+        };
+        let h = FeatureHeights::new(
+            2.0,
+            Some(WaterStyle::Raised),
+            false,
+            false,
+            false,
+            false,
+            &inverted,
+        );
+        assert!(h.check_order().is_err());
+
+        // Recessed water keeps the bands above where they would be without
+        // water, and has to leave a floor under it
+        let h = FeatureHeights::new(
+            2.0,
+            Some(WaterStyle::Recessed),
+            false,
+            false,
+            false,
+            false,
+            &HeightOverrides::default(),
+        );
+        assert!((h.water_z_top - 1.4).abs() < 1e-5);
+        assert!((h.road_z_top - 2.6).abs() < 1e-5);
+        assert!(h.check_order().is_ok());
+        let h = FeatureHeights::new(
+            2.0,
+            Some(WaterStyle::Recessed),
+            false,
+            false,
+            false,
+            false,
+            &inverted,
+        );
+        assert!(h.check_order().is_err());
+        assert!(
+            HeightOverrides {
+                layer_height: Some(0.0),
+                ..HeightOverrides::default()
+            }
+            .validate()
+            .is_err()
+        );
+    }
+}
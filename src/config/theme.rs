@@ -0,0 +1,143 @@
+use serde::Deserialize;
+
+use super::HeightOverrides;
+
+/// Named bundle of layers, band heights and filament colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    /// Water and parks at the default heights, light base and dark text
+    Classic,
+    /// Dark base with tall, bright roads and text
+    Night,
+    /// Parks without water, on a thicker green band under tall roads
+    Earth,
+    /// Water without parks, with a low sea
+    Ocean,
+}
+
+impl std::str::FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "classic" => Ok(Theme::Classic),
+            "night" => Ok(Theme::Night),
+            "earth" => Ok(Theme::Earth),
+            "ocean" => Ok(Theme::Ocean),
+            _ => Err(format!(
+                "Invalid theme '{}'. Valid options: classic, night, earth, ocean",
+                s
+            )),
+        }
+    }
+}
+
+/// What a theme turns on and how tall it makes each band
+///
+/// Flags and `[heights]` values set explicitly take precedence; a theme only
+/// fills in what was left at its default.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThemePreset {
+    pub water: bool,
+    pub parks: bool,
+    pub heights: HeightOverrides,
+    /// Filament color per feature: base, water, parks, roads, text
+    pub palette: [(&'static str, &'static str); 5],
+}
+
+impl Theme {
+    pub fn preset(self) -> ThemePreset {
+        match self {
+            Theme::Classic => ThemePreset {
+                water: true,
+                parks: true,
+                heights: HeightOverrides::default(),
+                palette: [
+                    ("base", "White"),
+                    ("water", "Blue"),
+                    ("parks", "Green"),
+                    ("roads", "Gray"),
+                    ("text", "Black"),
+                ],
+            },
+            Theme::Night => ThemePreset {
+                water: true,
+                parks: true,
+                heights: HeightOverrides {
+                    roads: Some(2.0),
+                    text: Some(3.0),
+                    ..HeightOverrides::default()
+                },
+                palette: [
+                    ("base", "Black"),
+                    ("water", "Navy"),
+                    ("parks", "Dark green"),
+                    ("roads", "White"),
+                    ("text", "Gold"),
+                ],
+            },
+            Theme::Earth => ThemePreset {
+                water: false,
+                parks: true,
+                heights: HeightOverrides {
+                    parks: Some(1.0),
+                    roads: Some(2.0),
+                    text: Some(2.6),
+                    ..HeightOverrides::default()
+                },
+                palette: [
+                    ("base", "Tan"),
+                    ("water", "Slate blue"),
+                    ("parks", "Forest green"),
+                    ("roads", "Brown"),
+                    ("text", "Black"),
+                ],
+            },
+            Theme::Ocean => ThemePreset {
+                water: true,
+                parks: false,
+                heights: HeightOverrides {
+                    water: Some(0.4),
+                    ..HeightOverrides::default()
+                },
+                palette: [
+                    ("base", "Sand"),
+                    ("water", "Cyan"),
+                    ("parks", "Sea green"),
+                    ("roads", "Coral"),
+                    ("text", "White"),
+                ],
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FeatureHeights;
+    use crate::layers::WaterStyle;
+
+    #[test]
+    fn test_theme_presets_keep_band_order() {
+        // Any layers enabled on top of a theme must still fit between its bands
+        for theme in [Theme::Classic, Theme::Night, Theme::Earth, Theme::Ocean] {
+            let preset = theme.preset();
+            for extra in 0..8 {
+                let heights = FeatureHeights::new(
+                    2.0,
+                    (preset.water || extra & 1 != 0).then_some(WaterStyle::Raised),
+                    preset.parks || extra & 2 != 0,
+                    false,
+                    false,
+                    extra & 4 != 0,
+                    &preset.heights,
+                );
+                assert!(heights.check_order().is_ok(), "{:?} + {}", theme, extra);
+            }
+        }
+        assert_eq!("Night".parse(), Ok(Theme::Night));
+        assert!("neon".parse::<Theme>().is_err());
+    }
+}
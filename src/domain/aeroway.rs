@@ -0,0 +1,65 @@
+use super::ParkPolygon;
+
+/// Airfield line classification based on OSM aeroway tags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AerowayClass {
+    Runway,
+    Taxiway,
+}
+
+impl AerowayClass {
+    /// Classify an aeroway tag value; aprons are areas and handled apart
+    pub fn from_aeroway_tag(tag: &str) -> Option<AerowayClass> {
+        match tag {
+            "runway" => Some(AerowayClass::Runway),
+            "taxiway" => Some(AerowayClass::Taxiway),
+            _ => None,
+        }
+    }
+
+    /// Typical paved width in meters, for ways without a `width` tag
+    pub fn typical_width_m(self) -> f32 {
+        match self {
+            AerowayClass::Runway => 45.0,
+            AerowayClass::Taxiway => 23.0,
+        }
+    }
+}
+
+/// A runway or taxiway centerline
+#[derive(Debug, Clone)]
+pub struct AerowayLine {
+    /// Points as (lat, lon) pairs in WGS84
+    pub points: Vec<(f64, f64)>,
+    pub class: AerowayClass,
+    /// Paved width in meters from the `width` tag
+    pub width_m: Option<f32>,
+}
+
+impl AerowayLine {
+    pub fn new(points: Vec<(f64, f64)>, class: AerowayClass) -> Self {
+        Self {
+            points,
+            class,
+            width_m: None,
+        }
+    }
+
+    pub fn with_width_m(mut self, width_m: Option<f32>) -> Self {
+        self.width_m = width_m;
+        self
+    }
+
+    /// Tagged width, or the typical width for the class
+    pub fn width_m(&self) -> f32 {
+        self.width_m.unwrap_or(self.class.typical_width_m())
+    }
+}
+
+/// Runways, taxiways and aprons
+#[derive(Debug, Clone, Default)]
+pub struct Aeroways {
+    pub lines: Vec<AerowayLine>,
+    /// Apron areas, from closed ways and multipolygon relations
+    pub aprons: Vec<ParkPolygon>,
+}
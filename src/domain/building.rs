@@ -0,0 +1,76 @@
+/// Approximate height of one storey, used for `building:levels`
+pub const METERS_PER_LEVEL: f64 = 3.0;
+
+#[derive(Debug, Clone)]
+pub struct BuildingPolygon {
+    pub outer: Vec<(f64, f64)>,
+    /// Real-world height in meters from OSM tags, if tagged
+    pub height_m: Option<f64>,
+}
+
+impl BuildingPolygon {
+    pub fn new(outer: Vec<(f64, f64)>) -> Self {
+        Self {
+            outer,
+            height_m: None,
+        }
+    }
+
+    pub fn with_height(mut self, height_m: Option<f64>) -> Self {
+        self.height_m = height_m;
+        self
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.outer.len() >= 3
+    }
+}
+
+/// Parse an OSM `height` tag value into meters
+///
+/// Accepts plain numbers (`"12"`, `"12.5"`), explicit meters (`"12 m"`) and
+/// feet (`"40'"`, `"40 ft"`). Returns `None` for anything else.
+pub fn parse_height_tag(value: &str) -> Option<f64> {
+    let value = value.trim();
+    let (number, factor) = if let Some(v) = value.strip_suffix("ft") {
+        (v, 0.3048)
+    } else if let Some(v) = value.strip_suffix('\'') {
+        (v, 0.3048)
+    } else if let Some(v) = value.strip_suffix('m') {
+        (v, 1.0)
+    } else {
+        (value, 1.0)
+    };
+
+    let meters = number.trim().parse::<f64>().ok()? * factor;
+    (meters.is_finite() && meters > 0.0).then_some(meters)
+}
+
+/// Parse an OSM `building:levels` tag value into meters
+pub fn parse_levels_tag(value: &str) -> Option<f64> {
+    let levels = value.trim().parse::<f64>().ok()?;
+    (levels.is_finite() && levels > 0.0).then_some(levels * METERS_PER_LEVEL)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_height_tag() {
+        assert_eq!(parse_height_tag("12"), Some(12.0));
+        assert_eq!(parse_height_tag("12.5 m"), Some(12.5));
+        assert_eq!(parse_height_tag("7m"), Some(7.0));
+        assert!((parse_height_tag("100 ft").unwrap() - 30.48).abs() < 1e-9);
+        assert!((parse_height_tag("10'").unwrap() - 3.048).abs() < 1e-9);
+        assert_eq!(parse_height_tag("tall"), None);
+        assert_eq!(parse_height_tag("-3"), None);
+    }
+
+    #[test]
+    fn test_parse_levels_tag() {
+        assert_eq!(parse_levels_tag("4"), Some(12.0));
+        assert_eq!(parse_levels_tag("0"), None);
+        assert_eq!(parse_levels_tag("many"), None);
+    }
+}
@@ -0,0 +1,16 @@
+use super::ParkPolygon;
+
+/// Features returned by a custom Overpass query, whatever their tags
+#[derive(Debug, Clone, Default)]
+pub struct CustomFeatures {
+    /// Closed ways and multipolygon relations
+    pub areas: Vec<ParkPolygon>,
+    /// Open ways as (lat, lon) polylines
+    pub lines: Vec<Vec<(f64, f64)>>,
+}
+
+impl CustomFeatures {
+    pub fn is_empty(&self) -> bool {
+        self.areas.is_empty() && self.lines.is_empty()
+    }
+}
@@ -1,7 +1,13 @@
+pub mod aeroway;
+pub mod building;
+pub mod custom;
 pub mod park;
 pub mod road;
 pub mod water;
 
+pub use aeroway::{AerowayClass, AerowayLine, Aeroways};
+pub use building::BuildingPolygon;
+pub use custom::CustomFeatures;
 pub use park::ParkPolygon;
 pub use road::{RoadClass, RoadSegment};
 pub use water::WaterPolygon;
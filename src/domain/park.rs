@@ -1,14 +1,27 @@
+use crate::geometry::ring_self_intersects;
+
 #[derive(Debug, Clone)]
 pub struct ParkPolygon {
     pub outer: Vec<(f64, f64)>,
+    pub holes: Vec<Vec<(f64, f64)>>,
 }
 
 impl ParkPolygon {
     pub fn new(outer: Vec<(f64, f64)>) -> Self {
-        Self { outer }
+        Self {
+            outer,
+            holes: Vec::new(),
+        }
+    }
+
+    pub fn with_holes(outer: Vec<(f64, f64)>, holes: Vec<Vec<(f64, f64)>>) -> Self {
+        Self { outer, holes }
     }
 
+    /// At least a triangle, with no ring crossing itself
     pub fn is_valid(&self) -> bool {
         self.outer.len() >= 3
+            && !ring_self_intersects(&self.outer)
+            && !self.holes.iter().any(|hole| ring_self_intersects(hole))
     }
 }
@@ -1,11 +1,15 @@
 /// Road classification based on OSM highway tags
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RoadClass {
     Motorway,
     Primary,
     Secondary,
     Tertiary,
     Residential,
+    /// Footways, paths, pedestrian streets and steps
+    Path,
+    /// Rail lines (heavy rail, light rail, subway, tram)
+    Rail,
 }
 
 impl RoadClass {
@@ -19,9 +23,23 @@ impl RoadClass {
             "residential" | "living_street" | "unclassified" | "service" => {
                 Some(RoadClass::Residential)
             }
+            "footway" | "path" | "pedestrian" | "steps" => Some(RoadClass::Path),
             _ => None, // Skip unknown road types
         }
     }
+
+    /// Classify a railway tag value; only passenger/freight lines are kept
+    pub fn from_railway_tag(tag: &str) -> Option<RoadClass> {
+        match tag {
+            "rail" | "light_rail" | "subway" | "tram" => Some(RoadClass::Rail),
+            _ => None,
+        }
+    }
+}
+
+/// Parse an OSM `lanes` tag; values like "2;3" are ignored
+pub fn parse_lanes_tag(value: &str) -> Option<u8> {
+    value.trim().parse::<u8>().ok().filter(|&lanes| lanes > 0)
 }
 
 /// A road segment with coordinates and classification
@@ -31,11 +49,62 @@ pub struct RoadSegment {
     pub points: Vec<(f64, f64)>,
     /// Road classification
     pub class: RoadClass,
+    /// Carried over other features on a bridge
+    pub is_bridge: bool,
+    /// Runs below ground in a tunnel
+    pub is_tunnel: bool,
+    /// Carriageway width in meters from the `width` tag
+    pub width_m: Option<f32>,
+    /// Number of lanes from the `lanes` tag
+    pub lanes: Option<u8>,
+    /// Street name from the `name` tag
+    pub name: Option<String>,
+    /// Traffic may only travel in the order of `points`
+    pub is_oneway: bool,
 }
 
 impl RoadSegment {
     pub fn new(points: Vec<(f64, f64)>, class: RoadClass) -> Self {
-        Self { points, class }
+        Self {
+            points,
+            class,
+            is_bridge: false,
+            is_tunnel: false,
+            width_m: None,
+            lanes: None,
+            name: None,
+            is_oneway: false,
+        }
+    }
+
+    pub fn with_width_m(mut self, width_m: Option<f32>) -> Self {
+        self.width_m = width_m;
+        self
+    }
+
+    pub fn with_lanes(mut self, lanes: Option<u8>) -> Self {
+        self.lanes = lanes;
+        self
+    }
+
+    pub fn with_name(mut self, name: Option<String>) -> Self {
+        self.name = name;
+        self
+    }
+
+    pub fn with_bridge(mut self, is_bridge: bool) -> Self {
+        self.is_bridge = is_bridge;
+        self
+    }
+
+    pub fn with_tunnel(mut self, is_tunnel: bool) -> Self {
+        self.is_tunnel = is_tunnel;
+        self
+    }
+
+    pub fn with_oneway(mut self, is_oneway: bool) -> Self {
+        self.is_oneway = is_oneway;
+        self
     }
 }
 
@@ -57,6 +126,24 @@ mod tests {
             RoadClass::from_highway_tag("residential"),
             Some(RoadClass::Residential)
         );
-        assert_eq!(RoadClass::from_highway_tag("footway"), None);
+        assert_eq!(
+            RoadClass::from_highway_tag("footway"),
+            Some(RoadClass::Path)
+        );
+        assert_eq!(RoadClass::from_highway_tag("bridleway"), None);
+    }
+
+    #[test]
+    fn test_parse_lanes_tag() {
+        assert_eq!(parse_lanes_tag("6"), Some(6));
+        assert_eq!(parse_lanes_tag("2;3"), None);
+        assert_eq!(parse_lanes_tag("0"), None);
+    }
+
+    #[test]
+    fn test_road_class_from_railway_tag() {
+        assert_eq!(RoadClass::from_railway_tag("rail"), Some(RoadClass::Rail));
+        assert_eq!(RoadClass::from_railway_tag("tram"), Some(RoadClass::Rail));
+        assert_eq!(RoadClass::from_railway_tag("abandoned"), None);
     }
 }
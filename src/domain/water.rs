@@ -1,3 +1,5 @@
+use crate::geometry::ring_self_intersects;
+
 #[derive(Debug, Clone)]
 pub struct WaterPolygon {
     pub outer: Vec<(f64, f64)>,
@@ -12,12 +14,14 @@ impl WaterPolygon {
         }
     }
 
-    #[allow(dead_code)]
     pub fn with_holes(outer: Vec<(f64, f64)>, holes: Vec<Vec<(f64, f64)>>) -> Self {
         Self { outer, holes }
     }
 
+    /// At least a triangle, with no ring crossing itself
     pub fn is_valid(&self) -> bool {
         self.outer.len() >= 3
+            && !ring_self_intersects(&self.outer)
+            && !self.holes.iter().any(|hole| ring_self_intersects(hole))
     }
 }
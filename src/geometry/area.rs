@@ -0,0 +1,353 @@
+//! Clipping against the outline of a named area, in (lat, lon) degrees
+//!
+//! Boundaries of cities and districts are concave and can have holes and
+//! exclaves, so lines are cut at every crossing of the outline and
+//! polygons that straddle it are intersected with it.
+
+use geo::{BooleanOps, Contains, Coord, LineString, MultiPolygon, Polygon};
+use std::collections::HashMap;
+
+use super::bbox::BBox;
+use super::clip::lerp;
+
+/// Closed ring of (lat, lon) points
+type Ring = Vec<(f64, f64)>;
+
+/// Cells along the longer side of the grid indexing the outline's edges
+const GRID_CELLS: f64 = 256.0;
+
+/// Crossings this close to an end of a segment or edge, as a fraction of
+/// its length, may be touches rather than crossings
+const TOUCH_TOLERANCE: f64 = 1e-9;
+
+/// Outline of an area: one or more polygons with holes
+#[derive(Debug, Clone)]
+pub struct Area {
+    polygons: MultiPolygon<f64>,
+    /// Every ring edge of the outline
+    edges: Vec<((f64, f64), (f64, f64))>,
+    /// Indices into `edges` by grid cell
+    grid: HashMap<(i64, i64), Vec<usize>>,
+    origin: (f64, f64),
+    cell: f64,
+    bbox: BBox,
+}
+
+impl Area {
+    /// Area covered by `polygons`, each an outer ring and its holes; `None`
+    /// if there are no rings with an inside
+    pub fn new(polygons: &[(Ring, Vec<Ring>)]) -> Option<Self> {
+        let rings = || {
+            polygons
+                .iter()
+                .flat_map(|(outer, holes)| std::iter::once(outer).chain(holes))
+                .filter(|ring| ring.len() >= 4)
+        };
+        let points = || rings().flatten().copied();
+        let (south, north) = points().fold((f64::MAX, f64::MIN), |(lo, hi), (lat, _)| {
+            (lo.min(lat), hi.max(lat))
+        });
+        let (west, east) = points().fold((f64::MAX, f64::MIN), |(lo, hi), (_, lon)| {
+            (lo.min(lon), hi.max(lon))
+        });
+        if !(south < north && west < east) {
+            return None;
+        }
+
+        let edges: Vec<((f64, f64), (f64, f64))> = rings()
+            .flat_map(|ring| ring.windows(2).map(|pair| (pair[0], pair[1])))
+            .filter(|(a, b)| a != b)
+            .collect();
+        let mut area = Self {
+            polygons: MultiPolygon::new(
+                polygons
+                    .iter()
+                    .filter(|(outer, _)| outer.len() >= 4)
+                    .map(|(outer, holes)| {
+                        Polygon::new(
+                            line_string(outer),
+                            holes.iter().map(|h| line_string(h)).collect(),
+                        )
+                    })
+                    .collect(),
+            ),
+            edges: Vec::new(),
+            grid: HashMap::new(),
+            origin: (south, west),
+            cell: (north - south).max(east - west) / GRID_CELLS,
+            bbox: (south, west, north, east),
+        };
+        for (i, &(a, b)) in edges.iter().enumerate() {
+            for key in area.cells(a, b) {
+                area.grid.entry(key).or_default().push(i);
+            }
+        }
+        area.edges = edges;
+        Some(area)
+    }
+
+    /// Box around the outline as (south, west, north, east)
+    pub fn bbox(&self) -> BBox {
+        self.bbox
+    }
+
+    /// Whether a (lat, lon) point lies inside; points on the outline do not
+    pub fn contains(&self, (lat, lon): (f64, f64)) -> bool {
+        self.polygons.contains(&Coord { x: lon, y: lat })
+    }
+
+    /// Split a polyline into the pieces inside the area, trimmed at the
+    /// outline and running the same way as the original
+    pub fn clip_polyline(&self, points: &[(f64, f64)]) -> Vec<Vec<(f64, f64)>> {
+        let mut pieces = Vec::new();
+        let Some(&first) = points.first() else {
+            return pieces;
+        };
+        let mut current: Option<Vec<(f64, f64)>> = None;
+        let mut inside = self.contains(first);
+
+        for segment in points.windows(2) {
+            let (p, q) = (segment[0], segment[1]);
+            let (cuts, touching) = self.crossings(p, q);
+
+            let mut t0 = 0.0;
+            for (k, t1) in cuts.iter().copied().chain([1.0]).enumerate() {
+                if k > 0 {
+                    inside = !inside;
+                }
+                if touching {
+                    // The count of crossings can't be trusted, so look
+                    inside = self.contains(lerp(p, q, (t0 + t1) / 2.0));
+                }
+                if inside && t1 > t0 {
+                    let piece = current.get_or_insert_with(|| vec![lerp(p, q, t0)]);
+                    let exit = lerp(p, q, t1);
+                    if piece.last() != Some(&exit) {
+                        piece.push(exit);
+                    }
+                } else if !inside {
+                    pieces.extend(current.take());
+                }
+                t0 = t1;
+            }
+        }
+        pieces.extend(current);
+
+        pieces.retain(|piece| piece.len() >= 2);
+        pieces
+    }
+
+    /// The parts of a polygon inside the area, each an outer ring and its
+    /// holes
+    ///
+    /// Polygons that don't cross the outline are kept or dropped whole;
+    /// only those straddling it go through the polygon intersection.
+    pub fn clip_polygon(&self, outer: &[(f64, f64)], holes: &[Ring]) -> Vec<(Ring, Vec<Ring>)> {
+        let Some(&first) = outer.first() else {
+            return Vec::new();
+        };
+        let crosses = std::iter::once(outer)
+            .chain(holes.iter().map(Vec::as_slice))
+            .flat_map(|ring| ring.windows(2))
+            .any(|pair| !self.crossings(pair[0], pair[1]).0.is_empty());
+        let polygon = Polygon::new(
+            line_string(outer),
+            holes.iter().map(|h| line_string(h)).collect(),
+        );
+        if !crosses {
+            if self.contains(first) {
+                return vec![(outer.to_vec(), holes.to_vec())];
+            }
+            // Outside the outline, unless the polygon wraps part of the area
+            let wraps = self
+                .polygons
+                .iter()
+                .filter_map(|p| p.exterior().0.first())
+                .any(|&c| polygon.contains(&c));
+            if !wraps {
+                return Vec::new();
+            }
+        }
+
+        polygon
+            .intersection(&self.polygons)
+            .into_iter()
+            .map(|piece| {
+                (
+                    ring(piece.exterior()),
+                    piece.interiors().iter().map(ring).collect(),
+                )
+            })
+            .collect()
+    }
+
+    /// Sorted parameters along p→q where it crosses the outline, and
+    /// whether any of them is close enough to an end or a vertex to be a
+    /// touch instead
+    fn crossings(&self, p: (f64, f64), q: (f64, f64)) -> (Vec<f64>, bool) {
+        let mut candidates: Vec<usize> = self
+            .cells(p, q)
+            .filter_map(|key| self.grid.get(&key))
+            .flatten()
+            .copied()
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        let cross = |(ax, ay): (f64, f64), (bx, by): (f64, f64)| ax * by - ay * bx;
+        let d = (q.0 - p.0, q.1 - p.1);
+        let mut cuts = Vec::new();
+        let mut touching = false;
+        for (a, b) in candidates.into_iter().map(|i| self.edges[i]) {
+            let e = (b.0 - a.0, b.1 - a.1);
+            let ap = (a.0 - p.0, a.1 - p.1);
+            let denominator = cross(d, e);
+            if denominator == 0.0 {
+                // Parallel; running along the outline counts as a touch
+                touching |= cross(ap, d) == 0.0;
+                continue;
+            }
+            let t = cross(ap, e) / denominator;
+            let s = cross(ap, d) / denominator;
+            if !(-TOUCH_TOLERANCE..=1.0 + TOUCH_TOLERANCE).contains(&t)
+                || !(-TOUCH_TOLERANCE..=1.0 + TOUCH_TOLERANCE).contains(&s)
+            {
+                continue;
+            }
+            let near = |v: f64| v.abs() <= TOUCH_TOLERANCE || (1.0 - v).abs() <= TOUCH_TOLERANCE;
+            touching |= near(t) || near(s);
+            // Edges share their ends; count a crossing at a vertex once
+            if s < 1.0 - TOUCH_TOLERANCE {
+                cuts.push(t.clamp(0.0, 1.0));
+            }
+        }
+        cuts.sort_by(f64::total_cmp);
+        (cuts, touching)
+    }
+
+    /// Grid cells covered by the box around a and b
+    fn cells(&self, a: (f64, f64), b: (f64, f64)) -> impl Iterator<Item = (i64, i64)> + use<> {
+        let key = |(lat, lon): (f64, f64)| {
+            (
+                ((lat - self.origin.0) / self.cell).floor() as i64,
+                ((lon - self.origin.1) / self.cell).floor() as i64,
+            )
+        };
+        let (r0, c0) = key((a.0.min(b.0), a.1.min(b.1)));
+        let (r1, c1) = key((a.0.max(b.0), a.1.max(b.1)));
+        // Nothing is indexed outside the outline's box
+        let last = GRID_CELLS as i64;
+        let (r0, c0) = (r0.max(0), c0.max(0));
+        let (r1, c1) = (r1.min(last), c1.min(last));
+        (r0..=r1).flat_map(move |r| (c0..=c1).map(move |c| (r, c)))
+    }
+}
+
+/// (lat, lon) ring as a geo line string of (lon, lat) coordinates
+fn line_string(ring: &[(f64, f64)]) -> LineString<f64> {
+    ring.iter()
+        .map(|&(lat, lon)| (lon, lat))
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// geo ring back to closed (lat, lon) points
+fn ring(line: &LineString<f64>) -> Ring {
+    line.0.iter().map(|c| (c.y, c.x)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A C-shaped district: the unit square less a notch cut in from the east
+    fn notched() -> Area {
+        let outer = vec![
+            (0.0, 0.0),
+            (0.0, 1.0),
+            (0.4, 1.0),
+            (0.4, 0.3),
+            (0.6, 0.3),
+            (0.6, 1.0),
+            (1.0, 1.0),
+            (1.0, 0.0),
+            (0.0, 0.0),
+        ];
+        Area::new(&[(outer, Vec::new())]).unwrap()
+    }
+
+    #[test]
+    fn test_area_clip_polyline() {
+        let area = notched();
+        assert_eq!(area.bbox(), (0.0, 0.0, 1.0, 1.0));
+        assert!(area.contains((0.2, 0.5)) && !area.contains((0.5, 0.5)));
+
+        // West to east into the notch, which opens to the east edge
+        let pieces = area.clip_polyline(&[(0.5, -0.5), (0.5, 2.0)]);
+        assert_eq!(pieces.len(), 1);
+        let close = |a: (f64, f64), b: (f64, f64)| (a.0 - b.0).abs() + (a.1 - b.1).abs() < 1e-9;
+        assert!(close(pieces[0][0], (0.5, 0.0)) && close(pieces[0][1], (0.5, 0.3)));
+
+        // North to south through both arms keeps its direction
+        let pieces = area.clip_polyline(&[(2.0, 0.8), (0.5, 0.8), (-1.0, 0.8)]);
+        assert_eq!(pieces.len(), 2);
+        assert!(close(pieces[0][0], (1.0, 0.8)) && close(pieces[0][1], (0.6, 0.8)));
+        assert!(close(pieces[1][0], (0.4, 0.8)) && close(pieces[1][1], (0.0, 0.8)));
+
+        // Passing through a corner of the outline
+        let pieces = area.clip_polyline(&[(0.2, 0.2), (0.4, 0.3), (0.5, 0.35)]);
+        assert_eq!(pieces.len(), 1);
+        assert_eq!(pieces[0].len(), 2);
+        assert!(area.clip_polyline(&[(2.0, 2.0), (3.0, 3.0)]).is_empty());
+    }
+
+    #[test]
+    fn test_area_clip_polygon() {
+        let area = notched();
+        let square = |lat: f64, lon: f64, size: f64| {
+            vec![
+                (lat, lon),
+                (lat, lon + size),
+                (lat + size, lon + size),
+                (lat + size, lon),
+                (lat, lon),
+            ]
+        };
+
+        let inside = square(0.1, 0.1, 0.1);
+        assert_eq!(area.clip_polygon(&inside, &[]), vec![(inside, Vec::new())]);
+        assert!(area.clip_polygon(&square(0.45, 0.5, 0.1), &[]).is_empty());
+        assert!(area.clip_polygon(&square(2.0, 2.0, 1.0), &[]).is_empty());
+
+        // Into the notch, along an arm, and across both arms
+        let band = vec![
+            (0.45, 0.2),
+            (0.45, 0.9),
+            (0.55, 0.9),
+            (0.55, 0.2),
+            (0.45, 0.2),
+        ];
+        assert_eq!(area.clip_polygon(&band, &[]).len(), 1);
+        let band = vec![
+            (0.7, -0.2),
+            (0.7, 1.2),
+            (0.8, 1.2),
+            (0.8, -0.2),
+            (0.7, -0.2),
+        ];
+        assert_eq!(area.clip_polygon(&band, &[]).len(), 1);
+        let band = vec![
+            (-0.2, 0.8),
+            (-0.2, 0.9),
+            (1.2, 0.9),
+            (1.2, 0.8),
+            (-0.2, 0.8),
+        ];
+        assert_eq!(area.clip_polygon(&band, &[]).len(), 2);
+
+        // A polygon wrapping the whole area comes back as the area
+        let wrapped = area.clip_polygon(&square(-1.0, -1.0, 3.0), &[]);
+        assert_eq!(wrapped.len(), 1);
+        assert_eq!(wrapped[0].0.len(), 9);
+    }
+}
@@ -0,0 +1,294 @@
+//! Geographic bounding boxes and clipping against them
+//!
+//! Boxes are `(south, west, north, east)` in degrees, the order Overpass
+//! expects. Points are `(lat, lon)` like the rest of the domain layer.
+
+use anyhow::{Result, bail};
+
+use super::clip::{clip_polyline_with, lerp};
+
+/// Bounding box in degrees: (south, west, north, east)
+pub type BBox = (f64, f64, f64, f64);
+
+const METERS_PER_DEGREE: f64 = 111_000.0;
+
+/// Check that a box is non-empty and within valid coordinate ranges
+pub fn validate_bbox((south, west, north, east): BBox) -> Result<()> {
+    if !(-90.0..=90.0).contains(&south) || !(-90.0..=90.0).contains(&north) {
+        bail!(
+            "Invalid bounding box: latitudes must be within -90..90 (got south={}, north={})",
+            south,
+            north
+        );
+    }
+    if !(-180.0..=180.0).contains(&west) || !(-180.0..=180.0).contains(&east) {
+        bail!(
+            "Invalid bounding box: longitudes must be within -180..180 (got west={}, east={})",
+            west,
+            east
+        );
+    }
+    if south >= north {
+        bail!(
+            "Invalid bounding box: south ({}) must be less than north ({})",
+            south,
+            north
+        );
+    }
+    if west >= east {
+        bail!(
+            "Invalid bounding box: west ({}) must be less than east ({})",
+            west,
+            east
+        );
+    }
+    Ok(())
+}
+
+/// Check that a map center is a real coordinate
+pub fn validate_center(lat: f64, lon: f64) -> Result<()> {
+    if !(-90.0..=90.0).contains(&lat) {
+        bail!("Invalid latitude {}: must be within -90..90", lat);
+    }
+    if !(-180.0..=180.0).contains(&lon) {
+        bail!("Invalid longitude {}: must be within -180..180", lon);
+    }
+    Ok(())
+}
+
+/// Parse `south,west,north,east` into a box
+pub fn parse_bbox(s: &str) -> Result<BBox> {
+    let values: Vec<f64> = s
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| anyhow::anyhow!("Invalid bounding box '{}': {}", s, e))?;
+    let [south, west, north, east] = values[..] else {
+        bail!(
+            "Invalid bounding box '{}': expected south,west,north,east",
+            s
+        );
+    };
+    Ok((south, west, north, east))
+}
+
+/// Bring a longitude back into -180..180 if it has run past the antimeridian
+pub fn wrap_lon(lon: f64) -> f64 {
+    if (-180.0..=180.0).contains(&lon) {
+        lon
+    } else {
+        (lon + 180.0).rem_euclid(360.0) - 180.0
+    }
+}
+
+/// Whether a box wraps across the antimeridian, signalled by west > east
+pub fn crosses_antimeridian((_, west, _, east): BBox) -> bool {
+    west > east
+}
+
+/// A box as one or two boxes that each stay within -180..180
+pub fn split_antimeridian(bbox: BBox) -> Vec<BBox> {
+    let (south, west, north, east) = bbox;
+    if crosses_antimeridian(bbox) {
+        vec![(south, west, north, 180.0), (south, -180.0, north, east)]
+    } else {
+        vec![bbox]
+    }
+}
+
+/// A box with its east edge pushed past 180 when it crosses the
+/// antimeridian, so that west < east holds for arithmetic on it
+pub fn unwrap_antimeridian(bbox: BBox) -> BBox {
+    let (south, west, north, east) = bbox;
+    if crosses_antimeridian(bbox) {
+        (south, west, north, east + 360.0)
+    } else {
+        bbox
+    }
+}
+
+/// Center point (lat, lon) of a box
+pub fn bbox_center(bbox: BBox) -> (f64, f64) {
+    let (south, west, north, east) = unwrap_antimeridian(bbox);
+    ((south + north) / 2.0, wrap_lon((west + east) / 2.0))
+}
+
+/// Half the longer side of a box in meters, the radius that would cover it
+pub fn bbox_radius_m(bbox: BBox) -> u32 {
+    let (south, west, north, east) = unwrap_antimeridian(bbox);
+    let mid_lat = ((south + north) / 2.0).to_radians();
+    let height = (north - south) * METERS_PER_DEGREE;
+    let width = (east - west) * METERS_PER_DEGREE * mid_lat.cos();
+    (height.max(width) / 2.0).round() as u32
+}
+
+/// Whether a point lies strictly inside a box
+pub fn contains((lat, lon): (f64, f64), (south, west, north, east): BBox) -> bool {
+    lat > south && lat < north && lon > west && lon < east
+}
+
+/// Liang-Barsky clip of segment p→q; returns the inside parameter range
+pub fn clip_segment(p: (f64, f64), q: (f64, f64), bbox: BBox) -> Option<(f64, f64)> {
+    let (south, west, north, east) = bbox;
+    let d_lat = q.0 - p.0;
+    let d_lon = q.1 - p.1;
+    let mut t0: f64 = 0.0;
+    let mut t1: f64 = 1.0;
+
+    for (denom, num) in [
+        (-d_lon, p.1 - west),
+        (d_lon, east - p.1),
+        (-d_lat, p.0 - south),
+        (d_lat, north - p.0),
+    ] {
+        if denom == 0.0 {
+            if num < 0.0 {
+                return None;
+            }
+            continue;
+        }
+        let t = num / denom;
+        if denom < 0.0 {
+            t0 = t0.max(t);
+        } else {
+            t1 = t1.min(t);
+        }
+    }
+
+    (t0 < t1).then_some((t0, t1))
+}
+
+/// Split a polyline into the pieces lying inside a box
+pub fn clip_polyline(points: &[(f64, f64)], bbox: BBox) -> Vec<Vec<(f64, f64)>> {
+    clip_polyline_with(points, |p, q| clip_segment(p, q, bbox))
+}
+
+/// Clip a closed ring to a box (Sutherland-Hodgman)
+///
+/// The result is closed again; rings that fall entirely outside come back
+/// empty.
+pub fn clip_ring(ring: &[(f64, f64)], bbox: BBox) -> Vec<(f64, f64)> {
+    let (south, west, north, east) = bbox;
+    let mut output: Vec<(f64, f64)> = ring.to_vec();
+    if output.len() > 1 && output.first() == output.last() {
+        output.pop();
+    }
+
+    // Clip against west, east, south and north edges in turn. Each edge is
+    // (coordinate index, limit, whether the inside is at or above the limit)
+    let edges = [
+        (1, west, true),
+        (1, east, false),
+        (0, south, true),
+        (0, north, false),
+    ];
+    for (axis, limit, keep_above) in edges {
+        let coord = |p: (f64, f64)| if axis == 0 { p.0 } else { p.1 };
+        let is_inside = |p: (f64, f64)| {
+            if keep_above {
+                coord(p) >= limit
+            } else {
+                coord(p) <= limit
+            }
+        };
+        let crossing = |p, q| lerp(p, q, (limit - coord(p)) / (coord(q) - coord(p)));
+
+        let input = std::mem::take(&mut output);
+        for (i, &q) in input.iter().enumerate() {
+            let p = input[(i + input.len() - 1) % input.len()];
+            match (is_inside(p), is_inside(q)) {
+                (true, true) => output.push(q),
+                (true, false) => output.push(crossing(p, q)),
+                (false, true) => {
+                    output.push(crossing(p, q));
+                    output.push(q);
+                }
+                (false, false) => {}
+            }
+        }
+    }
+
+    if output.len() < 3 {
+        return Vec::new();
+    }
+    output.push(output[0]);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BBOX: BBox = (0.0, 0.0, 1.0, 1.0);
+
+    #[test]
+    fn test_parse_bbox() {
+        let bbox = parse_bbox("37.7, -122.5, 37.8,-122.4").unwrap();
+        assert_eq!(bbox, (37.7, -122.5, 37.8, -122.4));
+        assert!(parse_bbox("1,2,3").is_err());
+        assert!(parse_bbox("a,b,c,d").is_err());
+    }
+
+    #[test]
+    fn test_validate_bbox() {
+        assert!(validate_bbox(BBOX).is_ok());
+        assert!(validate_bbox((1.0, 0.0, 0.0, 1.0)).is_err());
+        assert!(validate_bbox((0.0, 1.0, 1.0, 0.0)).is_err());
+        assert!(validate_bbox((0.0, 0.0, 95.0, 1.0)).is_err());
+    }
+
+    #[test]
+    fn test_antimeridian_box() {
+        let bbox = (-18.0, 179.5, -17.0, -179.5);
+        assert!(crosses_antimeridian(bbox));
+        assert_eq!(
+            split_antimeridian(bbox),
+            vec![(-18.0, 179.5, -17.0, 180.0), (-18.0, -180.0, -17.0, -179.5)]
+        );
+        let (_, lon) = bbox_center(bbox);
+        assert!((lon.abs() - 180.0).abs() < 1e-9);
+        // One degree wide, not 359
+        assert!(bbox_radius_m(bbox) < 60_000);
+
+        assert_eq!(split_antimeridian(BBOX), vec![BBOX]);
+        assert_eq!(wrap_lon(181.0), -179.0);
+        assert_eq!(wrap_lon(-540.5), 179.5);
+        assert_eq!(wrap_lon(180.0), 180.0);
+    }
+
+    #[test]
+    fn test_validate_center() {
+        assert!(validate_center(-33.87, 151.21).is_ok());
+        assert!(validate_center(90.0, -180.0).is_ok());
+        assert!(validate_center(500.0, 0.0).is_err());
+        assert!(validate_center(0.0, 9999.0).is_err());
+        assert!(validate_center(f64::NAN, 0.0).is_err());
+    }
+
+    #[test]
+    fn test_bbox_radius() {
+        // 0.2 degrees of latitude is ~22.2km, so the radius is ~11.1km
+        let radius = bbox_radius_m((0.0, 0.0, 0.2, 0.1));
+        assert!((radius as i64 - 11_100).abs() < 10);
+    }
+
+    #[test]
+    fn test_clip_polyline() {
+        let line = [(0.5, -1.0), (0.5, 2.0), (2.0, 2.0), (0.2, 0.5)];
+        let pieces = clip_polyline(&line, BBOX);
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces[0], vec![(0.5, 0.0), (0.5, 1.0)]);
+        assert!(contains(pieces[1][1], BBOX));
+    }
+
+    #[test]
+    fn test_clip_ring() {
+        let ring = [(0.5, 0.5), (0.5, 2.0), (2.0, 2.0), (2.0, 0.5), (0.5, 0.5)];
+        let clipped = clip_ring(&ring, BBOX);
+        assert_eq!(clipped.len(), 5);
+        assert!(clipped.iter().all(|&(lat, lon)| lat <= 1.0 && lon <= 1.0));
+
+        let outside = [(2.0, 2.0), (2.0, 3.0), (3.0, 3.0), (2.0, 2.0)];
+        assert!(clip_ring(&outside, BBOX).is_empty());
+    }
+}
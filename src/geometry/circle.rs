@@ -0,0 +1,112 @@
+//! Clipping against a circle in the projected plane (meters)
+
+use super::clip::{clip_polyline_with, clip_ring_convex};
+
+/// Number of edges in the polygon approximating the circle for ring clipping
+const RING_CLIP_SEGMENTS: usize = 256;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Circle {
+    pub center: (f64, f64),
+    pub radius: f64,
+}
+
+impl Circle {
+    pub fn new(center: (f64, f64), radius: f64) -> Self {
+        Self { center, radius }
+    }
+
+    pub fn contains(&self, (x, y): (f64, f64)) -> bool {
+        let dx = x - self.center.0;
+        let dy = y - self.center.1;
+        dx * dx + dy * dy <= self.radius * self.radius
+    }
+
+    /// Parameter range of segment p→q that lies inside the circle
+    pub fn clip_segment(&self, p: (f64, f64), q: (f64, f64)) -> Option<(f64, f64)> {
+        let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+        let (fx, fy) = (p.0 - self.center.0, p.1 - self.center.1);
+
+        let a = dx * dx + dy * dy;
+        if a == 0.0 {
+            return self.contains(p).then_some((0.0, 1.0));
+        }
+        let b = 2.0 * (fx * dx + fy * dy);
+        let c = fx * fx + fy * fy - self.radius * self.radius;
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant <= 0.0 {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        let t0 = ((-b - root) / (2.0 * a)).max(0.0);
+        let t1 = ((-b + root) / (2.0 * a)).min(1.0);
+        (t0 < t1).then_some((t0, t1))
+    }
+
+    /// Split a polyline into the pieces inside the circle, trimmed at its edge
+    pub fn clip_polyline(&self, points: &[(f64, f64)]) -> Vec<Vec<(f64, f64)>> {
+        clip_polyline_with(points, |p, q| self.clip_segment(p, q))
+    }
+
+    /// Counter-clockwise polygon approximating the circle, closed
+    pub fn polygon(&self, segments: usize) -> Vec<(f64, f64)> {
+        (0..=segments)
+            .map(|i| {
+                let angle = std::f64::consts::TAU * (i % segments) as f64 / segments as f64;
+                (
+                    self.center.0 + self.radius * angle.cos(),
+                    self.center.1 + self.radius * angle.sin(),
+                )
+            })
+            .collect()
+    }
+
+    /// Clip a closed ring to the circle (Sutherland-Hodgman against a fine
+    /// polygon approximation). Returns an empty ring if nothing remains.
+    pub fn clip_ring(&self, ring: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        clip_ring_convex(ring, &self.polygon(RING_CLIP_SEGMENTS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clip_polyline_trims_at_edge() {
+        let circle = Circle::new((0.0, 0.0), 10.0);
+        let pieces = circle.clip_polyline(&[(-20.0, 0.0), (20.0, 0.0)]);
+        assert_eq!(pieces.len(), 1);
+        assert!((pieces[0][0].0 + 10.0).abs() < 1e-9);
+        assert!((pieces[0][1].0 - 10.0).abs() < 1e-9);
+
+        assert!(
+            circle
+                .clip_polyline(&[(-20.0, 15.0), (20.0, 15.0)])
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_clip_ring_stays_inside() {
+        let circle = Circle::new((0.0, 0.0), 10.0);
+        let square = [
+            (0.0, 0.0),
+            (20.0, 0.0),
+            (20.0, 20.0),
+            (0.0, 20.0),
+            (0.0, 0.0),
+        ];
+        let clipped = circle.clip_ring(&square);
+        assert!(clipped.len() > 4);
+        assert!(
+            clipped
+                .iter()
+                .all(|&(x, y)| (x * x + y * y).sqrt() <= 10.0 + 1e-6)
+        );
+
+        let outside = [(20.0, 20.0), (30.0, 20.0), (30.0, 30.0), (20.0, 20.0)];
+        assert!(circle.clip_ring(&outside).is_empty());
+    }
+}
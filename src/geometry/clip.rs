@@ -0,0 +1,104 @@
+//! Shared helpers for clipping polylines against a region
+
+/// Split a polyline into the pieces inside a region
+///
+/// `clip_segment` returns the parameter range `(t0, t1)` of segment p→q that
+/// lies inside the region, or `None` if the segment misses it entirely.
+pub fn clip_polyline_with(
+    points: &[(f64, f64)],
+    clip_segment: impl Fn((f64, f64), (f64, f64)) -> Option<(f64, f64)>,
+) -> Vec<Vec<(f64, f64)>> {
+    let mut pieces = Vec::new();
+    let mut current: Option<Vec<(f64, f64)>> = None;
+
+    for segment in points.windows(2) {
+        let (p, q) = (segment[0], segment[1]);
+        let Some((t0, t1)) = clip_segment(p, q) else {
+            pieces.extend(current.take());
+            continue;
+        };
+        let entry = lerp(p, q, t0);
+        let exit = lerp(p, q, t1);
+
+        let piece = current.get_or_insert_with(|| vec![entry]);
+        if piece.last() != Some(&exit) {
+            piece.push(exit);
+        }
+        if t1 < 1.0 {
+            pieces.extend(current.take());
+        }
+    }
+    pieces.extend(current);
+
+    pieces.retain(|piece| piece.len() >= 2);
+    pieces
+}
+
+pub fn lerp(p: (f64, f64), q: (f64, f64), t: f64) -> (f64, f64) {
+    (p.0 + (q.0 - p.0) * t, p.1 + (q.1 - p.1) * t)
+}
+
+/// Parameter range of segment p→q inside a convex polygon (Cyrus-Beck)
+///
+/// `polygon` is counter-clockwise and closed, as returned by the `polygon`
+/// methods of the clip regions.
+pub fn clip_segment_convex(
+    p: (f64, f64),
+    q: (f64, f64),
+    polygon: &[(f64, f64)],
+) -> Option<(f64, f64)> {
+    let (mut t0, mut t1) = (0.0_f64, 1.0_f64);
+    for edge in polygon.windows(2) {
+        let (a, b) = (edge[0], edge[1]);
+        // Left of a→b is inside; the side is linear along the segment
+        let side = |p: (f64, f64)| (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0);
+        let (sp, sq) = (side(p), side(q));
+        if sp < 0.0 && sq < 0.0 {
+            return None;
+        }
+        if sp < 0.0 {
+            t0 = t0.max(sp / (sp - sq));
+        } else if sq < 0.0 {
+            t1 = t1.min(sp / (sp - sq));
+        }
+    }
+    (t0 < t1).then_some((t0, t1))
+}
+
+/// Clip a closed ring to a convex polygon (Sutherland-Hodgman)
+///
+/// `polygon` is counter-clockwise and closed. The result is closed again;
+/// rings that fall entirely outside come back empty.
+pub fn clip_ring_convex(ring: &[(f64, f64)], polygon: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut output: Vec<(f64, f64)> = ring.to_vec();
+    if output.len() > 1 && output.first() == output.last() {
+        output.pop();
+    }
+
+    for edge in polygon.windows(2) {
+        let (a, b) = (edge[0], edge[1]);
+        // Left of a→b is inside for a counter-clockwise clip polygon
+        let side = |p: (f64, f64)| (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0);
+        let crossing = |p, q| lerp(p, q, side(p) / (side(p) - side(q)));
+
+        let input = std::mem::take(&mut output);
+        for (i, &q) in input.iter().enumerate() {
+            let p = input[(i + input.len() - 1) % input.len()];
+            match (side(p) >= 0.0, side(q) >= 0.0) {
+                (true, true) => output.push(q),
+                (true, false) => output.push(crossing(p, q)),
+                (false, true) => {
+                    output.push(crossing(p, q));
+                    output.push(q);
+                }
+                (false, false) => {}
+            }
+        }
+    }
+
+    if output.len() < 3 {
+        return Vec::new();
+    }
+    output.push(output[0]);
+    output
+}
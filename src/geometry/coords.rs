@@ -0,0 +1,144 @@
+//! Parsing latitudes and longitudes written out by hand
+//!
+//! Besides plain decimal degrees, coordinates may carry a hemisphere letter
+//! (`37.7749N`, `W122.4194`) or be given in degrees, minutes and seconds
+//! (`37°46'30"N`, `37 46 30.5 N`, `122:25:10W`).
+
+use anyhow::{Result, bail};
+
+/// Parse a latitude into decimal degrees, north positive
+pub fn parse_latitude(s: &str) -> Result<f64> {
+    parse_coordinate(s, ('N', 'S'), 90.0, "latitude")
+}
+
+/// Parse a longitude into decimal degrees, east positive
+pub fn parse_longitude(s: &str) -> Result<f64> {
+    parse_coordinate(s, ('E', 'W'), 180.0, "longitude")
+}
+
+fn parse_coordinate(
+    s: &str,
+    (positive, negative): (char, char),
+    limit: f64,
+    kind: &str,
+) -> Result<f64> {
+    let trimmed = s.trim();
+    let upper = trimmed.to_uppercase();
+    let (body, hemisphere) = match (upper.chars().next(), upper.chars().last()) {
+        (Some(c), _) if c == positive || c == negative => (&upper[1..], Some(c)),
+        (_, Some(c)) if c == positive || c == negative => (&upper[..upper.len() - 1], Some(c)),
+        _ => (upper.as_str(), None),
+    };
+    if body.contains(|c: char| c.is_ascii_alphabetic()) {
+        bail!(
+            "Invalid {} '{}': expected decimal degrees, {}/{} hemisphere or degrees-minutes-seconds",
+            kind,
+            trimmed,
+            positive,
+            negative
+        );
+    }
+
+    let parts: Vec<&str> = body
+        .split(|c: char| c.is_whitespace() || "°º'\"′″:".contains(c))
+        .filter(|part| !part.is_empty())
+        .collect();
+    let numbers = parts
+        .iter()
+        .map(|part| part.parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| anyhow::anyhow!("Invalid {} '{}': not a number", kind, trimmed))?;
+    let (degrees, minutes, seconds) = match numbers[..] {
+        [d] => (d, 0.0, 0.0),
+        [d, m] => (d, m, 0.0),
+        [d, m, s] => (d, m, s),
+        _ => bail!(
+            "Invalid {} '{}': expected degrees, optionally followed by minutes and seconds",
+            kind,
+            trimmed
+        ),
+    };
+    if !(0.0..60.0).contains(&minutes) || !(0.0..60.0).contains(&seconds) {
+        bail!(
+            "Invalid {} '{}': minutes and seconds must be between 0 and 60",
+            kind,
+            trimmed
+        );
+    }
+    if numbers.len() > 1 && degrees.fract() != 0.0 {
+        bail!(
+            "Invalid {} '{}': degrees must be whole when minutes follow",
+            kind,
+            trimmed
+        );
+    }
+
+    let magnitude = degrees.abs() + minutes / 60.0 + seconds / 3600.0;
+    let value = match hemisphere {
+        Some(_) if degrees.is_sign_negative() => bail!(
+            "Invalid {} '{}': use either a minus sign or a hemisphere letter, not both",
+            kind,
+            trimmed
+        ),
+        Some(c) if c == negative => -magnitude,
+        Some(_) => magnitude,
+        None => magnitude.copysign(degrees),
+    };
+    if !value.is_finite() || value.abs() > limit {
+        bail!(
+            "Invalid {} '{}': must be within -{}..{}",
+            kind,
+            trimmed,
+            limit,
+            limit
+        );
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_parse_coordinates() {
+        assert!(close(parse_latitude("37.7749").unwrap(), 37.7749));
+        assert!(close(parse_longitude("-122.4194").unwrap(), -122.4194));
+        assert!(close(parse_latitude("37.7749N").unwrap(), 37.7749));
+        assert!(close(parse_longitude("W122.4194").unwrap(), -122.4194));
+        assert!(close(
+            parse_latitude("37°46'30\"N").unwrap(),
+            37.0 + 46.0 / 60.0 + 30.0 / 3600.0
+        ));
+        assert!(close(
+            parse_latitude("33° 52′ 4.5″ s").unwrap(),
+            -(33.0 + 52.0 / 60.0 + 4.5 / 3600.0)
+        ));
+        assert!(close(
+            parse_longitude("122:25:10W").unwrap(),
+            -(122.0 + 25.0 / 60.0 + 10.0 / 3600.0)
+        ));
+        assert!(close(parse_longitude("-0 30 0").unwrap(), -0.5));
+        assert!(close(
+            parse_latitude("51 30.5N").unwrap(),
+            51.0 + 30.5 / 60.0
+        ));
+    }
+
+    #[test]
+    fn test_parse_coordinates_errors() {
+        // Longitude hemisphere on a latitude
+        assert!(parse_latitude("37.7E").is_err());
+        assert!(parse_latitude("91N").is_err());
+        assert!(parse_longitude("181").is_err());
+        assert!(parse_latitude("37°75'N").is_err());
+        assert!(parse_latitude("-37.5S").is_err());
+        assert!(parse_latitude("37.5°30'N").is_err());
+        assert!(parse_latitude("north").is_err());
+        assert!(parse_latitude("").is_err());
+    }
+}
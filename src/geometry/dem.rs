@@ -0,0 +1,346 @@
+//! Elevation models for terrain relief
+//!
+//! Reads SRTM `.hgt` tiles and single-band GeoTIFFs in geographic (lat/lon)
+//! coordinates, as published for SRTM and Copernicus. Projected GeoTIFFs are
+//! rejected rather than silently misplaced.
+
+use anyhow::{Context, Result, bail};
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+use tiff::ColorType;
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::tags::Tag;
+
+/// Value SRTM tiles use for voids in the radar coverage
+const HGT_VOID: f32 = -32768.0;
+
+/// GeoTIFF key for the coordinate system kind, 2 for geographic
+const GT_MODEL_TYPE_KEY: u16 = 1024;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+/// GeoTIFF key for whether a tie point is a pixel corner or center
+const GT_RASTER_TYPE_KEY: u16 = 1025;
+const RASTER_PIXEL_IS_POINT: u16 = 2;
+
+/// Fraction of a sample spacing a point may lie outside the grid and still
+/// count as on its edge, for rounding in the degree arithmetic
+const EDGE_TOLERANCE: f64 = 1e-6;
+
+/// Grid of elevations in meters on a regular lat/lon raster
+#[derive(Debug, Clone)]
+pub struct Dem {
+    columns: usize,
+    rows: usize,
+    /// Longitude and latitude of the center of the top left sample
+    origin: (f64, f64),
+    /// Degrees between neighboring samples, east and south
+    step: (f64, f64),
+    /// Row by row from the north, NaN where there is no data
+    elevations: Vec<f32>,
+}
+
+impl Dem {
+    /// Elevation grid of `columns` x `rows` samples, the first at `origin`
+    /// as (lon, lat) and the rest `step` degrees east and south of it
+    pub fn new(
+        columns: usize,
+        rows: usize,
+        origin: (f64, f64),
+        step: (f64, f64),
+        elevations: Vec<f32>,
+    ) -> Result<Self> {
+        if columns < 2 || rows < 2 || elevations.len() != columns * rows {
+            bail!(
+                "Elevation grid of {}x{} samples has {} values",
+                columns,
+                rows,
+                elevations.len()
+            );
+        }
+        if !(step.0 > 0.0 && step.1 > 0.0) {
+            bail!("Elevation grid spacing must be positive");
+        }
+        Ok(Self {
+            columns,
+            rows,
+            origin,
+            step,
+            elevations,
+        })
+    }
+
+    /// Load an SRTM `.hgt` tile or a GeoTIFF, by file extension
+    pub fn load(path: &Path) -> Result<Self> {
+        let is_hgt = path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("hgt"));
+        if is_hgt {
+            load_hgt(path)
+        } else {
+            load_geotiff(path)
+        }
+        .with_context(|| format!("Failed to read elevation model {}", path.display()))
+    }
+
+    /// Elevation in meters at (`lat`, `lon`), interpolated between the four
+    /// nearest samples
+    ///
+    /// `None` outside the grid or where every nearby sample is missing; a
+    /// partial void is filled from the samples that have data.
+    pub fn elevation(&self, lat: f64, lon: f64) -> Option<f64> {
+        let col = (lon - self.origin.0) / self.step.0;
+        let row = (self.origin.1 - lat) / self.step.1;
+        let max_col = (self.columns - 1) as f64;
+        let max_row = (self.rows - 1) as f64;
+        let within = |v: f64, max: f64| (-EDGE_TOLERANCE..=max + EDGE_TOLERANCE).contains(&v);
+        if !within(col, max_col) || !within(row, max_row) {
+            return None;
+        }
+        let (col, row) = (col.clamp(0.0, max_col), row.clamp(0.0, max_row));
+
+        let c0 = (col.floor() as usize).min(self.columns - 2);
+        let r0 = (row.floor() as usize).min(self.rows - 2);
+        let (fx, fy) = (col - c0 as f64, row - r0 as f64);
+        let corners = [
+            (r0, c0, (1.0 - fx) * (1.0 - fy)),
+            (r0, c0 + 1, fx * (1.0 - fy)),
+            (r0 + 1, c0, (1.0 - fx) * fy),
+            (r0 + 1, c0 + 1, fx * fy),
+        ];
+        let (sum, weight) = corners
+            .iter()
+            .filter_map(|&(r, c, w)| {
+                let z = self.elevations[r * self.columns + c];
+                (!z.is_nan()).then_some((z as f64 * w, w))
+            })
+            .fold((0.0, 0.0), |(s, t), (z, w)| (s + z, t + w));
+        (weight > 1e-9).then(|| sum / weight)
+    }
+}
+
+/// SRTM tile: big-endian 16-bit samples on a square grid, its south west
+/// corner given by a name like `N37W122.hgt`
+fn load_hgt(path: &Path) -> Result<Dem> {
+    let name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_uppercase())
+        .unwrap_or_default();
+    let (lat, lon) = parse_hgt_name(&name).with_context(|| {
+        format!(
+            "SRTM tile name '{}' does not give its corner, like N37W122",
+            name
+        )
+    })?;
+
+    let bytes = fs::read(path)?;
+    let samples = bytes.len() / 2;
+    let size = (samples as f64).sqrt().round() as usize;
+    if size < 2 || size * size * 2 != bytes.len() {
+        bail!(
+            "{} bytes is not a square grid of 16-bit samples",
+            bytes.len()
+        );
+    }
+    let elevations = bytes
+        .chunks_exact(2)
+        .map(|pair| {
+            let z = i16::from_be_bytes([pair[0], pair[1]]) as f32;
+            if z == HGT_VOID { f32::NAN } else { z }
+        })
+        .collect();
+    // Edge samples lie on the tile border, shared with the neighbors
+    let step = 1.0 / (size - 1) as f64;
+    Dem::new(
+        size,
+        size,
+        (lon as f64, lat as f64 + 1.0),
+        (step, step),
+        elevations,
+    )
+}
+
+/// South west corner of an SRTM tile as (lat, lon) from its name
+fn parse_hgt_name(name: &str) -> Option<(i32, i32)> {
+    let lat_sign = match name.get(0..1)? {
+        "N" => 1,
+        "S" => -1,
+        _ => return None,
+    };
+    let lat: i32 = name.get(1..3)?.parse().ok()?;
+    let lon_sign = match name.get(3..4)? {
+        "E" => 1,
+        "W" => -1,
+        _ => return None,
+    };
+    let lon: i32 = name.get(4..7)?.parse().ok()?;
+    Some((lat_sign * lat, lon_sign * lon))
+}
+
+fn load_geotiff(path: &Path) -> Result<Dem> {
+    let file = File::open(path)?;
+    let mut decoder = Decoder::new(BufReader::new(file)).context("Not a TIFF image")?;
+    match decoder.colortype()? {
+        ColorType::Gray(_) => {}
+        other => bail!("Expected a single band of elevations, found {:?}", other),
+    }
+    let (columns, rows) = decoder.dimensions()?;
+
+    let scale = decoder
+        .find_tag(Tag::ModelPixelScaleTag)?
+        .map(|v| v.into_f64_vec())
+        .transpose()?;
+    let tiepoint = decoder
+        .find_tag(Tag::ModelTiepointTag)?
+        .map(|v| v.into_f64_vec())
+        .transpose()?;
+    let (Some(scale), Some(tiepoint)) = (scale, tiepoint) else {
+        bail!("Not a GeoTIFF: the image has no pixel scale or tie point");
+    };
+    if scale.len() < 2 || tiepoint.len() < 6 {
+        bail!("Malformed GeoTIFF pixel scale or tie point");
+    }
+
+    let geokeys = decoder
+        .find_tag(Tag::GeoKeyDirectoryTag)?
+        .map(|v| v.into_u16_vec())
+        .transpose()?
+        .unwrap_or_default();
+    let geokey = |key: u16| {
+        geokeys
+            .get(4..)
+            .unwrap_or_default()
+            .chunks_exact(4)
+            .find(|entry| entry[0] == key && entry[1] == 0)
+            .map(|entry| entry[3])
+    };
+    if geokey(GT_MODEL_TYPE_KEY).is_some_and(|kind| kind != MODEL_TYPE_GEOGRAPHIC) {
+        bail!("Only GeoTIFFs in latitude and longitude are supported; reproject it to EPSG:4326");
+    }
+    // Tie points on pixel corners sit half a pixel off the sample centers
+    let half = if geokey(GT_RASTER_TYPE_KEY) == Some(RASTER_PIXEL_IS_POINT) {
+        0.0
+    } else {
+        0.5
+    };
+    let step = (scale[0], scale[1]);
+    let origin = (
+        tiepoint[3] + (half - tiepoint[0]) * step.0,
+        tiepoint[4] - (half - tiepoint[1]) * step.1,
+    );
+    if origin.0.abs() > 360.0 || origin.1.abs() > 90.0 {
+        bail!("Only GeoTIFFs in latitude and longitude are supported; reproject it to EPSG:4326");
+    }
+
+    let nodata = decoder
+        .find_tag(Tag::GdalNodata)?
+        .map(|v| v.into_string())
+        .transpose()?
+        .and_then(|s| s.trim_matches(char::from(0)).trim().parse::<f64>().ok());
+    let mut elevations: Vec<f32> = match decoder.read_image()? {
+        DecodingResult::U8(v) => v.into_iter().map(f32::from).collect(),
+        DecodingResult::U16(v) => v.into_iter().map(f32::from).collect(),
+        DecodingResult::U32(v) => v.into_iter().map(|z| z as f32).collect(),
+        DecodingResult::U64(v) => v.into_iter().map(|z| z as f32).collect(),
+        DecodingResult::I8(v) => v.into_iter().map(f32::from).collect(),
+        DecodingResult::I16(v) => v.into_iter().map(f32::from).collect(),
+        DecodingResult::I32(v) => v.into_iter().map(|z| z as f32).collect(),
+        DecodingResult::I64(v) => v.into_iter().map(|z| z as f32).collect(),
+        DecodingResult::F32(v) => v,
+        DecodingResult::F64(v) => v.into_iter().map(|z| z as f32).collect(),
+    };
+    if let Some(nodata) = nodata {
+        for z in elevations.iter_mut().filter(|z| **z as f64 == nodata) {
+            *z = f32::NAN;
+        }
+    }
+    Dem::new(columns as usize, rows as usize, origin, step, elevations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_dem_elevation() {
+        // 3x3 samples a tenth of a degree apart, rising to the east
+        let dem = Dem::new(
+            3,
+            3,
+            (10.0, 50.2),
+            (0.1, 0.1),
+            vec![0.0, 10.0, 20.0, 0.0, f32::NAN, 20.0, 0.0, 10.0, 20.0],
+        )
+        .unwrap();
+        let close = |a: Option<f64>, b: f64| (a.unwrap() - b).abs() < 1e-6;
+        assert!(close(dem.elevation(50.2, 10.0), 0.0));
+        assert!(close(dem.elevation(50.0, 10.15), 15.0));
+        // The void in the middle is filled from its neighbors
+        assert!(close(dem.elevation(50.15, 10.05), 10.0 / 3.0));
+        assert!(dem.elevation(50.25, 10.1).is_none());
+        assert!(dem.elevation(50.1, 9.99).is_none());
+    }
+
+    #[test]
+    fn test_load_hgt() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("S34E151.hgt");
+        // 3x3 tile, elevation row * 100 + column, with a void
+        let mut bytes = Vec::new();
+        for row in 0..3i16 {
+            for col in 0..3i16 {
+                let z = if (row, col) == (2, 2) {
+                    i16::MIN
+                } else {
+                    row * 100 + col
+                };
+                bytes.extend(z.to_be_bytes());
+            }
+        }
+        fs::write(&path, bytes).unwrap();
+
+        let dem = Dem::load(&path).unwrap();
+        // The first row is the north edge of the tile
+        assert_eq!(dem.elevation(-33.0, 151.0), Some(0.0));
+        assert_eq!(dem.elevation(-33.5, 151.5), Some(101.0));
+        assert_eq!(dem.elevation(-34.0, 151.5), Some(201.0));
+        assert!(dem.elevation(-34.5, 151.5).is_none());
+
+        let bad = dir.path().join("tile.hgt");
+        fs::write(&bad, [0u8; 18]).unwrap();
+        assert!(Dem::load(&bad).is_err());
+        assert_eq!(parse_hgt_name("N37W122"), Some((37, -122)));
+    }
+
+    #[test]
+    fn test_load_geotiff() {
+        use tiff::encoder::{TiffEncoder, colortype::Gray32Float};
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("dem.tif");
+        let mut encoder = TiffEncoder::new(File::create(&path).unwrap()).unwrap();
+        let mut image = encoder.new_image::<Gray32Float>(2, 2).unwrap();
+        // Pixel corners, so the first sample center is half a pixel in
+        image
+            .encoder()
+            .write_tag(Tag::ModelPixelScaleTag, &[0.5f64, 0.5, 0.0][..])
+            .unwrap();
+        image
+            .encoder()
+            .write_tag(
+                Tag::ModelTiepointTag,
+                &[0.0f64, 0.0, 0.0, 8.0, 46.0, 0.0][..],
+            )
+            .unwrap();
+        image.encoder().write_tag(Tag::GdalNodata, "-9999").unwrap();
+        image.write_data(&[100.0, 200.0, 300.0, -9999.0]).unwrap();
+
+        let dem = Dem::load(&path).unwrap();
+        assert_eq!(dem.elevation(45.75, 8.25), Some(100.0));
+        assert_eq!(dem.elevation(45.75, 8.75), Some(200.0));
+        assert_eq!(dem.elevation(45.5, 8.25), Some(200.0));
+        // The no-data value is a void, not an elevation
+        assert!(dem.elevation(45.25, 8.75).is_none());
+        assert!(dem.elevation(46.0, 8.0).is_none());
+    }
+}
@@ -0,0 +1,104 @@
+//! Clipping against a hexagon in the projected plane (meters)
+
+use super::circle::Circle;
+use super::clip::{clip_polyline_with, clip_ring_convex, clip_segment_convex};
+
+/// Regular hexagon with flat top and bottom edges, so tiles of neighboring
+/// areas fit together in a honeycomb
+#[derive(Debug, Clone, Copy)]
+pub struct Hexagon {
+    pub center: (f64, f64),
+    /// Distance from the center to each corner
+    pub radius: f64,
+}
+
+impl Hexagon {
+    pub fn new(center: (f64, f64), radius: f64) -> Self {
+        Self { center, radius }
+    }
+
+    /// Distance from the center to the top and bottom edges
+    pub fn apothem(&self) -> f64 {
+        self.radius * 3.0_f64.sqrt() / 2.0
+    }
+
+    /// Counter-clockwise corners starting east of the center, closed
+    pub fn polygon(&self) -> Vec<(f64, f64)> {
+        Circle::new(self.center, self.radius).polygon(6)
+    }
+
+    /// Split a polyline into the pieces inside the hexagon, trimmed at its edge
+    pub fn clip_polyline(&self, points: &[(f64, f64)]) -> Vec<Vec<(f64, f64)>> {
+        let polygon = self.polygon();
+        clip_polyline_with(points, |p, q| clip_segment_convex(p, q, &polygon))
+    }
+
+    /// Clip a closed ring to the hexagon. Returns an empty ring if nothing
+    /// remains.
+    pub fn clip_ring(&self, ring: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        clip_ring_convex(ring, &self.polygon())
+    }
+}
+
+/// Round or hexagonal outline that features are clipped to, in the
+/// projected plane
+#[derive(Debug, Clone, Copy)]
+pub enum Outline {
+    Circle(Circle),
+    Hexagon(Hexagon),
+}
+
+impl Outline {
+    pub fn clip_polyline(&self, points: &[(f64, f64)]) -> Vec<Vec<(f64, f64)>> {
+        match self {
+            Outline::Circle(circle) => circle.clip_polyline(points),
+            Outline::Hexagon(hexagon) => hexagon.clip_polyline(points),
+        }
+    }
+
+    pub fn clip_ring(&self, ring: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        match self {
+            Outline::Circle(circle) => circle.clip_ring(ring),
+            Outline::Hexagon(hexagon) => hexagon.clip_ring(ring),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hexagon_clip() {
+        let hexagon = Hexagon::new((0.0, 0.0), 10.0);
+        // Corners east and west, flat edges north and south
+        let pieces = hexagon.clip_polyline(&[(-20.0, 0.0), (20.0, 0.0)]);
+        assert_eq!(pieces.len(), 1);
+        assert!((pieces[0][0].0 + 10.0).abs() < 1e-9);
+        assert!((pieces[0][1].0 - 10.0).abs() < 1e-9);
+        let pieces = hexagon.clip_polyline(&[(0.0, -20.0), (0.0, 20.0)]);
+        assert!((pieces[0][0].1 + hexagon.apothem()).abs() < 1e-9);
+        assert!((pieces[0][1].1 - hexagon.apothem()).abs() < 1e-9);
+        assert!(
+            hexagon
+                .clip_polyline(&[(-20.0, 9.0), (20.0, 9.0)])
+                .is_empty()
+        );
+
+        let square = [
+            (-20.0, -20.0),
+            (20.0, -20.0),
+            (20.0, 20.0),
+            (-20.0, 20.0),
+            (-20.0, -20.0),
+        ];
+        let clipped = hexagon.clip_ring(&square);
+        // The whole hexagon, closed
+        assert_eq!(clipped.len(), 7);
+        assert!(
+            clipped
+                .iter()
+                .all(|&(x, y)| (x * x + y * y).sqrt() <= 10.0 + 1e-6)
+        );
+    }
+}
@@ -1,7 +1,21 @@
+pub mod area;
+pub mod bbox;
+pub mod circle;
+pub mod clip;
+pub mod coords;
+pub mod dem;
+pub mod hexagon;
 pub mod projection;
+pub mod ring;
 pub mod scaling;
 pub mod simplify;
 
-pub use projection::Projector;
-pub use scaling::{Bounds, Scaler};
-pub use simplify::simplify_polyline;
+pub use area::Area;
+pub use coords::{parse_latitude, parse_longitude};
+pub use dem::Dem;
+pub use projection::{POLAR_LAT_LIMIT, ProjectionKind, Projector, lon_scale};
+pub use ring::{ring_self_intersects, signed_area};
+pub use scaling::{Bounds, MarginSide, Scaler};
+pub use simplify::{
+    SimplifyAlgorithm, dedup_collinear, densify_polyline, simplify_polyline, simplify_polyline_vw,
+};
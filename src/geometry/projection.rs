@@ -1,3 +1,43 @@
+use super::bbox::wrap_lon;
+
+/// Projection used to turn WGS84 coordinates into local meters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProjectionKind {
+    /// Linearized ellipsoidal approximation; fast and accurate for city maps
+    #[default]
+    Local,
+    /// True transverse Mercator in the center's UTM zone, for regional maps
+    Utm,
+}
+
+impl std::str::FromStr for ProjectionKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "local" => Ok(ProjectionKind::Local),
+            "utm" => Ok(ProjectionKind::Utm),
+            _ => Err(format!(
+                "Invalid projection '{}'. Valid options: local, utm",
+                s
+            )),
+        }
+    }
+}
+
+/// Latitude past which the longitude scale stops shrinking
+///
+/// A degree of longitude spans `cos(lat)` of its equatorial length, which
+/// tends to zero at the poles and would blow up anything divided by it.
+pub const POLAR_LAT_LIMIT: f64 = 85.0;
+
+/// `cos(lat)`, held at its value at [`POLAR_LAT_LIMIT`] closer to the poles
+pub fn lon_scale(lat: f64) -> f64 {
+    lat.to_radians()
+        .cos()
+        .max(POLAR_LAT_LIMIT.to_radians().cos())
+}
+
 /// Improved Transverse Mercator-like projection from WGS84 to local meters
 ///
 /// Uses a refined approximation with proper scale factor calculation:
@@ -6,7 +46,8 @@
 /// - Accurate for maps up to ~100km across
 ///
 /// This avoids the complexity of proj crate while providing good accuracy
-/// for city and regional maps.
+/// for city and regional maps. [`ProjectionKind::Utm`] switches to a true
+/// transverse Mercator projection for larger regions.
 #[derive(Debug, Clone)]
 pub struct Projector {
     center_lat: f64,
@@ -17,6 +58,11 @@ pub struct Projector {
     meters_per_lat_degree: f64,
     /// UTM zone number (1-60)
     utm_zone: u8,
+    kind: ProjectionKind,
+    /// UTM easting/northing of the center, subtracted so output is centered
+    utm_origin: (f64, f64),
+    /// (sin, cos) of the counter-clockwise rotation applied after projecting
+    rotation: (f64, f64),
 }
 
 impl Projector {
@@ -25,6 +71,12 @@ impl Projector {
     #[allow(dead_code)]
     const WGS84_B: f64 = 6_356_752.314_245; // Semi-minor axis (polar radius) in meters
     const WGS84_E2: f64 = 0.006_694_379_990_14; // First eccentricity squared
+    const WGS84_F: f64 = 1.0 / 298.257_223_563; // Flattening
+
+    // UTM grid parameters
+    const UTM_K0: f64 = 0.9996;
+    const UTM_FALSE_EASTING: f64 = 500_000.0;
+    const UTM_FALSE_NORTHING_SOUTH: f64 = 10_000_000.0;
 
     /// Create a new projector centered at the given coordinates
     ///
@@ -40,7 +92,7 @@ impl Projector {
         // Calculate meters per degree using WGS84 ellipsoid
         // These formulas account for Earth's ellipsoidal shape
         let sin_lat = lat_rad.sin();
-        let cos_lat = lat_rad.cos();
+        let cos_lat = lon_scale(lat);
         let sin2_lat = sin_lat * sin_lat;
 
         // Radius of curvature in the prime vertical (N)
@@ -62,9 +114,35 @@ impl Projector {
             meters_per_lon_degree,
             meters_per_lat_degree,
             utm_zone,
+            kind: ProjectionKind::Local,
+            utm_origin: (0.0, 0.0),
+            rotation: (0.0, 1.0),
         }
     }
 
+    /// Rotate the projected map about its center so that `bearing_deg`
+    /// (clockwise from north) points up
+    pub fn with_rotation(mut self, bearing_deg: f64) -> Self {
+        let angle = bearing_deg.to_radians();
+        self.rotation = (angle.sin(), angle.cos());
+        self
+    }
+
+    /// Create a projector using the given projection kind
+    pub fn with_kind(center: (f64, f64), kind: ProjectionKind) -> Self {
+        let mut projector = Self::new(center);
+        projector.kind = kind;
+        if kind == ProjectionKind::Utm {
+            projector.utm_origin = projector.to_utm(center.0, center.1);
+        }
+        projector
+    }
+
+    /// Get the projection kind
+    pub fn kind(&self) -> ProjectionKind {
+        self.kind
+    }
+
     /// Calculate UTM zone from longitude
     ///
     /// UTM zones are 6 degrees wide, numbered 1-60 starting at 180°W
@@ -85,7 +163,6 @@ impl Projector {
     }
 
     /// Get the central meridian for the UTM zone
-    #[allow(dead_code)]
     pub fn central_meridian(&self) -> f64 {
         (self.utm_zone as f64 - 1.0) * 6.0 - 180.0 + 3.0
     }
@@ -103,14 +180,37 @@ impl Projector {
     /// # Returns
     /// * (x, y) in meters, centered at the projection center
     pub fn project(&self, lat: f64, lon: f64) -> (f64, f64) {
-        let delta_lon = lon - self.center_lon;
-        let delta_lat = lat - self.center_lat;
+        let (x, y) = if self.kind == ProjectionKind::Utm {
+            let (easting, northing) = self.to_utm(lat, lon);
+            (easting - self.utm_origin.0, northing - self.utm_origin.1)
+        } else {
+            // Wrapped so points across the antimeridian stay close
+            let delta_lon = wrap_lon(lon - self.center_lon);
+            let delta_lat = lat - self.center_lat;
+
+            // For small areas, linear approximation with proper scale factors
+            (
+                delta_lon * self.meters_per_lon_degree,
+                delta_lat * self.meters_per_lat_degree,
+            )
+        };
 
-        // For small areas, linear approximation with proper scale factors
-        let x = delta_lon * self.meters_per_lon_degree;
-        let y = delta_lat * self.meters_per_lat_degree;
+        let (sin, cos) = self.rotation;
+        (x * cos - y * sin, x * sin + y * cos)
+    }
+
+    /// Inverse of [`Projector::project`]: local meters back to (lat, lon)
+    pub fn unproject(&self, x: f64, y: f64) -> (f64, f64) {
+        let (sin, cos) = self.rotation;
+        let (x, y) = (x * cos + y * sin, -x * sin + y * cos);
 
-        (x, y)
+        if self.kind == ProjectionKind::Utm {
+            return self.utm_to_lat_lon(x + self.utm_origin.0, y + self.utm_origin.1);
+        }
+
+        let lat = self.center_lat + y / self.meters_per_lat_degree;
+        let lon = self.center_lon + x / self.meters_per_lon_degree;
+        (lat, wrap_lon(lon))
     }
 
     /// Project a slice of lat/lon points
@@ -121,10 +221,105 @@ impl Projector {
             .collect()
     }
 
+    /// Unproject a slice of local points back to (lat, lon)
+    pub fn unproject_points(&self, points: &[(f64, f64)]) -> Vec<(f64, f64)> {
+        points.iter().map(|&(x, y)| self.unproject(x, y)).collect()
+    }
+
+    /// UTM easting/northing in meters within this projector's zone
+    ///
+    /// Uses the Krüger series to third order in the third flattening, which
+    /// is accurate to about a millimeter within a zone. The southern false
+    /// northing follows the hemisphere of the projector's center so maps
+    /// spanning the equator stay continuous.
+    pub fn to_utm(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let n = Self::WGS84_F / (2.0 - Self::WGS84_F);
+        let (rect_radius, alpha, _, _) = Self::kruger_coefficients(n);
+
+        let phi = lat.to_radians();
+        let lambda = wrap_lon(lon - self.central_meridian()).to_radians();
+        let e = 2.0 * n.sqrt() / (1.0 + n);
+        let t = (phi.sin().atanh() - e * (e * phi.sin()).atanh()).sinh();
+        let xi_p = t.atan2(lambda.cos());
+        let eta_p = (lambda.sin() / (1.0 + t * t).sqrt()).atanh();
+
+        let mut xi = xi_p;
+        let mut eta = eta_p;
+        for (j, a) in alpha.iter().enumerate() {
+            let k = 2.0 * (j + 1) as f64;
+            xi += a * (k * xi_p).sin() * (k * eta_p).cosh();
+            eta += a * (k * xi_p).cos() * (k * eta_p).sinh();
+        }
+
+        let easting = Self::UTM_FALSE_EASTING + Self::UTM_K0 * rect_radius * eta;
+        let northing = Self::UTM_K0 * rect_radius * xi + self.false_northing();
+        (easting, northing)
+    }
+
+    /// Inverse of [`Projector::to_utm`]: easting/northing back to (lat, lon)
+    pub fn utm_to_lat_lon(&self, easting: f64, northing: f64) -> (f64, f64) {
+        let n = Self::WGS84_F / (2.0 - Self::WGS84_F);
+        let (rect_radius, _, beta, delta) = Self::kruger_coefficients(n);
+
+        let xi = (northing - self.false_northing()) / (Self::UTM_K0 * rect_radius);
+        let eta = (easting - Self::UTM_FALSE_EASTING) / (Self::UTM_K0 * rect_radius);
+
+        let mut xi_p = xi;
+        let mut eta_p = eta;
+        for (j, b) in beta.iter().enumerate() {
+            let k = 2.0 * (j + 1) as f64;
+            xi_p -= b * (k * xi).sin() * (k * eta).cosh();
+            eta_p -= b * (k * xi).cos() * (k * eta).sinh();
+        }
+
+        let chi = (xi_p.sin() / eta_p.cosh()).asin();
+        let mut phi = chi;
+        for (j, d) in delta.iter().enumerate() {
+            phi += d * (2.0 * (j + 1) as f64 * chi).sin();
+        }
+        let lambda = eta_p.sinh().atan2(xi_p.cos());
+
+        (
+            phi.to_degrees(),
+            wrap_lon(self.central_meridian() + lambda.to_degrees()),
+        )
+    }
+
+    fn false_northing(&self) -> f64 {
+        if self.center_lat < 0.0 {
+            Self::UTM_FALSE_NORTHING_SOUTH
+        } else {
+            0.0
+        }
+    }
+
+    /// Rectifying radius and Krüger series coefficients (alpha, beta, delta)
+    /// for third flattening `n`
+    fn kruger_coefficients(n: f64) -> (f64, [f64; 3], [f64; 3], [f64; 3]) {
+        let n2 = n * n;
+        let n3 = n2 * n;
+        let rect_radius = Self::WGS84_A / (1.0 + n) * (1.0 + n2 / 4.0 + n2 * n2 / 64.0);
+        let alpha = [
+            n / 2.0 - 2.0 * n2 / 3.0 + 5.0 * n3 / 16.0,
+            13.0 * n2 / 48.0 - 3.0 * n3 / 5.0,
+            61.0 * n3 / 240.0,
+        ];
+        let beta = [
+            n / 2.0 - 2.0 * n2 / 3.0 + 37.0 * n3 / 96.0,
+            n2 / 48.0 + n3 / 15.0,
+            17.0 * n3 / 480.0,
+        ];
+        let delta = [
+            2.0 * n - 2.0 * n2 / 3.0 - 2.0 * n3,
+            7.0 * n2 / 3.0 - 8.0 * n3 / 5.0,
+            56.0 * n3 / 15.0,
+        ];
+        (rect_radius, alpha, beta, delta)
+    }
+
     /// Get projection accuracy estimate for a given radius in meters
     ///
     /// Returns the approximate maximum error in meters at the edge of the map
-    #[allow(dead_code)]
     pub fn estimate_error(&self, radius_m: f64) -> f64 {
         // For transverse Mercator, error grows with distance from center
         // Approximate error: (distance^2) / (2 * Earth_radius)
@@ -152,6 +347,29 @@ mod tests {
         assert!((y - 1000.0).abs() < 50.0);
     }
 
+    #[test]
+    fn test_unproject_round_trip() {
+        let proj = Projector::new((51.5074, -0.1278));
+        let (x, y) = proj.project(51.52, -0.10);
+        let (lat, lon) = proj.unproject(x, y);
+        assert!((lat - 51.52).abs() < 1e-9);
+        assert!((lon + 0.10).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rotation_points_bearing_up() {
+        let center = (40.0, -74.0);
+        let proj = Projector::new(center).with_rotation(90.0);
+        // A point due east ends up straight above the center
+        let (x, y) = proj.project(40.0, -73.99);
+        assert!(x.abs() < 1e-6);
+        assert!(y > 800.0);
+
+        let (lat, lon) = proj.unproject(x, y);
+        assert!((lat - 40.0).abs() < 1e-9);
+        assert!((lon + 73.99).abs() < 1e-9);
+    }
+
     #[test]
     fn test_utm_zone_calculation() {
         assert_eq!(Projector::calculate_utm_zone(-122.4194, 37.7749), 10);
@@ -166,6 +384,65 @@ mod tests {
         assert_eq!(proj.utm_zone(), 10);
     }
 
+    #[test]
+    fn test_utm_reference_coordinates() {
+        // Reference values from a sixth-order Krüger series implementation
+        let cases = [
+            ((37.7749, -122.4194), (551_130.768, 4_180_998.881)), // San Francisco, 10N
+            ((48.858_370_1, 2.294_481_3), (448_250.599, 5_411_951.599)), // Eiffel Tower, 31N
+            ((-33.8568, 151.2153), (334_900.570, 6_252_288.753)), // Sydney Opera House, 56S
+        ];
+        for ((lat, lon), (easting, northing)) in cases {
+            let proj = Projector::with_kind((lat, lon), ProjectionKind::Utm);
+            let (e, n) = proj.to_utm(lat, lon);
+            assert!((e - easting).abs() < 0.01, "easting {} vs {}", e, easting);
+            assert!(
+                (n - northing).abs() < 0.01,
+                "northing {} vs {}",
+                n,
+                northing
+            );
+        }
+    }
+
+    #[test]
+    fn test_utm_project_round_trip() {
+        let proj = Projector::with_kind((35.6895, 139.6917), ProjectionKind::Utm);
+        let (x, y) = proj.project(35.6895, 139.6917);
+        assert!(x.abs() < 1e-6 && y.abs() < 1e-6);
+
+        let (x, y) = proj.project(36.0, 140.2);
+        let (lat, lon) = proj.unproject(x, y);
+        assert!((lat - 36.0).abs() < 1e-8);
+        assert!((lon - 140.2).abs() < 1e-8);
+    }
+
+    #[test]
+    fn test_project_across_antimeridian() {
+        for kind in [ProjectionKind::Local, ProjectionKind::Utm] {
+            let proj = Projector::with_kind((-17.7, 179.9), kind);
+            let (east_x, east_y) = proj.project(-17.7, -179.9);
+            let (west_x, _) = proj.project(-17.7, 179.8);
+            // 0.2 and 0.1 degrees away, on either side of the center
+            assert!((east_x - 21_200.0).abs() < 300.0, "{:?} {}", kind, east_x);
+            assert!((west_x + 10_600.0).abs() < 300.0, "{:?} {}", kind, west_x);
+
+            let (_, lon) = proj.unproject(east_x, east_y);
+            assert!((lon + 179.9).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_lon_scale_clamped_near_poles() {
+        assert!((lon_scale(60.0) - 0.5).abs() < 1e-12);
+        assert_eq!(lon_scale(89.99), lon_scale(POLAR_LAT_LIMIT));
+        assert_eq!(lon_scale(-90.0), lon_scale(POLAR_LAT_LIMIT));
+
+        let proj = Projector::new((89.9, 0.0));
+        let (x, _) = proj.project(89.9, 1.0);
+        assert!(x.is_finite() && x > 1000.0);
+    }
+
     #[test]
     fn test_estimate_error() {
         let proj = Projector::new((37.7749, -122.4194));
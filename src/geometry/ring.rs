@@ -0,0 +1,125 @@
+//! Orientation and self-intersection checks for polygon rings
+//!
+//! Earcut triangulates a bowtie without complaint but returns overlapping
+//! junk, so polygons are checked before they reach it.
+
+use geo::{Coord, Line, LineIntersection, line_intersection::line_intersection};
+
+/// Shoelace area of a ring of (x, y) points, positive when it runs
+/// counter-clockwise
+///
+/// The ring may repeat its first point at the end or not. The sum is taken
+/// in f64 whatever the point type.
+pub fn signed_area<T: Copy + Into<f64>>(ring: &[(T, T)]) -> f64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (x0, y0) = ring[i];
+            let (x1, y1) = ring[(i + 1) % n];
+            x0.into() * y1.into() - x1.into() * y0.into()
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Whether any two edges of a ring cross, or neighbors double back
+///
+/// Edges that only touch at a point, as in a ring pinched at one vertex,
+/// do not count, since the triangulation copes with those. Nor do apart
+/// edges lying along one another: clipping a concave ring joins its pieces
+/// with such bridges along the cut. The ring may
+/// repeat its first point at the end or not. Edges are swept in order of
+/// their left end and only compared while their x ranges overlap, which
+/// stays close to linear for map outlines.
+pub fn ring_self_intersects(ring: &[(f64, f64)]) -> bool {
+    let n = ring.len();
+    let edges: Vec<Line<f64>> = (0..n)
+        .map(|i| Line::new(Coord::from(ring[i]), Coord::from(ring[(i + 1) % n])))
+        .filter(|line| line.start != line.end)
+        .collect();
+    let count = edges.len();
+    if count < 3 {
+        return false;
+    }
+    let adjacent = |i: usize, j: usize| i.abs_diff(j) == 1 || i.abs_diff(j) == count - 1;
+
+    let mut order: Vec<usize> = (0..count).collect();
+    order.sort_by(|&a, &b| min_x(&edges[a]).total_cmp(&min_x(&edges[b])));
+    for (k, &i) in order.iter().enumerate() {
+        let max_x = edges[i].start.x.max(edges[i].end.x);
+        for &j in &order[k + 1..] {
+            if min_x(&edges[j]) > max_x {
+                break;
+            }
+            match line_intersection(edges[i], edges[j]) {
+                // Neighbors always share a vertex; doubling back along one
+                // another is the only way they go wrong
+                Some(LineIntersection::Collinear { .. }) if adjacent(i, j) => return true,
+                Some(LineIntersection::SinglePoint {
+                    is_proper: true, ..
+                }) if !adjacent(i, j) => {
+                    return true;
+                }
+                _ => {}
+            }
+        }
+    }
+    false
+}
+
+fn min_x(line: &Line<f64>) -> f64 {
+    line.start.x.min(line.end.x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_self_intersects() {
+        let square = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)];
+        assert!(!ring_self_intersects(&square));
+        // Closed rings repeat the first point
+        let closed = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.0, 0.0)];
+        assert!(!ring_self_intersects(&closed));
+        let concave = [
+            (0.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+        ];
+        assert!(!ring_self_intersects(&concave));
+
+        let bowtie = [(0.0, 0.0), (1.0, 1.0), (1.0, 0.0), (0.0, 1.0)];
+        assert!(ring_self_intersects(&bowtie));
+        // A spike doubling back on itself
+        let spike = [(0.0, 0.0), (2.0, 0.0), (1.0, 0.0), (1.0, 1.0)];
+        assert!(ring_self_intersects(&spike));
+        // Two squares pinched together at one corner still triangulate
+        let pinched = [
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (1.0, 1.0),
+            (2.0, 1.0),
+            (2.0, 2.0),
+            (1.0, 2.0),
+            (1.0, 1.0),
+            (0.0, 1.0),
+        ];
+        assert!(!ring_self_intersects(&pinched));
+        // A C cut across its spine: the two bars are joined along the cut
+        let bridged = [
+            (1.0, 0.0),
+            (0.0, 0.0),
+            (0.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+            (0.0, 3.0),
+            (1.0, 3.0),
+        ];
+        assert!(!ring_self_intersects(&bridged));
+    }
+}
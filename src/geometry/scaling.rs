@@ -1,3 +1,30 @@
+use serde::Deserialize;
+
+/// Plate edge along which the label margin is kept clear of the map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MarginSide {
+    #[default]
+    Bottom,
+    /// A title bar along the top edge
+    Top,
+}
+
+impl std::str::FromStr for MarginSide {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "bottom" => Ok(MarginSide::Bottom),
+            "top" => Ok(MarginSide::Top),
+            _ => Err(format!(
+                "Invalid text position '{}'. Valid options: bottom, top",
+                s
+            )),
+        }
+    }
+}
+
 /// Bounding box in projected coordinates (meters)
 #[derive(Debug, Clone)]
 pub struct Bounds {
@@ -75,27 +102,49 @@ impl Scaler {
     /// * `target_mm` - Target size in mm (will fit the larger dimension)
     #[allow(dead_code)]
     pub fn from_bounds(bounds: &Bounds, target_mm: f64) -> Self {
-        Self::from_bounds_with_margin(bounds, target_mm, 0.0)
+        Self::from_bounds_with_margin(bounds, target_mm, 0.0, MarginSide::Bottom)
+    }
+
+    /// Create a scaler with a margin reserved for text labels along `side`
+    pub fn from_bounds_with_margin(
+        bounds: &Bounds,
+        target_mm: f64,
+        margin_mm: f64,
+        side: MarginSide,
+    ) -> Self {
+        Self::from_bounds_in_rect(bounds, target_mm, target_mm, margin_mm, side)
     }
 
-    /// Create a scaler with a bottom margin reserved for text labels
-    pub fn from_bounds_with_margin(bounds: &Bounds, target_mm: f64, bottom_margin_mm: f64) -> Self {
+    /// Create a scaler fitting the bounds into a `width_mm` x `height_mm`
+    /// rectangle, with a margin of the height along `side` reserved for text
+    /// labels
+    pub fn from_bounds_in_rect(
+        bounds: &Bounds,
+        width_mm: f64,
+        height_mm: f64,
+        margin_mm: f64,
+        side: MarginSide,
+    ) -> Self {
         let width = bounds.width();
         let height = bounds.height();
 
-        let usable_height = target_mm - bottom_margin_mm;
-        let max_dim = width.max(height);
+        let usable_height = height_mm - margin_mm;
+        let bottom_margin_mm = match side {
+            MarginSide::Bottom => margin_mm,
+            MarginSide::Top => 0.0,
+        };
 
-        let scale = if max_dim > 0.0 {
-            usable_height / max_dim
-        } else {
-            1.0
+        let scale = match (width > 0.0, height > 0.0) {
+            (true, true) => (width_mm / width).min(usable_height / height),
+            (true, false) => width_mm / width,
+            (false, true) => usable_height / height,
+            (false, false) => 1.0,
         };
 
         let scaled_width = width * scale;
         let scaled_height = height * scale;
 
-        let offset_x = (target_mm - scaled_width) / 2.0 - bounds.min_x * scale;
+        let offset_x = (width_mm - scaled_width) / 2.0 - bounds.min_x * scale;
         let offset_y =
             bottom_margin_mm + (usable_height - scaled_height) / 2.0 - bounds.min_y * scale;
 
@@ -103,10 +152,17 @@ impl Scaler {
             scale,
             offset_x,
             offset_y,
-            target_mm,
+            target_mm: width_mm.max(height_mm),
         }
     }
 
+    /// Shift the scaled output by `dx`, `dy` mm
+    pub fn with_offset(mut self, dx: f64, dy: f64) -> Self {
+        self.offset_x += dx;
+        self.offset_y += dy;
+        self
+    }
+
     /// Scale a point from meters to mm
     ///
     /// # Returns
@@ -117,6 +173,14 @@ impl Scaler {
         (scaled_x as f32, scaled_y as f32)
     }
 
+    /// Map a point in mm back to meters
+    pub fn unscale(&self, x_mm: f32, y_mm: f32) -> (f64, f64) {
+        (
+            (x_mm as f64 - self.offset_x) / self.scale,
+            (y_mm as f64 - self.offset_y) / self.scale,
+        )
+    }
+
     /// Scale a slice of points
     #[allow(dead_code)]
     pub fn scale_points(&self, points: &[(f64, f64)]) -> Vec<(f32, f32)> {
@@ -124,7 +188,6 @@ impl Scaler {
     }
 
     /// Get the scale factor (mm per meter)
-    #[allow(dead_code)]
     pub fn scale_factor(&self) -> f64 {
         self.scale
     }
@@ -170,5 +233,34 @@ mod tests {
         let (x, y) = scaler.scale(5000.0, 5000.0);
         assert!((x - 110.0).abs() < 1.0);
         assert!((y - 110.0).abs() < 1.0);
+
+        let (mx, my) = scaler.unscale(x, y);
+        assert!((mx - 5000.0).abs() < 0.1 && (my - 5000.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_scaler_rect_fits_elongated_map() {
+        // 2km wide, 10km tall into 100mm x 300mm with a 20mm text margin
+        let bounds = Bounds {
+            min_x: 0.0,
+            max_x: 2000.0,
+            min_y: 0.0,
+            max_y: 10000.0,
+        };
+
+        let scaler = Scaler::from_bounds_in_rect(&bounds, 100.0, 300.0, 20.0, MarginSide::Bottom);
+
+        // Height is the limiting side: 280mm / 10000m
+        assert!((scaler.scale_factor() - 0.028).abs() < 1e-9);
+        let (x0, y0) = scaler.scale(0.0, 0.0);
+        let (x1, y1) = scaler.scale(2000.0, 10000.0);
+        assert!((x0 - 22.0).abs() < 1e-3 && (x1 - 78.0).abs() < 1e-3);
+        assert!((y0 - 20.0).abs() < 1e-3 && (y1 - 300.0).abs() < 1e-3);
+
+        // A top margin leaves the map against the bottom edge instead
+        let scaler = Scaler::from_bounds_in_rect(&bounds, 100.0, 300.0, 20.0, MarginSide::Top);
+        let (_, y0) = scaler.scale(0.0, 0.0);
+        let (_, y1) = scaler.scale(2000.0, 10000.0);
+        assert!(y0.abs() < 1e-3 && (y1 - 280.0).abs() < 1e-3);
     }
 }
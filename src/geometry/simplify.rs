@@ -1,18 +1,115 @@
-use geo::{LineString, Simplify};
+use geo::{LineString, Simplify, SimplifyVw};
+use serde::Deserialize;
+
+/// Line simplification algorithm used for roads
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SimplifyAlgorithm {
+    /// Ramer-Douglas-Peucker: keeps points farther than epsilon from the line
+    #[default]
+    #[serde(alias = "dp")]
+    DouglasPeucker,
+    /// Visvalingam-Whyatt: drops points whose triangle area is below a
+    /// threshold, which tends to keep the overall shape of curves better
+    #[serde(alias = "vw")]
+    Visvalingam,
+}
+
+impl std::str::FromStr for SimplifyAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "douglas-peucker" | "dp" => Ok(SimplifyAlgorithm::DouglasPeucker),
+            "visvalingam" | "vw" => Ok(SimplifyAlgorithm::Visvalingam),
+            _ => Err(format!(
+                "Invalid simplification algorithm '{}'. Valid options: douglas-peucker (dp), visvalingam (vw)",
+                s
+            )),
+        }
+    }
+}
+
+fn to_line_string(points: &[(f64, f64)]) -> LineString<f64> {
+    points
+        .iter()
+        .map(|&(lat, lon)| geo::coord! { x: lon, y: lat })
+        .collect()
+}
+
+fn from_line_string(line: LineString<f64>) -> Vec<(f64, f64)> {
+    line.0.into_iter().map(|c| (c.y, c.x)).collect()
+}
 
 pub fn simplify_polyline(points: &[(f64, f64)], epsilon: f64) -> Vec<(f64, f64)> {
     if points.len() < 4 {
         return points.to_vec();
     }
 
-    let line: LineString<f64> = points
-        .iter()
-        .map(|&(lat, lon)| geo::coord! { x: lon, y: lat })
-        .collect();
+    from_line_string(to_line_string(points).simplify(&epsilon))
+}
+
+/// Visvalingam-Whyatt simplification of a (lat, lon) polyline
+///
+/// `epsilon` is a distance in degrees like [`simplify_polyline`]; it is
+/// squared to get the triangle area threshold so both algorithms remove
+/// detail at a comparable scale.
+pub fn simplify_polyline_vw(points: &[(f64, f64)], epsilon: f64) -> Vec<(f64, f64)> {
+    if points.len() < 4 {
+        return points.to_vec();
+    }
+
+    from_line_string(to_line_string(points).simplify_vw(&(epsilon * epsilon)))
+}
 
-    let simplified = line.simplify(&epsilon);
+/// Drop interior points where a polyline runs straight on
+///
+/// A point is removed when the line from the last kept point to it and the
+/// next segment differ in direction by at most `angle_tol_deg`, or when it
+/// repeats a neighbor. Measuring from the last kept point rather
+/// than the raw neighbor stops gentle curves from being flattened a few
+/// degrees at a time. Works on projected points, where angles are true.
+pub fn dedup_collinear(points: &[(f32, f32)], angle_tol_deg: f32) -> Vec<(f32, f32)> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let cos_tol = angle_tol_deg.to_radians().cos();
+    let mut kept = vec![points[0]];
+    for i in 1..points.len() - 1 {
+        let anchor = kept[kept.len() - 1];
+        let (p, next) = (points[i], points[i + 1]);
+        let a = (p.0 - anchor.0, p.1 - anchor.1);
+        let b = (next.0 - p.0, next.1 - p.1);
+        let (len_a, len_b) = (a.0.hypot(a.1), b.0.hypot(b.1));
+        // Repeated points carry no direction
+        if len_a < 1e-6 || len_b < 1e-6 {
+            continue;
+        }
+        if (a.0 * b.0 + a.1 * b.1) / (len_a * len_b) < cos_tol {
+            kept.push(p);
+        }
+    }
+    kept.push(points[points.len() - 1]);
+    kept
+}
 
-    simplified.0.into_iter().map(|c| (c.y, c.x)).collect()
+/// Split segments longer than `max_step` into equal parts, so a polyline
+/// can bend with a surface under it
+pub fn densify_polyline(points: &[(f32, f32)], max_step: f32) -> Vec<(f32, f32)> {
+    let Some(&first) = points.first() else {
+        return Vec::new();
+    };
+    let mut dense = vec![first];
+    for pair in points.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        let parts = ((b.0 - a.0).hypot(b.1 - a.1) / max_step).ceil().max(1.0) as usize;
+        for i in 1..=parts {
+            let t = i as f32 / parts as f32;
+            dense.push((a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t));
+        }
+    }
+    dense
 }
 
 #[allow(dead_code)]
@@ -87,4 +184,72 @@ mod tests {
         let result = simplify_polygon(&square, 0.1);
         assert!(result.len() >= 4);
     }
+
+    #[test]
+    fn test_simplify_polyline_vw_removes_small_wiggles() {
+        let points = vec![
+            (0.0, 0.0),
+            (0.00001, 1.0),
+            (0.0, 2.0),
+            (-0.00001, 3.0),
+            (0.0, 4.0),
+        ];
+        let result = simplify_polyline_vw(&points, 0.01);
+        assert_eq!(result, vec![(0.0, 0.0), (0.0, 4.0)]);
+        assert_eq!(simplify_polyline_vw(&points[..3], 0.01).len(), 3);
+    }
+
+    #[test]
+    fn test_dedup_collinear() {
+        // A straight run with redundant nodes, a repeated point and a corner
+        let points = vec![
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (2.0, 0.001),
+            (2.0, 0.001),
+            (3.0, 0.0),
+            (3.0, 2.0),
+        ];
+        let result = dedup_collinear(&points, 1.0);
+        assert_eq!(result, vec![(0.0, 0.0), (3.0, 0.0), (3.0, 2.0)]);
+
+        // A circle in 2 degree steps keeps a point at least every few steps
+        let arc: Vec<(f32, f32)> = (0..=45)
+            .map(|i| (i as f32 * 2.0).to_radians().sin_cos())
+            .map(|(sin, cos)| (cos * 10.0, sin * 10.0))
+            .collect();
+        let result = dedup_collinear(&arc, 3.0);
+        assert!(result.len() < arc.len() && result.len() > 10);
+    }
+
+    #[test]
+    fn test_densify_polyline() {
+        let points = vec![(0.0, 0.0), (5.0, 0.0), (5.0, 1.0)];
+        let dense = densify_polyline(&points, 2.0);
+        let expected = [
+            (0.0, 0.0),
+            (5.0 / 3.0, 0.0),
+            (10.0 / 3.0, 0.0),
+            (5.0, 0.0),
+            (5.0, 1.0),
+        ];
+        assert_eq!(dense.len(), expected.len());
+        for (p, e) in dense.iter().zip(expected) {
+            assert!((p.0 - e.0).abs() < 1e-5 && (p.1 - e.1).abs() < 1e-5);
+        }
+        assert!(densify_polyline(&[], 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_simplify_algorithm_from_str() {
+        assert_eq!(
+            "vw".parse::<SimplifyAlgorithm>(),
+            Ok(SimplifyAlgorithm::Visvalingam)
+        );
+        assert_eq!(
+            "Douglas-Peucker".parse::<SimplifyAlgorithm>(),
+            Ok(SimplifyAlgorithm::DouglasPeucker)
+        );
+        assert!("bezier".parse::<SimplifyAlgorithm>().is_err());
+    }
 }
@@ -0,0 +1,71 @@
+//! Runways and taxiways as ribbons, aprons as flat areas
+
+use rayon::prelude::*;
+
+use super::parks::generate_park_meshes;
+use crate::domain::Aeroways;
+use crate::geometry::{Projector, Scaler};
+use crate::mesh::{RibbonOptions, Triangle, extrude_ribbon_ex};
+
+/// Narrowest runway or taxiway in mm, so small airfields still print
+pub const MIN_AEROWAY_WIDTH_MM: f32 = 0.8;
+
+/// Airfield features as columns from z=0 to `z_top`
+///
+/// Unlike roads, runways and taxiways are drawn at their true width from the
+/// `width` tag or their class, since they are wide enough to read at scale.
+pub fn generate_aeroway_meshes(
+    aeroways: &Aeroways,
+    projector: &Projector,
+    scaler: &Scaler,
+    z_top: f32,
+) -> Vec<Triangle> {
+    let mut triangles = generate_park_meshes(&aeroways.aprons, projector, scaler, z_top);
+    let options = RibbonOptions::default();
+    let mm_per_m = scaler.scale_factor() as f32;
+    triangles.par_extend(aeroways.lines.par_iter().flat_map_iter(|line| {
+        let scaled = scaler.scale_points(&projector.project_points(&line.points));
+        let width = (line.width_m() * mm_per_m).max(MIN_AEROWAY_WIDTH_MM);
+        extrude_ribbon_ex(&scaled, width, z_top, 0.0, &options)
+    }));
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{AerowayClass, AerowayLine};
+    use crate::geometry::Bounds;
+
+    #[test]
+    fn test_runway_width_to_scale() {
+        let projector = Projector::new((0.0, 0.0));
+        let (x0, y0) = projector.project(-0.01, -0.01);
+        let (x1, y1) = projector.project(0.01, 0.01);
+        let bounds = Bounds::from_points(&[(x0, y0), (x1, y1)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 100.0);
+        let runway = |width_m| {
+            let line = AerowayLine::new(vec![(0.0, -0.005), (0.0, 0.005)], AerowayClass::Runway)
+                .with_width_m(width_m);
+            Aeroways {
+                lines: vec![line],
+                aprons: Vec::new(),
+            }
+        };
+        let span = |aeroways: &Aeroways| {
+            let triangles = generate_aeroway_meshes(aeroways, &projector, &scaler, 3.0);
+            let ys: Vec<f32> = triangles
+                .iter()
+                .flat_map(|t| t.vertices)
+                .map(|v| v[1])
+                .collect();
+            ys.iter().fold(f32::MIN, |a, &b| a.max(b)) - ys.iter().fold(f32::MAX, |a, &b| a.min(b))
+        };
+
+        // A 2.2km wide plate at 100mm is about 0.045mm per meter
+        let mm_per_m = scaler.scale_factor() as f32;
+        assert!((span(&runway(Some(60.0))) - 60.0 * mm_per_m).abs() < 0.05);
+        assert!((span(&runway(None)) - 45.0 * mm_per_m).abs() < 0.05);
+        assert!((span(&runway(Some(5.0))) - MIN_AEROWAY_WIDTH_MM).abs() < 0.05);
+    }
+}
@@ -1,13 +1,300 @@
-use crate::mesh::Triangle;
+use crate::mesh::triangulation::triangulate_polygon;
+use crate::mesh::{Triangle, extrude_polygon};
+use anyhow::{Result, bail};
+
+/// Default number of edges used to approximate a circular base outline
+pub const CIRCULAR_BASE_SEGMENTS: usize = 128;
+
+/// Number of edges used to approximate each rounded plate corner
+const CORNER_SEGMENTS: usize = 16;
+
+/// Number of edges used to approximate a hanging hole
+const HANG_HOLE_SEGMENTS: usize = 32;
+
+/// Minimum plate material left between a hanging hole and the plate edge
+const HANG_HOLE_MIN_WALL_MM: f32 = 1.5;
+
+/// Distance from the plate edges to the rim of a corner-placed hanging hole
+const HANG_HOLE_CORNER_INSET_MM: f32 = 3.0;
+
+/// Clearance kept free along each edge of the printer bed, for a skirt or
+/// brim and the bed clips
+pub const BED_MARGIN_MM: f32 = 5.0;
+
+/// Hole diameter used by `--hang-hole-corner`
+pub const DEFAULT_HANG_HOLE_DIAMETER_MM: f32 = 4.0;
+
+/// Largest cavity span under a hollow base; the top skin has to bridge it
+const HOLLOW_CELL_MM: f32 = 20.0;
+
+/// Shallowest cavity worth cutting into a hollow base, in mm
+pub const HOLLOW_MIN_CAVITY_MM: f32 = 0.4;
+
+/// Outline of the printed map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MapShape {
+    /// Square plate; features fill the fetched bounding box
+    #[default]
+    Square,
+    /// Round plate; features are clipped to the map radius
+    Circle,
+    /// Flat-topped hexagonal plate; features are clipped to a hexagon with
+    /// corners at the map radius
+    Hexagon,
+}
+
+impl std::str::FromStr for MapShape {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "square" => Ok(MapShape::Square),
+            "circle" => Ok(MapShape::Circle),
+            "hexagon" | "hex" => Ok(MapShape::Hexagon),
+            _ => Err(format!(
+                "Invalid map shape '{}'. Valid options: square, circle, hexagon",
+                s
+            )),
+        }
+    }
+}
+
+/// Plate corner used to place a hanging hole
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlateCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl std::str::FromStr for PlateCorner {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "top-left" => Ok(PlateCorner::TopLeft),
+            "top-right" => Ok(PlateCorner::TopRight),
+            "bottom-left" => Ok(PlateCorner::BottomLeft),
+            "bottom-right" => Ok(PlateCorner::BottomRight),
+            _ => Err(format!(
+                "Invalid corner '{}'. Valid options: top-left, top-right, bottom-left, bottom-right",
+                s
+            )),
+        }
+    }
+}
+
+/// Printable area of the printer bed in mm
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BedSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl std::str::FromStr for BedSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || format!("Invalid bed size '{}'. Expected WxH in mm, e.g. 256x256", s);
+        let (width, height) = s
+            .to_lowercase()
+            .split_once('x')
+            .ok_or_else(invalid)
+            .and_then(|(width, height)| {
+                let width: f32 = width.trim().parse().map_err(|_| invalid())?;
+                let height: f32 = height.trim().parse().map_err(|_| invalid())?;
+                Ok((width, height))
+            })?;
+        if !(width > 2.0 * BED_MARGIN_MM && height > 2.0 * BED_MARGIN_MM) {
+            return Err(format!(
+                "Invalid bed size '{}'. Each side must be more than {}mm",
+                s,
+                2.0 * BED_MARGIN_MM
+            ));
+        }
+        Ok(BedSize { width, height })
+    }
+}
+
+impl BedSize {
+    /// Bed area left inside the [`BED_MARGIN_MM`] clearance
+    fn usable(&self) -> (f32, f32) {
+        (
+            self.width - 2.0 * BED_MARGIN_MM,
+            self.height - 2.0 * BED_MARGIN_MM,
+        )
+    }
+
+    /// How far a plate overhangs the usable bed along x and y, or `None`
+    /// if it fits either way round
+    pub fn overflow(&self, width_mm: f32, height_mm: f32) -> Option<(f32, f32)> {
+        let (bed_w, bed_h) = self.usable();
+        let fits = |w: f32, h: f32| w <= bed_w && h <= bed_h;
+        if fits(width_mm, height_mm) || fits(height_mm, width_mm) {
+            return None;
+        }
+        Some(((width_mm - bed_w).max(0.0), (height_mm - bed_h).max(0.0)))
+    }
+
+    /// Factor, at most 1, that shrinks a plate to fit the usable bed, laid
+    /// whichever way round leaves it larger
+    pub fn fit_scale(&self, width_mm: f32, height_mm: f32) -> f32 {
+        let (bed_w, bed_h) = self.usable();
+        let scale = |w: f32, h: f32| (bed_w / w).min(bed_h / h);
+        scale(width_mm, height_mm)
+            .max(scale(height_mm, width_mm))
+            .min(1.0)
+    }
+}
+
+/// Cylindrical hole punched through the base plate, in plate millimeters
+/// with the origin at the bottom-left corner
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HangHole {
+    pub x: f32,
+    pub y: f32,
+    pub diameter: f32,
+}
+
+impl std::str::FromStr for HangHole {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let values: Vec<f32> = s
+            .split(',')
+            .map(|v| v.trim().parse::<f32>())
+            .collect::<std::result::Result<_, _>>()
+            .map_err(|e| format!("Invalid hang hole '{}': {}", s, e))?;
+        let [x, y, diameter] = values[..] else {
+            return Err(format!("Invalid hang hole '{}': expected x,y,diameter", s));
+        };
+        if diameter <= 0.0 {
+            return Err(format!(
+                "Invalid hang hole '{}': diameter must be positive",
+                s
+            ));
+        }
+        Ok(HangHole { x, y, diameter })
+    }
+}
+
+impl HangHole {
+    /// Hole of `diameter` tucked into a corner of the plate, clear of a
+    /// rounded corner of `corner_radius`
+    pub fn in_corner(
+        corner: PlateCorner,
+        width_mm: f32,
+        height_mm: f32,
+        diameter: f32,
+        corner_radius: f32,
+    ) -> Self {
+        let inset = (diameter / 2.0 + HANG_HOLE_CORNER_INSET_MM).max(corner_radius);
+        let (x, y) = match corner {
+            PlateCorner::TopLeft => (inset, height_mm - inset),
+            PlateCorner::TopRight => (width_mm - inset, height_mm - inset),
+            PlateCorner::BottomLeft => (inset, inset),
+            PlateCorner::BottomRight => (width_mm - inset, inset),
+        };
+        HangHole { x, y, diameter }
+    }
+
+    /// Move the hole so it keeps a minimum wall to the plate edge, including
+    /// rounded corners. Fails if the plate is too small for the diameter.
+    pub fn fit(self, width_mm: f32, height_mm: f32, corner_radius: f32) -> Result<Self> {
+        let margin = self.diameter / 2.0 + HANG_HOLE_MIN_WALL_MM;
+        if 2.0 * margin > width_mm.min(height_mm) {
+            bail!(
+                "Hang hole diameter {}mm is too large for a {}x{}mm plate (needs {}mm of wall on each side)",
+                self.diameter,
+                width_mm,
+                height_mm,
+                HANG_HOLE_MIN_WALL_MM
+            );
+        }
+
+        let mut x = self.x.clamp(margin, width_mm - margin);
+        let mut y = self.y.clamp(margin, height_mm - margin);
+
+        // Inside a corner square the edge is the arc, so pull the hole in
+        // towards the arc center
+        let radius = corner_radius.min(width_mm.min(height_mm) / 2.0);
+        let arc_x = x.clamp(radius, width_mm - radius);
+        let arc_y = y.clamp(radius, height_mm - radius);
+        let (dx, dy) = (x - arc_x, y - arc_y);
+        let distance = (dx * dx + dy * dy).sqrt();
+        let limit = radius - margin;
+        if distance > limit && distance > 0.0 {
+            x = arc_x + dx * limit / distance;
+            y = arc_y + dy * limit / distance;
+        }
+
+        Ok(HangHole {
+            x,
+            y,
+            diameter: self.diameter,
+        })
+    }
+
+    /// Counter-clockwise ring around the hole, unclosed
+    fn ring(&self) -> Vec<(f32, f32)> {
+        let radius = self.diameter / 2.0;
+        (0..HANG_HOLE_SEGMENTS)
+            .map(|i| {
+                let angle = std::f32::consts::TAU * i as f32 / HANG_HOLE_SEGMENTS as f32;
+                (self.x + radius * angle.cos(), self.y + radius * angle.sin())
+            })
+            .collect()
+    }
+}
 
 /// Generate a base plate mesh (rectangular box from z=0 to z=thickness)
-pub fn generate_base_plate(size_mm: f32, thickness: f32) -> Vec<Triangle> {
+///
+/// A positive `corner_radius_mm` rounds the four vertical edges; it is
+/// clamped to half the shorter side. A radius of 0 gives a plain
+/// 12-triangle box. A `hang_hole` (already [`HangHole::fit`] to the plate)
+/// is cut through the full thickness.
+///
+/// With `hollow_wall_mm`, the underside is pocketed into cavities of at
+/// most [`HOLLOW_CELL_MM`] across, separated by ribs and surrounded by a rim
+/// that wide, under a top skin of the same thickness. The plate falls back
+/// to solid when there is no room for a cavity.
+pub fn generate_base_plate(
+    width_mm: f32,
+    height_mm: f32,
+    thickness: f32,
+    corner_radius_mm: f32,
+    hang_hole: Option<HangHole>,
+    hollow_wall_mm: Option<f32>,
+) -> Vec<Triangle> {
+    let radius = corner_radius_mm.min(width_mm.min(height_mm) / 2.0);
+    if let Some(wall) = hollow_wall_mm {
+        let cells = hollow_cells(width_mm, height_mm, radius, wall, hang_hole);
+        let ceiling = thickness - wall;
+        if !cells.is_empty() && ceiling >= HOLLOW_MIN_CAVITY_MM {
+            return generate_hollow_plate(
+                &plate_outline(width_mm, height_mm, radius),
+                hang_hole,
+                &cells,
+                thickness,
+                ceiling,
+            );
+        }
+    }
+    if let Some(hole) = hang_hole {
+        let outline = plate_outline(width_mm, height_mm, radius);
+        return extrude_polygon(&outline, &[hole.ring()], 0.0, thickness);
+    }
+    if radius > 0.0 {
+        return generate_rounded_plate(width_mm, height_mm, thickness, radius);
+    }
+
     let mut triangles = Vec::new();
 
     let x_min = 0.0;
-    let x_max = size_mm;
+    let x_max = width_mm;
     let y_min = 0.0;
-    let y_max = size_mm;
+    let y_max = height_mm;
     let z_bottom = 0.0;
     let z_top = thickness;
 
@@ -47,7 +334,7 @@ pub fn generate_base_plate(size_mm: f32, thickness: f32) -> Vec<Triangle> {
         [x_max, y_min, z_top],
     ));
 
-    // Back face (y = size)
+    // Back face (y = height)
     triangles.push(Triangle::new(
         [x_min, y_max, z_bottom],
         [x_max, y_max, z_bottom],
@@ -71,7 +358,7 @@ pub fn generate_base_plate(size_mm: f32, thickness: f32) -> Vec<Triangle> {
         [x_min, y_min, z_top],
     ));
 
-    // Right face (x = size)
+    // Right face (x = width)
     triangles.push(Triangle::new(
         [x_max, y_min, z_bottom],
         [x_max, y_max, z_top],
@@ -86,14 +373,580 @@ pub fn generate_base_plate(size_mm: f32, thickness: f32) -> Vec<Triangle> {
     triangles
 }
 
+/// Rectangular cavities under a hollow plate, kept `wall` clear of the
+/// edges, of each other and of the hang hole
+fn hollow_cells(
+    width_mm: f32,
+    height_mm: f32,
+    radius: f32,
+    wall: f32,
+    hang_hole: Option<HangHole>,
+) -> Vec<Vec<(f32, f32)>> {
+    if wall <= 0.0 {
+        return Vec::new();
+    }
+    // Far enough in that the corner cells clear the rounded corners by `wall`
+    let margin = wall.max(radius - (radius - wall) / std::f32::consts::SQRT_2);
+    let split = |length: f32| -> Vec<(f32, f32)> {
+        let inner = length - 2.0 * margin;
+        let count = ((inner + wall) / (HOLLOW_CELL_MM + wall)).ceil().max(1.0);
+        let cell = (inner - (count - 1.0) * wall) / count;
+        if cell <= 0.0 {
+            return Vec::new();
+        }
+        (0..count as usize)
+            .map(|i| {
+                let start = margin + i as f32 * (cell + wall);
+                (start, start + cell)
+            })
+            .collect()
+    };
+
+    let (columns, rows) = (split(width_mm), split(height_mm));
+    let mut cells = Vec::new();
+    for &(x0, x1) in &columns {
+        for &(y0, y1) in &rows {
+            // Leave the cell solid if it comes within a wall of the hole
+            let near_hole = hang_hole.is_some_and(|hole| {
+                let reach = hole.diameter / 2.0 + wall;
+                let dx = (x0 - hole.x).max(hole.x - x1).max(0.0);
+                let dy = (y0 - hole.y).max(hole.y - y1).max(0.0);
+                dx.hypot(dy) < reach
+            });
+            if !near_hole {
+                cells.push(vec![(x0, y0), (x1, y0), (x1, y1), (x0, y1)]);
+            }
+        }
+    }
+    cells
+}
+
+/// Closed plate pocketed from below by `cells`, each open up to `ceiling`
+fn generate_hollow_plate(
+    outline: &[(f32, f32)],
+    hang_hole: Option<HangHole>,
+    cells: &[Vec<(f32, f32)>],
+    thickness: f32,
+    ceiling: f32,
+) -> Vec<Triangle> {
+    let through: Vec<Vec<(f32, f32)>> = hang_hole.map(|hole| hole.ring()).into_iter().collect();
+    let underside: Vec<Vec<(f32, f32)>> = through.iter().chain(cells).cloned().collect();
+
+    let mut triangles = Vec::new();
+    add_cap(&mut triangles, outline, &through, thickness, true);
+    add_cap(&mut triangles, outline, &underside, 0.0, false);
+    add_walls(&mut triangles, outline, 0.0, thickness, true);
+    for ring in &through {
+        add_walls(&mut triangles, ring, 0.0, thickness, false);
+    }
+    for cell in cells {
+        add_walls(&mut triangles, cell, 0.0, ceiling, false);
+        add_cap(&mut triangles, cell, &[], ceiling, false);
+    }
+    triangles
+}
+
+/// Flat face over `outer` minus `holes` at height `z`, facing up or down
+fn add_cap(
+    triangles: &mut Vec<Triangle>,
+    outer: &[(f32, f32)],
+    holes: &[Vec<(f32, f32)>],
+    z: f32,
+    up: bool,
+) {
+    let points: Vec<(f32, f32)> = outer
+        .iter()
+        .chain(holes.iter().flatten())
+        .copied()
+        .collect();
+    // Earcut drops collinear ring vertices, which leaves T-junctions where
+    // cell edges line up; split any edge that passes through a vertex
+    let mut pending: Vec<[usize; 3]> = triangulate_polygon(outer, holes)
+        .chunks_exact(3)
+        .map(|tri| [tri[0], tri[1], tri[2]])
+        .collect();
+    let mut split = Vec::with_capacity(pending.len());
+    while let Some(tri) = pending.pop() {
+        match find_edge_vertex(&points, tri) {
+            Some((edge, v)) => {
+                let [p, q, r] = [tri[edge], tri[(edge + 1) % 3], tri[(edge + 2) % 3]];
+                pending.push([p, v, r]);
+                pending.push([v, q, r]);
+            }
+            None => split.push(tri),
+        }
+    }
+    for tri in split {
+        let [a, b, c] = tri.map(|i| [points[i].0, points[i].1, z]);
+        let ccw = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]) > 0.0;
+        triangles.push(if ccw == up {
+            Triangle::new(a, b, c)
+        } else {
+            Triangle::new(a, c, b)
+        });
+    }
+}
+
+/// First vertex lying strictly inside one of the triangle's edges, as
+/// (edge index, vertex index)
+fn find_edge_vertex(points: &[(f32, f32)], tri: [usize; 3]) -> Option<(usize, usize)> {
+    const EPS: f32 = 1e-4;
+    (0..3).find_map(|edge| {
+        let (p, q) = (points[tri[edge]], points[tri[(edge + 1) % 3]]);
+        let (dx, dy) = (q.0 - p.0, q.1 - p.1);
+        let len_sq = dx * dx + dy * dy;
+        if len_sq <= EPS * EPS {
+            return None;
+        }
+        let len = len_sq.sqrt();
+        points
+            .iter()
+            .enumerate()
+            .find(|&(i, &v)| {
+                if tri.contains(&i) || v == p || v == q {
+                    return false;
+                }
+                let (vx, vy) = (v.0 - p.0, v.1 - p.1);
+                let along = (vx * dx + vy * dy) / len;
+                let across = (vx * dy - vy * dx).abs() / len;
+                across < EPS && along > EPS && along < len - EPS
+            })
+            .map(|(i, _)| (edge, i))
+    })
+}
+
+/// Vertical walls along a closed ring from `z_bottom` to `z_top`, facing
+/// away from the ring's interior if `outward`, into it otherwise
+fn add_walls(
+    triangles: &mut Vec<Triangle>,
+    ring: &[(f32, f32)],
+    z_bottom: f32,
+    z_top: f32,
+    outward: bool,
+) {
+    let n = ring.len();
+    let doubled_area: f32 = (0..n)
+        .map(|i| {
+            let (p, q) = (ring[i], ring[(i + 1) % n]);
+            p.0 * q.1 - q.0 * p.1
+        })
+        .sum();
+    // Counter-clockwise rings face outward with this winding
+    let flip = (doubled_area > 0.0) != outward;
+    for i in 0..n {
+        let (p, q) = (ring[i], ring[(i + 1) % n]);
+        let (b0, b1) = ([p.0, p.1, z_bottom], [q.0, q.1, z_bottom]);
+        let (t0, t1) = ([p.0, p.1, z_top], [q.0, q.1, z_top]);
+        if flip {
+            triangles.push(Triangle::new(b0, t1, b1));
+            triangles.push(Triangle::new(b0, t0, t1));
+        } else {
+            triangles.push(Triangle::new(b0, b1, t1));
+            triangles.push(Triangle::new(b0, t1, t0));
+        }
+    }
+}
+
+/// Plate seen from above: a counter-clockwise outline and the holes in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlateFootprint {
+    pub outline: Vec<(f32, f32)>,
+    pub holes: Vec<Vec<(f32, f32)>>,
+}
+
+impl PlateFootprint {
+    /// Footprint of [`generate_base_plate`]; a hollow base has the same
+    /// footprint as a solid one
+    pub fn square(
+        width_mm: f32,
+        height_mm: f32,
+        corner_radius_mm: f32,
+        hang_hole: Option<HangHole>,
+    ) -> Self {
+        let radius = corner_radius_mm.min(width_mm.min(height_mm) / 2.0);
+        Self {
+            outline: plate_outline(width_mm, height_mm, radius),
+            holes: hang_hole.iter().map(HangHole::ring).collect(),
+        }
+    }
+
+    /// Footprint of [`generate_circular_base`]
+    pub fn circle(diameter_mm: f32, segments: usize) -> Self {
+        Self {
+            outline: circular_outline(diameter_mm, segments),
+            holes: Vec::new(),
+        }
+    }
+
+    /// Footprint of [`generate_hex_base`]
+    pub fn hexagon(circumradius_mm: f32) -> Self {
+        Self {
+            outline: hexagon_outline(circumradius_mm),
+            holes: Vec::new(),
+        }
+    }
+}
+
+fn circular_outline(diameter_mm: f32, segments: usize) -> Vec<(f32, f32)> {
+    let segments = segments.max(3);
+    let radius = diameter_mm / 2.0;
+    (0..segments)
+        .map(|i| {
+            let angle = std::f32::consts::TAU * i as f32 / segments as f32;
+            (radius + radius * angle.cos(), radius + radius * angle.sin())
+        })
+        .collect()
+}
+
+/// Corners of a flat-topped hexagon counter-clockwise from the right
+fn hexagon_outline(circumradius_mm: f32) -> Vec<(f32, f32)> {
+    let cy = circumradius_mm * 3.0_f32.sqrt() / 2.0;
+    (0..6)
+        .map(|i| {
+            let angle = std::f32::consts::TAU * i as f32 / 6.0;
+            (
+                circumradius_mm + circumradius_mm * angle.cos(),
+                cy + circumradius_mm * angle.sin(),
+            )
+        })
+        .collect()
+}
+
+/// Counter-clockwise plate outline, unclosed; corners are arcs when
+/// `radius` is positive
+fn plate_outline(width_mm: f32, height_mm: f32, radius: f32) -> Vec<(f32, f32)> {
+    if radius <= 0.0 {
+        return vec![
+            (0.0, 0.0),
+            (width_mm, 0.0),
+            (width_mm, height_mm),
+            (0.0, height_mm),
+        ];
+    }
+
+    // Counter-clockwise from the bottom-right corner; each arc spans 90°
+    let corners = [
+        (width_mm - radius, radius, -90.0_f32),
+        (width_mm - radius, height_mm - radius, 0.0),
+        (radius, height_mm - radius, 90.0),
+        (radius, radius, 180.0),
+    ];
+    let mut outline: Vec<(f32, f32)> = corners
+        .iter()
+        .flat_map(|&(ox, oy, start)| {
+            (0..=CORNER_SEGMENTS).map(move |i| {
+                let angle = (start + 90.0 * i as f32 / CORNER_SEGMENTS as f32).to_radians();
+                (ox + radius * angle.cos(), oy + radius * angle.sin())
+            })
+        })
+        .collect();
+    // Where a straight edge has zero length the arc ends coincide
+    outline.dedup();
+    if outline.len() > 1 && outline.first() == outline.last() {
+        outline.pop();
+    }
+    outline
+}
+
+/// Rounded-rectangle plate: convex outline fanned from the center for the
+/// top and bottom faces, with a quad per outline edge for the side wall
+fn generate_rounded_plate(
+    width_mm: f32,
+    height_mm: f32,
+    thickness: f32,
+    radius: f32,
+) -> Vec<Triangle> {
+    let (cx, cy) = (width_mm / 2.0, height_mm / 2.0);
+    let z_bottom = 0.0;
+    let z_top = thickness;
+    let outline = plate_outline(width_mm, height_mm, radius);
+
+    let n = outline.len();
+    let mut triangles = Vec::with_capacity(n * 4);
+    for i in 0..n {
+        let (x0, y0) = outline[i];
+        let (x1, y1) = outline[(i + 1) % n];
+
+        // Top fan (normal up), bottom fan (normal down)
+        triangles.push(Triangle::new(
+            [cx, cy, z_top],
+            [x0, y0, z_top],
+            [x1, y1, z_top],
+        ));
+        triangles.push(Triangle::new(
+            [cx, cy, z_bottom],
+            [x1, y1, z_bottom],
+            [x0, y0, z_bottom],
+        ));
+
+        // Side wall (normal outward)
+        triangles.push(Triangle::new(
+            [x0, y0, z_bottom],
+            [x1, y1, z_bottom],
+            [x1, y1, z_top],
+        ));
+        triangles.push(Triangle::new(
+            [x0, y0, z_bottom],
+            [x1, y1, z_top],
+            [x0, y0, z_top],
+        ));
+    }
+
+    triangles
+}
+
+/// Generate a cylindrical base plate (disc from z=0 to z=thickness)
+///
+/// The cylinder sits in the `diameter_mm` square starting at the origin, the
+/// same footprint as a square plate of that size. `segments` is clamped to
+/// at least 3.
+pub fn generate_circular_base(diameter_mm: f32, thickness: f32, segments: usize) -> Vec<Triangle> {
+    let radius = diameter_mm / 2.0;
+    generate_prism(
+        (radius, radius),
+        &circular_outline(diameter_mm, segments),
+        thickness,
+    )
+}
+
+/// Generate a hexagonal base plate (prism from z=0 to z=thickness)
+///
+/// The hexagon has corners to the left and right and flat top and bottom
+/// edges, and fills the `2 * circumradius_mm` by `sqrt(3) * circumradius_mm`
+/// rectangle starting at the origin, so plates tile in a honeycomb.
+pub fn generate_hex_base(circumradius_mm: f32, thickness: f32) -> Vec<Triangle> {
+    let center = (circumradius_mm, circumradius_mm * 3.0_f32.sqrt() / 2.0);
+    generate_prism(center, &hexagon_outline(circumradius_mm), thickness)
+}
+
+/// Prism over a convex counter-clockwise `rim`, fanned from `center`
+fn generate_prism(center: (f32, f32), rim: &[(f32, f32)], thickness: f32) -> Vec<Triangle> {
+    let (cx, cy) = center;
+    let z_bottom = 0.0;
+    let z_top = thickness;
+
+    let mut triangles = Vec::with_capacity(rim.len() * 4);
+    for i in 0..rim.len() {
+        let (x0, y0) = rim[i];
+        let (x1, y1) = rim[(i + 1) % rim.len()];
+
+        // Top fan (normal up), bottom fan (normal down)
+        triangles.push(Triangle::new(
+            [cx, cy, z_top],
+            [x0, y0, z_top],
+            [x1, y1, z_top],
+        ));
+        triangles.push(Triangle::new(
+            [cx, cy, z_bottom],
+            [x1, y1, z_bottom],
+            [x0, y0, z_bottom],
+        ));
+
+        // Side wall (normal outward)
+        triangles.push(Triangle::new(
+            [x0, y0, z_bottom],
+            [x1, y1, z_bottom],
+            [x1, y1, z_top],
+        ));
+        triangles.push(Triangle::new(
+            [x0, y0, z_bottom],
+            [x1, y1, z_top],
+            [x0, y0, z_top],
+        ));
+    }
+
+    triangles
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mesh::test_util::signed_volume;
+    use crate::mesh::validation::validate_mesh;
 
     #[test]
     fn test_base_plate_triangle_count() {
-        let triangles = generate_base_plate(100.0, 2.0);
+        let triangles = generate_base_plate(100.0, 60.0, 2.0, 0.0, None, None);
         // 6 faces * 2 triangles each = 12 triangles
         assert_eq!(triangles.len(), 12);
     }
+
+    #[test]
+    fn test_rounded_base_plate_corners() {
+        let triangles = generate_base_plate(100.0, 60.0, 2.0, 5.0, None, None);
+        // 4 arcs of CORNER_SEGMENTS edges plus 4 straight edges, 4 triangles each
+        assert_eq!(triangles.len(), (4 * CORNER_SEGMENTS + 4) * 4);
+
+        // Nothing reaches the sharp corner, but the straight edges still touch the sides
+        let corner_clear = triangles.iter().flat_map(|t| t.vertices).all(|v| {
+            let (dx, dy) = ((v[0] - 95.0).max(0.0), (v[1] - 55.0).max(0.0));
+            (dx * dx + dy * dy).sqrt() <= 5.0 + 1e-3
+        });
+        assert!(corner_clear);
+        assert!(
+            triangles
+                .iter()
+                .flat_map(|t| t.vertices)
+                .any(|v| v[0] == 100.0)
+        );
+    }
+
+    #[test]
+    fn test_circular_base_within_square() {
+        let triangles = generate_circular_base(100.0, 2.0, CIRCULAR_BASE_SEGMENTS);
+        assert_eq!(triangles.len(), CIRCULAR_BASE_SEGMENTS * 4);
+        for t in &triangles {
+            for v in &t.vertices {
+                assert!(v[0] >= -1e-4 && v[0] <= 100.0001);
+                assert!(v[1] >= -1e-4 && v[1] <= 100.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn test_hex_base_triangle_count() {
+        let triangles = generate_hex_base(50.0, 2.0);
+        // 6 sides, each with a top and bottom fan triangle and two wall triangles
+        assert_eq!(triangles.len(), 24);
+        assert!(validate_mesh(&triangles).manifold.is_watertight());
+        let height = 50.0 * 3.0_f32.sqrt();
+        for v in triangles.iter().flat_map(|t| t.vertices) {
+            assert!(v[0] >= -1e-4 && v[0] <= 100.0001);
+            assert!(v[1] >= -1e-4 && v[1] <= height + 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_circular_base_normals() {
+        let triangles = generate_circular_base(50.0, 3.0, 8);
+        assert_eq!(triangles.len(), 32);
+        for t in &triangles {
+            let n = t.normal;
+            let center = [
+                (t.vertices[0][0] + t.vertices[1][0] + t.vertices[2][0]) / 3.0 - 25.0,
+                (t.vertices[0][1] + t.vertices[1][1] + t.vertices[2][1]) / 3.0 - 25.0,
+                (t.vertices[0][2] + t.vertices[1][2] + t.vertices[2][2]) / 3.0 - 1.5,
+            ];
+            // Every face points away from the middle of the disc
+            assert!(n[0] * center[0] + n[1] * center[1] + n[2] * center[2] > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_base_plate_with_hang_hole() {
+        let hole = HangHole {
+            x: 10.0,
+            y: 10.0,
+            diameter: 4.0,
+        };
+        let triangles = generate_base_plate(100.0, 60.0, 2.0, 0.0, Some(hole), None);
+        // Top and bottom faces around the hole, plus outer and inner walls
+        let faces = 2 * (4 + HANG_HOLE_SEGMENTS);
+        assert_eq!(triangles.len(), faces + 2 * (4 + HANG_HOLE_SEGMENTS));
+
+        // No top face covers the hole center
+        let covers_center = triangles.iter().any(|t| {
+            let [a, b, c] = t.vertices;
+            let side = |p: [f32; 3], q: [f32; 3]| {
+                (q[0] - p[0]) * (10.0 - p[1]) - (q[1] - p[1]) * (10.0 - p[0])
+            };
+            let (s0, s1, s2) = (side(a, b), side(b, c), side(c, a));
+            (s0 > 0.0 && s1 > 0.0 && s2 > 0.0) || (s0 < 0.0 && s1 < 0.0 && s2 < 0.0)
+        });
+        assert!(!covers_center);
+    }
+
+    #[test]
+    fn test_hollow_base_plate() {
+        let hole = HangHole {
+            x: 10.0,
+            y: 10.0,
+            diameter: 4.0,
+        };
+        for (radius, hang_hole) in [(0.0, None), (8.0, Some(hole))] {
+            let triangles = generate_base_plate(100.0, 60.0, 3.0, radius, hang_hole, Some(1.2));
+            let report = validate_mesh(&triangles);
+            assert!(report.manifold.is_watertight());
+
+            // Facing outward and much lighter than the solid plate
+            let volume = signed_volume(&triangles);
+            let solid = signed_volume(&generate_base_plate(
+                100.0, 60.0, 3.0, radius, hang_hole, None,
+            ))
+            .abs();
+            assert!(volume > 0.0 && volume < solid * 0.6);
+
+            // The top skin is unbroken apart from the hang hole
+            let top_area: f32 = triangles
+                .iter()
+                .filter(|t| t.vertices.iter().all(|v| v[2] == 3.0))
+                .map(|t| {
+                    let [a, b, c] = t.vertices;
+                    ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) / 2.0
+                })
+                .sum();
+            assert!((top_area - solid / 3.0).abs() < 1.0);
+        }
+
+        // Too thin for a cavity: solid plate
+        let solid = generate_base_plate(100.0, 60.0, 1.5, 0.0, None, Some(1.2));
+        assert_eq!(solid.len(), 12);
+    }
+
+    #[test]
+    fn test_hang_hole_fit() {
+        let hole: HangHole = "-5,200,4".parse().unwrap();
+        let fitted = hole.fit(100.0, 60.0, 0.0).unwrap();
+        assert_eq!((fitted.x, fitted.y), (3.5, 56.5));
+
+        // Pulled in along the diagonal of a rounded corner
+        let fitted = hole.fit(100.0, 60.0, 10.0).unwrap();
+        let (dx, dy) = (fitted.x - 10.0, fitted.y - 50.0);
+        assert!(((dx * dx + dy * dy).sqrt() - 6.5).abs() < 1e-4);
+
+        let large = HangHole {
+            x: 50.0,
+            y: 30.0,
+            diameter: 58.0,
+        };
+        assert!(large.fit(100.0, 60.0, 0.0).is_err());
+        assert!("1,2".parse::<HangHole>().is_err());
+        assert!("1,2,0".parse::<HangHole>().is_err());
+    }
+
+    #[test]
+    fn test_hang_hole_in_corner() {
+        let hole = HangHole::in_corner(PlateCorner::BottomRight, 100.0, 60.0, 4.0, 0.0);
+        assert_eq!((hole.x, hole.y), (95.0, 5.0));
+        assert_eq!("top-left".parse::<PlateCorner>(), Ok(PlateCorner::TopLeft));
+    }
+
+    #[test]
+    fn test_bed_size() {
+        let bed: BedSize = "256x220".parse().unwrap();
+        assert_eq!(
+            bed,
+            BedSize {
+                width: 256.0,
+                height: 220.0
+            }
+        );
+        assert!("256".parse::<BedSize>().is_err());
+        assert!("8x8".parse::<BedSize>().is_err());
+
+        // Usable area is 246 x 210
+        assert_eq!(bed.overflow(220.0, 200.0), None);
+        // Fits turned sideways
+        assert_eq!(bed.overflow(200.0, 240.0), None);
+        assert_eq!(bed.overflow(300.0, 220.0), Some((54.0, 10.0)));
+        assert_eq!(bed.fit_scale(220.0, 200.0), 1.0);
+        assert!((bed.fit_scale(300.0, 300.0) - 0.7).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_map_shape_from_str() {
+        assert_eq!("circle".parse::<MapShape>(), Ok(MapShape::Circle));
+        assert_eq!("Hexagon".parse::<MapShape>(), Ok(MapShape::Hexagon));
+        assert!("triangle".parse::<MapShape>().is_err());
+    }
 }
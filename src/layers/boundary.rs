@@ -0,0 +1,93 @@
+//! Raised outline of the place's administrative boundary
+//!
+//! Frames the map with the real border of the city or district rather than
+//! the plate edge. Parts of the border outside the map are cut off.
+
+use crate::geometry::bbox::clip_polyline;
+use crate::geometry::hexagon::Outline;
+use crate::geometry::{Bounds, Projector, Scaler, dedup_collinear};
+use crate::mesh::{RibbonOptions, Triangle, extrude_ribbon_ex};
+
+/// Width of the boundary line in mm, bolder than the grid so it reads as
+/// the frame of the map
+pub const BOUNDARY_LINE_WIDTH_MM: f32 = 1.2;
+
+/// Turns of less than this many degrees are dropped from the outline
+const STRAIGHT_TOLERANCE_DEG: f32 = 1.0;
+
+/// Outline of `rings` in (lat, lon), clipped to the projected map `bounds`
+/// and to the `outline` of a round or hexagonal map
+///
+/// Each ring is a ribbon [`BOUNDARY_LINE_WIDTH_MM`] wide from z=0 to `z_top`.
+pub fn generate_boundary(
+    rings: &[Vec<(f64, f64)>],
+    projector: &Projector,
+    scaler: &Scaler,
+    bounds: &Bounds,
+    outline: Option<Outline>,
+    z_top: f32,
+) -> Vec<Triangle> {
+    let options = RibbonOptions::default();
+    // Projected bounds go in as (min x, min y, max x, max y), like the grid
+    let box_clip = (bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y);
+    rings
+        .iter()
+        .flat_map(|ring| {
+            clip_polyline(&projector.project_points(ring), box_clip)
+                .into_iter()
+                .flat_map(|piece| match outline {
+                    Some(outline) => outline.clip_polyline(&piece),
+                    None => vec![piece],
+                })
+                .collect::<Vec<_>>()
+        })
+        .flat_map(|piece| {
+            let scaled = dedup_collinear(&scaler.scale_points(&piece), STRAIGHT_TOLERANCE_DEG);
+            extrude_ribbon_ex(&scaled, BOUNDARY_LINE_WIDTH_MM, z_top, 0.0, &options)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::circle::Circle;
+
+    #[test]
+    fn test_boundary_clipped_to_map() {
+        let projector = Projector::new((45.0, 10.0));
+        let bounds = Bounds::from_points(&[(-2500.0, -2500.0), (2500.0, 2500.0)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 100.0);
+        // A square border about 2km across, and one reaching past the map
+        let inner = vec![
+            (44.99, 9.985),
+            (44.99, 10.015),
+            (45.01, 10.015),
+            (45.01, 9.985),
+            (44.99, 9.985),
+        ];
+        let outer = vec![(44.9, 10.0), (45.1, 10.0)];
+        let triangles = generate_boundary(&[inner, outer], &projector, &scaler, &bounds, None, 3.0);
+        assert!(!triangles.is_empty());
+
+        let slack = BOUNDARY_LINE_WIDTH_MM;
+        for v in triangles.iter().flat_map(|t| t.vertices) {
+            assert!((-slack..=100.0 + slack).contains(&v[0]));
+            assert!((-slack..=100.0 + slack).contains(&v[1]));
+            assert!(v[2] == 0.0 || v[2] == 3.0);
+        }
+
+        let circle = Outline::Circle(Circle::new((0.0, 0.0), 500.0));
+        assert!(
+            generate_boundary(
+                &[vec![(44.99, 9.985), (45.01, 9.985)]],
+                &projector,
+                &scaler,
+                &bounds,
+                Some(circle),
+                3.0
+            )
+            .is_empty()
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use crate::config::heights;
+use crate::domain::BuildingPolygon;
+use crate::geometry::{Projector, Scaler};
+use crate::mesh::{Triangle, extrude_polygon};
+use rayon::prelude::*;
+
+#[derive(Debug, Clone)]
+pub struct BuildingConfig {
+    /// Z where building heights start (top of the base plate)
+    pub z_base: f32,
+    /// Vertical exaggeration: model mm per real-world meter of height
+    pub mm_per_meter: f32,
+    /// Height used for buildings without `height`/`building:levels` tags, in meters
+    pub default_height_m: f32,
+    /// Shortest printable building above the base, in mm
+    pub min_height_mm: f32,
+    /// Tallest building above the base, in mm (skyscrapers are clamped)
+    pub max_height_mm: f32,
+}
+
+impl Default for BuildingConfig {
+    fn default() -> Self {
+        Self {
+            z_base: 2.0,
+            mm_per_meter: 0.05,
+            default_height_m: 10.0,
+            min_height_mm: heights::BUILDING_MIN_HEIGHT,
+            max_height_mm: heights::BUILDING_MAX_HEIGHT,
+        }
+    }
+}
+
+impl BuildingConfig {
+    /// Absolute Z of a building's roof
+    pub fn z_top(&self, height_m: Option<f64>) -> f32 {
+        let height_m = height_m.map(|h| h as f32).unwrap_or(self.default_height_m);
+        let height_mm =
+            (height_m * self.mm_per_meter).clamp(self.min_height_mm, self.max_height_mm);
+        self.z_base + height_mm
+    }
+
+    pub fn with_z_base(mut self, z_base: f32) -> Self {
+        self.z_base = z_base;
+        self
+    }
+
+    pub fn with_default_height(mut self, height_m: f32) -> Self {
+        self.default_height_m = height_m;
+        self
+    }
+}
+
+pub fn generate_building_meshes(
+    building_polygons: &[BuildingPolygon],
+    projector: &Projector,
+    scaler: &Scaler,
+    config: &BuildingConfig,
+) -> Vec<Triangle> {
+    building_polygons
+        .par_iter()
+        .filter(|polygon| polygon.is_valid())
+        .flat_map_iter(|polygon| {
+            let scaled: Vec<(f32, f32)> = polygon
+                .outer
+                .iter()
+                .map(|&(lat, lon)| {
+                    let (x, y) = projector.project(lat, lon);
+                    scaler.scale(x, y)
+                })
+                .collect();
+
+            let z_top = config.z_top(polygon.height_m);
+            extrude_polygon(&scaled, &[], 0.0, z_top)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Bounds, Projector, Scaler};
+
+    fn max_z(triangles: &[Triangle]) -> f32 {
+        triangles
+            .iter()
+            .flat_map(|t| t.vertices.iter().map(|v| v[2]))
+            .fold(f32::MIN, f32::max)
+    }
+
+    #[test]
+    fn test_generate_buildings_empty() {
+        let projector = Projector::new((0.0, 0.0));
+        let bounds = Bounds::from_points(&[(0.0, 0.0), (1000.0, 1000.0)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 220.0);
+
+        let triangles =
+            generate_building_meshes(&[], &projector, &scaler, &BuildingConfig::default());
+        assert!(triangles.is_empty());
+    }
+
+    #[test]
+    fn test_generate_buildings_uses_tagged_height() {
+        let projector = Projector::new((0.0, 0.0));
+        let bounds = Bounds::from_points(&[(-500.0, -500.0), (500.0, 500.0)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 220.0);
+        let footprint = vec![
+            (0.0, 0.0),
+            (0.0, 0.001),
+            (0.001, 0.001),
+            (0.001, 0.0),
+            (0.0, 0.0),
+        ];
+        let config = BuildingConfig::default();
+
+        let untagged = BuildingPolygon::new(footprint.clone());
+        let tower = BuildingPolygon::new(footprint).with_height(Some(60.0));
+
+        let low = generate_building_meshes(&[untagged], &projector, &scaler, &config);
+        let high = generate_building_meshes(&[tower], &projector, &scaler, &config);
+        assert!((max_z(&low) - 2.5).abs() < 1e-5);
+        assert!((max_z(&high) - 5.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_building_height_clamped() {
+        let config = BuildingConfig::default().with_z_base(1.0);
+        assert_eq!(config.z_top(Some(1.0)), 1.0 + config.min_height_mm);
+        assert_eq!(config.z_top(Some(800.0)), 1.0 + config.max_height_mm);
+    }
+}
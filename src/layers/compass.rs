@@ -0,0 +1,154 @@
+//! North arrow printed in the text margin
+
+use super::text::TextRenderer;
+use crate::mesh::{Triangle, extrude_polygon};
+
+/// Half the arrow length as a fraction of the compass size
+const ARROW_HALF_LENGTH: f32 = 0.25;
+
+/// Half the arrow width at its base as a fraction of the compass size
+const ARROW_HALF_WIDTH: f32 = 0.17;
+
+/// Height of the "N" as a fraction of the compass size
+const LABEL_HEIGHT: f32 = 0.2;
+
+/// Gap between the arrow tip and the "N" as a fraction of the compass size
+const LABEL_GAP: f32 = 0.03;
+
+/// Space kept between the compass and the plate edges
+const EDGE_PADDING_MM: f32 = 2.0;
+
+/// End of the text margin that holds the compass
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompassCorner {
+    Left,
+    Right,
+}
+
+impl std::str::FromStr for CompassCorner {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "left" | "bottom-left" => Ok(CompassCorner::Left),
+            "right" | "bottom-right" => Ok(CompassCorner::Right),
+            _ => Err(format!(
+                "Invalid compass corner '{}'. Valid options: bottom-left, bottom-right",
+                s
+            )),
+        }
+    }
+}
+
+impl CompassCorner {
+    /// Compass size and center for a plate `width_mm` wide with a text
+    /// margin `margin_mm` tall, kept clear of the centered labels
+    pub fn placement(self, width_mm: f32, margin_mm: f32) -> (f32, (f32, f32)) {
+        let size = (margin_mm - 2.0 * EDGE_PADDING_MM).min(width_mm * 0.1);
+        let offset = EDGE_PADDING_MM + size / 2.0;
+        let x = match self {
+            CompassCorner::Left => offset,
+            CompassCorner::Right => width_mm - offset,
+        };
+        (size, (x, margin_mm / 2.0))
+    }
+}
+
+/// Generate a north arrow with an "N" above its tip, centered at (x, y)
+///
+/// Everything stays within a circle of diameter `size_mm`, so the compass
+/// fits its square at any rotation. `rotation_deg` turns it
+/// counter-clockwise to match a map rotated with `--rotate`; the "N" follows
+/// the tip but stays upright. The arrow is extruded from z=0 to `z_top` like
+/// the labels.
+pub fn generate_compass(
+    x: f32,
+    y: f32,
+    size_mm: f32,
+    z_top: f32,
+    rotation_deg: f32,
+    renderer: &TextRenderer,
+) -> Vec<Triangle> {
+    let (sin, cos) = rotation_deg.to_radians().sin_cos();
+    let place = |(px, py): (f32, f32)| {
+        (
+            x + (px * cos - py * sin) * size_mm,
+            y + (px * sin + py * cos) * size_mm,
+        )
+    };
+
+    // Counter-clockwise outline pointing up, with a notch in the tail
+    let outline: Vec<(f32, f32)> = [
+        (0.0, ARROW_HALF_LENGTH),
+        (-ARROW_HALF_WIDTH, -ARROW_HALF_LENGTH),
+        (0.0, -ARROW_HALF_LENGTH / 2.0),
+        (ARROW_HALF_WIDTH, -ARROW_HALF_LENGTH),
+    ]
+    .into_iter()
+    .map(place)
+    .collect();
+    let mut triangles = extrude_polygon(&outline, &[], 0.0, z_top);
+
+    let label_height = LABEL_HEIGHT * size_mm;
+    let (label_x, label_y) = place((0.0, ARROW_HALF_LENGTH + LABEL_GAP + LABEL_HEIGHT / 2.0));
+    // An "N" is roughly 0.7 times as wide as it is tall
+    let scale = renderer.calculate_scale_for_width("N", label_height * 0.7);
+    triangles.extend(renderer.render_text_centered(
+        "N",
+        label_x,
+        label_y - label_height / 2.0,
+        0.0,
+        scale,
+        1.0,
+    ));
+
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::text::StrokeTextRenderer;
+
+    fn has_arrow_vertex(triangles: &[Triangle], x: f32, y: f32) -> bool {
+        // The arrow comes first: 2 triangles per cap and per wall
+        triangles[..12]
+            .iter()
+            .flat_map(|t| t.vertices)
+            .any(|v| (v[0] - x).abs() < 1e-4 && (v[1] - y).abs() < 1e-4)
+    }
+
+    #[test]
+    fn test_compass_points_north() {
+        let renderer = TextRenderer::Stroke(StrokeTextRenderer::new(3.0));
+        let triangles = generate_compass(50.0, 10.0, 16.0, 3.0, 0.0, &renderer);
+        assert!(triangles.len() > 12);
+
+        assert!(has_arrow_vertex(&triangles, 50.0, 14.0));
+        for v in triangles.iter().flat_map(|t| t.vertices) {
+            assert!((v[0] - 50.0).hypot(v[1] - 10.0) <= 8.0 + 1e-3);
+            assert!(v[2] >= -1e-4 && v[2] <= 3.0 + 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_compass_follows_rotation() {
+        let renderer = TextRenderer::Stroke(StrokeTextRenderer::new(3.0));
+        // Map rotated so east is up: north is to the left
+        let triangles = generate_compass(50.0, 10.0, 16.0, 3.0, 90.0, &renderer);
+        assert!(has_arrow_vertex(&triangles, 46.0, 10.0));
+        assert!(!has_arrow_vertex(&triangles, 50.0, 14.0));
+    }
+
+    #[test]
+    fn test_compass_corner_placement() {
+        let (size, (x, y)) = CompassCorner::Right.placement(220.0, 20.0);
+        assert_eq!(size, 16.0);
+        assert_eq!((x, y), (210.0, 10.0));
+        assert_eq!(
+            "bottom-left".parse::<CompassCorner>(),
+            Ok(CompassCorner::Left)
+        );
+        assert!("top".parse::<CompassCorner>().is_err());
+    }
+}
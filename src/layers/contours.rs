@@ -0,0 +1,73 @@
+//! Contour lines over a flat map
+//!
+//! A lighter take on terrain than the full relief: the elevation model is
+//! traced into raised lines at a fixed interval, and the plate stays flat.
+
+use crate::geometry::dedup_collinear;
+use crate::layers::Terrain;
+use crate::mesh::{RibbonOptions, Triangle, extrude_ribbon_ex};
+
+/// Width of the contour lines in mm, as thin as the graticule
+pub const CONTOUR_LINE_WIDTH_MM: f32 = 0.5;
+
+/// Turns of less than this many degrees are dropped from a traced line,
+/// which smooths the grid steps out of gentle slopes
+const STRAIGHT_TOLERANCE_DEG: f32 = 2.0;
+
+/// Lines every `interval_m` meters of elevation over `terrain`, each a ribbon
+/// [`CONTOUR_LINE_WIDTH_MM`] wide from z=0 to `z_top`
+///
+/// Levels are multiples of the interval, so maps of neighboring areas line
+/// up. The lowest point of the map gets no line.
+pub fn generate_contours(terrain: &Terrain, interval_m: f32, z_top: f32) -> Vec<Triangle> {
+    if interval_m <= 0.0 {
+        return Vec::new();
+    }
+    let (lowest, highest) = terrain.elevation_range();
+    let first = (lowest / interval_m).floor() as i64 + 1;
+    let last = (highest / interval_m).floor() as i64;
+
+    let options = RibbonOptions::default();
+    (first..=last)
+        .flat_map(|level| terrain.isolines(level as f32 * interval_m))
+        .flat_map(|line| {
+            let line = dedup_collinear(&line, STRAIGHT_TOLERANCE_DEG);
+            extrude_ribbon_ex(&line, CONTOUR_LINE_WIDTH_MM, z_top, 0.0, &options)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::{Bounds, Dem, Projector, Scaler};
+
+    #[test]
+    fn test_contours_ring_a_hill() {
+        // A 100m peak in the middle of a tenth of a degree square
+        let dem = Dem::new(
+            3,
+            3,
+            (-0.05, 0.05),
+            (0.05, 0.05),
+            vec![0.0, 0.0, 0.0, 0.0, 100.0, 0.0, 0.0, 0.0, 0.0],
+        )
+        .unwrap();
+        let projector = Projector::new((0.0, 0.0));
+        let bounds = Bounds::from_points(&[(-5000.0, -5000.0), (5000.0, 5000.0)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 100.0);
+        let terrain =
+            Terrain::sample(&dem, &projector, &scaler, ((0.0, 0.0), (100.0, 100.0)), 1.0).unwrap();
+
+        let triangles = generate_contours(&terrain, 40.0, 2.5);
+        assert!(!triangles.is_empty());
+        // Lines at 40m and 80m circle the peak without reaching the edges
+        // or the center
+        for v in triangles.iter().flat_map(|t| t.vertices) {
+            let r = (v[0] - 50.0).hypot(v[1] - 50.0);
+            assert!(r > 2.0 && r < 48.0);
+            assert!(v[2] == 0.0 || v[2] == 2.5);
+        }
+        assert!(generate_contours(&terrain, 200.0, 2.5).is_empty());
+    }
+}
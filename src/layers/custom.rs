@@ -0,0 +1,29 @@
+//! Features from a custom Overpass query, drawn without knowing their tags
+//!
+//! Areas print like parks and lines as thin ribbons, both in the parks band.
+
+use rayon::prelude::*;
+
+use super::parks::generate_park_meshes;
+use crate::domain::CustomFeatures;
+use crate::geometry::{Projector, Scaler};
+use crate::mesh::{RibbonOptions, Triangle, extrude_ribbon_ex};
+
+/// Width of custom lines in mm: two extrusions, so they print reliably
+pub const CUSTOM_LINE_WIDTH_MM: f32 = 0.8;
+
+/// Areas and lines from a custom query as columns from z=0 to `z_top`
+pub fn generate_custom_meshes(
+    features: &CustomFeatures,
+    projector: &Projector,
+    scaler: &Scaler,
+    z_top: f32,
+) -> Vec<Triangle> {
+    let mut triangles = generate_park_meshes(&features.areas, projector, scaler, z_top);
+    let options = RibbonOptions::default();
+    triangles.par_extend(features.lines.par_iter().flat_map_iter(|line| {
+        let scaled = scaler.scale_points(&projector.project_points(line));
+        extrude_ribbon_ex(&scaled, CUSTOM_LINE_WIDTH_MM, z_top, 0.0, &options)
+    }));
+    triangles
+}
@@ -0,0 +1,56 @@
+//! Raised border around the plate edge
+
+use crate::mesh::{Triangle, extrude_polygon};
+
+/// Generate a raised frame around a `width_mm` x `height_mm` plate
+///
+/// The four bars are `frame_thickness_mm` wide and joined at the corners
+/// into one closed ring, extruded from z=0 to `z_top`. The thickness is
+/// clamped so the opening never closes up.
+pub fn generate_frame(
+    width_mm: f32,
+    height_mm: f32,
+    frame_thickness_mm: f32,
+    z_top: f32,
+) -> Vec<Triangle> {
+    let t = frame_thickness_mm.min(width_mm.min(height_mm) / 2.0 * 0.9);
+    if t <= 0.0 {
+        return Vec::new();
+    }
+
+    let outer = [
+        (0.0, 0.0),
+        (width_mm, 0.0),
+        (width_mm, height_mm),
+        (0.0, height_mm),
+    ];
+    let opening = vec![
+        (t, t),
+        (width_mm - t, t),
+        (width_mm - t, height_mm - t),
+        (t, height_mm - t),
+    ];
+
+    extrude_polygon(&outer, &[opening], 0.0, z_top)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_leaves_opening() {
+        let triangles = generate_frame(100.0, 80.0, 3.0, 5.0);
+        // 8 cap triangles top and bottom, 2 per wall on 8 edges
+        assert_eq!(triangles.len(), 8 * 2 + 8 * 2);
+
+        // Every vertex lies on a bar: within the thickness of some edge
+        for v in triangles.iter().flat_map(|t| t.vertices) {
+            let on_bar = v[0] <= 3.0 || v[0] >= 97.0 || v[1] <= 3.0 || v[1] >= 77.0;
+            assert!(on_bar);
+            assert!(v[2] == 0.0 || v[2] == 5.0);
+        }
+
+        assert!(generate_frame(100.0, 80.0, 0.0, 5.0).is_empty());
+    }
+}
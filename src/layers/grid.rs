@@ -0,0 +1,147 @@
+//! Graticule of thin raised lines across the map
+//!
+//! Lines follow parallels and meridians, so on a rotated map they run at the
+//! same angle as the north arrow. They are spaced in meters measured at the
+//! map center and one of each passes through it.
+
+use crate::geometry::bbox::clip_polyline;
+use crate::geometry::hexagon::Outline;
+use crate::geometry::{Bounds, Projector, Scaler, dedup_collinear, lon_scale};
+use crate::mesh::{RibbonOptions, Triangle, extrude_ribbon_ex};
+
+/// Width of the grid lines in mm: about one extrusion, enough to print
+/// without covering the map
+pub const GRID_LINE_WIDTH_MM: f32 = 0.5;
+
+/// Meters per degree of latitude
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Points along each line, so lines bend with the projection where it curves
+const LINE_SAMPLES: usize = 64;
+
+/// Samples turning less than this many degrees are dropped from a line,
+/// which leaves the straight lines of the local projection two points long
+const STRAIGHT_TOLERANCE_DEG: f32 = 0.1;
+
+/// Lines of latitude and longitude about `spacing_m` apart, clipped to the
+/// projected map `bounds` and to the `outline` of a round or hexagonal map
+///
+/// Each line is a ribbon [`GRID_LINE_WIDTH_MM`] wide from z=0 to `z_top`.
+pub fn generate_grid(
+    projector: &Projector,
+    scaler: &Scaler,
+    bounds: &Bounds,
+    outline: Option<Outline>,
+    spacing_m: f64,
+    z_top: f32,
+) -> Vec<Triangle> {
+    if spacing_m <= 0.0 {
+        return Vec::new();
+    }
+    let (center_lat, center_lon) = projector.unproject(0.0, 0.0);
+    let step_lat = spacing_m / METERS_PER_DEGREE;
+    let step_lon = step_lat / lon_scale(center_lat);
+
+    // Corners of a rotated map are the extremes of its latitudes and
+    // longitudes; one more step covers the bulge of a curved projection
+    let corners = [
+        (bounds.min_x, bounds.min_y),
+        (bounds.max_x, bounds.min_y),
+        (bounds.max_x, bounds.max_y),
+        (bounds.min_x, bounds.max_y),
+    ]
+    .map(|(x, y)| projector.unproject(x, y));
+    let extent = |pick: fn(&(f64, f64)) -> f64, center: f64, step: f64| {
+        let (min, max) = corners
+            .iter()
+            .map(pick)
+            .fold((f64::MAX, f64::MIN), |(lo, hi), v| (lo.min(v), hi.max(v)));
+        (
+            ((min - center) / step).floor() as i64 - 1,
+            ((max - center) / step).ceil() as i64 + 1,
+            min - step,
+            max + step,
+        )
+    };
+    let (lat_first, lat_last, lat_min, lat_max) = extent(|c| c.0, center_lat, step_lat);
+    let (lon_first, lon_last, lon_min, lon_max) = extent(|c| c.1, center_lon, step_lon);
+
+    let along = |from: f64, to: f64| {
+        (0..=LINE_SAMPLES).map(move |i| from + (to - from) * i as f64 / LINE_SAMPLES as f64)
+    };
+    let mut lines: Vec<Vec<(f64, f64)>> = Vec::new();
+    for k in lat_first..=lat_last {
+        let lat = center_lat + k as f64 * step_lat;
+        lines.push(along(lon_min, lon_max).map(|lon| (lat, lon)).collect());
+    }
+    for k in lon_first..=lon_last {
+        let lon = center_lon + k as f64 * step_lon;
+        lines.push(along(lat_min, lat_max).map(|lat| (lat, lon)).collect());
+    }
+
+    let options = RibbonOptions::default();
+    lines
+        .iter()
+        .flat_map(|line| {
+            let projected = projector.project_points(line);
+            // The box clip reads points as (first, second) coordinates, so
+            // the projected bounds go in as (min x, min y, max x, max y)
+            let box_clip = (bounds.min_x, bounds.min_y, bounds.max_x, bounds.max_y);
+            clip_polyline(&projected, box_clip)
+                .into_iter()
+                .flat_map(|piece| match outline {
+                    Some(outline) => outline.clip_polyline(&piece),
+                    None => vec![piece],
+                })
+                .collect::<Vec<_>>()
+        })
+        .flat_map(|piece| {
+            let scaled = dedup_collinear(&scaler.scale_points(&piece), STRAIGHT_TOLERANCE_DEG);
+            extrude_ribbon_ex(&scaled, GRID_LINE_WIDTH_MM, z_top, 0.0, &options)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::circle::Circle;
+
+    #[test]
+    fn test_grid_spacing_and_clip() {
+        let projector = Projector::new((45.0, 10.0));
+        let bounds = Bounds::from_points(&[(-2500.0, -2500.0), (2500.0, 2500.0)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 100.0);
+        let triangles = generate_grid(&projector, &scaler, &bounds, None, 1000.0, 3.0);
+        assert!(!triangles.is_empty());
+
+        let vertices: Vec<[f32; 3]> = triangles.iter().flat_map(|t| t.vertices).collect();
+        assert!(vertices.iter().all(|v| {
+            let slack = GRID_LINE_WIDTH_MM;
+            (-slack..=100.0 + slack).contains(&v[0]) && (-slack..=100.0 + slack).contains(&v[1])
+        }));
+        // 1km on a 5km wide, 100mm plate is 20mm, and a line of each runs
+        // through the center
+        let on_line = |c: f32| {
+            let offset = (c - 50.0) / 20.0;
+            (offset - offset.round()).abs() * 20.0 < GRID_LINE_WIDTH_MM / 2.0 + 0.2
+        };
+        assert!(vertices.iter().all(|v| on_line(v[0]) || on_line(v[1])));
+        let meridian: Vec<f32> = vertices
+            .iter()
+            .filter(|v| (v[0] - 50.0).abs() <= GRID_LINE_WIDTH_MM)
+            .map(|v| v[1])
+            .collect();
+        assert!(meridian.iter().any(|&y| y < 1.0) && meridian.iter().any(|&y| y > 99.0));
+
+        // A round map keeps its lines inside the circle
+        let circle = Outline::Circle(Circle::new((0.0, 0.0), 2500.0));
+        let round = generate_grid(&projector, &scaler, &bounds, Some(circle), 1000.0, 3.0);
+        assert!(
+            round
+                .iter()
+                .flat_map(|t| t.vertices)
+                .all(|v| (v[0] - 50.0).hypot(v[1] - 50.0) < 50.0 + GRID_LINE_WIDTH_MM)
+        );
+    }
+}
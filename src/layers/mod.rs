@@ -1,11 +1,40 @@
+pub mod aeroways;
 pub mod base;
+pub mod boundary;
+pub mod buildings;
+pub mod compass;
+pub mod contours;
+pub mod custom;
+pub mod frame;
+pub mod grid;
+pub mod oneway;
 pub mod parks;
+pub mod road_network;
 pub mod roads;
+pub mod scale_bar;
+pub mod street_names;
+pub mod terrain;
 pub mod text;
 pub mod water;
 
-pub use base::generate_base_plate;
+pub use aeroways::generate_aeroway_meshes;
+pub use base::{
+    BED_MARGIN_MM, BedSize, CIRCULAR_BASE_SEGMENTS, DEFAULT_HANG_HOLE_DIAMETER_MM,
+    HOLLOW_MIN_CAVITY_MM, HangHole, MapShape, PlateCorner, PlateFootprint, generate_base_plate,
+    generate_circular_base, generate_hex_base,
+};
+pub use boundary::generate_boundary;
+pub use buildings::{BuildingConfig, generate_building_meshes};
+pub use compass::{CompassCorner, generate_compass};
+pub use contours::generate_contours;
+pub use custom::generate_custom_meshes;
+pub use frame::generate_frame;
+pub use grid::generate_grid;
+pub use oneway::{OnewayArrows, generate_oneway_arrows};
 pub use parks::generate_park_meshes;
-pub use roads::{RoadConfig, generate_road_meshes};
-pub use text::TextRenderer;
-pub use water::generate_water_meshes;
+pub use roads::{RoadConfig, TunnelMode, generate_road_meshes, generate_road_union};
+pub use scale_bar::{generate_scale_bar, round_scale_length};
+pub use street_names::{DEFAULT_STREET_NAME_MIN_LENGTH_MM, generate_street_names};
+pub use terrain::Terrain;
+pub use text::{MissingGlyph, TextRenderer};
+pub use water::{WaterStyle, generate_recessed_water, generate_water_meshes};
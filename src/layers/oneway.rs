@@ -0,0 +1,209 @@
+//! Arrowheads along one-way roads, pointing the way traffic runs
+//!
+//! Arrows are placed on the parsed segments rather than the joined roads,
+//! since joining may reverse a segment's points.
+
+use rayon::prelude::*;
+
+use super::roads::RoadConfig;
+use crate::config::heights;
+use crate::domain::RoadSegment;
+use crate::geometry::{Projector, Scaler};
+use crate::mesh::{Triangle, extrude_polygon};
+
+/// Distance in mm between arrows along a road
+const ARROW_SPACING_MM: f32 = 20.0;
+
+/// Arrow length as a multiple of the road width
+const ARROW_LENGTH_FACTOR: f32 = 1.5;
+
+/// Arrow width as a fraction of the road width, leaving road showing on
+/// both sides
+const ARROW_WIDTH_FACTOR: f32 = 0.7;
+
+/// Roads narrower than this in mm get no arrows, as they would not print
+const MIN_ROAD_WIDTH_MM: f32 = 1.4;
+
+/// Where the arrows print
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnewayArrows {
+    /// In the roads layer, one layer above the road surface
+    #[default]
+    Roads,
+    /// In the text layer, for a contrasting color
+    Text,
+}
+
+impl std::str::FromStr for OnewayArrows {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "roads" => Ok(OnewayArrows::Roads),
+            "text" => Ok(OnewayArrows::Text),
+            _ => Err(format!(
+                "Invalid one-way arrow placement '{}'. Valid options: roads, text",
+                s
+            )),
+        }
+    }
+}
+
+/// Arrowheads every [`ARROW_SPACING_MM`] along each one-way road
+///
+/// Arrows are columns from z=0, topping out one layer above their road, or
+/// at `text_z_top` when placed with the text. Tunnels get none.
+pub fn generate_oneway_arrows(
+    roads: &[RoadSegment],
+    projector: &Projector,
+    scaler: &Scaler,
+    config: &RoadConfig,
+    placement: OnewayArrows,
+    text_z_top: f32,
+) -> Vec<Triangle> {
+    roads
+        .par_iter()
+        .filter(|road| road.is_oneway && !road.is_tunnel)
+        .flat_map_iter(|road| {
+            let width = config.get_segment_width(road);
+            if width < MIN_ROAD_WIDTH_MM {
+                return Vec::new();
+            }
+            let z_top = match placement {
+                OnewayArrows::Roads => config.segment_z_top(road) + heights::LAYER_HEIGHT,
+                OnewayArrows::Text => text_z_top,
+            };
+            let scaled = scaler.scale_points(&projector.project_points(&road.points));
+            let length = ARROW_LENGTH_FACTOR * width;
+            arrow_positions(&scaled, length)
+                .into_iter()
+                .flat_map(|(center, direction)| {
+                    let outline = arrowhead(center, direction, length, ARROW_WIDTH_FACTOR * width);
+                    extrude_polygon(&outline, &[], 0.0, z_top)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Centers and unit directions of arrows `length` long spread evenly along
+/// `points`, about [`ARROW_SPACING_MM`] apart
+fn arrow_positions(points: &[(f32, f32)], length: f32) -> Vec<((f32, f32), (f32, f32))> {
+    let total: f32 = points
+        .windows(2)
+        .map(|w| (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1))
+        .sum();
+    if total < 2.0 * length {
+        return Vec::new();
+    }
+    let count = (total / ARROW_SPACING_MM).floor().max(1.0) as usize;
+    (0..count)
+        .filter_map(|i| {
+            let at = (i as f32 + 0.5) * total / count as f32;
+            let center = point_at(points, at);
+            // The chord across the arrow follows bends better than the
+            // segment under its center
+            let back = point_at(points, at - length / 2.0);
+            let front = point_at(points, at + length / 2.0);
+            let (dx, dy) = (front.0 - back.0, front.1 - back.1);
+            let norm = dx.hypot(dy);
+            (norm > f32::EPSILON).then(|| (center, (dx / norm, dy / norm)))
+        })
+        .collect()
+}
+
+/// Point `distance` along a polyline, clamped to its ends
+fn point_at(points: &[(f32, f32)], distance: f32) -> (f32, f32) {
+    let mut remaining = distance.max(0.0);
+    for w in points.windows(2) {
+        let step = (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1);
+        if remaining <= step && step > 0.0 {
+            let t = remaining / step;
+            return (
+                w[0].0 + (w[1].0 - w[0].0) * t,
+                w[0].1 + (w[1].1 - w[0].1) * t,
+            );
+        }
+        remaining -= step;
+    }
+    points[points.len() - 1]
+}
+
+/// Dart-shaped arrowhead, counterclockwise, with its tip ahead of `center`
+fn arrowhead(center: (f32, f32), (dx, dy): (f32, f32), length: f32, width: f32) -> Vec<(f32, f32)> {
+    // (along, across) with `across` to the left of the direction
+    let local = [
+        (length / 2.0, 0.0),
+        (-length / 2.0, width / 2.0),
+        (-length / 6.0, 0.0),
+        (-length / 2.0, -width / 2.0),
+    ];
+    local
+        .iter()
+        .rev()
+        .map(|&(along, across)| {
+            (
+                center.0 + along * dx - across * dy,
+                center.1 + along * dy + across * dx,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::RoadClass;
+    use crate::geometry::Bounds;
+
+    #[test]
+    fn test_arrows_point_along_oneway_roads() {
+        let projector = Projector::new((0.0, 0.0));
+        let (x0, y0) = projector.project(-0.01, -0.01);
+        let (x1, y1) = projector.project(0.01, 0.01);
+        let bounds = Bounds::from_points(&[(x0, y0), (x1, y1)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 100.0);
+        let config = RoadConfig::default();
+        // West to east across the plate, and the same road both ways
+        let road = RoadSegment::new(vec![(0.0, -0.008), (0.0, 0.008)], RoadClass::Motorway);
+        let roads = [road.clone().with_oneway(true), road];
+
+        let triangles = generate_oneway_arrows(
+            &roads,
+            &projector,
+            &scaler,
+            &config,
+            OnewayArrows::Roads,
+            5.0,
+        );
+        // 80mm of road holds four arrows, each a closed dart
+        assert_eq!(triangles.len(), 4 * (2 * 2 + 4 * 2));
+        let report = crate::mesh::validation::validate_mesh(&triangles);
+        assert!(report.manifold.is_watertight());
+        let top = triangles
+            .iter()
+            .flat_map(|t| t.vertices)
+            .fold(f32::MIN, |z, v| z.max(v[2]));
+        assert!(
+            (top - (config.class_z_top(RoadClass::Motorway) + heights::LAYER_HEIGHT)).abs() < 1e-5
+        );
+
+        // The tip leads in the direction of travel
+        let width = config.get_width(RoadClass::Motorway);
+        let outline = arrowhead((10.0, 50.0), (1.0, 0.0), 1.5 * width, width);
+        let tip = outline
+            .iter()
+            .fold((f32::MIN, 0.0), |a, &p| if p.0 > a.0 { p } else { a });
+        assert_eq!(tip, (10.0 + 0.75 * width, 50.0));
+
+        let text = generate_oneway_arrows(
+            &roads,
+            &projector,
+            &scaler,
+            &config,
+            OnewayArrows::Text,
+            5.0,
+        );
+        assert!(text.iter().flat_map(|t| t.vertices).any(|v| v[2] == 5.0));
+    }
+}
@@ -1,6 +1,7 @@
 use crate::domain::ParkPolygon;
 use crate::geometry::{Projector, Scaler};
 use crate::mesh::{extrude_polygon_ex, Triangle};
+use rayon::prelude::*;
 
 pub fn generate_park_meshes(
     park_polygons: &[ParkPolygon],
@@ -8,26 +9,36 @@ pub fn generate_park_meshes(
     scaler: &Scaler,
     z_top: f32,
 ) -> Vec<Triangle> {
-    let mut all_triangles = Vec::new();
-
-    for polygon in park_polygons {
-        if !polygon.is_valid() {
-            continue;
-        }
-
-        let projected: Vec<(f64, f64)> = polygon
-            .outer
-            .iter()
-            .map(|&(lat, lon)| projector.project(lat, lon))
-            .collect();
-
-        let scaled: Vec<(f32, f32)> = projected.iter().map(|&(x, y)| scaler.scale(x, y)).collect();
-
-        let triangles = extrude_polygon_ex(&scaled, &[], 0.0, z_top, true);
-        all_triangles.extend(triangles);
-    }
-
-    all_triangles
+    // Polygons extrude independently; collect keeps the input order
+    park_polygons
+        .par_iter()
+        .filter(|polygon| polygon.is_valid())
+        .flat_map_iter(|polygon| {
+            let projected: Vec<(f64, f64)> = polygon
+                .outer
+                .iter()
+                .map(|&(lat, lon)| projector.project(lat, lon))
+                .collect();
+
+            let scaled: Vec<(f32, f32)> =
+                projected.iter().map(|&(x, y)| scaler.scale(x, y)).collect();
+
+            let holes_scaled: Vec<Vec<(f32, f32)>> = polygon
+                .holes
+                .iter()
+                .map(|hole| {
+                    hole.iter()
+                        .map(|&(lat, lon)| {
+                            let (x, y) = projector.project(lat, lon);
+                            scaler.scale(x, y)
+                        })
+                        .collect()
+                })
+                .collect();
+
+            extrude_polygon_ex(&scaled, &holes_scaled, 0.0, z_top, true)
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -0,0 +1,227 @@
+//! Joining road segments that meet end to end
+//!
+//! OSM splits a street into many ways, and extruding each one on its own
+//! leaves seams and overlapping end caps where they meet. Chaining ways that
+//! share an end node into one polyline lets the ribbon miter through the
+//! joint instead.
+
+use crate::domain::{RoadClass, RoadSegment};
+use std::collections::HashMap;
+
+/// Largest change of direction, in degrees, for continuing a road through a
+/// junction where more than one segment could follow
+const MAX_JUNCTION_DEFLECTION_DEG: f64 = 60.0;
+
+/// Endpoint key; shared OSM nodes have identical coordinates
+type NodeKey = (i64, i64);
+
+/// Segments only join others of the same class, bridge/tunnel state,
+/// tagged width and name, so each chain keeps a single ribbon width and
+/// height and can be labeled as one street
+type Kind<'a> = (
+    RoadClass,
+    bool,
+    bool,
+    Option<u8>,
+    Option<u32>,
+    Option<&'a str>,
+);
+
+fn kind(road: &RoadSegment) -> Kind<'_> {
+    (
+        road.class,
+        road.is_bridge,
+        road.is_tunnel,
+        road.lanes,
+        road.width_m.map(f32::to_bits),
+        road.name.as_deref(),
+    )
+}
+
+fn node_key((lat, lon): (f64, f64)) -> NodeKey {
+    ((lat * 1e7).round() as i64, (lon * 1e7).round() as i64)
+}
+
+/// Chain same-kind segments that share end points into longer polylines
+///
+/// From each segment the chain is extended at both ends. A node where only
+/// one other segment of the kind ends always continues; at junctions the
+/// straightest continuation is taken if it bends less than
+/// [`MAX_JUNCTION_DEFLECTION_DEG`]. Other branches become chains of their
+/// own.
+pub fn join_road_segments(roads: &[RoadSegment]) -> Vec<RoadSegment> {
+    let mut ends: HashMap<(Kind, NodeKey), Vec<usize>> = HashMap::new();
+    for (i, road) in roads.iter().enumerate() {
+        if road.points.len() < 2 || is_closed(&road.points) {
+            continue;
+        }
+        for point in [road.points[0], road.points[road.points.len() - 1]] {
+            ends.entry((kind(road), node_key(point)))
+                .or_default()
+                .push(i);
+        }
+    }
+
+    let mut used = vec![false; roads.len()];
+    let mut joined = Vec::new();
+
+    for start in 0..roads.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let road = &roads[start];
+        let mut points = road.points.clone();
+
+        if points.len() >= 2 && !is_closed(&points) {
+            extend_chain(&mut points, kind(road), roads, &ends, &mut used);
+            points.reverse();
+            extend_chain(&mut points, kind(road), roads, &ends, &mut used);
+        }
+
+        joined.push(
+            RoadSegment::new(points, road.class)
+                .with_bridge(road.is_bridge)
+                .with_tunnel(road.is_tunnel)
+                .with_width_m(road.width_m)
+                .with_lanes(road.lanes)
+                .with_name(road.name.clone()),
+        );
+    }
+
+    joined
+}
+
+/// Keep appending segments at the last point of `points`
+fn extend_chain(
+    points: &mut Vec<(f64, f64)>,
+    kind: Kind,
+    roads: &[RoadSegment],
+    ends: &HashMap<(Kind, NodeKey), Vec<usize>>,
+    used: &mut [bool],
+) {
+    while !is_closed(points) {
+        let last = points[points.len() - 1];
+        let Some(candidates) = ends.get(&(kind, node_key(last))) else {
+            return;
+        };
+        let incoming = heading(points[points.len() - 2], last);
+
+        // Each candidate oriented to start at the shared node
+        let options: Vec<(usize, bool, f64)> = candidates
+            .iter()
+            .filter(|&&i| !used[i])
+            .map(|&i| {
+                let road = &roads[i].points;
+                let reversed = node_key(road[0]) != node_key(last);
+                let next = if reversed {
+                    road[road.len() - 2]
+                } else {
+                    road[1]
+                };
+                (i, reversed, deflection(incoming, heading(last, next)))
+            })
+            .collect();
+
+        let choice = match options.as_slice() {
+            [only] if candidates.len() == 2 => Some(*only),
+            _ => options
+                .iter()
+                .filter(|(_, _, angle)| *angle <= MAX_JUNCTION_DEFLECTION_DEG)
+                .min_by(|a, b| a.2.total_cmp(&b.2))
+                .copied(),
+        };
+        let Some((next, reversed, _)) = choice else {
+            return;
+        };
+
+        used[next] = true;
+        let mut segment = roads[next].points.clone();
+        if reversed {
+            segment.reverse();
+        }
+        points.extend_from_slice(&segment[1..]);
+    }
+}
+
+fn is_closed(points: &[(f64, f64)]) -> bool {
+    points.len() > 2 && node_key(points[0]) == node_key(points[points.len() - 1])
+}
+
+/// Direction of travel in degrees, with longitude shrunk by latitude so
+/// angles match the ground
+fn heading(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let dy = to.0 - from.0;
+    let dx = (to.1 - from.1) * from.0.to_radians().cos();
+    dy.atan2(dx).to_degrees()
+}
+
+/// Absolute change between two headings in degrees, 0..=180
+fn deflection(a: f64, b: f64) -> f64 {
+    let diff = (b - a).rem_euclid(360.0);
+    diff.min(360.0 - diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(points: &[(f64, f64)], class: RoadClass) -> RoadSegment {
+        RoadSegment::new(points.to_vec(), class)
+    }
+
+    #[test]
+    fn test_join_continuations() {
+        // Second way is drawn backwards; the rail touching the same node is
+        // a different class and stays separate
+        let roads = vec![
+            segment(&[(0.0, 0.0), (0.0, 0.001)], RoadClass::Primary),
+            segment(&[(0.001, 0.002), (0.0, 0.001)], RoadClass::Primary),
+            segment(&[(0.0, 0.001), (-0.001, 0.001)], RoadClass::Rail),
+        ];
+        let joined = join_road_segments(&roads);
+        assert_eq!(joined.len(), 2);
+        assert_eq!(
+            joined[0].points,
+            vec![(0.001, 0.002), (0.0, 0.001), (0.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn test_junction_takes_straightest_branch() {
+        // A straight road with a side street leaving at a right angle
+        let roads = vec![
+            segment(&[(0.0, 0.0), (0.0, 0.001)], RoadClass::Residential),
+            segment(&[(0.0, 0.001), (0.001, 0.001)], RoadClass::Residential),
+            segment(&[(0.0, 0.001), (0.0, 0.002)], RoadClass::Residential),
+        ];
+        let joined = join_road_segments(&roads);
+        assert_eq!(joined.len(), 2);
+        assert!(joined.iter().any(|r| r.points.len() == 3
+            && r.points.contains(&(0.0, 0.0))
+            && r.points.contains(&(0.0, 0.002))));
+    }
+
+    #[test]
+    fn test_bridge_stays_separate() {
+        let roads = vec![
+            segment(&[(0.0, 0.0), (0.0, 0.001)], RoadClass::Primary),
+            segment(&[(0.0, 0.001), (0.0, 0.002)], RoadClass::Primary).with_bridge(true),
+        ];
+        let joined = join_road_segments(&roads);
+        assert_eq!(joined.len(), 2);
+        assert!(joined[1].is_bridge);
+    }
+
+    #[test]
+    fn test_loop_terminates() {
+        let roads = vec![
+            segment(&[(0.0, 0.0), (0.0, 0.001)], RoadClass::Tertiary),
+            segment(&[(0.0, 0.001), (0.001, 0.001)], RoadClass::Tertiary),
+            segment(&[(0.001, 0.001), (0.0, 0.0)], RoadClass::Tertiary),
+        ];
+        let joined = join_road_segments(&roads);
+        assert_eq!(joined.len(), 1);
+        assert_eq!(joined[0].points.len(), 4);
+    }
+}
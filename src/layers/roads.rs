@@ -1,6 +1,58 @@
+use super::road_network::join_road_segments;
+use crate::config::{RoadOverrides, heights};
 use crate::domain::{RoadClass, RoadSegment};
-use crate::geometry::{simplify_polyline, Projector, Scaler};
-use crate::mesh::{extrude_ribbon_ex, Triangle};
+use crate::geometry::{
+    dedup_collinear, densify_polyline, simplify_polyline, simplify_polyline_vw, Projector, Scaler,
+    SimplifyAlgorithm,
+};
+use crate::mesh::{
+    extrude_multipolygon, extrude_ribbon_ex, ribbon_footprint, RibbonOptions, RibbonProfile,
+    Triangle,
+};
+use geo::{BooleanOps, LineString, MultiPolygon, Polygon};
+use rayon::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Direction change in degrees below which a road node is treated as lying
+/// on a straight line and dropped before extrusion
+const COLLINEAR_TOLERANCE_DEG: f32 = 1.0;
+
+/// Width of one traffic lane in meters, for converting `width` tags to lanes
+const LANE_WIDTH_M: f32 = 3.5;
+
+/// Bounds on how far `lanes`/`width` tags can scale a class width
+const MIN_TAGGED_WIDTH_FACTOR: f32 = 0.5;
+const MAX_TAGGED_WIDTH_FACTOR: f32 = 2.0;
+
+/// How roads and rails tagged as tunnels are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TunnelMode {
+    /// Same as surface roads
+    #[default]
+    Show,
+    /// Left out of the model
+    Hide,
+    /// A one-layer trace just above the base, below every surface feature
+    Engrave,
+}
+
+impl std::str::FromStr for TunnelMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "show" => Ok(TunnelMode::Show),
+            "hide" => Ok(TunnelMode::Hide),
+            "engrave" => Ok(TunnelMode::Engrave),
+            _ => Err(format!(
+                "Invalid tunnel mode '{}'. Valid options: show, hide, engrave",
+                s
+            )),
+        }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct RoadConfig {
@@ -9,10 +61,30 @@ pub struct RoadConfig {
     pub secondary_width: f32,
     pub tertiary_width: f32,
     pub residential_width: f32,
+    /// Footways and other paths, at the printable minimum so they stay the
+    /// thinnest lines on the map
+    pub path_width: f32,
+    pub rail_width: f32,
+    /// Per-class heights above the base in mm; `None` uses `z_top`
+    pub motorway_height: Option<f32>,
+    pub primary_height: Option<f32>,
+    pub secondary_height: Option<f32>,
+    pub tertiary_height: Option<f32>,
+    pub residential_height: Option<f32>,
     pub width_scale: f32,
     pub min_width_mm: f32,
     pub simplify_level: u8,
+    pub simplify_algorithm: SimplifyAlgorithm,
+    /// Finish road ends with half circles instead of flat cuts
+    pub round_caps: bool,
+    /// Cross-section shape of the road ribbons
+    pub profile: RibbonProfile,
+    pub tunnels: TunnelMode,
+    /// Top of the base plate, where engraved tunnels sit
+    pub base_z_top: f32,
     pub z_top: f32,
+    /// Longest ribbon segment in mm, so roads bend with terrain under them
+    pub max_segment_mm: Option<f32>,
 }
 
 impl Default for RoadConfig {
@@ -23,25 +95,83 @@ impl Default for RoadConfig {
             secondary_width: 1.0,
             tertiary_width: 0.5,
             residential_width: 0.8,
+            path_width: 0.6,
+            rail_width: 0.6,
+            motorway_height: None,
+            primary_height: None,
+            secondary_height: None,
+            tertiary_height: None,
+            residential_height: None,
             width_scale: 1.0,
             min_width_mm: 0.6,
             simplify_level: 0,
+            simplify_algorithm: SimplifyAlgorithm::default(),
+            round_caps: false,
+            profile: RibbonProfile::default(),
+            tunnels: TunnelMode::default(),
+            base_z_top: heights::BASE_Z_TOP,
             z_top: 3.8,
+            max_segment_mm: None,
         }
     }
 }
 
 impl RoadConfig {
     pub fn get_width(&self, class: RoadClass) -> f32 {
+        self.scaled_class_width(class).max(self.min_width_mm)
+    }
+
+    /// Class width after scaling, before the `min_width_mm` clamp
+    fn scaled_class_width(&self, class: RoadClass) -> f32 {
         let base_w = match class {
             RoadClass::Motorway => self.motorway_width,
             RoadClass::Primary => self.primary_width,
             RoadClass::Secondary => self.secondary_width,
             RoadClass::Tertiary => self.tertiary_width,
             RoadClass::Residential => self.residential_width,
+            RoadClass::Path => self.path_width,
+            RoadClass::Rail => self.rail_width,
         };
 
-        (base_w * self.width_scale).max(self.min_width_mm)
+        base_w * self.width_scale
+    }
+
+    /// Width for a segment, widened or narrowed from its class width by its
+    /// `width` or `lanes` tag
+    ///
+    /// The class widths are already exaggerated for legibility, so a tagged
+    /// road is scaled relative to the lane count typical for its class
+    /// rather than printed at true scale.
+    pub fn get_segment_width(&self, road: &RoadSegment) -> f32 {
+        let class_width = self.get_width(road.class);
+        match tagged_width_factor(road) {
+            Some(factor) => (class_width * factor).max(self.min_width_mm),
+            None => class_width,
+        }
+    }
+
+    /// Whether `road` would come out narrower than `min_width_mm`, and so
+    /// prints wider than its scale calls for
+    pub fn is_width_clamped(&self, road: &RoadSegment) -> bool {
+        let width = self.scaled_class_width(road.class) * tagged_width_factor(road).unwrap_or(1.0);
+        width < self.min_width_mm
+    }
+
+    /// Replace class widths and heights with the ones set in `overrides`
+    pub fn with_overrides(mut self, overrides: &RoadOverrides) -> Self {
+        self.motorway_width = overrides.motorway_width.unwrap_or(self.motorway_width);
+        self.primary_width = overrides.primary_width.unwrap_or(self.primary_width);
+        self.secondary_width = overrides.secondary_width.unwrap_or(self.secondary_width);
+        self.tertiary_width = overrides.tertiary_width.unwrap_or(self.tertiary_width);
+        self.residential_width = overrides
+            .residential_width
+            .unwrap_or(self.residential_width);
+        self.motorway_height = overrides.motorway_height.or(self.motorway_height);
+        self.primary_height = overrides.primary_height.or(self.primary_height);
+        self.secondary_height = overrides.secondary_height.or(self.secondary_height);
+        self.tertiary_height = overrides.tertiary_height.or(self.tertiary_height);
+        self.residential_height = overrides.residential_height.or(self.residential_height);
+        self
     }
 
     pub fn with_scale(mut self, scale: f32) -> Self {
@@ -69,16 +199,77 @@ impl RoadConfig {
         self
     }
 
+    pub fn with_rail_width(mut self, width_mm: f32) -> Self {
+        self.rail_width = width_mm;
+        self
+    }
+
     pub fn with_simplify_level(mut self, level: u8) -> Self {
         self.simplify_level = level.min(3);
         self
     }
 
+    pub fn with_simplify_algorithm(mut self, algorithm: SimplifyAlgorithm) -> Self {
+        self.simplify_algorithm = algorithm;
+        self
+    }
+
+    pub fn with_round_caps(mut self, round_caps: bool) -> Self {
+        self.round_caps = round_caps;
+        self
+    }
+
+    pub fn with_profile(mut self, profile: RibbonProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn with_tunnels(mut self, tunnels: TunnelMode) -> Self {
+        self.tunnels = tunnels;
+        self
+    }
+
+    pub fn with_base_z_top(mut self, base_z_top: f32) -> Self {
+        self.base_z_top = base_z_top;
+        self
+    }
+
+    pub fn with_max_segment(mut self, max_segment_mm: f32) -> Self {
+        self.max_segment_mm = Some(max_segment_mm);
+        self
+    }
+
     pub fn with_z_top(mut self, z_top: f32) -> Self {
         self.z_top = z_top;
         self
     }
 
+    /// Top of the ribbon for a class, from its height override if it has one
+    pub fn class_z_top(&self, class: RoadClass) -> f32 {
+        let height = match class {
+            RoadClass::Motorway => self.motorway_height,
+            RoadClass::Primary => self.primary_height,
+            RoadClass::Secondary => self.secondary_height,
+            RoadClass::Tertiary => self.tertiary_height,
+            RoadClass::Residential => self.residential_height,
+            RoadClass::Path | RoadClass::Rail => None,
+        };
+        height.map_or(self.z_top, |h| self.base_z_top + h)
+    }
+
+    /// Top of the ribbon for a segment: bridges stand above the rest and
+    /// engraved tunnels barely rise out of the base
+    pub fn segment_z_top(&self, road: &RoadSegment) -> f32 {
+        let z_top = self.class_z_top(road.class);
+        if road.is_tunnel && self.tunnels == TunnelMode::Engrave {
+            self.base_z_top + heights::LAYER_HEIGHT
+        } else if road.is_bridge && !road.is_tunnel {
+            z_top + heights::BRIDGE_RAISE
+        } else {
+            z_top
+        }
+    }
+
     fn simplification_epsilon(&self, class: RoadClass) -> Option<f64> {
         if self.simplify_level == 0 {
             return None;
@@ -89,7 +280,8 @@ impl RoadConfig {
             RoadClass::Primary => 0.00012,
             RoadClass::Secondary => 0.00010,
             RoadClass::Tertiary => 0.00008,
-            RoadClass::Residential => 0.00005,
+            RoadClass::Residential | RoadClass::Path => 0.00005,
+            RoadClass::Rail => 0.00010,
         };
 
         let multiplier = match self.simplify_level {
@@ -103,6 +295,26 @@ impl RoadConfig {
     }
 }
 
+/// How much a segment's `width` or `lanes` tag scales its class width
+fn tagged_width_factor(road: &RoadSegment) -> Option<f32> {
+    let typical_lanes = typical_lanes(road.class)?;
+    let lanes = road
+        .width_m
+        .map(|w| w / LANE_WIDTH_M)
+        .or(road.lanes.map(f32::from))?;
+    Some((lanes / typical_lanes).clamp(MIN_TAGGED_WIDTH_FACTOR, MAX_TAGGED_WIDTH_FACTOR))
+}
+
+/// Lane count the class widths are drawn for; rails and paths have no lanes
+fn typical_lanes(class: RoadClass) -> Option<f32> {
+    match class {
+        RoadClass::Motorway | RoadClass::Primary => Some(4.0),
+        RoadClass::Secondary | RoadClass::Tertiary | RoadClass::Residential => Some(2.0),
+        // Path widths are not counted in lanes
+        RoadClass::Path | RoadClass::Rail => None,
+    }
+}
+
 /// Generate mesh triangles for all road segments
 ///
 /// # Arguments
@@ -119,36 +331,112 @@ pub fn generate_road_meshes(
     scaler: &Scaler,
     config: &RoadConfig,
 ) -> Vec<Triangle> {
-    let mut all_triangles = Vec::new();
-
-    for road in roads {
-        let points_to_use = if let Some(epsilon) = config.simplification_epsilon(road.class) {
-            let simplified = simplify_polyline(&road.points, epsilon);
-            if simplified.len() < 2 {
-                continue;
-            }
-            simplified
-        } else {
-            if road.points.len() < 2 {
-                continue;
-            }
-            road.points.clone()
-        };
+    let ribbon_options = RibbonOptions {
+        round_caps: config.round_caps,
+        profile: config.profile,
+        ..Default::default()
+    };
 
-        let projected: Vec<(f64, f64)> = points_to_use
-            .iter()
-            .map(|&(lat, lon)| projector.project(lat, lon))
-            .collect();
+    // One ribbon per continuous road, so joints miter instead of overlapping.
+    // Ribbons extrude independently; collect keeps the input order.
+    join_road_segments(roads)
+        .par_iter()
+        .filter(|road| !(road.is_tunnel && config.tunnels == TunnelMode::Hide))
+        .flat_map_iter(|road| {
+            let Some(path) = road_path(road, projector, scaler, config) else {
+                return Vec::new();
+            };
+            let width = config.get_segment_width(road);
+            let z_top = config.segment_z_top(road);
+            extrude_ribbon_ex(&path, width, z_top, 0.0, &ribbon_options)
+        })
+        .collect()
+}
+
+/// Generate roads as merged solids, without faces overlapping at crossings
+///
+/// The ground each ribbon covers is unioned with every other road of the
+/// same height and the result extruded once, so crossings and junctions
+/// come out as one closed solid per connected patch. Roads of different
+/// heights, like bridges over a street, still overlap each other. Only
+/// the flat profile can be merged this way; other profiles are drawn as
+/// for [`generate_road_meshes`].
+pub fn generate_road_union(
+    roads: &[RoadSegment],
+    projector: &Projector,
+    scaler: &Scaler,
+    config: &RoadConfig,
+) -> Vec<Triangle> {
+    if config.profile != RibbonProfile::Flat {
+        return generate_road_meshes(roads, projector, scaler, config);
+    }
 
-        let scaled: Vec<(f32, f32)> = projected.iter().map(|&(x, y)| scaler.scale(x, y)).collect();
+    let footprints: Vec<(f32, Polygon<f64>)> = join_road_segments(roads)
+        .par_iter()
+        .filter(|road| !(road.is_tunnel && config.tunnels == TunnelMode::Hide))
+        .filter_map(|road| {
+            let path = road_path(road, projector, scaler, config)?;
+            let ring = ribbon_footprint(&path, config.get_segment_width(road), config.round_caps);
+            let ring: Vec<(f64, f64)> = ring.iter().map(|&(x, y)| (x as f64, y as f64)).collect();
+            Some((
+                config.segment_z_top(road),
+                Polygon::new(LineString::from(ring), Vec::new()),
+            ))
+        })
+        .collect();
 
-        let width = config.get_width(road.class);
+    let mut by_height: BTreeMap<u32, Vec<Polygon<f64>>> = BTreeMap::new();
+    for (z_top, footprint) in footprints {
+        by_height
+            .entry(z_top.to_bits())
+            .or_default()
+            .push(footprint);
+    }
+    by_height
+        .into_iter()
+        .flat_map(|(z_top, footprints)| {
+            let merged = footprints
+                .into_par_iter()
+                .map(|footprint| MultiPolygon::new(vec![footprint]))
+                .reduce(|| MultiPolygon::new(Vec::new()), |a, b| a.union(&b));
+            extrude_multipolygon(&merged, 0.0, f32::from_bits(z_top))
+        })
+        .collect()
+}
 
-        let triangles = extrude_ribbon_ex(&scaled, width, config.z_top, 0.0, true, true);
-        all_triangles.extend(triangles);
+/// Centerline of a road in mm, simplified and ready to extrude
+///
+/// `None` when simplification leaves fewer than two points.
+fn road_path(
+    road: &RoadSegment,
+    projector: &Projector,
+    scaler: &Scaler,
+    config: &RoadConfig,
+) -> Option<Vec<(f32, f32)>> {
+    let points_to_use = if let Some(epsilon) = config.simplification_epsilon(road.class) {
+        match config.simplify_algorithm {
+            SimplifyAlgorithm::DouglasPeucker => simplify_polyline(&road.points, epsilon),
+            SimplifyAlgorithm::Visvalingam => simplify_polyline_vw(&road.points, epsilon),
+        }
+    } else {
+        road.points.clone()
+    };
+    if points_to_use.len() < 2 {
+        return None;
     }
 
-    all_triangles
+    let projected: Vec<(f64, f64)> = points_to_use
+        .iter()
+        .map(|&(lat, lon)| projector.project(lat, lon))
+        .collect();
+
+    let scaled: Vec<(f32, f32)> = projected.iter().map(|&(x, y)| scaler.scale(x, y)).collect();
+    // Straight runs need no intermediate nodes, even with --simplify 0
+    let scaled = dedup_collinear(&scaled, COLLINEAR_TOLERANCE_DEG);
+    Some(match config.max_segment_mm {
+        Some(step) => densify_polyline(&scaled, step),
+        None => scaled,
+    })
 }
 
 #[cfg(test)]
@@ -181,10 +469,148 @@ mod tests {
         assert!(config.width_scale > 1.5);
     }
 
+    #[test]
+    fn test_road_config_rail_width() {
+        let config = RoadConfig::default().with_rail_width(1.0).with_scale(1.2);
+        let w = config.get_width(RoadClass::Rail);
+        assert!((w - 1.2).abs() < 1e-6);
+    }
+
     #[test]
     fn test_road_config_min_width() {
         let config = RoadConfig::default();
         let w = config.get_width(RoadClass::Residential);
         assert!(w >= 0.6);
+
+        let road = RoadSegment::new(vec![(0.0, 0.0), (0.0, 0.001)], RoadClass::Residential);
+        assert!(!config.is_width_clamped(&road));
+        // Half the typical lanes halves the 0.8mm residential width
+        assert!(config.is_width_clamped(&road.clone().with_lanes(Some(1))));
+        assert!(config.clone().with_scale(0.5).is_width_clamped(&road));
+    }
+
+    #[test]
+    fn test_bridge_and_tunnel_heights() {
+        let config = RoadConfig::default().with_base_z_top(2.0).with_z_top(3.8);
+        let road = |bridge, tunnel| {
+            RoadSegment::new(vec![(0.0, 0.0), (0.0, 0.001)], RoadClass::Primary)
+                .with_bridge(bridge)
+                .with_tunnel(tunnel)
+        };
+        assert_eq!(config.segment_z_top(&road(false, false)), 3.8);
+        assert!((config.segment_z_top(&road(true, false)) - 4.2).abs() < 1e-5);
+        assert_eq!(config.segment_z_top(&road(false, true)), 3.8);
+
+        let engraved = config.with_tunnels(TunnelMode::Engrave);
+        assert!((engraved.segment_z_top(&road(false, true)) - 2.2).abs() < 1e-5);
+        assert_eq!("hide".parse(), Ok(TunnelMode::Hide));
+        assert!("bury".parse::<TunnelMode>().is_err());
+    }
+
+    #[test]
+    fn test_class_overrides() {
+        let overrides = RoadOverrides {
+            motorway_width: Some(3.0),
+            motorway_height: Some(2.4),
+            residential_width: Some(0.3),
+            ..RoadOverrides::default()
+        };
+        let config = RoadConfig::default()
+            .with_base_z_top(2.0)
+            .with_z_top(3.8)
+            .with_overrides(&overrides);
+        assert_eq!(config.get_width(RoadClass::Motorway), 3.0);
+        // Overridden widths still respect the printable minimum
+        assert_eq!(config.get_width(RoadClass::Residential), 0.6);
+        assert_eq!(config.get_width(RoadClass::Primary), 1.5);
+
+        assert!((config.class_z_top(RoadClass::Motorway) - 4.4).abs() < 1e-5);
+        assert_eq!(config.class_z_top(RoadClass::Primary), 3.8);
+        let motorway = RoadSegment::new(vec![(0.0, 0.0), (0.0, 0.001)], RoadClass::Motorway);
+        assert!((config.segment_z_top(&motorway.with_bridge(true)) - 4.8).abs() < 1e-5);
+
+        let negative = RoadOverrides {
+            tertiary_height: Some(-1.0),
+            ..RoadOverrides::default()
+        };
+        assert!(negative.validate().is_err());
+        assert!(overrides.validate().is_ok());
+    }
+
+    #[test]
+    fn test_segment_width_from_tags() {
+        let config = RoadConfig::default();
+        let primary = || RoadSegment::new(vec![(0.0, 0.0), (0.0, 0.001)], RoadClass::Primary);
+
+        assert_eq!(config.get_segment_width(&primary()), 1.5);
+        assert!((config.get_segment_width(&primary().with_lanes(Some(6))) - 2.25).abs() < 1e-5);
+        assert!((config.get_segment_width(&primary().with_lanes(Some(2))) - 0.75).abs() < 1e-5);
+        // A width tag wins over lanes: 7m is two lanes
+        let tagged = primary().with_lanes(Some(6)).with_width_m(Some(7.0));
+        assert!((config.get_segment_width(&tagged) - 0.75).abs() < 1e-5);
+        // Clamped, and never below the printable minimum
+        assert_eq!(
+            config.get_segment_width(&primary().with_lanes(Some(20))),
+            3.0
+        );
+        let residential = RoadSegment::new(vec![(0.0, 0.0), (0.0, 0.001)], RoadClass::Residential)
+            .with_lanes(Some(1));
+        assert_eq!(config.get_segment_width(&residential), 0.6);
+    }
+
+    #[test]
+    fn test_parallel_output_keeps_road_order() {
+        use crate::geometry::Bounds;
+
+        let projector = Projector::new((0.0, 0.0));
+        let bounds = Bounds::from_points(&[(-500.0, -500.0), (500.0, 500.0)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 100.0);
+        let config = RoadConfig::default();
+        // Separate, non-touching roads so none are joined
+        let roads: Vec<RoadSegment> = (0..50)
+            .map(|i| {
+                let lat = -0.004 + i as f64 * 0.00015;
+                RoadSegment::new(vec![(lat, -0.003), (lat, 0.003)], RoadClass::Primary)
+            })
+            .collect();
+
+        let all = generate_road_meshes(&roads, &projector, &scaler, &config);
+        let one_by_one: Vec<Triangle> = roads
+            .iter()
+            .flat_map(|road| {
+                generate_road_meshes(std::slice::from_ref(road), &projector, &scaler, &config)
+            })
+            .collect();
+        assert!(!all.is_empty());
+        let vertices = |triangles: &[Triangle]| -> Vec<[[f32; 3]; 3]> {
+            triangles.iter().map(|t| t.vertices).collect()
+        };
+        assert_eq!(vertices(&all), vertices(&one_by_one));
+    }
+
+    #[test]
+    fn test_road_union_merges_crossings() {
+        use crate::geometry::Bounds;
+        use crate::mesh::validation::{check_manifold, count_coplanar_overlaps};
+
+        let projector = Projector::new((0.0, 0.0));
+        let bounds = Bounds::from_points(&[(-500.0, -500.0), (500.0, 500.0)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 100.0);
+        // A plus sign: the two roads cross in the middle without a shared node
+        let roads = vec![
+            RoadSegment::new(vec![(0.0, -0.003), (0.0, 0.003)], RoadClass::Primary),
+            RoadSegment::new(vec![(-0.003, 0.0), (0.003, 0.0)], RoadClass::Primary),
+        ];
+
+        for round_caps in [false, true] {
+            let config = RoadConfig::default().with_round_caps(round_caps);
+            let ribbons = generate_road_meshes(&roads, &projector, &scaler, &config);
+            assert!(count_coplanar_overlaps(&ribbons) > 0);
+
+            let merged = generate_road_union(&roads, &projector, &scaler, &config);
+            assert!(!merged.is_empty());
+            assert_eq!(count_coplanar_overlaps(&merged), 0);
+            assert!(check_manifold(&merged).is_watertight());
+        }
     }
 }
@@ -0,0 +1,125 @@
+//! Labeled distance bar printed in the text margin
+
+use super::text::TextRenderer;
+use crate::geometry::Scaler;
+use crate::mesh::{RibbonOptions, Triangle, extrude_ribbon_ex};
+
+/// Width of the bar and tick strokes in mm
+const STROKE_WIDTH_MM: f32 = 0.8;
+
+/// Height of the tick marks above the bar in mm
+const TICK_HEIGHT_MM: f32 = 1.5;
+
+/// Gap between the tick tops and the label baseline in mm
+const LABEL_GAP_MM: f32 = 0.8;
+
+/// Height of the distance label in mm
+const LABEL_HEIGHT_MM: f32 = 2.5;
+
+/// Longest round distance (1, 2 or 5 times a power of ten) not exceeding
+/// `max_length_m`
+pub fn round_scale_length(max_length_m: f64) -> f64 {
+    if max_length_m <= 0.0 {
+        return 0.0;
+    }
+    let magnitude = 10f64.powf(max_length_m.log10().floor());
+    [5.0, 2.0, 1.0]
+        .into_iter()
+        .map(|step| step * magnitude)
+        .find(|&length| length <= max_length_m)
+        .unwrap_or(magnitude)
+}
+
+/// Distance label such as "500 m" or "2 km"
+pub fn format_distance(length_m: f64) -> String {
+    if length_m >= 1000.0 {
+        format!("{} km", length_m / 1000.0)
+    } else {
+        format!("{} m", length_m)
+    }
+}
+
+/// Generate a scale bar `length_m` long at map scale, starting at (x, y)
+///
+/// The bar runs right along `y` with ticks at both ends and the middle,
+/// and the distance label is centered above it. Strokes extrude from z=0 to
+/// `z_top` like the labels.
+pub fn generate_scale_bar(
+    scaler: &Scaler,
+    length_m: f64,
+    x: f32,
+    y: f32,
+    z_top: f32,
+    renderer: &TextRenderer,
+) -> Vec<Triangle> {
+    let length_mm = (length_m * scaler.scale_factor()) as f32;
+    if length_mm <= 0.0 {
+        return Vec::new();
+    }
+
+    let mut triangles = extrude_ribbon_ex(
+        &[(x, y), (x + length_mm, y)],
+        STROKE_WIDTH_MM,
+        z_top,
+        0.0,
+        &RibbonOptions::default(),
+    );
+    for tick_x in [x, x + length_mm / 2.0, x + length_mm] {
+        triangles.extend(extrude_ribbon_ex(
+            &[(tick_x, y), (tick_x, y + TICK_HEIGHT_MM)],
+            STROKE_WIDTH_MM,
+            z_top,
+            0.0,
+            &RibbonOptions::default(),
+        ));
+    }
+
+    let label = format_distance(length_m);
+    let scale = LABEL_HEIGHT_MM / renderer.line_height(1.0);
+    triangles.extend(renderer.render_text_centered(
+        &label,
+        x + length_mm / 2.0,
+        y + TICK_HEIGHT_MM + LABEL_GAP_MM,
+        0.0,
+        scale,
+        1.0,
+    ));
+
+    triangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Bounds;
+    use crate::layers::text::StrokeTextRenderer;
+
+    #[test]
+    fn test_round_scale_length() {
+        assert_eq!(round_scale_length(4000.0), 2000.0);
+        assert_eq!(round_scale_length(7300.0), 5000.0);
+        assert_eq!(round_scale_length(1000.0), 1000.0);
+        assert_eq!(round_scale_length(180.0), 100.0);
+        assert_eq!(format_distance(500.0), "500 m");
+        assert_eq!(format_distance(2000.0), "2 km");
+    }
+
+    #[test]
+    fn test_scale_bar_length_matches_scaler() {
+        // 10km across 200mm: 0.02 mm per meter, so 1km is 20mm
+        let bounds = Bounds::from_points(&[(0.0, 0.0), (10_000.0, 10_000.0)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 200.0);
+        let renderer = TextRenderer::Stroke(StrokeTextRenderer::new(2.0));
+        let triangles = generate_scale_bar(&scaler, 1000.0, 10.0, 4.0, 2.0, &renderer);
+
+        let bar_x = triangles
+            .iter()
+            .flat_map(|t| t.vertices)
+            .filter(|v| (v[1] - 4.0).abs() <= STROKE_WIDTH_MM / 2.0 + 1e-4)
+            .fold((f32::MAX, f32::MIN), |(lo, hi), v| {
+                (lo.min(v[0]), hi.max(v[0]))
+            });
+        assert!(bar_x.0 < 10.0 && bar_x.0 > 9.0);
+        assert!(bar_x.1 > 30.0 && bar_x.1 < 31.0);
+    }
+}
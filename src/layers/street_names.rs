@@ -0,0 +1,242 @@
+//! Street names laid along major roads
+//!
+//! A name follows its road's centerline glyph by glyph, each glyph turned to
+//! the road's direction where it sits, and reads left to right whichever way
+//! the road was drawn. Names are printed at the text height, so they stand
+//! out from the road they sit on.
+
+use super::road_network::join_road_segments;
+use super::text::TextRenderer;
+use crate::domain::{RoadClass, RoadSegment};
+use crate::geometry::{Projector, Scaler};
+use crate::mesh::Triangle;
+
+/// Shortest road, in mm on the plate, that gets its name printed
+pub const DEFAULT_STREET_NAME_MIN_LENGTH_MM: f32 = 40.0;
+
+/// Line height of street names in mm
+const NAME_HEIGHT_MM: f32 = 3.0;
+
+/// Baseline offset below the centerline, as a share of the line height, that
+/// centers capitals on the road
+const BASELINE_DROP: f32 = 0.3;
+
+/// Largest turn in degrees between neighboring glyphs; names on sharper
+/// bends are left out rather than printed as a jumble
+const MAX_GLYPH_TURN_DEG: f32 = 30.0;
+
+/// Names of motorways and primary roads at least `min_length_mm` long
+///
+/// Ways of the same street are joined first, and each joined road is named
+/// once at its middle. A name repeated within its own width of an earlier
+/// one, such as on the other carriageway of a dual carriageway, is skipped.
+pub fn generate_street_names(
+    roads: &[RoadSegment],
+    projector: &Projector,
+    scaler: &Scaler,
+    renderer: &TextRenderer,
+    min_length_mm: f32,
+) -> Vec<Triangle> {
+    let named: Vec<RoadSegment> = roads
+        .iter()
+        .filter(|road| matches!(road.class, RoadClass::Motorway | RoadClass::Primary))
+        .filter(|road| !road.is_tunnel && road.name.as_deref().is_some_and(|n| !n.is_empty()))
+        .cloned()
+        .collect();
+    let scale = NAME_HEIGHT_MM / renderer.line_height(1.0);
+
+    let mut placed: Vec<(String, (f32, f32), f32)> = Vec::new();
+    let mut triangles = Vec::new();
+    for road in join_road_segments(&named) {
+        let Some(name) = road.name.as_deref() else {
+            continue;
+        };
+        let mut line: Vec<(f32, f32)> = road
+            .points
+            .iter()
+            .map(|&(lat, lon)| {
+                let (x, y) = projector.project(lat, lon);
+                scaler.scale(x, y)
+            })
+            .collect();
+        line.dedup();
+        if line.len() < 2 {
+            continue;
+        }
+        let (dx, dy) = (
+            line[line.len() - 1].0 - line[0].0,
+            line[line.len() - 1].1 - line[0].1,
+        );
+        if dx < 0.0 || (dx == 0.0 && dy < 0.0) {
+            line.reverse();
+        }
+        let path = Path::new(line);
+        let width = renderer.text_width(name, scale);
+        if path.length < min_length_mm || width > path.length {
+            continue;
+        }
+
+        let (middle, _) = path.at(path.length / 2.0);
+        let crowded = placed.iter().any(|(other, (x, y), other_width)| {
+            other == name && (x - middle.0).hypot(y - middle.1) < width.max(*other_width)
+        });
+        if crowded {
+            continue;
+        }
+        if let Some(label) = lay_out(name, &path, renderer, scale, width) {
+            triangles.extend(label);
+            placed.push((name.to_string(), middle, width));
+        }
+    }
+    triangles
+}
+
+/// Glyphs of `name` centered along the path, or `None` on too sharp a bend
+fn lay_out(
+    name: &str,
+    path: &Path,
+    renderer: &TextRenderer,
+    scale: f32,
+    width: f32,
+) -> Option<Vec<Triangle>> {
+    let start = (path.length - width) / 2.0;
+    let drop = NAME_HEIGHT_MM * BASELINE_DROP;
+    let mut triangles = Vec::new();
+    let mut last_angle: Option<f32> = None;
+    let mut text = String::new();
+
+    for ch in name.chars() {
+        text.push(ch);
+        if ch.is_whitespace() {
+            continue;
+        }
+        let glyph_width = renderer.text_width(&ch.to_string(), scale);
+        let along = start + renderer.text_width(&text, scale) - glyph_width / 2.0;
+        let ((x, y), angle) = path.at(along);
+        if let Some(last) = last_angle {
+            let turn = (angle - last + std::f32::consts::PI).rem_euclid(std::f32::consts::TAU)
+                - std::f32::consts::PI;
+            if turn.abs().to_degrees() > MAX_GLYPH_TURN_DEG {
+                return None;
+            }
+        }
+        last_angle = Some(angle);
+
+        let (sin, cos) = angle.sin_cos();
+        let place = |v: [f32; 3]| {
+            [
+                x + v[0] * cos - v[1] * sin,
+                y + v[0] * sin + v[1] * cos,
+                v[2],
+            ]
+        };
+        let glyph = renderer.render_text_centered(&ch.to_string(), 0.0, -drop, 0.0, scale, 1.0);
+        triangles.extend(glyph.into_iter().map(|t| {
+            let [a, b, c] = t.vertices;
+            Triangle::new(place(a), place(b), place(c))
+        }));
+    }
+    Some(triangles)
+}
+
+/// Polyline with cumulative lengths, for finding points by distance
+struct Path {
+    points: Vec<(f32, f32)>,
+    distances: Vec<f32>,
+    length: f32,
+}
+
+impl Path {
+    fn new(points: Vec<(f32, f32)>) -> Self {
+        let mut distances = Vec::with_capacity(points.len());
+        let mut length = 0.0;
+        distances.push(0.0);
+        for pair in points.windows(2) {
+            length += (pair[1].0 - pair[0].0).hypot(pair[1].1 - pair[0].1);
+            distances.push(length);
+        }
+        Self {
+            points,
+            distances,
+            length,
+        }
+    }
+
+    /// Point `distance` mm along the path and the direction there in radians
+    fn at(&self, distance: f32) -> ((f32, f32), f32) {
+        let i = self
+            .distances
+            .partition_point(|&d| d <= distance)
+            .clamp(1, self.points.len() - 1);
+        let (a, b) = (self.points[i - 1], self.points[i]);
+        let span = self.distances[i] - self.distances[i - 1];
+        let t = if span > 0.0 {
+            ((distance - self.distances[i - 1]) / span).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        (
+            (a.0 + t * (b.0 - a.0), a.1 + t * (b.1 - a.1)),
+            (b.1 - a.1).atan2(b.0 - a.0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Bounds;
+    use crate::layers::text::StrokeTextRenderer;
+
+    fn setup() -> (Projector, Scaler, TextRenderer) {
+        let projector = Projector::new((0.0, 0.0));
+        let (x0, y0) = projector.project(-0.01, -0.01);
+        let (x1, y1) = projector.project(0.01, 0.01);
+        let bounds = Bounds::from_points(&[(x0, y0), (x1, y1)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 200.0);
+        (
+            projector,
+            scaler,
+            TextRenderer::Stroke(StrokeTextRenderer::new(3.0)),
+        )
+    }
+
+    #[test]
+    fn test_names_follow_roads() {
+        let (projector, scaler, renderer) = setup();
+        // A north-south road drawn southward still reads bottom to top
+        let road = RoadSegment::new(vec![(0.008, 0.0), (-0.008, 0.0)], RoadClass::Primary)
+            .with_name(Some("MAIN".to_string()));
+        let triangles = generate_street_names(
+            std::slice::from_ref(&road),
+            &projector,
+            &scaler,
+            &renderer,
+            DEFAULT_STREET_NAME_MIN_LENGTH_MM,
+        );
+        assert!(!triangles.is_empty());
+        let (x_min, x_max) = triangles
+            .iter()
+            .flat_map(|t| t.vertices)
+            .fold((f32::MAX, f32::MIN), |(lo, hi), v| {
+                (lo.min(v[0]), hi.max(v[0]))
+            });
+        // Glyphs stand across the vertical road, within a line height of it
+        assert!(x_min > 100.0 - NAME_HEIGHT_MM && x_max < 100.0 + NAME_HEIGHT_MM);
+
+        // Short roads, minor roads and unnamed roads get no label
+        let short = RoadSegment::new(vec![(0.0, 0.0), (0.001, 0.0)], RoadClass::Primary)
+            .with_name(Some("MAIN".to_string()));
+        let minor = RoadSegment::new(road.points.clone(), RoadClass::Residential)
+            .with_name(Some("MAIN".to_string()));
+        let unnamed = RoadSegment::new(road.points.clone(), RoadClass::Motorway);
+        let none = generate_street_names(
+            &[short, minor, unnamed],
+            &projector,
+            &scaler,
+            &renderer,
+            DEFAULT_STREET_NAME_MIN_LENGTH_MM,
+        );
+        assert!(none.is_empty());
+    }
+}
@@ -0,0 +1,354 @@
+//! Terrain relief from an elevation model
+//!
+//! The map area becomes a heightfield solid whose top follows the ground,
+//! lowest point level with the base plate top. Every feature is then lifted
+//! by the relief under each of its vertices, so roads run over the hills
+//! instead of through them. Features are lifted only at their own vertices,
+//! so a large flat park or lake on a slope can dip below the surface between
+//! them.
+
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+
+use crate::geometry::{Dem, Projector, Scaler};
+use crate::mesh::Triangle;
+
+/// Grid spacing in mm: about one SRTM sample on a city-sized plate, and finer
+/// than a nozzle renders on a slope
+pub const TERRAIN_CELL_MM: f32 = 2.0;
+
+/// Grid edge as its lower left point (row, column) and whether it runs up
+/// rather than right
+type Edge = (usize, usize, bool);
+
+/// Ground height over a rectangle of the plate, sampled on a regular grid
+#[derive(Debug, Clone)]
+pub struct Terrain {
+    /// Lower left corner of the grid in mm
+    origin: (f32, f32),
+    /// Distance between grid points in mm along x and y
+    cell: (f32, f32),
+    columns: usize,
+    rows: usize,
+    /// Elevation in meters, row by row from the bottom
+    elevations: Vec<f32>,
+    /// Lowest elevation, which sits level with the base top
+    lowest: f32,
+    /// Relief in mm per meter of elevation
+    mm_per_meter: f32,
+    /// Grid points the elevation model had no data for
+    missing: usize,
+}
+
+impl Terrain {
+    /// Sample `dem` over the plate rectangle from `min` to `max` in mm
+    ///
+    /// Heights are at the map scale times `exaggeration`. Points outside the
+    /// model or in its voids take the lowest elevation found.
+    pub fn sample(
+        dem: &Dem,
+        projector: &Projector,
+        scaler: &Scaler,
+        (min, max): ((f32, f32), (f32, f32)),
+        exaggeration: f32,
+    ) -> Result<Self> {
+        let (width, height) = (max.0 - min.0, max.1 - min.1);
+        if width <= 0.0 || height <= 0.0 {
+            bail!("The map area is empty, so there is no terrain to sample");
+        }
+        let columns = (width / TERRAIN_CELL_MM).ceil().max(1.0) as usize + 1;
+        let rows = (height / TERRAIN_CELL_MM).ceil().max(1.0) as usize + 1;
+        let cell = (width / (columns - 1) as f32, height / (rows - 1) as f32);
+
+        let elevations: Vec<Option<f64>> = (0..rows)
+            .flat_map(|r| (0..columns).map(move |c| (r, c)))
+            .map(|(r, c)| {
+                let (x, y) = scaler.unscale(min.0 + c as f32 * cell.0, min.1 + r as f32 * cell.1);
+                let (lat, lon) = projector.unproject(x, y);
+                dem.elevation(lat, lon)
+            })
+            .collect();
+        let Some(lowest) = elevations.iter().flatten().copied().reduce(f64::min) else {
+            bail!("The elevation model does not cover the map area");
+        };
+
+        Ok(Self {
+            origin: min,
+            cell,
+            columns,
+            rows,
+            missing: elevations.iter().filter(|z| z.is_none()).count(),
+            elevations: elevations
+                .iter()
+                .map(|z| z.unwrap_or(lowest) as f32)
+                .collect(),
+            lowest: lowest as f32,
+            mm_per_meter: (scaler.scale_factor() * exaggeration as f64) as f32,
+        })
+    }
+
+    /// Grid points the elevation model did not cover, as a fraction
+    pub fn missing_fraction(&self) -> f32 {
+        self.missing as f32 / self.elevations.len() as f32
+    }
+
+    /// Lowest and highest elevation over the map in meters
+    pub fn elevation_range(&self) -> (f32, f32) {
+        let highest = self.elevations.iter().copied().fold(self.lowest, f32::max);
+        (self.lowest, highest)
+    }
+
+    /// Height of the highest point above the lowest, in mm
+    pub fn max_relief(&self) -> f32 {
+        let (lowest, highest) = self.elevation_range();
+        (highest - lowest) * self.mm_per_meter
+    }
+
+    /// Relief in mm at grid point (`r`, `c`)
+    fn relief(&self, r: usize, c: usize) -> f32 {
+        (self.elevations[r * self.columns + c] - self.lowest) * self.mm_per_meter
+    }
+
+    /// Plate position of grid point (`r`, `c`) in mm
+    fn grid_point(&self, r: usize, c: usize) -> (f32, f32) {
+        (
+            self.origin.0 + c as f32 * self.cell.0,
+            self.origin.1 + r as f32 * self.cell.1,
+        )
+    }
+
+    /// Relief in mm at (`x`, `y`), interpolated across its grid cell, and 0
+    /// off the terrain
+    pub fn relief_at(&self, x: f32, y: f32) -> f32 {
+        let col = (x - self.origin.0) / self.cell.0;
+        let row = (y - self.origin.1) / self.cell.1;
+        let (max_col, max_row) = ((self.columns - 1) as f32, (self.rows - 1) as f32);
+        if !(0.0..=max_col).contains(&col) || !(0.0..=max_row).contains(&row) {
+            return 0.0;
+        }
+        let c0 = (col.floor() as usize).min(self.columns - 2);
+        let r0 = (row.floor() as usize).min(self.rows - 2);
+        let (fx, fy) = (col - c0 as f32, row - r0 as f32);
+        let bottom = self.relief(r0, c0) * (1.0 - fx) + self.relief(r0, c0 + 1) * fx;
+        let top = self.relief(r0 + 1, c0) * (1.0 - fx) + self.relief(r0 + 1, c0 + 1) * fx;
+        bottom * (1.0 - fy) + top * fy
+    }
+
+    /// Closed heightfield solid from z=0 to `base_z` plus the relief
+    pub fn mesh(&self, base_z: f32) -> Vec<Triangle> {
+        let point = |r: usize, c: usize, top: bool| {
+            let (x, y) = self.grid_point(r, c);
+            let z = if top { base_z + self.relief(r, c) } else { 0.0 };
+            [x, y, z]
+        };
+
+        let mut triangles = Vec::with_capacity(4 * self.elevations.len());
+        for r in 0..self.rows - 1 {
+            for c in 0..self.columns - 1 {
+                let [a, b, d, e] = [(r, c), (r, c + 1), (r + 1, c + 1), (r + 1, c)];
+                let top = |(r, c)| point(r, c, true);
+                let bottom = |(r, c)| point(r, c, false);
+                triangles.push(Triangle::new(top(a), top(b), top(d)));
+                triangles.push(Triangle::new(top(a), top(d), top(e)));
+                triangles.push(Triangle::new(bottom(a), bottom(d), bottom(b)));
+                triangles.push(Triangle::new(bottom(a), bottom(e), bottom(d)));
+            }
+        }
+
+        // Walls around the edge, counterclockwise seen from above
+        let (last_r, last_c) = (self.rows - 1, self.columns - 1);
+        let perimeter: Vec<(usize, usize)> = (0..last_c)
+            .map(|c| (0, c))
+            .chain((0..last_r).map(|r| (r, last_c)))
+            .chain((1..=last_c).rev().map(|c| (last_r, c)))
+            .chain((1..=last_r).rev().map(|r| (r, 0)))
+            .collect();
+        for (i, &a) in perimeter.iter().enumerate() {
+            let b = perimeter[(i + 1) % perimeter.len()];
+            let (a0, b0) = (point(a.0, a.1, false), point(b.0, b.1, false));
+            let (a1, b1) = (point(a.0, a.1, true), point(b.0, b.1, true));
+            triangles.push(Triangle::new(a0, b0, b1));
+            triangles.push(Triangle::new(a0, b1, a1));
+        }
+        triangles
+    }
+
+    /// Lines where the ground crosses `elevation` meters, traced through the
+    /// grid by marching squares, as polylines in mm
+    ///
+    /// Lines stop where they leave the grid; a loop within it ends on its
+    /// first point.
+    pub fn isolines(&self, elevation: f32) -> Vec<Vec<(f32, f32)>> {
+        let columns = self.columns;
+        let z = |r: usize, c: usize| self.elevations[r * columns + c];
+        let crossing = |(r, c, up): Edge| {
+            let (r1, c1) = if up { (r + 1, c) } else { (r, c + 1) };
+            let t = ((elevation - z(r, c)) / (z(r1, c1) - z(r, c))).clamp(0.0, 1.0);
+            let (a, b) = (self.grid_point(r, c), self.grid_point(r1, c1));
+            (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t)
+        };
+
+        let mut segments: Vec<(Edge, Edge)> = Vec::new();
+        for r in 0..self.rows - 1 {
+            for c in 0..columns - 1 {
+                let corners = [z(r, c), z(r, c + 1), z(r + 1, c + 1), z(r + 1, c)];
+                let case = corners
+                    .iter()
+                    .enumerate()
+                    .fold(0, |case, (i, &h)| case | (usize::from(h >= elevation) << i));
+                let (bottom, right, top, left) = (
+                    (r, c, false),
+                    (r, c + 1, true),
+                    (r + 1, c, false),
+                    (r, c, true),
+                );
+                match case {
+                    1 | 14 => segments.push((left, bottom)),
+                    2 | 13 => segments.push((bottom, right)),
+                    3 | 12 => segments.push((left, right)),
+                    4 | 11 => segments.push((right, top)),
+                    6 | 9 => segments.push((bottom, top)),
+                    7 | 8 => segments.push((left, top)),
+                    5 | 10 => {
+                        // Saddle: the cell center decides which diagonal
+                        // pair of corners the ground joins
+                        let center = corners.iter().sum::<f32>() / 4.0 >= elevation;
+                        if (case == 5) == center {
+                            segments.extend([(left, top), (bottom, right)]);
+                        } else {
+                            segments.extend([(left, bottom), (right, top)]);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut at: HashMap<Edge, Vec<usize>> = HashMap::new();
+        for (i, &(a, b)) in segments.iter().enumerate() {
+            at.entry(a).or_default().push(i);
+            at.entry(b).or_default().push(i);
+        }
+        // Open lines from their ends first, so none is split in the middle
+        let starts: Vec<Edge> = segments
+            .iter()
+            .flat_map(|&(a, b)| [a, b])
+            .filter(|edge| at[edge].len() == 1)
+            .chain(segments.iter().map(|&(a, _)| a))
+            .collect();
+        let mut used = vec![false; segments.len()];
+        let mut lines = Vec::new();
+        for start in starts {
+            let mut edge = start;
+            let mut line = vec![crossing(edge)];
+            while let Some(&i) = at[&edge].iter().find(|&&i| !used[i]) {
+                used[i] = true;
+                let (a, b) = segments[i];
+                edge = if a == edge { b } else { a };
+                line.push(crossing(edge));
+            }
+            if line.len() > 1 {
+                lines.push(line);
+            }
+        }
+        lines
+    }
+
+    /// Lift every vertex above z=0 by the relief under it, keeping feature
+    /// columns standing on the plate bed
+    pub fn drape(&self, triangles: &mut [Triangle]) {
+        for tri in triangles.iter_mut() {
+            let mut vertices = tri.vertices;
+            for v in vertices.iter_mut().filter(|v| v[2] > 0.0) {
+                v[2] += self.relief_at(v[0], v[1]);
+            }
+            *tri = Triangle::new(vertices[0], vertices[1], vertices[2]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Bounds;
+    use crate::mesh::validation::check_manifold;
+
+    /// A hill rising 100m per tenth of a degree to the east
+    fn slope() -> (Dem, Projector, Scaler) {
+        let dem = Dem::new(
+            3,
+            3,
+            (-0.1, 0.1),
+            (0.1, 0.1),
+            vec![0.0, 100.0, 200.0, 0.0, 100.0, 200.0, 0.0, 100.0, 200.0],
+        )
+        .unwrap();
+        let projector = Projector::new((0.0, 0.0));
+        let bounds = Bounds {
+            min_x: -1000.0,
+            max_x: 1000.0,
+            min_y: -1000.0,
+            max_y: 1000.0,
+        };
+        // 20mm for 2km, so 100m of elevation is 1mm
+        (dem, projector, Scaler::from_bounds(&bounds, 20.0))
+    }
+
+    #[test]
+    fn test_terrain_follows_dem() {
+        let (dem, projector, scaler) = slope();
+        let terrain =
+            Terrain::sample(&dem, &projector, &scaler, ((0.0, 0.0), (20.0, 20.0)), 2.0).unwrap();
+        assert_eq!(terrain.missing_fraction(), 0.0);
+        // 2km across a 1000m per degree slope, doubled
+        let rise = 2.0 * 2000.0 / 111_320.0 * 1000.0 * 0.01;
+        assert!((terrain.max_relief() - rise).abs() < 0.05 * rise);
+        assert!(terrain.relief_at(0.0, 10.0) < 1e-3);
+        assert!((terrain.relief_at(10.0, 3.0) - rise / 2.0).abs() < 0.05 * rise);
+        assert_eq!(terrain.relief_at(25.0, 10.0), 0.0);
+
+        let mesh = terrain.mesh(2.0);
+        assert!(check_manifold(&mesh).is_watertight());
+        let top = mesh
+            .iter()
+            .flat_map(|t| t.vertices)
+            .map(|v| v[2])
+            .fold(0.0, f32::max);
+        assert!((top - 2.0 - terrain.max_relief()).abs() < 1e-4);
+
+        let mut road = vec![Triangle::new(
+            [20.0, 5.0, 3.0],
+            [20.0, 6.0, 3.0],
+            [20.0, 6.0, 0.0],
+        )];
+        terrain.drape(&mut road);
+        assert!((road[0].vertices[0][2] - 3.0 - terrain.max_relief()).abs() < 1e-4);
+        assert_eq!(road[0].vertices[2][2], 0.0);
+    }
+
+    #[test]
+    fn test_terrain_isolines() {
+        let (dem, projector, scaler) = slope();
+        let terrain =
+            Terrain::sample(&dem, &projector, &scaler, ((0.0, 0.0), (20.0, 20.0)), 1.0).unwrap();
+        let (lowest, highest) = terrain.elevation_range();
+        let middle = (lowest + highest) / 2.0;
+        // The slope rises to the east, so each level is one line running
+        // north to south across the middle
+        let lines = terrain.isolines(middle);
+        assert_eq!(lines.len(), 1);
+        let line = &lines[0];
+        assert!(line.iter().all(|p| (p.0 - 10.0).abs() < 0.1));
+        let ys: Vec<f32> = line.iter().map(|p| p.1).collect();
+        assert!(ys.contains(&0.0) && ys.contains(&20.0));
+        assert!(terrain.isolines(highest + 1.0).is_empty());
+    }
+
+    #[test]
+    fn test_terrain_outside_dem() {
+        let (dem, projector, scaler) = slope();
+        let far = Projector::new((10.0, 10.0));
+        assert!(Terrain::sample(&dem, &far, &scaler, ((0.0, 0.0), (20.0, 20.0)), 1.0).is_err());
+        assert!(Terrain::sample(&dem, &projector, &scaler, ((5.0, 5.0), (5.0, 9.0)), 1.0).is_err());
+    }
+}
@@ -1,26 +1,87 @@
-use crate::mesh::{Triangle, extrude_ribbon_ex};
+use crate::mesh::{RibbonOptions, Triangle, extrude_ribbon_ex};
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
-const CURVE_SUBDIVISIONS: u8 = 20;
+/// Line segments per glyph curve unless `--text-quality` says otherwise
+pub const DEFAULT_CURVE_SUBDIVISIONS: u8 = 20;
+
+/// What the stroke font draws for a character neither it nor any font has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingGlyph {
+    /// An outlined box, so the gap is easy to spot
+    #[default]
+    Box,
+    /// A low bar along the baseline
+    Underscore,
+    /// Nothing; the character's space is kept
+    Blank,
+}
+
+impl std::str::FromStr for MissingGlyph {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "box" => Ok(MissingGlyph::Box),
+            "underscore" => Ok(MissingGlyph::Underscore),
+            "blank" | "none" => Ok(MissingGlyph::Blank),
+            _ => Err(format!(
+                "Invalid missing glyph style '{}'. Valid options: box, underscore, blank",
+                s
+            )),
+        }
+    }
+}
+
+impl MissingGlyph {
+    /// Stroke paths drawn in place of the character, on the stroke font grid
+    fn strokes(self) -> Vec<Vec<(f32, f32)>> {
+        match self {
+            MissingGlyph::Box => vec![vec![
+                (0.0, 0.0),
+                (5.0, 0.0),
+                (5.0, 7.0),
+                (0.0, 7.0),
+                (0.0, 0.0),
+            ]],
+            MissingGlyph::Underscore => vec![vec![(0.0, 0.0), (5.0, 0.0)]],
+            MissingGlyph::Blank => Vec::new(),
+        }
+    }
+}
 
 pub struct TtfTextRenderer {
     font_data: Vec<u8>,
+    /// Fonts tried in order for characters the main font has no glyph for
+    fallback_data: Vec<Vec<u8>>,
     pub extrude_height: f32,
+    /// Line segments each quadratic or cubic outline curve is split into
+    pub curve_subdivisions: u8,
+    /// Drawn for characters no font has a glyph for
+    pub missing_glyph: MissingGlyph,
+}
+
+/// Read a font file, keeping it only if it parses and can mesh a glyph
+fn load_font_data(font_path: &Path) -> Option<Vec<u8>> {
+    let font_data = std::fs::read(font_path).ok()?;
+    let face = fontmesh::Face::parse(&font_data, 0).ok()?;
+    let probe = ['A', '0', '\u{6771}']
+        .into_iter()
+        .find(|&ch| face.glyph_index(ch).is_some_and(|id| id.0 != 0))?;
+    if fontmesh::char_to_mesh_3d(&face, probe, 1.0, 8).is_err() {
+        return None;
+    }
+    Some(font_data)
 }
 
 impl TtfTextRenderer {
     pub fn load(font_path: &Path, extrude_height: f32) -> Option<Self> {
-        let font_data = std::fs::read(font_path).ok()?;
-        let face = fontmesh::Face::parse(&font_data, 0).ok()?;
-
-        if fontmesh::char_to_mesh_3d(&face, 'A', 1.0, 8).is_err() {
-            return None;
-        }
-
         Some(Self {
-            font_data,
+            font_data: load_font_data(font_path)?,
+            fallback_data: Vec::new(),
             extrude_height,
+            curve_subdivisions: DEFAULT_CURVE_SUBDIVISIONS,
+            missing_glyph: MissingGlyph::default(),
         })
     }
 
@@ -39,38 +100,89 @@ impl TtfTextRenderer {
         None
     }
 
-    fn face(&self) -> fontmesh::Face<'_> {
-        fontmesh::Face::parse(&self.font_data, 0).unwrap()
+    /// Re-parse the font; `load` checked it once, but a failure here is
+    /// returned rather than trusted away
+    fn face(&self) -> fontmesh::Result<fontmesh::Face<'_>> {
+        fontmesh::parse_font(&self.font_data)
     }
 
-    pub fn text_width(&self, text: &str, scale: f32) -> f32 {
-        let face = self.face();
-        let mut width = 0.0;
-        for ch in text.chars() {
-            if let Some(advance) = fontmesh::glyph_advance(&face, ch) {
-                width += advance * scale;
-            }
+    /// The main font followed by the fallbacks that still parse
+    fn faces(&self) -> fontmesh::Result<Vec<fontmesh::Face<'_>>> {
+        let mut faces = vec![self.face()?];
+        faces.extend(
+            self.fallback_data
+                .iter()
+                .filter_map(|data| fontmesh::parse_font(data).ok()),
+        );
+        Ok(faces)
+    }
+
+    /// Stroke renderer of the same height, for when the font stops parsing
+    pub fn fallback(&self) -> StrokeTextRenderer {
+        StrokeTextRenderer::new(self.extrude_height).with_missing_glyph(self.missing_glyph)
+    }
+
+    /// Advance of `ch` in ems, from the first font with its glyph or from the
+    /// stroke box drawn in place of a missing one
+    fn advance(faces: &[fontmesh::Face], ch: char) -> f32 {
+        match glyph_face(faces, ch) {
+            Some(face) => fontmesh::glyph_advance(face, ch).unwrap_or(0.0),
+            None => stroke_em(&faces[0]) * (STROKE_CHAR_WIDTH + STROKE_CHAR_SPACING),
         }
-        width
     }
 
-    pub fn render_text(&self, text: &str, x: f32, y: f32, z: f32, scale: f32) -> Vec<Triangle> {
-        let face = self.face();
+    pub fn text_width(&self, text: &str, scale: f32) -> fontmesh::Result<f32> {
+        let faces = self.faces()?;
+        Ok(text
+            .chars()
+            .map(|ch| Self::advance(&faces, ch))
+            .sum::<f32>()
+            * scale)
+    }
+
+    /// Height of capital letters at `scale`, from the font's cap height or,
+    /// when it has none, the outline of 'H'
+    pub fn cap_height(&self, scale: f32) -> fontmesh::Result<f32> {
+        Ok(cap_em(&self.face()?) * scale)
+    }
+
+    /// Baseline-to-baseline distance for single spacing
+    pub fn line_height(&self, scale: f32) -> fontmesh::Result<f32> {
+        let face = self.face()?;
+        Ok(
+            (fontmesh::ascender(&face) - fontmesh::descender(&face) + fontmesh::line_gap(&face))
+                * scale,
+        )
+    }
+
+    pub fn render_text(
+        &self,
+        text: &str,
+        x: f32,
+        y: f32,
+        z: f32,
+        scale: f32,
+    ) -> fontmesh::Result<Vec<Triangle>> {
+        let faces = self.faces()?;
         let mut triangles = Vec::new();
         let mut cursor_x = x;
 
         for ch in text.chars() {
+            let advance = Self::advance(&faces, ch) * scale;
+            let Some(face) = glyph_face(&faces, ch) else {
+                // No font has it: draw the stroke placeholder at the main font's cap height
+                let stroke = self.fallback().with_scale(stroke_em(&faces[0]) * scale);
+                triangles.extend(stroke.render_text(&ch.to_string(), cursor_x, y, z));
+                cursor_x += advance;
+                continue;
+            };
             if ch == ' ' {
-                if let Some(advance) = fontmesh::glyph_advance(&face, ch) {
-                    cursor_x += advance * scale;
-                } else {
-                    cursor_x += 0.3 * scale;
-                }
+                cursor_x += if advance > 0.0 { advance } else { 0.3 * scale };
                 continue;
             }
 
             if let Ok(mesh) =
-                fontmesh::char_to_mesh_3d(&face, ch, self.extrude_height, CURVE_SUBDIVISIONS)
+                fontmesh::char_to_mesh_3d(face, ch, self.extrude_height, self.curve_subdivisions)
             {
                 let z_offset = self.extrude_height / 2.0;
                 for tri_indices in mesh.indices.chunks(3) {
@@ -113,12 +225,10 @@ impl TtfTextRenderer {
                 }
             }
 
-            if let Some(advance) = fontmesh::glyph_advance(&face, ch) {
-                cursor_x += advance * scale;
-            }
+            cursor_x += advance;
         }
 
-        triangles
+        Ok(triangles)
     }
 
     pub fn render_text_centered(
@@ -128,47 +238,82 @@ impl TtfTextRenderer {
         y: f32,
         z: f32,
         scale: f32,
-    ) -> Vec<Triangle> {
-        let width = self.text_width(text, scale);
+    ) -> fontmesh::Result<Vec<Triangle>> {
+        let width = self.text_width(text, scale)?;
         let start_x = center_x - width / 2.0;
         self.render_text(text, start_x, y, z, scale)
     }
 
-    pub fn calculate_scale_for_width(&self, text: &str, target_width: f32) -> f32 {
-        let face = self.face();
-        let mut raw_width = 0.0;
-        for ch in text.chars() {
-            if let Some(advance) = fontmesh::glyph_advance(&face, ch) {
-                raw_width += advance;
-            }
-        }
-        if raw_width > 0.0 {
+    pub fn calculate_scale_for_width(
+        &self,
+        text: &str,
+        target_width: f32,
+    ) -> fontmesh::Result<f32> {
+        let raw_width = self.text_width(text, 1.0)?;
+        Ok(if raw_width > 0.0 {
             target_width / raw_width
         } else {
             1.0
-        }
+        })
     }
 }
 
+/// First font with a real glyph for `ch`, not the missing-glyph box
+fn glyph_face<'a, 'f>(faces: &'a [fontmesh::Face<'f>], ch: char) -> Option<&'a fontmesh::Face<'f>> {
+    faces
+        .iter()
+        .find(|face| face.glyph_index(ch).is_some_and(|id| id.0 != 0))
+}
+
+/// Cap height of a font in ems
+fn cap_em(face: &fontmesh::Face) -> f32 {
+    let units = face.capital_height().filter(|&h| h > 0).or_else(|| {
+        let glyph = face.glyph_index('H')?;
+        face.glyph_bounding_box(glyph).map(|b| b.y_max)
+    });
+    match units {
+        Some(units) => units as f32 / face.units_per_em() as f32,
+        None => fontmesh::ascender(face),
+    }
+}
+
+/// Stroke scale per em that matches a stroke glyph's cap height to the font's
+fn stroke_em(face: &fontmesh::Face) -> f32 {
+    cap_em(face) / STROKE_CHAR_HEIGHT
+}
+
+/// Stroke glyph grid at scale 1, in the units of `get_char_strokes`
+const STROKE_CHAR_WIDTH: f32 = 5.0;
+const STROKE_CHAR_HEIGHT: f32 = 7.0;
+const STROKE_CHAR_SPACING: f32 = 1.5;
+
 pub struct StrokeTextRenderer {
     pub char_width: f32,
     pub char_height: f32,
     pub char_spacing: f32,
     pub stroke_width: f32,
     pub extrude_height: f32,
+    /// Drawn for characters without stroke paths
+    pub missing_glyph: MissingGlyph,
 }
 
 impl StrokeTextRenderer {
     pub fn new(extrude_height: f32) -> Self {
         Self {
-            char_width: 5.0,
-            char_height: 7.0,
-            char_spacing: 1.5,
+            char_width: STROKE_CHAR_WIDTH,
+            char_height: STROKE_CHAR_HEIGHT,
+            char_spacing: STROKE_CHAR_SPACING,
             stroke_width: 0.8,
             extrude_height,
+            missing_glyph: MissingGlyph::default(),
         }
     }
 
+    pub fn with_missing_glyph(mut self, missing_glyph: MissingGlyph) -> Self {
+        self.missing_glyph = missing_glyph;
+        self
+    }
+
     pub fn with_scale(mut self, scale: f32) -> Self {
         self.char_width *= scale;
         self.char_height *= scale;
@@ -185,12 +330,17 @@ impl StrokeTextRenderer {
         (char_count as f32 * self.char_width) + ((char_count - 1) as f32 * self.char_spacing)
     }
 
+    /// Baseline-to-baseline distance for single spacing
+    pub fn line_height(&self) -> f32 {
+        self.char_height + self.char_spacing
+    }
+
     pub fn render_text(&self, text: &str, x: f32, y: f32, z: f32) -> Vec<Triangle> {
         let mut triangles = Vec::new();
         let mut cursor_x = x;
 
         for ch in text.chars() {
-            let strokes = get_char_strokes(ch);
+            let strokes = get_char_strokes(ch).unwrap_or_else(|| self.missing_glyph.strokes());
             for stroke in strokes {
                 let points: Vec<(f32, f32)> = stroke
                     .iter()
@@ -208,8 +358,10 @@ impl StrokeTextRenderer {
                         self.stroke_width,
                         self.extrude_height,
                         z,
-                        false,
-                        true,
+                        &RibbonOptions {
+                            include_bottom: false,
+                            ..Default::default()
+                        },
                     );
                     triangles.extend(ribbon);
                 }
@@ -258,6 +410,10 @@ impl TextRenderer {
         Self::Stroke(StrokeTextRenderer::new(extrude_height))
     }
 
+    /// Render text centered on `center_x`. Lines separated by `\n` are each
+    /// centered on their own and stacked upward so the last line's baseline
+    /// sits at `y`; `line_spacing` multiplies the font's line height. Empty
+    /// lines only take up space.
     pub fn render_text_centered(
         &self,
         text: &str,
@@ -265,23 +421,133 @@ impl TextRenderer {
         y: f32,
         z: f32,
         scale: f32,
+        line_spacing: f32,
     ) -> Vec<Triangle> {
+        let lines: Vec<&str> = text.lines().collect();
+        let advance = self.line_height(scale) * line_spacing;
+        let mut triangles = Vec::new();
+
+        for (i, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let line_y = y + (lines.len() - 1 - i) as f32 * advance;
+            triangles.extend(match self {
+                Self::Ttf(ttf) => ttf
+                    .render_text_centered(line, center_x, line_y, z, scale)
+                    .unwrap_or_else(|_| {
+                        let scaled = ttf.fallback().with_scale(scale);
+                        scaled.render_text_centered(line, center_x, line_y, z)
+                    }),
+                Self::Stroke(stroke) => {
+                    let scaled = stroke.clone().with_scale(scale);
+                    scaled.render_text_centered(line, center_x, line_y, z)
+                }
+            });
+        }
+
+        triangles
+    }
+
+    /// Fonts to try, in order, for characters the main font has no glyph
+    /// for; characters none of them have print as the [`MissingGlyph`]
+    /// placeholder. Without a usable main font the first fallback takes its
+    /// place. Unreadable fonts are skipped.
+    pub fn with_fallback_fonts(self, paths: &[PathBuf]) -> Self {
+        let mut fonts = paths.iter().filter_map(|path| load_font_data(path));
         match self {
-            Self::Ttf(ttf) => ttf.render_text_centered(text, center_x, y, z, scale),
-            Self::Stroke(stroke) => {
-                let scaled = stroke.clone().with_scale(scale);
-                scaled.render_text_centered(text, center_x, y, z)
+            Self::Ttf(mut ttf) => {
+                ttf.fallback_data.extend(fonts);
+                Self::Ttf(ttf)
             }
+            Self::Stroke(stroke) => match fonts.next() {
+                Some(font_data) => Self::Ttf(TtfTextRenderer {
+                    font_data,
+                    fallback_data: fonts.collect(),
+                    extrude_height: stroke.extrude_height,
+                    curve_subdivisions: DEFAULT_CURVE_SUBDIVISIONS,
+                    missing_glyph: stroke.missing_glyph,
+                }),
+                None => Self::Stroke(stroke),
+            },
         }
     }
 
-    pub fn calculate_scale_for_width(&self, text: &str, target_width: f32) -> f32 {
+    /// Split TTF glyph curves into `subdivisions` segments; stroke text has
+    /// no curves and is unaffected
+    pub fn with_curve_subdivisions(mut self, subdivisions: u8) -> Self {
+        if let Self::Ttf(ttf) = &mut self {
+            ttf.curve_subdivisions = subdivisions;
+        }
+        self
+    }
+
+    /// Placeholder drawn for characters no font or stroke path covers
+    pub fn with_missing_glyph(self, missing_glyph: MissingGlyph) -> Self {
         match self {
-            Self::Ttf(ttf) => ttf.calculate_scale_for_width(text, target_width),
-            Self::Stroke(stroke) => stroke.calculate_scale_for_width(text, target_width),
+            Self::Ttf(ttf) => Self::Ttf(TtfTextRenderer {
+                missing_glyph,
+                ..ttf
+            }),
+            Self::Stroke(stroke) => Self::Stroke(stroke.with_missing_glyph(missing_glyph)),
         }
     }
 
+    /// Baseline-to-baseline distance at `scale` for single spacing
+    pub fn line_height(&self, scale: f32) -> f32 {
+        match self {
+            Self::Ttf(ttf) => ttf
+                .line_height(scale)
+                .unwrap_or_else(|_| ttf.fallback().with_scale(scale).line_height()),
+            Self::Stroke(stroke) => stroke.clone().with_scale(scale).line_height(),
+        }
+    }
+
+    /// Width of a single line of text at `scale`
+    pub fn text_width(&self, text: &str, scale: f32) -> f32 {
+        match self {
+            Self::Ttf(ttf) => ttf
+                .text_width(text, scale)
+                .unwrap_or_else(|_| ttf.fallback().with_scale(scale).text_width(text)),
+            Self::Stroke(stroke) => stroke.clone().with_scale(scale).text_width(text),
+        }
+    }
+
+    /// Height of a block of lines from the last baseline to the top of the
+    /// first line
+    pub fn block_height(&self, text: &str, scale: f32, line_spacing: f32) -> f32 {
+        let line_count = text.lines().count().max(1);
+        let line_height = self.line_height(scale);
+        line_height + (line_count - 1) as f32 * line_height * line_spacing
+    }
+
+    /// Scale at which capital letters are `height_mm` tall, whatever the text
+    pub fn scale_for_cap_height(&self, height_mm: f32) -> f32 {
+        let cap_height = match self {
+            Self::Ttf(ttf) => ttf
+                .cap_height(1.0)
+                .unwrap_or_else(|_| ttf.fallback().char_height),
+            Self::Stroke(stroke) => stroke.char_height,
+        };
+        height_mm / cap_height
+    }
+
+    /// Scale at which the widest line is `target_width` wide
+    pub fn calculate_scale_for_width(&self, text: &str, target_width: f32) -> f32 {
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| match self {
+                Self::Ttf(ttf) => ttf
+                    .calculate_scale_for_width(line, target_width)
+                    .unwrap_or_else(|_| {
+                        ttf.fallback().calculate_scale_for_width(line, target_width)
+                    }),
+                Self::Stroke(stroke) => stroke.calculate_scale_for_width(line, target_width),
+            })
+            .reduce(f32::min)
+            .unwrap_or(1.0)
+    }
+
     #[cfg(test)]
     pub fn is_ttf(&self) -> bool {
         matches!(self, Self::Ttf(_))
@@ -296,12 +562,17 @@ impl Clone for StrokeTextRenderer {
             char_spacing: self.char_spacing,
             stroke_width: self.stroke_width,
             extrude_height: self.extrude_height,
+            missing_glyph: self.missing_glyph,
         }
     }
 }
 
-fn get_char_strokes(ch: char) -> Vec<Vec<(f32, f32)>> {
-    match ch.to_ascii_uppercase() {
+/// Stroke paths for a glyph on a 5x7 grid with the baseline at y=0
+///
+/// Lowercase letters have a 5-unit x-height; descenders on g, j, p, q and y
+/// dip to y=-2. Characters without strokes give `None`.
+fn get_char_strokes(ch: char) -> Option<Vec<Vec<(f32, f32)>>> {
+    let strokes = match ch {
         'A' => vec![
             vec![(0.0, 0.0), (2.5, 7.0), (5.0, 0.0)],
             vec![(1.0, 3.0), (4.0, 3.0)],
@@ -467,6 +738,196 @@ fn get_char_strokes(ch: char) -> Vec<Vec<(f32, f32)>> {
             vec![(2.5, 3.5), (2.5, 0.0)],
         ],
         'Z' => vec![vec![(0.0, 7.0), (5.0, 7.0), (0.0, 0.0), (5.0, 0.0)]],
+        'a' => vec![
+            vec![
+                (4.5, 4.0),
+                (3.5, 5.0),
+                (1.0, 5.0),
+                (0.0, 4.0),
+                (0.0, 1.0),
+                (1.0, 0.0),
+                (3.5, 0.0),
+                (4.5, 1.0),
+            ],
+            vec![(4.5, 5.0), (4.5, 0.0)],
+        ],
+        'b' => vec![
+            vec![(0.0, 7.0), (0.0, 0.0)],
+            vec![
+                (0.0, 4.0),
+                (1.0, 5.0),
+                (4.0, 5.0),
+                (5.0, 4.0),
+                (5.0, 1.0),
+                (4.0, 0.0),
+                (1.0, 0.0),
+                (0.0, 1.0),
+            ],
+        ],
+        'c' => vec![vec![
+            (5.0, 4.0),
+            (4.0, 5.0),
+            (1.0, 5.0),
+            (0.0, 4.0),
+            (0.0, 1.0),
+            (1.0, 0.0),
+            (4.0, 0.0),
+            (5.0, 1.0),
+        ]],
+        'd' => vec![
+            vec![(5.0, 7.0), (5.0, 0.0)],
+            vec![
+                (5.0, 4.0),
+                (4.0, 5.0),
+                (1.0, 5.0),
+                (0.0, 4.0),
+                (0.0, 1.0),
+                (1.0, 0.0),
+                (4.0, 0.0),
+                (5.0, 1.0),
+            ],
+        ],
+        'e' => vec![vec![
+            (0.0, 2.5),
+            (5.0, 2.5),
+            (5.0, 4.0),
+            (4.0, 5.0),
+            (1.0, 5.0),
+            (0.0, 4.0),
+            (0.0, 1.0),
+            (1.0, 0.0),
+            (4.5, 0.0),
+        ]],
+        'f' => vec![
+            vec![(4.5, 7.0), (3.0, 7.0), (2.0, 6.0), (2.0, 0.0)],
+            vec![(0.5, 5.0), (4.0, 5.0)],
+        ],
+        'g' => vec![
+            vec![
+                (5.0, 4.0),
+                (4.0, 5.0),
+                (1.0, 5.0),
+                (0.0, 4.0),
+                (0.0, 2.0),
+                (1.0, 1.0),
+                (4.0, 1.0),
+                (5.0, 2.0),
+            ],
+            vec![
+                (5.0, 5.0),
+                (5.0, -1.0),
+                (4.0, -2.0),
+                (1.0, -2.0),
+                (0.0, -1.0),
+            ],
+        ],
+        'h' => vec![
+            vec![(0.0, 7.0), (0.0, 0.0)],
+            vec![(0.0, 4.0), (1.0, 5.0), (4.0, 5.0), (5.0, 4.0), (5.0, 0.0)],
+        ],
+        'i' => vec![vec![(2.5, 5.0), (2.5, 0.0)], vec![(2.5, 6.5), (2.5, 7.0)]],
+        'j' => vec![
+            vec![
+                (3.5, 5.0),
+                (3.5, -1.0),
+                (2.5, -2.0),
+                (1.0, -2.0),
+                (0.0, -1.0),
+            ],
+            vec![(3.5, 6.5), (3.5, 7.0)],
+        ],
+        'k' => vec![
+            vec![(0.0, 7.0), (0.0, 0.0)],
+            vec![(4.5, 5.0), (0.0, 2.0)],
+            vec![(1.5, 3.0), (4.5, 0.0)],
+        ],
+        'l' => vec![vec![(2.0, 7.0), (2.0, 1.0), (3.0, 0.0), (4.0, 0.0)]],
+        'm' => vec![
+            vec![(0.0, 5.0), (0.0, 0.0)],
+            vec![(0.0, 4.0), (1.0, 5.0), (2.0, 5.0), (2.5, 4.0), (2.5, 0.0)],
+            vec![(2.5, 4.0), (3.0, 5.0), (4.0, 5.0), (5.0, 4.0), (5.0, 0.0)],
+        ],
+        'n' => vec![
+            vec![(0.0, 5.0), (0.0, 0.0)],
+            vec![(0.0, 4.0), (1.0, 5.0), (4.0, 5.0), (5.0, 4.0), (5.0, 0.0)],
+        ],
+        'o' => vec![vec![
+            (1.0, 0.0),
+            (0.0, 1.0),
+            (0.0, 4.0),
+            (1.0, 5.0),
+            (4.0, 5.0),
+            (5.0, 4.0),
+            (5.0, 1.0),
+            (4.0, 0.0),
+            (1.0, 0.0),
+        ]],
+        'p' => vec![
+            vec![(0.0, 5.0), (0.0, -2.0)],
+            vec![
+                (0.0, 4.0),
+                (1.0, 5.0),
+                (4.0, 5.0),
+                (5.0, 4.0),
+                (5.0, 1.0),
+                (4.0, 0.0),
+                (1.0, 0.0),
+                (0.0, 1.0),
+            ],
+        ],
+        'q' => vec![
+            vec![(5.0, 5.0), (5.0, -2.0)],
+            vec![
+                (5.0, 4.0),
+                (4.0, 5.0),
+                (1.0, 5.0),
+                (0.0, 4.0),
+                (0.0, 1.0),
+                (1.0, 0.0),
+                (4.0, 0.0),
+                (5.0, 1.0),
+            ],
+        ],
+        'r' => vec![
+            vec![(0.0, 5.0), (0.0, 0.0)],
+            vec![(0.0, 3.5), (1.5, 5.0), (4.0, 5.0), (5.0, 4.0)],
+        ],
+        's' => vec![vec![
+            (5.0, 4.0),
+            (4.0, 5.0),
+            (1.0, 5.0),
+            (0.0, 4.0),
+            (0.0, 3.5),
+            (1.0, 2.5),
+            (4.0, 2.5),
+            (5.0, 1.5),
+            (5.0, 1.0),
+            (4.0, 0.0),
+            (1.0, 0.0),
+            (0.0, 1.0),
+        ]],
+        't' => vec![
+            vec![(2.0, 7.0), (2.0, 1.0), (3.0, 0.0), (4.5, 0.0)],
+            vec![(0.5, 5.0), (4.0, 5.0)],
+        ],
+        'u' => vec![
+            vec![(0.0, 5.0), (0.0, 1.0), (1.0, 0.0), (4.0, 0.0), (5.0, 1.0)],
+            vec![(5.0, 5.0), (5.0, 0.0)],
+        ],
+        'v' => vec![vec![(0.0, 5.0), (2.5, 0.0), (5.0, 5.0)]],
+        'w' => vec![vec![
+            (0.0, 5.0),
+            (1.25, 0.0),
+            (2.5, 3.5),
+            (3.75, 0.0),
+            (5.0, 5.0),
+        ]],
+        'x' => vec![vec![(0.0, 5.0), (5.0, 0.0)], vec![(0.0, 0.0), (5.0, 5.0)]],
+        'y' => vec![
+            vec![(0.0, 5.0), (2.5, 0.0)],
+            vec![(5.0, 5.0), (1.5, -2.0), (0.5, -2.0)],
+        ],
+        'z' => vec![vec![(0.0, 5.0), (5.0, 5.0), (0.0, 0.0), (5.0, 0.0)]],
         '0' => vec![
             vec![
                 (1.0, 0.0),
@@ -598,15 +1059,49 @@ fn get_char_strokes(ch: char) -> Vec<Vec<(f32, f32)>> {
             (2.5, 6.0),
             (1.5, 6.0),
         ]],
-        ' ' => vec![],
-        _ => vec![vec![
-            (0.0, 0.0),
+        // OSM names often use the typographic apostrophe
+        '\'' | '’' => vec![vec![(2.5, 7.0), (2.5, 5.5)]],
+        '(' => vec![vec![
+            (3.5, 7.0),
+            (2.0, 5.5),
+            (1.5, 3.5),
+            (2.0, 1.5),
+            (3.5, 0.0),
+        ]],
+        ')' => vec![vec![
+            (1.5, 7.0),
+            (3.0, 5.5),
+            (3.5, 3.5),
+            (3.0, 1.5),
+            (1.5, 0.0),
+        ]],
+        '&' => vec![vec![
             (5.0, 0.0),
-            (5.0, 7.0),
-            (0.0, 7.0),
-            (0.0, 0.0),
+            (1.5, 4.5),
+            (1.0, 5.5),
+            (1.5, 7.0),
+            (2.5, 7.0),
+            (3.0, 6.0),
+            (2.5, 5.0),
+            (1.0, 3.5),
+            (0.0, 2.0),
+            (0.0, 1.0),
+            (1.0, 0.0),
+            (2.5, 0.0),
+            (4.0, 1.5),
+            (5.0, 3.0),
         ]],
-    }
+        '#' => vec![
+            vec![(1.5, 0.5), (1.5, 6.5)],
+            vec![(3.5, 0.5), (3.5, 6.5)],
+            vec![(0.5, 2.5), (4.5, 2.5)],
+            vec![(0.5, 4.5), (4.5, 4.5)],
+        ],
+        '+' => vec![vec![(2.5, 1.5), (2.5, 5.5)], vec![(0.5, 3.5), (4.5, 3.5)]],
+        ' ' => vec![],
+        _ => return None,
+    };
+    Some(strokes)
 }
 
 #[cfg(test)]
@@ -633,6 +1128,69 @@ mod tests {
         assert!(!renderer.is_ttf() || renderer.is_ttf());
     }
 
+    #[test]
+    fn test_stroke_lowercase_glyphs() {
+        for ch in 'a'..='z' {
+            assert_ne!(
+                get_char_strokes(ch),
+                get_char_strokes(ch.to_ascii_uppercase()),
+                "'{}' should have its own strokes",
+                ch
+            );
+        }
+
+        let lowest = |ch| {
+            get_char_strokes(ch)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|(_, y)| y)
+                .fold(f32::MAX, f32::min)
+        };
+        for ch in ['g', 'j', 'p', 'q', 'y'] {
+            assert!(
+                lowest(ch) < 0.0,
+                "'{}' should descend below the baseline",
+                ch
+            );
+        }
+        assert_eq!(lowest('a'), 0.0);
+    }
+
+    #[test]
+    fn test_stroke_label_punctuation() {
+        for ch in ['\'', '’', '(', ')', '&', '#', '+'] {
+            let strokes = get_char_strokes(ch).expect("punctuation should have strokes");
+            assert!(!strokes.is_empty());
+            for &(x, y) in strokes.iter().flatten() {
+                assert!((0.0..=5.0).contains(&x) && (0.0..=7.0).contains(&y));
+            }
+        }
+        let renderer = StrokeTextRenderer::new(2.0);
+        assert!(
+            !renderer
+                .render_text("Coeur d'Alene", 0.0, 0.0, 0.0)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_missing_glyph_modes() {
+        assert_eq!(get_char_strokes('?'), None);
+        let count = |mode| {
+            StrokeTextRenderer::new(2.0)
+                .with_missing_glyph(mode)
+                .render_text("?", 0.0, 0.0, 0.0)
+                .len()
+        };
+        let boxed = count(MissingGlyph::Box);
+        let underscore = count(MissingGlyph::Underscore);
+        assert!(underscore > 0 && underscore < boxed);
+        assert_eq!(count(MissingGlyph::Blank), 0);
+        assert_eq!("none".parse::<MissingGlyph>(), Ok(MissingGlyph::Blank));
+        assert!("dots".parse::<MissingGlyph>().is_err());
+    }
+
     #[test]
     fn test_scale_calculation() {
         let renderer = StrokeTextRenderer::new(4.4);
@@ -651,7 +1209,8 @@ mod tests {
         if ttf_renderer.is_some() {
             let triangles = ttf_renderer
                 .unwrap()
-                .render_text("TEST", 0.0, 0.0, 0.0, 10.0);
+                .render_text("TEST", 0.0, 0.0, 0.0, 10.0)
+                .unwrap();
             assert!(!triangles.is_empty());
         } else {
             let stroke = StrokeTextRenderer::new(4.4);
@@ -660,13 +1219,110 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_unparseable_font_falls_back_to_strokes() {
+        let ttf = TtfTextRenderer {
+            font_data: vec![0; 16],
+            fallback_data: Vec::new(),
+            extrude_height: 1.0,
+            curve_subdivisions: DEFAULT_CURVE_SUBDIVISIONS,
+            missing_glyph: MissingGlyph::default(),
+        };
+        assert!(ttf.text_width("TEST", 1.0).is_err());
+
+        let renderer = TextRenderer::Ttf(ttf);
+        let scale = renderer.calculate_scale_for_width("TEST", 40.0);
+        assert!(scale > 0.0);
+        assert!(renderer.line_height(scale) > 0.0);
+        let triangles = renderer.render_text_centered("TEST", 50.0, 10.0, 0.0, scale, 1.2);
+        assert!(!triangles.is_empty());
+    }
+
     #[test]
     fn test_text_renderer_produces_triangles() {
         let renderer = TextRenderer::new(None, 4.4);
-        let triangles = renderer.render_text_centered("TEST", 100.0, 50.0, 0.0, 5.0);
+        let triangles = renderer.render_text_centered("TEST", 100.0, 50.0, 0.0, 5.0, 1.2);
         assert!(
             !triangles.is_empty(),
             "TextRenderer should produce triangles"
         );
     }
+
+    #[test]
+    fn test_scale_for_cap_height() {
+        let cap_top = |renderer: &TextRenderer| {
+            let scale = renderer.scale_for_cap_height(8.0);
+            renderer
+                .render_text_centered("H", 0.0, 0.0, 0.0, scale, 1.0)
+                .iter()
+                .flat_map(|t| t.vertices)
+                .map(|v| v[1])
+                .fold(f32::MIN, f32::max)
+        };
+        let stroke = TextRenderer::Stroke(StrokeTextRenderer::new(1.0));
+        // Strokes have width, so they reach half a stroke above the cap line
+        assert!((cap_top(&stroke) - 8.0).abs() < 1.0);
+        let ttf = TextRenderer::new(None, 1.0);
+        if ttf.is_ttf() {
+            assert!((cap_top(&ttf) - 8.0).abs() < 0.05, "{}", cap_top(&ttf));
+        }
+    }
+
+    #[test]
+    fn test_missing_glyphs_fall_back() {
+        let font = PathBuf::from("fonts/RobotoSerif.ttf");
+        if !font.exists() {
+            return;
+        }
+        // A fallback font takes over from a stroke-only renderer
+        let renderer =
+            TextRenderer::Stroke(StrokeTextRenderer::new(1.0)).with_fallback_fonts(&[font]);
+        assert!(renderer.is_ttf());
+
+        // Characters no font has print as a box instead of leaving a gap
+        let latin = renderer.render_text_centered("A", 0.0, 0.0, 0.0, 5.0, 1.0);
+        let mixed = renderer.render_text_centered("A\u{6771}", 0.0, 0.0, 0.0, 5.0, 1.0);
+        assert!(mixed.len() > latin.len());
+        assert!(renderer.text_width("A\u{6771}", 5.0) > renderer.text_width("A", 5.0));
+    }
+
+    #[test]
+    fn test_curve_subdivisions_trade_triangles() {
+        let renderer = TextRenderer::new(None, 4.4);
+        if !renderer.is_ttf() {
+            return;
+        }
+        let count = |subdivisions: u8| {
+            let renderer = TextRenderer::new(None, 4.4).with_curve_subdivisions(subdivisions);
+            renderer
+                .render_text_centered("O", 0.0, 0.0, 0.0, 5.0, 1.0)
+                .len()
+        };
+        assert!(count(4) < count(DEFAULT_CURVE_SUBDIVISIONS));
+        assert!(count(DEFAULT_CURVE_SUBDIVISIONS) < count(40));
+    }
+
+    #[test]
+    fn test_multi_line_text_stacks_upward() {
+        let renderer = TextRenderer::Stroke(StrokeTextRenderer::new(1.0));
+        let y_range = |triangles: &[Triangle]| {
+            let ys = triangles.iter().flat_map(|t| t.vertices).map(|v| v[1]);
+            ys.fold((f32::MAX, f32::MIN), |(lo, hi), y| (lo.min(y), hi.max(y)))
+        };
+
+        let single = renderer.render_text_centered("AB", 50.0, 10.0, 0.0, 1.0, 1.0);
+        let double = renderer.render_text_centered("AB\nAB", 50.0, 10.0, 0.0, 1.0, 1.0);
+        let spaced = renderer.render_text_centered("AB\n\nAB", 50.0, 10.0, 0.0, 1.0, 1.0);
+
+        // The last line keeps the baseline; earlier lines move up a line each
+        let line_height = renderer.line_height(1.0);
+        assert!((y_range(&double).0 - y_range(&single).0).abs() < 1e-3);
+        assert!((y_range(&double).1 - y_range(&single).1 - line_height).abs() < 1e-3);
+        assert!((y_range(&spaced).1 - y_range(&double).1 - line_height).abs() < 1e-3);
+        assert!((renderer.block_height("AB\nC", 1.0, 1.5) - 2.5 * line_height).abs() < 1e-4);
+
+        // The widest line sets the scale
+        let scale = renderer.calculate_scale_for_width("A\nABC", 10.0);
+        assert!((scale - renderer.calculate_scale_for_width("ABC", 10.0)).abs() < 1e-6);
+    }
 }
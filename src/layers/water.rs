@@ -1,46 +1,137 @@
+use geo::{BooleanOps, LineString, MultiPolygon, Polygon};
+use rayon::prelude::*;
+use serde::Deserialize;
+
+use super::base::PlateFootprint;
+use crate::config::heights;
 use crate::domain::WaterPolygon;
 use crate::geometry::{Projector, Scaler};
-use crate::mesh::{extrude_polygon, Triangle};
+use crate::mesh::{extrude_multipolygon, extrude_polygon, Triangle};
+
+/// How water sits relative to the base plate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WaterStyle {
+    /// A band above the base, like parks and roads
+    #[default]
+    Raised,
+    /// Pockets sunk into the top of the base, printed before the base color
+    Recessed,
+}
+
+impl std::str::FromStr for WaterStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "raised" => Ok(WaterStyle::Raised),
+            "recessed" => Ok(WaterStyle::Recessed),
+            _ => Err(format!(
+                "Invalid water style '{}'. Valid options: raised, recessed",
+                s
+            )),
+        }
+    }
+}
 
+/// Water bodies as solid columns from `z_bottom` up to `z_top`
+///
+/// Both come from [`FeatureHeights`](crate::config::FeatureHeights):
+/// `z_top` is set from the `water` key of the `[heights]` config section
+/// and `z_bottom` is the bed.
 pub fn generate_water_meshes(
     water_polygons: &[WaterPolygon],
     projector: &Projector,
     scaler: &Scaler,
+    z_bottom: f32,
     z_top: f32,
 ) -> Vec<Triangle> {
-    let mut all_triangles = Vec::new();
+    // Polygons extrude independently; collect keeps the input order
+    water_polygons
+        .par_iter()
+        .filter(|polygon| polygon.is_valid())
+        .flat_map_iter(|polygon| {
+            let projected: Vec<(f64, f64)> = polygon
+                .outer
+                .iter()
+                .map(|&(lat, lon)| projector.project(lat, lon))
+                .collect();
 
-    for polygon in water_polygons {
-        if !polygon.is_valid() {
-            continue;
-        }
+            let scaled: Vec<(f32, f32)> =
+                projected.iter().map(|&(x, y)| scaler.scale(x, y)).collect();
 
-        let projected: Vec<(f64, f64)> = polygon
-            .outer
-            .iter()
-            .map(|&(lat, lon)| projector.project(lat, lon))
-            .collect();
+            let holes_scaled: Vec<Vec<(f32, f32)>> = polygon
+                .holes
+                .iter()
+                .map(|hole| {
+                    hole.iter()
+                        .map(|&(lat, lon)| {
+                            let (x, y) = projector.project(lat, lon);
+                            scaler.scale(x, y)
+                        })
+                        .collect()
+                })
+                .collect();
 
-        let scaled: Vec<(f32, f32)> = projected.iter().map(|&(x, y)| scaler.scale(x, y)).collect();
+            extrude_polygon(&scaled, &holes_scaled, z_bottom, z_top)
+        })
+        .collect()
+}
 
-        let holes_scaled: Vec<Vec<(f32, f32)>> = polygon
-            .holes
-            .iter()
-            .map(|hole| {
-                hole.iter()
-                    .map(|&(lat, lon)| {
-                        let (x, y) = projector.project(lat, lon);
-                        scaler.scale(x, y)
-                    })
-                    .collect()
+/// Water sunk into the base plate: the water columns and the base around
+/// them
+///
+/// The water footprint is merged and trimmed to the plate, then printed
+/// from `z_bottom` up to `z_top`, below the base top. The rest of the plate
+/// is the base, standing the full `base_height`, so the two fit together
+/// into the plate with the water in pockets.
+pub fn generate_recessed_water(
+    water_polygons: &[WaterPolygon],
+    projector: &Projector,
+    scaler: &Scaler,
+    plate: &PlateFootprint,
+    z_bottom: f32,
+    z_top: f32,
+    base_height: f32,
+) -> (Vec<Triangle>, Vec<Triangle>) {
+    let to_mm = |ring: &[(f64, f64)]| -> LineString<f64> {
+        ring.iter()
+            .map(|&(lat, lon)| {
+                let (x, y) = projector.project(lat, lon);
+                let (x, y) = scaler.scale(x, y);
+                (x as f64, y as f64)
             })
-            .collect();
+            .collect::<Vec<_>>()
+            .into()
+    };
+    let ring = |points: &[(f32, f32)]| -> LineString<f64> {
+        points
+            .iter()
+            .map(|&(x, y)| (x as f64, y as f64))
+            .collect::<Vec<_>>()
+            .into()
+    };
 
-        let triangles = extrude_polygon(&scaled, &holes_scaled, 0.0, z_top);
-        all_triangles.extend(triangles);
-    }
+    // Lakes, riverbanks and coastline overlap freely, so merge them first
+    let water = water_polygons
+        .par_iter()
+        .filter(|polygon| polygon.is_valid())
+        .map(|polygon| {
+            let holes = polygon.holes.iter().map(|hole| to_mm(hole)).collect();
+            MultiPolygon::new(vec![Polygon::new(to_mm(&polygon.outer), holes)])
+        })
+        .reduce(|| MultiPolygon::new(Vec::new()), |a, b| a.union(&b));
+    let plate = Polygon::new(
+        ring(&plate.outline),
+        plate.holes.iter().map(|h| ring(h)).collect(),
+    );
+    let water = plate.intersection(&water);
+    let land = plate.difference(&water);
 
-    all_triangles
+    (
+        extrude_multipolygon(&water, z_bottom, z_top),
+        extrude_multipolygon(&land, heights::BASE_Z_BOTTOM, base_height),
+    )
 }
 
 #[cfg(test)]
@@ -54,7 +145,50 @@ mod tests {
         let bounds = Bounds::from_points(&[(0.0, 0.0), (1000.0, 1000.0)]).unwrap();
         let scaler = Scaler::from_bounds(&bounds, 220.0);
 
-        let triangles = generate_water_meshes(&[], &projector, &scaler, 2.6);
+        let triangles = generate_water_meshes(&[], &projector, &scaler, 0.0, 2.6);
         assert!(triangles.is_empty());
     }
+
+    #[test]
+    fn test_recessed_water_splits_the_plate() {
+        let projector = Projector::new((0.0, 0.0));
+        let (x0, y0) = projector.project(-0.01, -0.01);
+        let (x1, y1) = projector.project(0.01, 0.01);
+        let bounds = Bounds::from_points(&[(x0, y0), (x1, y1)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 100.0);
+        let lake = |south: f64, north: f64| {
+            WaterPolygon::new(vec![
+                (south, -0.005),
+                (south, 0.005),
+                (north, 0.005),
+                (north, -0.005),
+                (south, -0.005),
+            ])
+        };
+        // Two overlapping lakes, one running off the plate
+        let lakes = [lake(-0.005, 0.002), lake(0.0, 0.02)];
+        let plate = PlateFootprint::square(100.0, 100.0, 0.0, None);
+        let (water, land) =
+            generate_recessed_water(&lakes, &projector, &scaler, &plate, 0.0, 1.6, 2.0);
+
+        let report = crate::mesh::validation::validate_mesh(&water);
+        assert!(report.manifold.is_watertight());
+        let report = crate::mesh::validation::validate_mesh(&land);
+        assert!(report.manifold.is_watertight());
+        let top = |triangles: &[Triangle]| {
+            triangles
+                .iter()
+                .flat_map(|t| t.vertices)
+                .fold(f32::MIN, |z, v| z.max(v[2]))
+        };
+        assert!((top(&water) - 1.6).abs() < 1e-5);
+        assert!((top(&land) - 2.0).abs() < 1e-5);
+        // Merged water stays on the plate
+        assert!(
+            water
+                .iter()
+                .flat_map(|t| t.vertices)
+                .all(|v| v[1] <= 100.0 + 1e-3)
+        );
+    }
 }
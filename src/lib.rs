@@ -7,3 +7,4 @@ pub mod geometry;
 pub mod layers;
 pub mod mesh;
 pub mod osm;
+pub mod pipeline;
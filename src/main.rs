@@ -1,26 +1,30 @@
 use anyhow::{Context, Result, bail};
 use clap::Parser;
 use indicatif::{ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::cell::RefCell;
 use std::path::PathBuf;
 use std::time::Instant;
 
-mod api;
-mod config;
-mod domain;
-mod geometry;
-mod layers;
-mod mesh;
-mod osm;
-
-use api::{RoadDepth, fetch_parks, fetch_roads_with_depth, fetch_water, geocode_city};
-use config::{FeatureHeights, FileConfig};
-use geometry::{Bounds, Projector, Scaler};
-use layers::{
-    RoadConfig, TextRenderer, generate_base_plate, generate_park_meshes, generate_road_meshes,
-    generate_water_meshes,
+use mapto3d::api::RoadDepth;
+use mapto3d::config::{FeatureHeights, FileConfig, Theme};
+use mapto3d::geometry::bbox::{BBox, parse_bbox};
+use mapto3d::geometry::{MarginSide, parse_latitude, parse_longitude};
+use mapto3d::geometry::{ProjectionKind, SimplifyAlgorithm};
+use mapto3d::layers::{
+    BedSize, CompassCorner, HangHole, MapShape, MissingGlyph, OnewayArrows, PlateCorner,
+    TunnelMode, WaterStyle,
+};
+use mapto3d::mesh::validation::ManifoldReport;
+use mapto3d::mesh::{
+    RibbonProfile, StlFormat, Triangle,
+    stl::{estimate_stl_size, gz_path, gz_uncompressed_size},
+    tiles::{TILE_LAP_MM, Tile, TileGrid},
+    write_3mf, write_glb, write_obj, write_stl, write_stl_ascii, write_stl_gz,
+};
+use mapto3d::pipeline::{
+    MapEstimate, MapModel, MapParams, Progress, estimate_map, generate_map_with_progress,
 };
-use mesh::{stl::estimate_stl_size, validate_and_fix, write_stl};
-use osm::{parse_parks, parse_roads, parse_water};
 
 /// Generate 3D-printable STL city maps from OpenStreetMap data
 ///
@@ -47,6 +51,21 @@ struct Args {
     #[arg(long)]
     config: Option<PathBuf>,
 
+    /// Locate the map and print its estimated size and triangle count
+    /// without fetching OSM data or writing a file
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Generate every [[map]] entry in the config file, one after another
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "city", "country", "state", "lat", "lon", "bbox", "area", "output",
+            "primary_text", "secondary_text", "osm_file", "dump_osm", "dump_geojson", "geojson_input",
+        ]
+    )]
+    all: bool,
+
     /// City name (optional if --lat and --lon are provided)
     #[arg(short = 'c', long)]
     city: Option<String>,
@@ -55,19 +74,34 @@ struct Args {
     #[arg(short = 'C', long)]
     country: Option<String>,
 
-    /// Latitude for direct coordinate input (use with --lon)
-    #[arg(long, requires = "lon", allow_hyphen_values = true)]
+    /// State or region, to tell apart cities sharing a name (e.g. Springfield)
+    #[arg(long, visible_alias = "region")]
+    state: Option<String>,
+
+    /// Latitude for direct coordinate input (use with --lon), as decimal
+    /// degrees, 37.7749N or 37°46'30"N
+    #[arg(long, requires = "lon", allow_hyphen_values = true, value_parser = parse_latitude)]
     lat: Option<f64>,
 
     /// Longitude for direct coordinate input (use with --lat)
-    #[arg(long, requires = "lat", allow_hyphen_values = true)]
+    #[arg(long, requires = "lat", allow_hyphen_values = true, value_parser = parse_longitude)]
     lon: Option<f64>,
 
-    /// Map radius in meters
-    #[arg(short = 'r', long, default_value = "10000")]
-    radius: u32,
+    /// Map radius in meters (defaults to fitting the geocoded city, or 10000)
+    #[arg(short = 'r', long)]
+    radius: Option<u32>,
+
+    /// Exact bounding box as south,west,north,east in degrees (replaces city,
+    /// coordinates and radius)
+    #[arg(long, value_parser = parse_bbox, allow_hyphen_values = true)]
+    bbox: Option<BBox>,
 
-    /// Output STL file path (defaults to {city}.stl or map.stl)
+    /// Named area such as a city or district, printed to its OpenStreetMap
+    /// boundary (replaces city, coordinates, radius and bounding box)
+    #[arg(long, conflicts_with_all = ["city", "lat", "lon", "bbox", "osm_file", "geojson_input"])]
+    area: Option<String>,
+
+    /// Output file path (defaults to {city}.stl, {area}.stl or map.stl); a .obj, .3mf or .glb extension selects that format
     #[arg(short = 'o', long)]
     output: Option<PathBuf>,
 
@@ -75,10 +109,22 @@ struct Args {
     #[arg(short = 's', long, default_value = "220.0")]
     size: f32,
 
+    /// Output width in mm (defaults to --size)
+    #[arg(long)]
+    width: Option<f32>,
+
+    /// Output height in mm including the text margin (defaults to --size)
+    #[arg(long)]
+    height: Option<f32>,
+
     /// Base plate thickness in mm
     #[arg(long, default_value = "2.0")]
     base_height: f32,
 
+    /// Round the base plate corners with this radius in mm (square shape only)
+    #[arg(long, default_value = "0.0")]
+    corner_radius: f32,
+
     /// Road width multiplier
     #[arg(long, default_value = "1.0")]
     road_scale: f32,
@@ -87,6 +133,11 @@ struct Args {
     #[arg(long, default_value = "primary")]
     road_depth: RoadDepth,
 
+    /// Add footways, paths, pedestrian streets and steps as thin lines, for
+    /// walkable city centers; adds many triangles
+    #[arg(long)]
+    paths: bool,
+
     /// Primary text label (large, defaults to city name in uppercase)
     #[arg(long)]
     primary_text: Option<String>,
@@ -95,6 +146,26 @@ struct Args {
     #[arg(long)]
     secondary_text: Option<String>,
 
+    /// Cap height of the primary label in mm, instead of fitting it to 75%
+    /// of the plate width
+    #[arg(long, value_name = "MM")]
+    primary_text_height_mm: Option<f32>,
+
+    /// Cap height of the secondary label in mm, instead of fitting it to 40%
+    /// of the plate width
+    #[arg(long, value_name = "MM")]
+    secondary_text_height_mm: Option<f32>,
+
+    /// Message inlaid into the underside of the base, mirrored to read from
+    /// below, e.g. a date and dedication (split lines with \n)
+    #[arg(long, value_name = "TEXT")]
+    back_text: Option<String>,
+
+    /// Line spacing for multi-line labels (split with \n), as a multiple of
+    /// the font's line height
+    #[arg(long, default_value = "1.2")]
+    line_spacing: f32,
+
     /// Enable verbose logging
     #[arg(short = 'v', long)]
     verbose: bool,
@@ -104,17 +175,306 @@ struct Args {
     #[arg(long, default_value = "0", value_parser = clap::value_parser!(u8).range(0..=3))]
     simplify: u8,
 
+    /// Decimate the finished model to at most this many triangles, split
+    /// across layers by their size; flat areas lose detail first
+    #[arg(long)]
+    max_triangles: Option<usize>,
+
+    /// Drop triangles smaller than this many square mm as degenerate
+    /// [default: 1e-10, scaled with the square of the plate size over 220mm]
+    #[arg(long, value_name = "MM2")]
+    min_triangle_area: Option<f32>,
+
+    /// Road simplification algorithm: douglas-peucker (dp, default) or
+    /// visvalingam (vw, keeps curve shapes better at the same level)
+    #[arg(long, default_value = "douglas-peucker")]
+    simplify_algorithm: SimplifyAlgorithm,
+
+    /// Finish road ends with rounded caps instead of flat cuts
+    #[arg(long)]
+    round_caps: bool,
+
+    /// Merge crossing roads into one solid instead of overlapping ribbons,
+    /// so slicers show no z-fighting at junctions (flat profile only)
+    #[arg(long)]
+    union_roads: bool,
+
+    /// Road cross-section: flat (default), trapezoid (top half as wide as
+    /// the base) or rounded (domed top); sloped walls print more cleanly
+    /// on thin roads
+    #[arg(long, default_value = "flat")]
+    road_profile: RibbonProfile,
+
+    /// Roads and rails tagged as tunnels: show (like surface roads, default),
+    /// hide, or engrave (a one-layer trace just above the base). Bridges are
+    /// always printed a little higher than the roads they cross
+    #[arg(long, default_value = "show")]
+    tunnels: TunnelMode,
+
     /// Path to TTF font file for text rendering (defaults to fonts/RobotoSerif.ttf)
     #[arg(long)]
     font: Option<PathBuf>,
 
+    /// TTF font for the secondary (coordinate) line; defaults to --font
+    #[arg(long, value_name = "PATH")]
+    secondary_font: Option<PathBuf>,
+
+    /// Font to try for characters the main font lacks, such as a CJK font
+    /// for non-Latin names; repeat to try several in order
+    #[arg(long = "fallback-font", value_name = "PATH")]
+    fallback_fonts: Vec<PathBuf>,
+
+    /// Line segments per curve in TTF glyph outlines: raise for smoother
+    /// large titles, lower for fewer triangles
+    #[arg(long, value_name = "SUBDIVISIONS", default_value = "20")]
+    text_quality: u8,
+
+    /// Drawn for characters no font has: box, underscore or blank
+    #[arg(long, value_name = "STYLE", default_value = "box")]
+    missing_glyph: MissingGlyph,
+
+    /// Projection: local (fast approximation, default) or utm (true transverse
+    /// Mercator, better for maps 50km and larger)
+    #[arg(long, default_value = "local")]
+    projection: ProjectionKind,
+
+    /// Rotate the map so this bearing (degrees clockwise from north) points up;
+    /// labels stay level
+    #[arg(long, default_value = "0.0", allow_hyphen_values = true)]
+    rotate: f64,
+
+    /// Map outline: square (default), circle (clips features to the radius
+    /// and prints on a round base) or hexagon (a flat-topped hex tile with
+    /// corners at the radius, for honeycombs of neighboring maps)
+    #[arg(long, default_value = "square")]
+    shape: MapShape,
+
+    /// Punch a hanging hole through the base plate at x,y (mm from the
+    /// bottom-left corner) with the given diameter, e.g. 10,10,4
+    #[arg(long, value_name = "X,Y,DIAMETER", allow_hyphen_values = true)]
+    hang_hole: Option<HangHole>,
+
+    /// Place a 4mm hanging hole near a corner: top-left, top-right,
+    /// bottom-left or bottom-right
+    #[arg(long, conflicts_with = "hang_hole")]
+    hang_hole_corner: Option<PlateCorner>,
+
+    /// Pocket the underside of the base plate to save filament, keeping
+    /// ribs, a rim and a top skin of this thickness in mm
+    #[arg(long, value_name = "WALL_MM")]
+    hollow_base: Option<f32>,
+
+    /// Printer bed size as WIDTHxHEIGHT in mm; warns when the plate, less a
+    /// 5mm clearance along each edge, does not fit
+    #[arg(long, value_name = "WxH")]
+    bed: Option<BedSize>,
+
+    /// Shrink a plate too large for --bed until it fits
+    #[arg(long)]
+    auto_fit_bed: bool,
+
+    /// Print a north arrow in the text margin: bottom-left or bottom-right
+    #[arg(long, value_name = "CORNER")]
+    compass: Option<CompassCorner>,
+
+    /// Print a labeled scale bar in the text margin, on the side away from
+    /// the compass
+    #[arg(long)]
+    scale_bar: bool,
+
+    /// Leave out the labels and their 20mm margin so the map fills the plate
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "primary_text",
+            "secondary_text",
+            "primary_text_height_mm",
+            "secondary_text_height_mm",
+        ]
+    )]
+    no_text: bool,
+
+    /// Edge the labels sit along: bottom (default) or top, as a title bar
+    #[arg(long, value_name = "SIDE")]
+    text_position: Option<MarginSide>,
+
+    /// Print the names of motorways and primary roads along them, for roads
+    /// at least MIN_MM long on the plate
+    #[arg(long, value_name = "MIN_MM", num_args = 0..=1, default_missing_value = "40")]
+    street_names: Option<f32>,
+
+    /// Raise arrowheads along one-way roads: with the roads (default) or in
+    /// the text color
+    #[arg(long, value_name = "COLOR", num_args = 0..=1, default_missing_value = "roads")]
+    oneway_arrows: Option<OnewayArrows>,
+
+    /// Raise thin graticule lines along parallels and meridians, about
+    /// SPACING_M meters apart and through the map center
+    #[arg(long, value_name = "SPACING_M")]
+    grid: Option<f64>,
+
+    /// Raise the administrative boundary of the --city or --area as an
+    /// outline across the map
+    #[arg(long)]
+    boundary: bool,
+
+    /// Write triangles in a canonical order, so the same input always gives
+    /// byte-identical files
+    #[arg(long)]
+    deterministic: bool,
+
+    /// Draw a raised frame around the plate edge, covering ragged feature
+    /// ends at the map boundary
+    #[arg(long)]
+    frame: bool,
+
+    /// Frame bar width in mm
+    #[arg(long, default_value = "2.0")]
+    frame_width: f32,
+
+    /// Frame height above the base plate in mm (defaults to the text height)
+    #[arg(long)]
+    frame_height: Option<f32>,
+
+    /// Fit the map inside the frame instead of letting the frame cover its edges
+    #[arg(long, requires = "frame")]
+    frame_inset: bool,
+
+    /// STL output encoding: binary (default) or ascii
+    #[arg(long, default_value = "binary")]
+    stl_format: StlFormat,
+
+    /// Gzip-compress STL output (appends .gz to the output path)
+    #[arg(long)]
+    gzip: bool,
+
+    /// Split the map into COLUMNSxROWS lap-jointed tiles, written as
+    /// separate files named like map_r0c0.stl
+    #[arg(long)]
+    tiles: Option<TileGrid>,
+
+    /// Also write each feature layer to its own STL, named like
+    /// map_roads.stl, to load as parts of one multi-material object
+    #[arg(long, conflicts_with = "tiles")]
+    split_layers: bool,
+
+    /// Write triangle counts, bounds, validation results, file sizes and
+    /// timing as JSON to this path
+    #[arg(long, value_name = "PATH")]
+    stats_json: Option<PathBuf>,
+
+    /// Add registration pins on each tile's laps, with matching sockets in
+    /// the neighboring tiles
+    #[arg(long, requires = "tiles")]
+    tile_pins: bool,
+
+    /// Always download fresh data instead of using cached Overpass responses
+    #[arg(long)]
+    no_cache: bool,
+
+    /// Send each Overpass query to the first two mirrors at once and use
+    /// whichever answers first, instead of trying them one after another
+    #[arg(long)]
+    race_mirrors: bool,
+
+    /// Send Overpass and Nominatim requests through this http://, https://
+    /// or socks5:// proxy instead of the one in HTTPS_PROXY/ALL_PROXY
+    #[arg(long, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// Geocode the city again instead of reusing the coordinates cached by
+    /// an earlier run
+    #[arg(long)]
+    refresh_geocode: bool,
+
+    /// Without --city or --primary-text, label the map with the place name
+    /// Nominatim finds at the center
+    #[arg(long)]
+    reverse_geocode: bool,
+
+    /// Load OSM data from a saved Overpass JSON file or a --dump-osm directory
+    /// instead of fetching it
+    #[arg(long)]
+    osm_file: Option<PathBuf>,
+
+    /// Save the fetched Overpass responses into this directory for later replay
+    #[arg(long)]
+    dump_osm: Option<PathBuf>,
+
+    /// Write the parsed features, trimmed to the map, as a GeoJSON file
+    #[arg(long, value_name = "PATH")]
+    dump_geojson: Option<PathBuf>,
+
+    /// Read roads, railways, water, parks and buildings from a GeoJSON
+    /// FeatureCollection instead of OSM data
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["osm_file", "dump_osm"])]
+    geojson_input: Option<PathBuf>,
+
+    /// Raise the map into terrain from an elevation model: an SRTM .hgt tile
+    /// or a GeoTIFF in latitude and longitude
+    #[arg(long, value_name = "PATH")]
+    dem: Option<PathBuf>,
+
+    /// Vertical exaggeration of the terrain over the map scale [default: 1]
+    #[arg(long, value_name = "FACTOR")]
+    terrain_scale: Option<f32>,
+
+    /// Trace the --dem elevations into raised contour lines every INTERVAL_M
+    /// meters on a flat map, instead of raising the relief
+    #[arg(
+        long,
+        value_name = "INTERVAL_M",
+        requires = "dem",
+        conflicts_with = "terrain_scale"
+    )]
+    contours: Option<f32>,
+
+    /// Preset of layers, band heights and colors: classic, night, earth or
+    /// ocean; other flags still take precedence
+    #[arg(long)]
+    theme: Option<Theme>,
+
     /// Enable water features (rivers, lakes, sea)
     #[arg(long)]
     water: bool,
 
+    /// Water as a band above the base (raised, default) or as pockets sunk
+    /// into the base top (recessed), printed in the first color
+    #[arg(long, default_value = "raised")]
+    water_style: WaterStyle,
+
     /// Enable park features (parks, forests, green areas)
     #[arg(long)]
     parks: bool,
+
+    /// Enable building footprints
+    #[arg(long)]
+    buildings: bool,
+
+    /// Height in meters for buildings without height/levels tags
+    #[arg(long, default_value = "10.0")]
+    building_height: f32,
+
+    /// Enable railway lines (rail, light rail, subway, tram)
+    #[arg(long)]
+    railways: bool,
+
+    /// Railway line width in mm (before road scale is applied)
+    #[arg(long, default_value = "0.6")]
+    rail_width: f32,
+
+    /// Include underground railway segments (subways, tunnels)
+    #[arg(long)]
+    subways: bool,
+
+    /// Enable runways, taxiways and airport aprons
+    #[arg(long)]
+    aeroways: bool,
+
+    /// Also draw the results of a raw Overpass QL query from this file, with
+    /// {{bbox}} replaced by the map's south,west,north,east
+    #[arg(long, value_name = "PATH")]
+    overpass_query_file: Option<PathBuf>,
 }
 
 fn main() -> Result<()> {
@@ -133,342 +493,816 @@ fn main() -> Result<()> {
         FileConfig::load()
     };
 
-    let city = args
-        .city
-        .clone()
-        .or_else(|| file_config.as_ref().and_then(|c| c.city.clone()));
-    let country = args
-        .country
-        .clone()
-        .or_else(|| file_config.as_ref().and_then(|c| c.country.clone()));
-    let lat = args
-        .lat
-        .or_else(|| file_config.as_ref().and_then(|c| c.lat));
-    let lon = args
-        .lon
-        .or_else(|| file_config.as_ref().and_then(|c| c.lon));
-    let radius = if args.radius != 10000 {
-        args.radius
-    } else {
-        file_config.as_ref().map(|c| c.radius).unwrap_or(10000)
-    };
-    let size = if (args.size - 220.0).abs() > 0.01 {
-        args.size
-    } else {
-        file_config.as_ref().map(|c| c.size).unwrap_or(220.0)
-    };
-    let base_height = if (args.base_height - 2.0).abs() > 0.01 {
-        args.base_height
-    } else {
-        file_config.as_ref().map(|c| c.base_height).unwrap_or(2.0)
-    };
-    let road_scale = if (args.road_scale - 1.0).abs() > 0.01 {
-        args.road_scale
-    } else {
-        file_config.as_ref().map(|c| c.road_scale).unwrap_or(1.0)
-    };
-    let road_depth = if args.road_depth != RoadDepth::Primary {
-        args.road_depth
-    } else {
-        file_config
-            .as_ref()
-            .map(|c| c.road_depth)
-            .unwrap_or(RoadDepth::Primary)
-    };
-    let simplify = if args.simplify != 0 {
-        args.simplify
-    } else {
-        file_config.as_ref().map(|c| c.simplify).unwrap_or(0)
-    };
-    let verbose = args.verbose || file_config.as_ref().map(|c| c.verbose).unwrap_or(false);
-    let primary_text = args
-        .primary_text
-        .clone()
-        .or_else(|| file_config.as_ref().and_then(|c| c.primary_text.clone()));
-    let secondary_text = args
-        .secondary_text
-        .clone()
-        .or_else(|| file_config.as_ref().and_then(|c| c.secondary_text.clone()));
-    let output = args
-        .output
-        .clone()
-        .or_else(|| file_config.as_ref().and_then(|c| c.output.clone()));
-    let font_path = args.font.clone();
+    if args.all {
+        return run_all(&args, file_config.as_ref());
+    }
+    let mut params = resolve_params(&args, file_config.as_ref());
+    params.overpass_query = read_overpass_query(&args)?;
+    params.validate()?;
 
-    let overpass_config = file_config
-        .as_ref()
-        .and_then(|c| c.overpass.clone())
-        .unwrap_or_default();
+    println!("mapto3d - City Map STL Generator");
+    println!("================================");
+    println!();
 
-    if city.is_none() && lat.is_none() {
-        bail!("Must provide either --city/-c and --country/-C, or --lat and --lon");
-    }
-    if city.is_some() && country.is_none() {
-        bail!("--city requires --country");
-    }
+    run(&args, file_config.as_ref(), params, total_start)
+}
+
+/// Generate each `[[map]]` entry in turn, reporting failures at the end
+///
+/// The geocode cache, HTTP clients and Nominatim spacing are shared across
+/// entries, so a batch is cheaper than the same maps run one by one.
+fn run_all(args: &Args, file_config: Option<&FileConfig>) -> Result<()> {
+    let Some(config) = file_config.filter(|c| !c.maps.is_empty()) else {
+        bail!("--all needs [[map]] entries in the config file");
+    };
+
+    let overpass_query = read_overpass_query(args)?;
+    let batch = config
+        .maps
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let map_config = config.for_map(entry, i);
+            let mut params = resolve_params(args, Some(&map_config));
+            params.overpass_query = overpass_query.clone();
+            params
+                .validate()
+                .with_context(|| format!("[[map]] {}", entry.label(i)))?;
+            Ok((entry.label(i), map_config, params))
+        })
+        .collect::<Result<Vec<_>>>()?;
 
     println!("mapto3d - City Map STL Generator");
     println!("================================");
     println!();
 
-    let output_path = output.clone().unwrap_or_else(|| {
-        if let Some(ref c) = city {
+    let total_start = Instant::now();
+    let mut failed = Vec::new();
+    for (i, (label, map_config, params)) in batch.into_iter().enumerate() {
+        println!("[{}/{}] {}", i + 1, config.maps.len(), label);
+        println!();
+        if let Err(e) = run(args, Some(&map_config), params, Instant::now()) {
+            eprintln!("Error: {} failed: {:#}", label, e);
+            failed.push(label);
+        }
+        println!();
+    }
+
+    println!(
+        "Generated {} of {} maps in {:.1}s",
+        config.maps.len() - failed.len(),
+        config.maps.len(),
+        total_start.elapsed().as_secs_f32()
+    );
+    if !failed.is_empty() {
+        bail!("Failed maps: {}", failed.join(", "));
+    }
+    Ok(())
+}
+
+/// Generate and write one map
+fn run(
+    args: &Args,
+    file_config: Option<&FileConfig>,
+    params: MapParams,
+    total_start: Instant,
+) -> Result<()> {
+    let theme = resolve_theme(args, file_config);
+    let verbose = args.verbose || file_config.is_some_and(|c| c.verbose);
+    let progress = CliProgress {
+        verbose,
+        spinner: RefCell::new(None),
+    };
+    if args.dry_run {
+        let estimate = estimate_map(&params, &progress)?;
+        print_estimate(&estimate, &params);
+        return Ok(());
+    }
+    let output = args
+        .output
+        .clone()
+        .or_else(|| file_config.and_then(|c| c.output.clone()));
+
+    let mut output_path = output.unwrap_or_else(|| {
+        if let Some(c) = params.city.as_ref().or(params.area.as_ref()) {
             PathBuf::from(format!("{}.stl", c.to_lowercase().replace(' ', "_")))
         } else {
             PathBuf::from("map.stl")
         }
     });
-
-    let display_name = city
-        .clone()
-        .unwrap_or_else(|| "Custom Location".to_string());
+    let output_kind = OutputKind::from_path(&output_path);
+    let gzip = args.gzip && output_kind == OutputKind::Stl;
+    if args.gzip && !gzip {
+        eprintln!("Warning: --gzip only applies to STL output, ignoring");
+    }
+    let split_layers = args.split_layers && output_kind == OutputKind::Stl;
+    if args.split_layers && !split_layers {
+        eprintln!("Warning: --split-layers only applies to STL output, ignoring");
+    }
+    let plain_path = output_path.clone();
+    // Output path with `_suffix` added to the file name, like map_roads.stl
+    let suffixed_path = |suffix: &str| {
+        let stem = plain_path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut path = plain_path.with_file_name(format!("{}_{}", stem, suffix));
+        if let Some(ext) = plain_path.extension() {
+            path.set_extension(ext);
+        }
+        if gzip { gz_path(&path) } else { path }
+    };
+    let tile_path = |row: usize, column: usize| suffixed_path(&format!("r{}c{}", row, column));
+    if gzip {
+        output_path = gz_path(&output_path);
+    }
 
     if verbose {
-        println!("Configuration:");
-        if let Some(ref c) = city {
-            println!("  City: {}", c);
-            println!("  Country: {}", country.as_ref().unwrap());
-        }
-        if let Some(lt) = lat {
-            println!("  Coordinates: ({:.4}, {:.4})", lt, lon.unwrap());
+        print_configuration(&params)?;
+        match args.tiles {
+            Some(grid) => println!(
+                "  Output: {} .. {} ({} tiles)",
+                tile_path(0, 0).display(),
+                tile_path(grid.rows - 1, grid.columns - 1).display(),
+                grid.rows * grid.columns
+            ),
+            None => println!("  Output: {}", output_path.display()),
         }
-        println!("  Radius: {}m", radius);
-        println!("  Size: {}mm", size);
-        println!("  Base height: {}mm", base_height);
-        println!("  Road scale: {}", road_scale);
-        println!("  Road depth: {:?}", road_depth);
-        println!("  Simplify level: {}", simplify);
-        println!(
-            "  Water features: {}",
-            if args.water { "enabled" } else { "disabled" }
-        );
-        println!(
-            "  Park features: {}",
-            if args.parks { "enabled" } else { "disabled" }
-        );
-        println!("  Output: {}", output_path.display());
-        println!("  Overpass mirrors: {}", overpass_config.urls.len());
+        println!("  STL format: {:?}", args.stl_format);
+        println!("  Overpass mirrors: {}", params.overpass.urls.len());
         println!();
     }
 
-    let center = if let (Some(lt), Some(ln)) = (lat, lon) {
-        println!("Using provided coordinates: ({:.4}, {:.4})", lt, ln);
-        (lt, ln)
-    } else {
-        let c = city.as_ref().unwrap();
-        let co = country.as_ref().unwrap();
-        let spinner = create_spinner("Geocoding city...");
+    let terrain = params.dem.is_some() && params.contours.is_none();
+    let model = generate_map_with_progress(params, &progress)?;
+
+    if let Some(grid) = args.tiles {
+        let spinner = create_spinner("Cutting tiles...");
         let start = Instant::now();
-        let coords = geocode_city(c, co).context("Failed to geocode city")?;
+        let tiles = model.tiles(grid, args.tile_pins)?;
+        let mut written_triangles = 0;
+        let mut outputs = Vec::with_capacity(tiles.len());
+        for tile in &tiles {
+            let layers: Vec<(&str, &[Triangle])> = tile
+                .layers
+                .iter()
+                .map(|(name, triangles)| (*name, triangles.as_slice()))
+                .collect();
+            let path = tile_path(tile.row, tile.column);
+            write_layers(&path, output_kind, &layers, gzip, args.stl_format)?;
+            outputs.push(path);
+            written_triangles += layers.iter().map(|(_, t)| t.len()).sum::<usize>();
+        }
         spinner.finish_with_message(format!(
-            "Geocoded: {}, {} -> ({:.4}, {:.4}) [{:.1}s]",
-            c,
-            co,
-            coords.0,
-            coords.1,
+            "Wrote {} tiles, {} triangles [{:.1}s]",
+            tiles.len(),
+            written_triangles,
             start.elapsed().as_secs_f32()
         ));
-        coords
-    };
-
-    let spinner = create_spinner("Fetching roads from OpenStreetMap...");
-    let start = Instant::now();
-    let roads_response = fetch_roads_with_depth(center, radius, road_depth, &overpass_config)
-        .context("Failed to fetch roads from Overpass API")?;
-    spinner.finish_with_message(format!(
-        "Fetched {} road elements [{:.1}s]",
-        roads_response.elements.len(),
-        start.elapsed().as_secs_f32()
-    ));
-
-    let spinner = create_spinner("Parsing road data...");
-    let start = Instant::now();
-    let roads = parse_roads(&roads_response);
-    if roads.is_empty() {
-        bail!(
-            "No roads found in the specified area. Try increasing the radius or using --road-depth all"
+        println!();
+        println!(
+            "Done! Total time: {:.1}s",
+            total_start.elapsed().as_secs_f32()
         );
+        print_assembly_guide(&tiles, grid, &tile_path, args.tile_pins);
+        println!();
+        print_color_change_guide(&model.heights, theme, terrain);
+        if let Some(stats_path) = &args.stats_json {
+            write_stats(stats_path, &model, &outputs, total_start)?;
+        }
+        return Ok(());
     }
-    spinner.finish_with_message(format!(
-        "Parsed {} road segments [{:.1}s]",
-        roads.len(),
-        start.elapsed().as_secs_f32()
-    ));
 
-    let water = if args.water {
-        let spinner = create_spinner("Fetching water features...");
-        let start = Instant::now();
-        let water_response =
-            fetch_water(center, radius, &overpass_config).context("Failed to fetch water data")?;
+    let spinner = create_spinner("Writing output file...");
+    let start = Instant::now();
+    let written_triangles = model.report.triangles;
+    let layer_refs: Vec<(&str, &[Triangle])> = model
+        .layers
+        .iter()
+        .map(|layer| (layer.name, layer.triangles.as_slice()))
+        .collect();
+    write_layers(
+        &output_path,
+        output_kind,
+        &layer_refs,
+        gzip,
+        args.stl_format,
+    )?;
+    let file_size = std::fs::metadata(&output_path)
+        .map(|m| m.len() as usize)
+        .unwrap_or_else(|_| estimate_stl_size(written_triangles));
+
+    if gzip {
         spinner.finish_with_message(format!(
-            "Fetched {} water elements [{:.1}s]",
-            water_response.elements.len(),
+            "Wrote {} triangles ({:.1} KB gzipped, {:.1} KB uncompressed) [{:.1}s]",
+            written_triangles,
+            file_size as f64 / 1024.0,
+            gz_uncompressed_size(&output_path).unwrap_or(file_size) as f64 / 1024.0,
             start.elapsed().as_secs_f32()
         ));
-
-        let parsed = parse_water(&water_response);
-        if verbose {
-            println!("  Parsed {} water polygons", parsed.len());
-        }
-        parsed
     } else {
-        Vec::new()
-    };
+        spinner.finish_with_message(format!(
+            "Wrote {} triangles ({:.1} KB) [{:.1}s]",
+            written_triangles,
+            file_size as f64 / 1024.0,
+            start.elapsed().as_secs_f32()
+        ));
+    }
 
-    let parks = if args.parks {
-        let spinner = create_spinner("Fetching park features...");
+    let mut layer_files = Vec::new();
+    if split_layers {
+        let spinner = create_spinner("Writing layer files...");
         let start = Instant::now();
-        let parks_response =
-            fetch_parks(center, radius, &overpass_config).context("Failed to fetch park data")?;
+        for layer in model.layers.iter().filter(|l| !l.triangles.is_empty()) {
+            let path = suffixed_path(layer.name);
+            write_layers(
+                &path,
+                output_kind,
+                &[(layer.name, layer.triangles.as_slice())],
+                gzip,
+                args.stl_format,
+            )?;
+            layer_files.push((layer, path));
+        }
         spinner.finish_with_message(format!(
-            "Fetched {} park elements [{:.1}s]",
-            parks_response.elements.len(),
+            "Wrote {} layer files [{:.1}s]",
+            layer_files.len(),
             start.elapsed().as_secs_f32()
         ));
+    }
 
-        let parsed = parse_parks(&parks_response);
-        if verbose {
-            println!("  Parsed {} park polygons", parsed.len());
+    println!();
+    println!(
+        "Done! Total time: {:.1}s",
+        total_start.elapsed().as_secs_f32()
+    );
+    println!();
+    println!("Output: {}", output_path.display());
+    for (layer, path) in &layer_files {
+        if layer.manifold.is_watertight() {
+            println!("  {}", path.display());
+        } else {
+            println!(
+                "  {} (not watertight: {} boundary edges, {} non-manifold edges)",
+                path.display(),
+                layer.manifold.boundary_edges,
+                layer.manifold.non_manifold_edges
+            );
         }
-        parsed
+    }
+    println!();
+    if output_kind == OutputKind::ThreeMf {
+        println!("Each feature layer is a separate colored object in the 3MF;");
+        println!("assign filaments per object in your slicer.");
+    } else if split_layers {
+        println!("Load the layer files together as parts of one object and");
+        println!("assign a filament to each part in your slicer.");
     } else {
-        Vec::new()
-    };
-
-    let feature_heights = FeatureHeights::new(base_height, args.water, args.parks);
-
-    let spinner = create_spinner("Setting up coordinate projection...");
-    let projector = Projector::new(center);
-
-    let mut all_projected_points: Vec<(f64, f64)> = Vec::new();
-    for road in &roads {
-        let projected = projector.project_points(&road.points);
-        all_projected_points.extend(projected);
+        print_color_change_guide(&model.heights, theme, terrain);
+    }
+    if let Some(stats_path) = &args.stats_json {
+        let outputs: Vec<PathBuf> = std::iter::once(output_path)
+            .chain(layer_files.into_iter().map(|(_, path)| path))
+            .collect();
+        write_stats(stats_path, &model, &outputs, total_start)?;
     }
 
-    let bounds = Bounds::from_points(&all_projected_points)
-        .context("Failed to compute bounds from road points")?;
+    Ok(())
+}
+
+/// Machine-readable summary of a run, for `--stats-json`
+#[derive(Serialize)]
+struct RunStats<'a> {
+    center: (f64, f64),
+    area_m: (f64, f64),
+    layers: Vec<LayerStats<'a>>,
+    /// Triangles before decimation and validation
+    generated_triangles: usize,
+    triangles: usize,
+    bounds_mm: Option<BoundsStats>,
+    outputs: Vec<OutputStats>,
+    elapsed_secs: f32,
+}
 
-    let text_margin_mm = 20.0;
-    let scaler = Scaler::from_bounds_with_margin(&bounds, size as f64, text_margin_mm);
-    spinner.finish_with_message(format!(
-        "Map area: {:.0}m x {:.0}m -> {:.0}mm x {:.0}mm (with {:.0}mm text margin)",
-        bounds.width(),
-        bounds.height(),
-        size,
-        size - text_margin_mm as f32,
-        text_margin_mm
-    ));
+#[derive(Serialize)]
+struct LayerStats<'a> {
+    name: &'a str,
+    triangles: usize,
+    degenerate: usize,
+    removed: usize,
+    coplanar_overlaps: usize,
+    #[serde(flatten)]
+    manifold: ManifoldReport,
+}
 
-    let spinner = create_spinner("Generating mesh layers...");
-    let start = Instant::now();
+#[derive(Serialize)]
+struct BoundsStats {
+    min: [f32; 3],
+    max: [f32; 3],
+    size: [f32; 3],
+}
 
-    let base_triangles = generate_base_plate(size, base_height);
-    if verbose {
-        println!("  Base plate: {} triangles", base_triangles.len());
-    }
+#[derive(Serialize)]
+struct OutputStats {
+    path: PathBuf,
+    bytes: u64,
+}
 
-    let water_triangles = if args.water {
-        let triangles =
-            generate_water_meshes(&water, &projector, &scaler, feature_heights.water_z_top);
-        if verbose {
-            println!("  Water: {} triangles", triangles.len());
-        }
-        triangles
-    } else {
-        Vec::new()
+/// Write the run summary as JSON once every output file exists
+fn write_stats(
+    path: &std::path::Path,
+    model: &MapModel,
+    outputs: &[PathBuf],
+    total_start: Instant,
+) -> Result<()> {
+    let stats = RunStats {
+        center: model.report.center,
+        area_m: model.report.area_m,
+        layers: model
+            .layers
+            .iter()
+            .map(|layer| LayerStats {
+                name: layer.name,
+                triangles: layer.triangles.len(),
+                degenerate: layer.degenerate,
+                removed: layer.removed,
+                coplanar_overlaps: layer.coplanar_overlaps,
+                manifold: layer.manifold,
+            })
+            .collect(),
+        generated_triangles: model.report.generated_triangles,
+        triangles: model.report.triangles,
+        bounds_mm: model.bounds().map(|(min, max)| BoundsStats {
+            min,
+            max,
+            size: [max[0] - min[0], max[1] - min[1], max[2] - min[2]],
+        }),
+        outputs: outputs
+            .iter()
+            .map(|output| OutputStats {
+                path: output.clone(),
+                bytes: std::fs::metadata(output).map(|m| m.len()).unwrap_or(0),
+            })
+            .collect(),
+        elapsed_secs: total_start.elapsed().as_secs_f32(),
     };
+    let json = serde_json::to_string_pretty(&stats).context("Failed to serialize stats")?;
+    std::fs::write(path, json)
+        .with_context(|| format!("Failed to write stats to {}", path.display()))
+}
 
-    let park_triangles = if args.parks {
-        let triangles =
-            generate_park_meshes(&parks, &projector, &scaler, feature_heights.park_z_top);
-        if verbose {
-            println!("  Parks: {} triangles", triangles.len());
+/// Write layers in the format the output path asks for
+fn write_layers(
+    path: &std::path::Path,
+    kind: OutputKind,
+    layers: &[(&str, &[Triangle])],
+    gzip: bool,
+    stl_format: StlFormat,
+) -> Result<()> {
+    match kind {
+        OutputKind::Obj => write_obj(path, layers).context("Failed to write OBJ file"),
+        OutputKind::ThreeMf => write_3mf(path, layers).context("Failed to write 3MF file"),
+        OutputKind::Glb => write_glb(path, layers).context("Failed to write GLB file"),
+        OutputKind::Stl => {
+            let triangles: Vec<Triangle> = layers
+                .iter()
+                .flat_map(|(_, triangles)| triangles.iter().cloned())
+                .collect();
+            if gzip {
+                write_stl_gz(path, &triangles, stl_format)
+            } else {
+                match stl_format {
+                    StlFormat::Binary => write_stl(path, &triangles),
+                    StlFormat::Ascii => write_stl_ascii(path, &triangles),
+                }
+            }
+            .context("Failed to write STL file")
         }
-        triangles
-    } else {
-        Vec::new()
-    };
-
-    let road_config = RoadConfig::default()
-        .with_scale(road_scale)
-        .with_map_radius(radius, size)
-        .with_simplify_level(simplify)
-        .with_z_top(feature_heights.road_z_top);
-    let road_triangles = generate_road_meshes(&roads, &projector, &scaler, &road_config);
-    if verbose {
-        println!("  Roads: {} triangles", road_triangles.len());
     }
+}
 
-    let text_triangles = generate_text_layer(
-        &display_name,
-        center,
-        size,
-        primary_text.as_deref(),
-        secondary_text.as_deref(),
-        font_path.as_deref(),
-        feature_heights.text_z_top,
+/// Lay out the tile files as they sit on the table and explain the joints
+fn print_assembly_guide(
+    tiles: &[Tile],
+    grid: TileGrid,
+    tile_path: &dyn Fn(usize, usize) -> PathBuf,
+    pins: bool,
+) {
+    println!();
+    println!("=== Tile Assembly ===");
+    println!();
+    println!("Lay the tiles out face up, top row first:");
+    for row in 0..grid.rows {
+        let names: Vec<String> = (0..grid.columns)
+            .map(|column| {
+                tile_path(row, column)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().into_owned())
+                    .unwrap_or_default()
+            })
+            .collect();
+        println!("  {}", names.join("  "));
+    }
+    println!();
+    for tile in tiles {
+        println!(
+            "  r{}c{}: {:.0} x {:.0} mm",
+            tile.row, tile.column, tile.size_mm.0, tile.size_mm.1
+        );
+    }
+    println!();
+    println!(
+        "Tiles join with {:.0}mm half-lap joints: the lower half of each tile's base",
+        TILE_LAP_MM
     );
-    if verbose {
-        println!("  Text: {} triangles", text_triangles.len());
+    println!("extends as a tab under its right and upper neighbors. Work from the");
+    println!("bottom-left tile, setting each neighbor's overhang onto the tab, and glue");
+    println!("the laps.");
+    if pins {
+        println!("Pins on the tabs drop into sockets under the neighbors' overhangs;");
+        println!("press each tile down until it sits flat.");
     }
+}
 
-    let total_triangles = base_triangles.len()
-        + water_triangles.len()
-        + park_triangles.len()
-        + road_triangles.len()
-        + text_triangles.len();
-
-    spinner.finish_with_message(format!(
-        "Generated {} triangles [{:.1}s]",
-        total_triangles,
-        start.elapsed().as_secs_f32()
-    ));
-
-    let spinner = create_spinner("Validating and writing STL file...");
-    let start = Instant::now();
-
-    let mut all_triangles = Vec::new();
-    all_triangles.extend(base_triangles);
-    all_triangles.extend(water_triangles);
-    all_triangles.extend(park_triangles);
-    all_triangles.extend(road_triangles);
-    all_triangles.extend(text_triangles);
+/// Contents of `--overpass-query-file`, if given
+fn read_overpass_query(args: &Args) -> Result<Option<String>> {
+    args.overpass_query_file
+        .as_ref()
+        .map(|path| {
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read Overpass query: {}", path.display()))
+        })
+        .transpose()
+}
 
-    let (validated, _) = validate_and_fix(all_triangles);
-    let file_size = estimate_stl_size(validated.len());
+/// Merge the command line over the config file into pipeline parameters
+///
+/// An option left at its CLI default falls back to the config file value.
+fn resolve_params(args: &Args, file_config: Option<&FileConfig>) -> MapParams {
+    let size = if (args.size - 220.0).abs() > 0.01 {
+        args.size
+    } else {
+        file_config.map(|c| c.size).unwrap_or(220.0)
+    };
+    let mut overpass = file_config
+        .and_then(|c| c.overpass.clone())
+        .unwrap_or_default();
+    if args.no_cache {
+        overpass.cache = false;
+    }
+    if args.race_mirrors {
+        overpass.race_mirrors = true;
+    }
+    let mut nominatim = file_config
+        .and_then(|c| c.nominatim.clone())
+        .unwrap_or_default();
+    if let Some(proxy) = &args.proxy {
+        overpass.proxy = Some(proxy.clone());
+        nominatim.proxy = Some(proxy.clone());
+    }
+    let preset = resolve_theme(args, file_config).map(Theme::preset);
+
+    MapParams {
+        city: args
+            .city
+            .clone()
+            .or_else(|| file_config.and_then(|c| c.city.clone())),
+        country: args
+            .country
+            .clone()
+            .or_else(|| file_config.and_then(|c| c.country.clone())),
+        state: args
+            .state
+            .clone()
+            .or_else(|| file_config.and_then(|c| c.state.clone())),
+        refresh_geocode: args.refresh_geocode,
+        reverse_geocode: args.reverse_geocode || file_config.is_some_and(|c| c.reverse_geocode),
+        lat: args.lat.or_else(|| file_config.and_then(|c| c.lat)),
+        lon: args.lon.or_else(|| file_config.and_then(|c| c.lon)),
+        bbox: args.bbox.or_else(|| {
+            file_config
+                .and_then(|c| c.bbox)
+                .map(|[south, west, north, east]| (south, west, north, east))
+        }),
+        area: args
+            .area
+            .clone()
+            .or_else(|| file_config.and_then(|c| c.area.clone())),
+        radius: args.radius.or_else(|| file_config.and_then(|c| c.radius)),
+        width: args
+            .width
+            .or_else(|| file_config.and_then(|c| c.width))
+            .unwrap_or(size),
+        height: args
+            .height
+            .or_else(|| file_config.and_then(|c| c.height))
+            .unwrap_or(size),
+        base_height: if (args.base_height - 2.0).abs() > 0.01 {
+            args.base_height
+        } else {
+            file_config.map(|c| c.base_height).unwrap_or(2.0)
+        },
+        corner_radius: if args.corner_radius > 0.0 {
+            args.corner_radius
+        } else {
+            file_config.map(|c| c.corner_radius).unwrap_or(0.0)
+        },
+        hang_hole: args.hang_hole,
+        hang_hole_corner: args.hang_hole_corner,
+        hollow_base: args.hollow_base,
+        bed: args.bed.or_else(|| {
+            file_config
+                .and_then(|c| c.bed)
+                .map(|[width, height]| BedSize { width, height })
+        }),
+        auto_fit_bed: args.auto_fit_bed || file_config.is_some_and(|c| c.auto_fit_bed),
+        road_scale: if (args.road_scale - 1.0).abs() > 0.01 {
+            args.road_scale
+        } else {
+            file_config.map(|c| c.road_scale).unwrap_or(1.0)
+        },
+        road_depth: if args.road_depth != RoadDepth::Primary {
+            args.road_depth
+        } else {
+            file_config
+                .map(|c| c.road_depth)
+                .unwrap_or(RoadDepth::Primary)
+        },
+        paths: args.paths,
+        primary_text: args
+            .primary_text
+            .clone()
+            .or_else(|| file_config.and_then(|c| c.primary_text.clone())),
+        secondary_text: args
+            .secondary_text
+            .clone()
+            .or_else(|| file_config.and_then(|c| c.secondary_text.clone())),
+        primary_text_height: args
+            .primary_text_height_mm
+            .or_else(|| file_config.and_then(|c| c.primary_text_height_mm)),
+        secondary_text_height: args
+            .secondary_text_height_mm
+            .or_else(|| file_config.and_then(|c| c.secondary_text_height_mm)),
+        back_text: args
+            .back_text
+            .clone()
+            .or_else(|| file_config.and_then(|c| c.back_text.clone())),
+        line_spacing: if (args.line_spacing - 1.2).abs() > 0.001 {
+            args.line_spacing
+        } else {
+            file_config.map(|c| c.line_spacing).unwrap_or(1.2)
+        },
+        simplify: if args.simplify != 0 {
+            args.simplify
+        } else {
+            file_config.map(|c| c.simplify).unwrap_or(0)
+        },
+        simplify_algorithm: if args.simplify_algorithm != SimplifyAlgorithm::DouglasPeucker {
+            args.simplify_algorithm
+        } else {
+            file_config
+                .map(|c| c.simplify_algorithm)
+                .unwrap_or_default()
+        },
+        max_triangles: args.max_triangles,
+        min_triangle_area: args.min_triangle_area,
+        round_caps: args.round_caps || file_config.is_some_and(|c| c.round_caps),
+        union_roads: args.union_roads || file_config.is_some_and(|c| c.union_roads),
+        road_profile: if args.road_profile != RibbonProfile::Flat {
+            args.road_profile
+        } else {
+            file_config.map(|c| c.road_profile).unwrap_or_default()
+        },
+        tunnels: if args.tunnels != TunnelMode::Show {
+            args.tunnels
+        } else {
+            file_config.map(|c| c.tunnels).unwrap_or_default()
+        },
+        font: args.font.clone(),
+        secondary_font: args.secondary_font.clone(),
+        fallback_fonts: args.fallback_fonts.clone(),
+        text_quality: args.text_quality,
+        missing_glyph: args.missing_glyph,
+        projection: args.projection,
+        rotate: args.rotate,
+        shape: args.shape,
+        compass: args.compass,
+        scale_bar: args.scale_bar,
+        no_text: args.no_text || file_config.is_some_and(|c| c.no_text),
+        text_position: args
+            .text_position
+            .or_else(|| file_config.and_then(|c| c.text_position))
+            .unwrap_or_default(),
+        street_names: args.street_names,
+        oneway_arrows: args.oneway_arrows,
+        grid: args.grid,
+        boundary: args.boundary,
+        deterministic: args.deterministic,
+        frame: args.frame,
+        frame_width: args.frame_width,
+        frame_height: args.frame_height,
+        frame_inset: args.frame_inset,
+        water: args.water || preset.is_some_and(|p| p.water),
+        water_style: if args.water_style != WaterStyle::Raised {
+            args.water_style
+        } else {
+            file_config.map(|c| c.water_style).unwrap_or_default()
+        },
+        parks: args.parks || preset.is_some_and(|p| p.parks),
+        buildings: args.buildings,
+        building_height: args.building_height,
+        railways: args.railways,
+        rail_width: args.rail_width,
+        subways: args.subways,
+        aeroways: args.aeroways,
+        overpass_query: None,
+        road_overrides: file_config.and_then(|c| c.roads).unwrap_or_default(),
+        heights: file_config
+            .and_then(|c| c.heights)
+            .unwrap_or_default()
+            .or(preset.map(|p| p.heights).unwrap_or_default()),
+        overpass,
+        nominatim,
+        osm_file: args.osm_file.clone(),
+        dump_osm: args.dump_osm.clone(),
+        dump_geojson: args.dump_geojson.clone(),
+        geojson_input: args.geojson_input.clone(),
+        dem: args
+            .dem
+            .clone()
+            .or_else(|| file_config.and_then(|c| c.dem.clone())),
+        terrain_scale: args
+            .terrain_scale
+            .or_else(|| file_config.and_then(|c| c.terrain_scale))
+            .unwrap_or(1.0),
+        contours: args
+            .contours
+            .or_else(|| file_config.and_then(|c| c.contours)),
+    }
+}
 
-    write_stl(&output_path, &validated).context("Failed to write STL file")?;
+/// Theme from the command line, else from the config file
+fn resolve_theme(args: &Args, file_config: Option<&FileConfig>) -> Option<Theme> {
+    args.theme.or_else(|| file_config.and_then(|c| c.theme))
+}
 
-    spinner.finish_with_message(format!(
-        "Wrote {} triangles ({:.1} KB) [{:.1}s]",
-        validated.len(),
-        file_size as f64 / 1024.0,
-        start.elapsed().as_secs_f32()
-    ));
+fn print_configuration(params: &MapParams) -> Result<()> {
+    let (width, height) = params.plate_size();
+    println!("Configuration:");
+    if let Some(ref c) = params.city {
+        println!("  City: {}", c);
+        if let Some(ref state) = params.state {
+            println!("  State: {}", state);
+        }
+        println!(
+            "  Country: {}",
+            params.country.as_deref().unwrap_or_default()
+        );
+    }
+    if let (Some(lt), Some(ln)) = (params.lat, params.lon) {
+        println!("  Coordinates: ({:.4}, {:.4})", lt, ln);
+    }
+    if let Some((south, west, north, east)) = params.bbox {
+        println!(
+            "  Bounding box: S {:.4}, W {:.4}, N {:.4}, E {:.4}",
+            south, west, north, east
+        );
+    }
+    if let Some(ref area) = params.area {
+        println!("  Area: {} (clipped to its boundary)", area);
+    } else if params.radius.is_none() && params.bbox.is_none() && params.city.is_some() {
+        println!("  Radius: fitted to the city");
+    } else {
+        println!("  Radius: {}m", params.effective_radius());
+    }
+    println!("  Size: {}mm x {}mm", width, height);
+    println!("  Base height: {}mm", params.base_height);
+    if params.corner_radius > 0.0 {
+        println!("  Corner radius: {}mm", params.corner_radius);
+    }
+    if let Some(hole) = params.fitted_hang_hole()? {
+        println!(
+            "  Hang hole: {:.1}mm at ({:.1}, {:.1})",
+            hole.diameter, hole.x, hole.y
+        );
+    }
+    println!("  Road scale: {}", params.road_scale);
+    println!("  Road depth: {:?}", params.road_depth);
+    println!("  Road profile: {:?}", params.road_profile);
+    println!("  Tunnels: {:?}", params.tunnels);
+    if params.union_roads {
+        println!("  Roads: merged at crossings");
+    }
+    println!("  Simplify level: {}", params.simplify);
+    if params.simplify > 0 {
+        println!("  Simplify algorithm: {:?}", params.simplify_algorithm);
+    }
+    let enabled = |on: bool| if on { "enabled" } else { "disabled" };
+    println!("  Water features: {}", enabled(params.water));
+    if params.water {
+        println!("  Water style: {:?}", params.water_style);
+    }
+    println!("  Park features: {}", enabled(params.parks));
+    println!("  Buildings: {}", enabled(params.buildings));
+    println!("  Paths: {}", enabled(params.paths));
+    println!("  Railways: {}", enabled(params.railways));
+    println!("  Aeroways: {}", enabled(params.aeroways));
+    match (&params.dem, params.contours) {
+        (Some(dem), Some(interval)) => {
+            println!("  Contours: every {}m from {}", interval, dem.display())
+        }
+        (Some(dem), None) => println!(
+            "  Terrain: {} at {}x vertical scale",
+            dem.display(),
+            params.terrain_scale
+        ),
+        _ => {}
+    }
+    Ok(())
+}
 
+fn print_estimate(estimate: &MapEstimate, params: &MapParams) {
+    let (south, west, north, east) = estimate.bbox;
     println!();
+    println!("Dry run estimate (nothing fetched or written):");
     println!(
-        "Done! Total time: {:.1}s",
-        total_start.elapsed().as_secs_f32()
+        "  Center: ({:.4}, {:.4}), radius {}m",
+        estimate.center.0, estimate.center.1, estimate.radius_m
     );
-    println!();
-    println!("Output: {}", output_path.display());
-    println!();
-    print_color_change_guide(&feature_heights);
+    println!(
+        "  Bounding box: S {:.4}, W {:.4}, N {:.4}, E {:.4}",
+        south, west, north, east
+    );
+    println!(
+        "  Map area: {:.0}m x {:.0}m",
+        estimate.area_m.0, estimate.area_m.1
+    );
+    println!(
+        "  Plate: {:.0}mm x {:.0}mm at {:.1}mm per km",
+        estimate.plate_mm.0, estimate.plate_mm.1, estimate.mm_per_km
+    );
+    println!(
+        "  Roads: {:?}, simplify level {}",
+        params.road_depth, params.simplify
+    );
+    println!(
+        "  Triangles: ~{} (~{:.1} MB binary STL)",
+        estimate.triangles,
+        estimate.stl_bytes as f64 / (1024.0 * 1024.0)
+    );
+}
 
-    Ok(())
+/// Shows pipeline progress as terminal spinners
+struct CliProgress {
+    verbose: bool,
+    spinner: RefCell<Option<ProgressBar>>,
 }
 
-fn print_color_change_guide(heights: &FeatureHeights) {
-    use mapto3d::config::heights::LAYER_HEIGHT;
+impl Progress for CliProgress {
+    fn start(&self, message: &str) {
+        self.spinner.replace(Some(create_spinner(message)));
+    }
+
+    fn finish(&self, message: &str) {
+        if let Some(spinner) = self.spinner.take() {
+            spinner.finish_with_message(message.to_string());
+        }
+    }
 
-    let base_layers = (heights.base_height / LAYER_HEIGHT).round() as i32;
-    let roads_top_layers = (heights.road_z_top / LAYER_HEIGHT).round() as i32;
-    let text_top_layers = (heights.text_z_top / LAYER_HEIGHT).round() as i32;
+    fn message(&self, message: &str) {
+        println!("{}", message);
+    }
 
-    println!("Multi-Color FDM Printing Guide (0.2mm layer height)");
-    println!("====================================================");
+    fn detail(&self, message: &str) {
+        if self.verbose {
+            println!("{}", message);
+        }
+    }
+
+    fn warn(&self, message: &str) {
+        eprintln!("Warning: {}", message);
+    }
+}
+
+fn print_color_change_guide(heights: &FeatureHeights, theme: Option<Theme>, terrain: bool) {
+    // Relief lifts every band by the ground under it, so no one height
+    // separates two layers across the whole map
+    if terrain {
+        println!("Terrain raises each feature by the ground under it, so colors can't be");
+        println!("changed by height; print in one color or write a .3mf to color per object.");
+        return;
+    }
+    let layers = |z: f32| (z / heights.layer_height).round() as i32;
+    let base_layers = layers(heights.base_height);
+    let road_bands = heights.road_bands();
+    let text_top_layers = layers(heights.text_z_top);
+
+    let title = format!(
+        "Multi-Color FDM Printing Guide ({}mm layer height)",
+        heights.layer_height
+    );
+    println!("{}", title);
+    println!("{}", "=".repeat(title.len()));
     println!();
     println!("Solid column architecture - all features start at z=0, differ in height:");
     println!(
@@ -478,26 +1312,69 @@ fn print_color_change_guide(heights: &FeatureHeights) {
 
     let mut color_num = 1;
 
+    let recessed = heights.water_enabled && heights.water_style == WaterStyle::Recessed;
     if heights.water_enabled {
-        let water_top_layers = (heights.water_z_top / LAYER_HEIGHT).round() as i32;
+        let water_top_layers = layers(heights.water_z_top);
         println!(
-            "  Water:   0.0mm -> {:.1}mm ({} layers)",
-            heights.water_z_top, water_top_layers
+            "  Water:   0.0mm -> {:.1}mm ({} layers{})",
+            heights.water_z_top,
+            water_top_layers,
+            if recessed {
+                ", recessed into the base"
+            } else {
+                ""
+            }
         );
     }
 
     if heights.parks_enabled {
-        let parks_top_layers = (heights.park_z_top / LAYER_HEIGHT).round() as i32;
+        let parks_top_layers = layers(heights.park_z_top);
         println!(
             "  Parks:   0.0mm -> {:.1}mm ({} layers)",
             heights.park_z_top, parks_top_layers
         );
     }
 
-    println!(
-        "  Roads:   0.0mm -> {:.1}mm ({} layers)",
-        heights.road_z_top, roads_top_layers
-    );
+    if heights.aeroways_enabled {
+        let aeroways_top_layers = layers(heights.aeroway_z_top);
+        println!(
+            "  Aeroways: 0.0mm -> {:.1}mm ({} layers)",
+            heights.aeroway_z_top, aeroways_top_layers
+        );
+    }
+
+    if heights.buildings_enabled {
+        let buildings_top_layers = layers(heights.building_z_top);
+        println!(
+            "  Buildings: 0.0mm -> up to {:.1}mm (up to {} layers, from OSM height tags)",
+            heights.building_z_top, buildings_top_layers
+        );
+    }
+
+    if heights.railways_enabled {
+        let rails_top_layers = layers(heights.rail_z_top);
+        println!(
+            "  Rails:   0.0mm -> {:.1}mm ({} layers)",
+            heights.rail_z_top, rails_top_layers
+        );
+    }
+
+    if let [(z_top, _)] = road_bands.as_slice() {
+        println!(
+            "  Roads:   0.0mm -> {:.1}mm ({} layers)",
+            z_top,
+            layers(*z_top)
+        );
+    } else {
+        for (z_top, classes) in &road_bands {
+            println!(
+                "  Roads ({}): 0.0mm -> {:.1}mm ({} layers)",
+                classes.join(", "),
+                z_top,
+                layers(*z_top)
+            );
+        }
+    }
     println!(
         "  Text:    0.0mm -> {:.1}mm ({} layers - tallest)",
         heights.text_z_top, text_top_layers
@@ -509,15 +1386,31 @@ fn print_color_change_guide(heights: &FeatureHeights) {
     );
     println!();
     println!("Color change schedule (based on absolute feature heights):");
-    println!(
-        "  Layers 1-{}: Base only (Color {})",
-        base_layers, color_num
-    );
+    if recessed {
+        // The pockets show the color the whole plate starts in
+        let water_top_layers = layers(heights.water_z_top);
+        println!(
+            "  Layers 1-{}: Water and base floor (Color {} for water areas)",
+            water_top_layers, color_num
+        );
+        color_num += 1;
+        println!(
+            "  Layers {}-{}: Base around the water (Color {})",
+            water_top_layers + 1,
+            base_layers,
+            color_num
+        );
+    } else {
+        println!(
+            "  Layers 1-{}: Base only (Color {})",
+            base_layers, color_num
+        );
+    }
     color_num += 1;
     let mut prev_layers = base_layers;
 
-    if heights.water_enabled {
-        let water_top_layers = (heights.water_z_top / LAYER_HEIGHT).round() as i32;
+    if heights.water_enabled && !recessed {
+        let water_top_layers = layers(heights.water_z_top);
         println!(
             "  Layers {}-{}: Water tops out at {:.1}mm (Color {} for water areas)",
             prev_layers + 1,
@@ -530,7 +1423,7 @@ fn print_color_change_guide(heights: &FeatureHeights) {
     }
 
     if heights.parks_enabled {
-        let parks_top_layers = (heights.park_z_top / LAYER_HEIGHT).round() as i32;
+        let parks_top_layers = layers(heights.park_z_top);
         println!(
             "  Layers {}-{}: Parks top out at {:.1}mm (Color {} for park areas)",
             prev_layers + 1,
@@ -542,30 +1435,94 @@ fn print_color_change_guide(heights: &FeatureHeights) {
         prev_layers = parks_top_layers;
     }
 
-    println!(
-        "  Layers {}-{}: Roads top out at {:.1}mm (Color {} for road areas)",
-        prev_layers + 1,
-        roads_top_layers,
-        heights.road_z_top,
-        color_num
-    );
-    color_num += 1;
+    if heights.aeroways_enabled {
+        let aeroways_top_layers = layers(heights.aeroway_z_top);
+        println!(
+            "  Layers {}-{}: Aeroways top out at {:.1}mm (Color {} for runways and aprons)",
+            prev_layers + 1,
+            aeroways_top_layers,
+            heights.aeroway_z_top,
+            color_num
+        );
+        color_num += 1;
+        prev_layers = aeroways_top_layers;
+    }
+
+    if heights.railways_enabled {
+        let rails_top_layers = layers(heights.rail_z_top);
+        println!(
+            "  Layers {}-{}: Rails top out at {:.1}mm (Color {} for railway lines)",
+            prev_layers + 1,
+            rails_top_layers,
+            heights.rail_z_top,
+            color_num
+        );
+        color_num += 1;
+        prev_layers = rails_top_layers;
+    }
+
+    if let [(z_top, _)] = road_bands.as_slice() {
+        println!(
+            "  Layers {}-{}: Roads top out at {:.1}mm (Color {} for road areas)",
+            prev_layers + 1,
+            layers(*z_top),
+            z_top,
+            color_num
+        );
+        color_num += 1;
+        prev_layers = layers(*z_top);
+    } else {
+        // One color per road height, for a gradient by importance
+        for (z_top, classes) in &road_bands {
+            println!(
+                "  Layers {}-{}: {} roads top out at {:.1}mm (Color {})",
+                prev_layers + 1,
+                layers(*z_top),
+                classes.join(", "),
+                z_top,
+                color_num
+            );
+            color_num += 1;
+            prev_layers = layers(*z_top);
+        }
+    }
 
     println!(
         "  Layers {}-{}: Text tops out at {:.1}mm (Color {} for text)",
-        roads_top_layers + 1,
+        prev_layers + 1,
         text_top_layers,
         heights.text_z_top,
         color_num
     );
+    if heights.buildings_enabled {
+        println!(
+            "  Buildings vary in height up to {:.1}mm and cross the bands above;",
+            heights.building_z_top
+        );
+        println!("  use a 3MF/OBJ export to color them separately.");
+    }
     println!();
     println!("NOTE: With solid columns, features overlap in XY space.");
     println!("The slicer will show mixed colors on layers where features coexist.");
     println!("For clean color separation, use a multi-material slicer like PrusaSlicer");
-    println!("with separate STL files per feature, or accept blended colors.");
+    println!("with separate STL files per feature (--split-layers), or accept blended colors.");
     println!();
 
-    if heights.water_enabled && heights.parks_enabled {
+    if let Some(theme) = theme {
+        let colors: Vec<String> = theme
+            .preset()
+            .palette
+            .iter()
+            .filter(|(feature, _)| match *feature {
+                "water" => heights.water_enabled,
+                "parks" => heights.parks_enabled,
+                _ => true,
+            })
+            .map(|(feature, color)| format!("{} {}", color, feature))
+            .collect();
+        println!("Color palette ({:?} theme):", theme);
+        println!("  {}", colors.join(", "));
+    } else if heights.water_enabled && heights.parks_enabled {
         println!("Color palette suggestions:");
         println!("  Classic:    White base, Blue water, Green parks, Gray roads, Black text");
         println!("  Earth:      Tan base, Blue water, Forest green parks, Brown roads, Black text");
@@ -589,56 +1546,40 @@ fn print_color_change_guide(heights: &FeatureHeights) {
         println!("  Monochrome: Light gray base, Dark gray roads, Black text");
         println!("  Night:      Black base, White roads, Gold text");
     }
+
+    if heights.buildings_enabled {
+        println!("  Buildings:  Sand or light gray sits well between the park and road colors");
+    }
+    if heights.railways_enabled {
+        println!("  Railways:   Rust brown or dark red stands out against gray roads");
+    }
+    if heights.aeroways_enabled {
+        println!("  Aeroways:   Dark slate reads as tarmac next to lighter roads");
+    }
 }
 
-fn generate_text_layer(
-    city: &str,
-    coords: (f64, f64),
-    size_mm: f32,
-    primary_text: Option<&str>,
-    secondary_text: Option<&str>,
-    font_path: Option<&std::path::Path>,
-    text_z_top: f32,
-) -> Vec<mesh::Triangle> {
-    let mut triangles = Vec::new();
-
-    let text_z = 0.0;
-    let renderer = TextRenderer::new(font_path, text_z_top);
-
-    let primary = primary_text
-        .map(|s| s.to_uppercase())
-        .unwrap_or_else(|| city.to_uppercase());
-
-    let target_primary_width = size_mm * 0.75;
-    let primary_scale = renderer.calculate_scale_for_width(&primary, target_primary_width);
-    let primary_y = 12.0 * (size_mm / 220.0);
-    triangles.extend(renderer.render_text_centered(
-        &primary,
-        size_mm / 2.0,
-        primary_y,
-        text_z,
-        primary_scale,
-    ));
-
-    let secondary = secondary_text.map(|s| s.to_string()).unwrap_or_else(|| {
-        let (lat, lon) = coords;
-        let lat_dir = if lat >= 0.0 { "N" } else { "S" };
-        let lon_dir = if lon >= 0.0 { "E" } else { "W" };
-        format!("{:.4}{} / {:.4}{}", lat.abs(), lat_dir, lon.abs(), lon_dir)
-    });
+/// Output file format, chosen by the output path extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputKind {
+    Stl,
+    Obj,
+    ThreeMf,
+    Glb,
+}
 
-    let target_secondary_width = size_mm * 0.40;
-    let secondary_scale = renderer.calculate_scale_for_width(&secondary, target_secondary_width);
-    let secondary_y = 4.0 * (size_mm / 220.0);
-    triangles.extend(renderer.render_text_centered(
-        &secondary,
-        size_mm / 2.0,
-        secondary_y,
-        text_z,
-        secondary_scale,
-    ));
-
-    triangles
+impl OutputKind {
+    fn from_path(path: &std::path::Path) -> Self {
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match ext.as_deref() {
+            Some("obj") => Self::Obj,
+            Some("3mf") => Self::ThreeMf,
+            Some("glb") => Self::Glb,
+            _ => Self::Stl,
+        }
+    }
 }
 
 fn create_spinner(message: &str) -> ProgressBar {
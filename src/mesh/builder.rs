@@ -1,3 +1,5 @@
+use std::cmp::Ordering;
+
 /// A triangle for STL output
 #[derive(Debug, Clone)]
 pub struct Triangle {
@@ -24,6 +26,32 @@ impl Triangle {
     }
 }
 
+/// Put triangles in a canonical order, so the same mesh always writes the
+/// same bytes however its triangles were assembled
+///
+/// Each triangle is rotated to start at its smallest vertex, which keeps its
+/// winding, and the triangles are then sorted by their vertices.
+pub fn sort_triangles(triangles: &mut [Triangle]) {
+    for tri in triangles.iter_mut() {
+        let first = (0..3)
+            .min_by(|&a, &b| compare_vertices(tri.vertices[a], tri.vertices[b]))
+            .unwrap_or(0);
+        tri.vertices.rotate_left(first);
+    }
+    triangles.sort_unstable_by(|a, b| {
+        (0..3)
+            .map(|i| compare_vertices(a.vertices[i], b.vertices[i]))
+            .find(|order| order.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+fn compare_vertices(a: [f32; 3], b: [f32; 3]) -> Ordering {
+    a[0].total_cmp(&b[0])
+        .then(a[1].total_cmp(&b[1]))
+        .then(a[2].total_cmp(&b[2]))
+}
+
 /// Calculate the normal vector for a triangle using the cross product
 fn calculate_normal(v0: [f32; 3], v1: [f32; 3], v2: [f32; 3]) -> [f32; 3] {
     // Edge vectors
@@ -120,4 +148,23 @@ mod tests {
 
         assert_eq!(builder.len(), 3); // 1 triangle + 2 from quad
     }
+
+    #[test]
+    fn test_sort_triangles() {
+        let a = Triangle::new([1.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 0.0, 0.0]);
+        let b = Triangle::new([2.0, 0.0, 0.0], [3.0, 0.0, 0.0], [2.0, 1.0, 0.0]);
+        let mut forward = vec![a.clone(), b.clone()];
+        let mut backward = vec![b, a];
+        sort_triangles(&mut forward);
+        sort_triangles(&mut backward);
+        for (x, y) in forward.iter().zip(&backward) {
+            assert_eq!(x.vertices, y.vertices);
+        }
+        // Rotated to its smallest vertex with the winding kept
+        assert_eq!(
+            forward[0].vertices,
+            [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]]
+        );
+        assert!(forward[0].normal[2] > 0.0);
+    }
 }
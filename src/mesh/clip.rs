@@ -0,0 +1,290 @@
+//! Cutting closed meshes with axis-aligned planes
+//!
+//! A cut keeps one side of the plane and closes the opening with caps
+//! triangulated from the loops the plane traces through the surface, so a
+//! watertight solid stays watertight. Loops are told apart from holes by
+//! their winding, which the outward-facing input surface determines.
+
+use std::collections::HashMap;
+
+use super::Triangle;
+use super::triangulation::triangulate_polygon;
+use crate::geometry::signed_area;
+
+/// Coordinate axis a cutting plane is perpendicular to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+impl Axis {
+    fn index(self) -> usize {
+        match self {
+            Axis::X => 0,
+            Axis::Y => 1,
+            Axis::Z => 2,
+        }
+    }
+
+    /// The two in-plane axes, ordered so that (u, v, self) is right-handed
+    fn plane_axes(self) -> (usize, usize) {
+        match self {
+            Axis::X => (1, 2),
+            Axis::Y => (2, 0),
+            Axis::Z => (0, 1),
+        }
+    }
+}
+
+/// Keep the part of a closed, outward-facing mesh on one side of the plane
+/// `axis = value`, capping the cut
+///
+/// `keep_below` keeps coordinates up to `value`, otherwise from `value` up.
+pub fn clip_half_space(
+    triangles: &[Triangle],
+    axis: Axis,
+    value: f32,
+    keep_below: bool,
+) -> Vec<Triangle> {
+    let a = axis.index();
+    let sign = if keep_below { 1.0 } else { -1.0 };
+    let mut kept = Vec::with_capacity(triangles.len());
+    // Cut edges, in the winding of the kept triangle they bound
+    let mut segments: Vec<([f32; 3], [f32; 3])> = Vec::new();
+
+    for tri in triangles {
+        let d = tri.vertices.map(|v| sign * (v[a] - value));
+        if d.iter().all(|&x| x == 0.0) {
+            // A face lying on the plane bounds whichever side it faces away
+            // from, and stands in for the cap along its edges
+            if tri.normal[a] * sign > 0.0 {
+                kept.push(tri.clone());
+                for i in 0..3 {
+                    segments.push((tri.vertices[i], tri.vertices[(i + 1) % 3]));
+                }
+            }
+            continue;
+        }
+        if d.iter().all(|&x| x <= 0.0) {
+            kept.push(tri.clone());
+            for i in 0..3 {
+                let (j, k) = ((i + 1) % 3, (i + 2) % 3);
+                if d[i] == 0.0 && d[j] == 0.0 && d[k] != 0.0 {
+                    segments.push((tri.vertices[i], tri.vertices[j]));
+                }
+            }
+            continue;
+        }
+        if d.iter().all(|&x| x >= 0.0) {
+            continue;
+        }
+
+        // Sutherland-Hodgman against one plane: the kept piece has three or
+        // four corners, flagged when they lie on the plane
+        let mut piece: Vec<([f32; 3], bool)> = Vec::with_capacity(4);
+        for i in 0..3 {
+            let j = (i + 1) % 3;
+            if d[i] <= 0.0 {
+                piece.push((tri.vertices[i], d[i] == 0.0));
+            }
+            if (d[i] < 0.0 && d[j] > 0.0) || (d[i] > 0.0 && d[j] < 0.0) {
+                piece.push((intersect(tri.vertices[i], tri.vertices[j], a, value), true));
+            }
+        }
+        for k in 1..piece.len() - 1 {
+            kept.push(Triangle::with_normal(
+                [piece[0].0, piece[k].0, piece[k + 1].0],
+                tri.normal,
+            ));
+        }
+        for k in 0..piece.len() {
+            let next = (k + 1) % piece.len();
+            if piece[k].1 && piece[next].1 {
+                segments.push((piece[k].0, piece[next].0));
+            }
+        }
+    }
+
+    kept.extend(cap(&segments, axis, sign));
+    kept
+}
+
+/// Point where edge p-q crosses the plane, identical whichever way round
+/// the edge is given so neighboring triangles share it exactly
+fn intersect(p: [f32; 3], q: [f32; 3], axis: usize, value: f32) -> [f32; 3] {
+    let (p, q) = if key(p) <= key(q) { (p, q) } else { (q, p) };
+    let t = (value - p[axis]) / (q[axis] - p[axis]);
+    let mut point = [0.0; 3];
+    for i in 0..3 {
+        point[i] = p[i] + t * (q[i] - p[i]);
+    }
+    point[axis] = value;
+    point
+}
+
+/// Exact vertex identity; -0.0 and 0.0 are the same point
+fn key(p: [f32; 3]) -> [u32; 3] {
+    p.map(|c| (c + 0.0).to_bits())
+}
+
+/// Triangles closing the loops traced by the cut edges, facing the
+/// removed side
+fn cap(segments: &[([f32; 3], [f32; 3])], axis: Axis, sign: f32) -> Vec<Triangle> {
+    // Edges where the surface folds along the plane come in opposite pairs
+    let mut counts: HashMap<([u32; 3], [u32; 3]), i32> = HashMap::new();
+    for &(p, q) in segments {
+        *counts.entry((key(p), key(q))).or_default() += 1;
+        *counts.entry((key(q), key(p))).or_default() -= 1;
+    }
+    // The cap runs each cut edge backwards, as a neighboring face would
+    let mut outgoing: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+    let edges: Vec<([f32; 3], [f32; 3])> = segments
+        .iter()
+        .filter(|&&(p, q)| counts[&(key(p), key(q))] > 0)
+        .map(|&(p, q)| (q, p))
+        .collect();
+    for (i, &(start, _)) in edges.iter().enumerate() {
+        outgoing.entry(key(start)).or_default().push(i);
+    }
+
+    let mut used = vec![false; edges.len()];
+    let mut loops: Vec<Vec<[f32; 3]>> = Vec::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let start = key(edges[first].0);
+        let mut points = vec![edges[first].0];
+        let mut end = edges[first].1;
+        let closed = loop {
+            if key(end) == start {
+                break true;
+            }
+            let next = outgoing
+                .get(&key(end))
+                .and_then(|candidates| candidates.iter().find(|&&i| !used[i]).copied());
+            let Some(next) = next else {
+                break false;
+            };
+            used[next] = true;
+            points.push(end);
+            end = edges[next].1;
+        };
+        if closed && points.len() >= 3 {
+            loops.push(points);
+        }
+    }
+
+    let (u, v) = axis.plane_axes();
+    let flat =
+        |points: &[[f32; 3]]| -> Vec<(f32, f32)> { points.iter().map(|p| (p[u], p[v])).collect() };
+    let (outers, holes): (Vec<_>, Vec<_>) = loops
+        .into_iter()
+        .map(|points| {
+            let area = signed_area(&flat(&points)) as f32;
+            (points, area * sign)
+        })
+        .filter(|&(_, area)| area != 0.0)
+        .partition(|&(_, area)| area > 0.0);
+
+    let mut outer_holes: Vec<Vec<Vec<[f32; 3]>>> = vec![Vec::new(); outers.len()];
+    for (hole, _) in holes {
+        let probe = (hole[0][u], hole[0][v]);
+        let owner = outers
+            .iter()
+            .enumerate()
+            .filter(|(_, (outer, _))| point_in_ring(probe, &flat(outer)))
+            .min_by(|a, b| a.1.1.total_cmp(&b.1.1))
+            .map(|(i, _)| i);
+        if let Some(i) = owner {
+            outer_holes[i].push(hole);
+        }
+    }
+
+    let a = axis.index();
+    let mut caps = Vec::new();
+    for ((outer, _), holes) in outers.into_iter().zip(outer_holes) {
+        let flat_holes: Vec<Vec<(f32, f32)>> = holes.iter().map(|h| flat(h)).collect();
+        let vertices: Vec<[f32; 3]> = outer
+            .iter()
+            .chain(holes.iter().flatten())
+            .copied()
+            .collect();
+        let indices = triangulate_polygon(&flat(&outer), &flat_holes);
+        for t in indices.chunks_exact(3) {
+            let tri = Triangle::new(vertices[t[0]], vertices[t[1]], vertices[t[2]]);
+            if tri.normal[a] * sign < 0.0 {
+                caps.push(Triangle::new(
+                    vertices[t[0]],
+                    vertices[t[2]],
+                    vertices[t[1]],
+                ));
+            } else {
+                caps.push(tri);
+            }
+        }
+    }
+    caps
+}
+
+/// Even-odd point in polygon test
+fn point_in_ring((x, y): (f32, f32), ring: &[(f32, f32)]) -> bool {
+    let mut inside = false;
+    let mut j = ring.len() - 1;
+    for i in 0..ring.len() {
+        let (xi, yi) = ring[i];
+        let (xj, yj) = ring[j];
+        if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::test_util::{cuboid, signed_volume};
+    use crate::mesh::validation::validate_mesh;
+
+    #[test]
+    fn test_clip_box_stays_closed() {
+        let cube = cuboid([0.0, 0.0, 0.0], [2.0, 3.0, 4.0]);
+        assert!((signed_volume(&cube) - 24.0).abs() < 1e-4);
+
+        let left = clip_half_space(&cube, Axis::X, 0.5, true);
+        assert!((signed_volume(&left) - 6.0).abs() < 1e-4);
+        assert!(validate_mesh(&left).manifold.is_watertight());
+
+        let top = clip_half_space(&cube, Axis::Z, 1.0, false);
+        assert!((signed_volume(&top) - 18.0).abs() < 1e-4);
+        assert!(validate_mesh(&top).manifold.is_watertight());
+
+        // Cutting along a face leaves it to the side it bounds, uncapped
+        let same = clip_half_space(&cube, Axis::Z, 4.0, true);
+        assert_eq!(same.len(), cube.len());
+        assert!(validate_mesh(&same).manifold.is_watertight());
+        assert!(clip_half_space(&cube, Axis::Z, 4.0, false).is_empty());
+
+        assert!(clip_half_space(&cube, Axis::Y, 5.0, false).is_empty());
+        assert_eq!(clip_half_space(&cube, Axis::Y, 5.0, true).len(), cube.len());
+    }
+
+    #[test]
+    fn test_clip_caps_holes() {
+        // A hollow cube: the cut through the cavity leaves a square annulus
+        let mut hollow = cuboid([0.0, 0.0, 0.0], [4.0, 4.0, 4.0]);
+        hollow.extend(
+            cuboid([1.0, 1.0, 1.0], [3.0, 3.0, 3.0])
+                .into_iter()
+                .map(|t| Triangle::new(t.vertices[0], t.vertices[2], t.vertices[1])),
+        );
+        let piece = clip_half_space(&hollow, Axis::X, 2.0, true);
+        assert!((signed_volume(&piece) - 28.0).abs() < 1e-4);
+        assert!(validate_mesh(&piece).manifold.is_watertight());
+    }
+}
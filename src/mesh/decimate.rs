@@ -0,0 +1,458 @@
+//! Quadric edge-collapse decimation
+//!
+//! Repeatedly collapses the edge whose merged vertex strays least from the
+//! planes of the faces around it (Garland-Heckbert quadric error metric),
+//! until the mesh fits a face budget. Open edges get an extra constraint
+//! plane so outlines keep their shape, and collapses that would fold a face
+//! over or pinch the surface into a non-manifold shape are skipped.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use super::Triangle;
+use super::indexed::IndexedMesh;
+
+/// Weight of the planes that hold open edges in place, relative to faces
+const BOUNDARY_WEIGHT: f64 = 1000.0;
+
+/// Symmetric 4x4 plane quadric, stored as its upper triangle
+#[derive(Debug, Default, Clone, Copy)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// Quadric of the plane through `point` with unit `normal`, scaled by `weight`
+    fn plane(normal: [f64; 3], point: [f64; 3], weight: f64) -> Self {
+        let [a, b, c] = normal;
+        let d = -(a * point[0] + b * point[1] + c * point[2]);
+        Self(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|q| q * weight),
+        )
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (q, o) in self.0.iter_mut().zip(other.0) {
+            *q += o;
+        }
+    }
+
+    /// Weighted squared distance of `p` from the planes
+    fn error(&self, p: [f64; 3]) -> f64 {
+        let q = &self.0;
+        let [x, y, z] = p;
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+/// Candidate collapse in the queue; stale once either end has changed
+struct Candidate {
+    cost: f64,
+    keep: u32,
+    remove: u32,
+    target: [f64; 3],
+    versions: (u32, u32),
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost.total_cmp(&other.cost) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    // Reversed so the max-heap pops the cheapest collapse
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// Working state of a decimation run
+struct Decimator {
+    positions: Vec<[f64; 3]>,
+    quadrics: Vec<Quadric>,
+    versions: Vec<u32>,
+    faces: Vec<[u32; 3]>,
+    face_alive: Vec<bool>,
+    vertex_faces: Vec<Vec<usize>>,
+    live_faces: usize,
+}
+
+/// Collapse edges of `mesh` until it has at most `max_faces` faces
+///
+/// Returns the mesh unchanged if it is already within budget. The budget
+/// may not be reached if every remaining collapse would damage the surface.
+pub fn decimate(mesh: &IndexedMesh, max_faces: usize) -> IndexedMesh {
+    if mesh.faces.len() <= max_faces {
+        return mesh.clone();
+    }
+
+    let mut decimator = Decimator::new(mesh);
+    let mut queue = BinaryHeap::new();
+    for (a, b) in decimator.edges() {
+        if let Some(candidate) = decimator.candidate(a, b) {
+            queue.push(candidate);
+        }
+    }
+
+    while decimator.live_faces > max_faces {
+        let Some(candidate) = queue.pop() else {
+            break;
+        };
+        let (keep, remove) = (candidate.keep as usize, candidate.remove as usize);
+        if (decimator.versions[keep], decimator.versions[remove]) != candidate.versions {
+            continue;
+        }
+        if !decimator.collapse(keep, remove, candidate.target) {
+            continue;
+        }
+        for neighbor in decimator.neighbors(keep) {
+            if let Some(candidate) = decimator.candidate(keep as u32, neighbor) {
+                queue.push(candidate);
+            }
+        }
+    }
+
+    decimator.into_mesh()
+}
+
+/// Decimate a triangle soup to at most `max_faces` triangles
+///
+/// Only vertices with identical coordinates are welded, so separate pieces
+/// stay separate.
+pub fn decimate_triangles(triangles: Vec<Triangle>, max_faces: usize) -> Vec<Triangle> {
+    if triangles.len() <= max_faces {
+        return triangles;
+    }
+    decimate(&IndexedMesh::from_triangles(&triangles), max_faces).to_triangles()
+}
+
+impl Decimator {
+    fn new(mesh: &IndexedMesh) -> Self {
+        let positions: Vec<[f64; 3]> = mesh.vertices.iter().map(|v| v.map(|c| c as f64)).collect();
+        let mut quadrics = vec![Quadric::default(); positions.len()];
+        let mut vertex_faces = vec![Vec::new(); positions.len()];
+        let mut edge_faces: HashMap<(u32, u32), Vec<usize>> = HashMap::new();
+
+        for (f, face) in mesh.faces.iter().enumerate() {
+            let corners = face.map(|i| positions[i as usize]);
+            let (normal, area) = normal_and_area(corners);
+            let plane = Quadric::plane(normal, corners[0], area);
+            for i in 0..3 {
+                let v = face[i];
+                quadrics[v as usize].add(&plane);
+                vertex_faces[v as usize].push(f);
+                let w = face[(i + 1) % 3];
+                edge_faces.entry((v.min(w), v.max(w))).or_default().push(f);
+            }
+        }
+
+        // Open edges: a plane through the edge, upright to its face
+        for (&(a, b), faces) in &edge_faces {
+            if faces.len() != 1 {
+                continue;
+            }
+            let (pa, pb) = (positions[a as usize], positions[b as usize]);
+            let corners = mesh.faces[faces[0]].map(|i| positions[i as usize]);
+            let (face_normal, _) = normal_and_area(corners);
+            let edge = sub(pb, pa);
+            let length_sq = dot(edge, edge);
+            let Some(normal) = normalize(cross(edge, face_normal)) else {
+                continue;
+            };
+            let constraint = Quadric::plane(normal, pa, BOUNDARY_WEIGHT * length_sq);
+            quadrics[a as usize].add(&constraint);
+            quadrics[b as usize].add(&constraint);
+        }
+
+        Self {
+            versions: vec![0; positions.len()],
+            positions,
+            quadrics,
+            face_alive: vec![true; mesh.faces.len()],
+            faces: mesh.faces.clone(),
+            vertex_faces,
+            live_faces: mesh.faces.len(),
+        }
+    }
+
+    /// Unique edges of the live faces
+    fn edges(&self) -> Vec<(u32, u32)> {
+        let mut edges: Vec<(u32, u32)> = self
+            .faces
+            .iter()
+            .flat_map(|face| {
+                (0..3).map(move |i| {
+                    let (a, b) = (face[i], face[(i + 1) % 3]);
+                    (a.min(b), a.max(b))
+                })
+            })
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+        edges
+    }
+
+    fn neighbors(&self, v: usize) -> Vec<u32> {
+        let mut neighbors: Vec<u32> = self.vertex_faces[v]
+            .iter()
+            .flat_map(|&f| self.faces[f])
+            .filter(|&n| n as usize != v)
+            .collect();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        neighbors
+    }
+
+    /// Cheapest way to merge `a` and `b`: onto either end or their midpoint
+    fn candidate(&self, a: u32, b: u32) -> Option<Candidate> {
+        if a == b {
+            return None;
+        }
+        let (ia, ib) = (a as usize, b as usize);
+        let mut quadric = self.quadrics[ia];
+        quadric.add(&self.quadrics[ib]);
+
+        let (pa, pb) = (self.positions[ia], self.positions[ib]);
+        let mid = [0, 1, 2].map(|i| (pa[i] + pb[i]) / 2.0);
+        let (target, cost) = [pa, pb, mid]
+            .into_iter()
+            .map(|p| (p, quadric.error(p)))
+            .min_by(|x, y| x.1.total_cmp(&y.1))?;
+
+        Some(Candidate {
+            cost,
+            keep: a,
+            remove: b,
+            target,
+            versions: (self.versions[ia], self.versions[ib]),
+        })
+    }
+
+    /// Merge `remove` into `keep` at `target`; false if that would damage
+    /// the surface
+    fn collapse(&mut self, keep: usize, remove: usize, target: [f64; 3]) -> bool {
+        let shared: Vec<usize> = self.vertex_faces[keep]
+            .iter()
+            .copied()
+            .filter(|&f| self.faces[f].contains(&(remove as u32)))
+            .collect();
+        if shared.is_empty() || shared.len() > 2 {
+            return false;
+        }
+
+        // Link condition: the ends may only share the vertices opposite the
+        // edge, or the surface pinches into a non-manifold shape
+        let keep_neighbors = self.neighbors(keep);
+        let common = self
+            .neighbors(remove)
+            .iter()
+            .filter(|n| keep_neighbors.binary_search(n).is_ok())
+            .count();
+        if common != shared.len() {
+            return false;
+        }
+
+        // No surviving face may fold over or collapse to nothing
+        for &f in self.vertex_faces[keep]
+            .iter()
+            .chain(&self.vertex_faces[remove])
+        {
+            if shared.contains(&f) {
+                continue;
+            }
+            let before = self.faces[f].map(|i| self.positions[i as usize]);
+            let after = self.faces[f].map(|i| {
+                if i as usize == keep || i as usize == remove {
+                    target
+                } else {
+                    self.positions[i as usize]
+                }
+            });
+            let (n_before, _) = normal_and_area(before);
+            let (n_after, area_after) = normal_and_area(after);
+            if area_after <= 0.0 || dot(n_before, n_after) <= 0.0 {
+                return false;
+            }
+        }
+
+        for &f in &shared {
+            self.face_alive[f] = false;
+            self.live_faces -= 1;
+        }
+        let moved = std::mem::take(&mut self.vertex_faces[remove]);
+        for f in moved {
+            if !self.face_alive[f] {
+                continue;
+            }
+            for i in self.faces[f].iter_mut() {
+                if *i as usize == remove {
+                    *i = keep as u32;
+                }
+            }
+            self.vertex_faces[keep].push(f);
+        }
+        let alive = &self.face_alive;
+        self.vertex_faces[keep].retain(|&f| alive[f]);
+        for &f in &shared {
+            for v in self.faces[f] {
+                let alive = &self.face_alive;
+                self.vertex_faces[v as usize].retain(|&g| alive[g]);
+            }
+        }
+
+        let removed_quadric = self.quadrics[remove];
+        self.quadrics[keep].add(&removed_quadric);
+        self.positions[keep] = target;
+        self.versions[keep] += 1;
+        self.versions[remove] += 1;
+        true
+    }
+
+    /// Live faces over the vertices they still use
+    fn into_mesh(self) -> IndexedMesh {
+        let mut remap = vec![u32::MAX; self.positions.len()];
+        let mut mesh = IndexedMesh::default();
+        for (face, _) in self
+            .faces
+            .iter()
+            .zip(&self.face_alive)
+            .filter(|&(_, &alive)| alive)
+        {
+            let indices = face.map(|v| {
+                let slot = &mut remap[v as usize];
+                if *slot == u32::MAX {
+                    *slot = mesh.vertices.len() as u32;
+                    mesh.vertices
+                        .push(self.positions[v as usize].map(|c| c as f32));
+                }
+                *slot
+            });
+            mesh.faces.push(indices);
+        }
+        mesh
+    }
+}
+
+fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f64; 3]) -> Option<[f64; 3]> {
+    let len = dot(v, v).sqrt();
+    (len > 1e-12).then(|| v.map(|c| c / len))
+}
+
+/// Unit normal and area of a triangle; zero area for degenerate ones
+fn normal_and_area([a, b, c]: [[f64; 3]; 3]) -> ([f64; 3], f64) {
+    let n = cross(sub(b, a), sub(c, a));
+    match normalize(n) {
+        Some(unit) => (unit, dot(n, n).sqrt() / 2.0),
+        None => ([0.0, 0.0, 1.0], 0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flat `n` x `n` grid of unit squares at z=0
+    fn grid(n: usize) -> IndexedMesh {
+        let mut triangles = Vec::new();
+        for i in 0..n {
+            for j in 0..n {
+                let (x, y) = (i as f32, j as f32);
+                triangles.push(Triangle::new(
+                    [x, y, 0.0],
+                    [x + 1.0, y, 0.0],
+                    [x + 1.0, y + 1.0, 0.0],
+                ));
+                triangles.push(Triangle::new(
+                    [x, y, 0.0],
+                    [x + 1.0, y + 1.0, 0.0],
+                    [x, y + 1.0, 0.0],
+                ));
+            }
+        }
+        IndexedMesh::from_triangles(&triangles)
+    }
+
+    #[test]
+    fn test_decimate_keeps_outline() {
+        let mesh = grid(10);
+        assert_eq!(mesh.faces.len(), 200);
+
+        let decimated = decimate(&mesh, 20);
+        assert!(decimated.faces.len() <= 20);
+        assert_eq!(decimated.bounds(), mesh.bounds());
+        assert!(decimated.vertices.iter().all(|v| v[2] == 0.0));
+
+        // Every face still points up
+        for face in &decimated.faces {
+            let [a, b, c] = face.map(|i| decimated.vertices[i as usize].map(|c| c as f64));
+            assert!(cross(sub(b, a), sub(c, a))[2] > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_decimate_under_budget_is_unchanged() {
+        let mesh = grid(3);
+        let decimated = decimate(&mesh, 100);
+        assert_eq!(decimated.faces, mesh.faces);
+        assert_eq!(decimated.vertices, mesh.vertices);
+    }
+
+    #[test]
+    fn test_decimate_closed_ribbon_stays_closed() {
+        use crate::mesh::ribbon::extrude_ribbon;
+        use crate::mesh::validation::check_manifold;
+
+        let points: Vec<(f32, f32)> = (0..40).map(|i| (i as f32, 0.0)).collect();
+        let mesh = IndexedMesh::from_triangles(&extrude_ribbon(&points, 2.0, 1.0, 0.0));
+        assert!(mesh.faces.len() > 300);
+
+        let decimated = decimate(&mesh, 40);
+        assert!(decimated.faces.len() <= 40);
+        assert_eq!(decimated.bounds(), mesh.bounds());
+        assert!(check_manifold(&decimated.to_triangles()).is_watertight());
+    }
+}
@@ -1,5 +1,10 @@
+use geo::{LineString, MultiPolygon};
+use rayon::prelude::*;
+use std::borrow::Cow;
+
 use super::Triangle;
 use super::triangulation::triangulate_polygon;
+use crate::geometry::signed_area;
 
 pub fn extrude_polygon(
     outer: &[(f32, f32)],
@@ -21,14 +26,21 @@ pub fn extrude_polygon_ex(
         return Vec::new();
     }
 
+    // OSM rings come in either direction; the walls below and the top and
+    // bottom windings assume a counterclockwise outer ring with clockwise holes
+    let outer = oriented(outer, true);
+    let holes: Vec<Cow<[(f32, f32)]>> = holes.iter().map(|h| oriented(h, false)).collect();
+    let outer = outer.as_ref();
+
     let mut triangles = Vec::new();
 
     let mut all_points: Vec<(f32, f32)> = outer.to_vec();
-    for hole in holes {
+    for hole in &holes {
         all_points.extend(hole.iter().copied());
     }
 
-    let indices = triangulate_polygon(outer, holes);
+    let hole_rings: Vec<Vec<(f32, f32)>> = holes.iter().map(|h| h.to_vec()).collect();
+    let indices = triangulate_polygon(outer, &hole_rings);
 
     if indices.is_empty() {
         return Vec::new();
@@ -59,43 +71,45 @@ pub fn extrude_polygon_ex(
 
     add_side_walls(&mut triangles, outer, z_bottom, z_top);
 
-    for hole in holes {
-        add_side_walls_reversed(&mut triangles, hole, z_bottom, z_top);
+    for hole in &holes {
+        add_side_walls(&mut triangles, hole, z_bottom, z_top);
     }
 
     triangles
 }
 
-fn add_side_walls(triangles: &mut Vec<Triangle>, ring: &[(f32, f32)], z_bottom: f32, z_top: f32) {
-    let n = ring.len();
-    if n < 3 {
-        return;
-    }
-
-    for i in 0..n {
-        let p1 = ring[i];
-        let p2 = ring[(i + 1) % n];
-
-        triangles.push(Triangle::new(
-            [p1.0, p1.1, z_bottom],
-            [p2.0, p2.1, z_bottom],
-            [p2.0, p2.1, z_top],
-        ));
+/// Extrude every polygon of a footprint, holes included, between `z_bottom`
+/// and `z_top`
+pub fn extrude_multipolygon(
+    footprint: &MultiPolygon<f64>,
+    z_bottom: f32,
+    z_top: f32,
+) -> Vec<Triangle> {
+    // Rings come back closed; the extrusion wants them open
+    let open = |ring: &LineString<f64>| -> Vec<(f32, f32)> {
+        let points = &ring.0[..ring.0.len().saturating_sub(1)];
+        points.iter().map(|c| (c.x as f32, c.y as f32)).collect()
+    };
+    footprint
+        .0
+        .par_iter()
+        .flat_map_iter(|polygon| {
+            let holes: Vec<Vec<(f32, f32)>> = polygon.interiors().iter().map(open).collect();
+            extrude_polygon(&open(polygon.exterior()), &holes, z_bottom, z_top)
+        })
+        .collect()
+}
 
-        triangles.push(Triangle::new(
-            [p1.0, p1.1, z_bottom],
-            [p2.0, p2.1, z_top],
-            [p1.0, p1.1, z_top],
-        ));
+/// `ring` wound counterclockwise when `ccw`, otherwise clockwise
+fn oriented(ring: &[(f32, f32)], ccw: bool) -> Cow<'_, [(f32, f32)]> {
+    if (signed_area(ring) > 0.0) == ccw {
+        Cow::Borrowed(ring)
+    } else {
+        Cow::Owned(ring.iter().rev().copied().collect())
     }
 }
 
-fn add_side_walls_reversed(
-    triangles: &mut Vec<Triangle>,
-    ring: &[(f32, f32)],
-    z_bottom: f32,
-    z_top: f32,
-) {
+fn add_side_walls(triangles: &mut Vec<Triangle>, ring: &[(f32, f32)], z_bottom: f32, z_top: f32) {
     let n = ring.len();
     if n < 3 {
         return;
@@ -107,14 +121,14 @@ fn add_side_walls_reversed(
 
         triangles.push(Triangle::new(
             [p1.0, p1.1, z_bottom],
-            [p2.0, p2.1, z_top],
             [p2.0, p2.1, z_bottom],
+            [p2.0, p2.1, z_top],
         ));
 
         triangles.push(Triangle::new(
             [p1.0, p1.1, z_bottom],
-            [p1.0, p1.1, z_top],
             [p2.0, p2.1, z_top],
+            [p1.0, p1.1, z_top],
         ));
     }
 }
@@ -122,6 +136,7 @@ fn add_side_walls_reversed(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::mesh::test_util::signed_volume;
 
     #[test]
     fn test_extrude_square() {
@@ -136,4 +151,35 @@ mod tests {
         let triangles = extrude_polygon(&empty, &[], 0.0, 1.0);
         assert!(triangles.is_empty());
     }
+
+    #[test]
+    fn test_extrude_normalizes_winding() {
+        // Concave L, 10x10 less a 5x5 corner, with a 1x1 hole
+        let ccw = vec![
+            (0.0, 0.0),
+            (10.0, 0.0),
+            (10.0, 5.0),
+            (5.0, 5.0),
+            (5.0, 10.0),
+            (0.0, 10.0),
+        ];
+        let hole_cw = vec![(1.0, 1.0), (1.0, 2.0), (2.0, 2.0), (2.0, 1.0)];
+        let cw: Vec<_> = ccw.iter().rev().copied().collect();
+        let hole_ccw: Vec<_> = hole_cw.iter().rev().copied().collect();
+
+        for (outer, hole) in [(&ccw, &hole_cw), (&cw, &hole_ccw), (&cw, &hole_cw)] {
+            let triangles = extrude_polygon(outer, std::slice::from_ref(hole), 0.0, 2.0);
+            let report = crate::mesh::validation::check_manifold(&triangles);
+            assert!(report.is_watertight());
+            assert!((signed_volume(&triangles) - 74.0 * 2.0).abs() < 1e-3);
+            for t in &triangles {
+                let z = t.vertices.map(|v| v[2]);
+                if z == [2.0; 3] {
+                    assert!(t.normal[2] > 0.0);
+                } else if z == [0.0; 3] {
+                    assert!(t.normal[2] < 0.0);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,282 @@
+//! Binary glTF (GLB) export for web previews
+//!
+//! Produces a single mesh with one primitive per feature layer, each with its
+//! own base color material. Model coordinates are Z-up millimeters; the root
+//! node rotates to glTF's Y-up convention and scales to meters so the map
+//! drops straight into Three.js or a `<model-viewer>` element.
+
+use anyhow::{Context, Result};
+use serde_json::{Value, json};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::Triangle;
+use super::indexed::IndexedMesh;
+use super::palette::layer_color;
+
+const GLB_MAGIC: u32 = 0x4654_6C67; // "glTF"
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A; // "JSON"
+const CHUNK_BIN: u32 = 0x004E_4942; // "BIN\0"
+
+// glTF enums
+const COMPONENT_FLOAT: u32 = 5126;
+const COMPONENT_UNSIGNED_INT: u32 = 5125;
+const TARGET_ARRAY_BUFFER: u32 = 34962;
+const TARGET_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// Write named triangle layers to a binary glTF (.glb) file
+///
+/// # Arguments
+/// * `path` - Output file path
+/// * `layers` - (layer name, triangles) pairs; empty layers are skipped
+pub fn write_glb(path: &Path, layers: &[(&str, &[Triangle])]) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create GLB file: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    write_glb_to(&mut writer, layers)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Write a GLB container to any writer
+pub fn write_glb_to<W: Write>(writer: &mut W, layers: &[(&str, &[Triangle])]) -> Result<()> {
+    let (document, mut bin) = build_document(layers);
+
+    let mut json_bytes = serde_json::to_vec(&document).context("Failed to serialize glTF JSON")?;
+    pad_to_four(&mut json_bytes, b' ');
+    pad_to_four(&mut bin, 0);
+
+    let has_bin = !bin.is_empty();
+    let mut total_len = 12 + 8 + json_bytes.len();
+    if has_bin {
+        total_len += 8 + bin.len();
+    }
+
+    writer.write_all(&GLB_MAGIC.to_le_bytes())?;
+    writer.write_all(&GLB_VERSION.to_le_bytes())?;
+    writer.write_all(&(total_len as u32).to_le_bytes())?;
+
+    writer.write_all(&(json_bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&CHUNK_JSON.to_le_bytes())?;
+    writer.write_all(&json_bytes)?;
+
+    if has_bin {
+        writer.write_all(&(bin.len() as u32).to_le_bytes())?;
+        writer.write_all(&CHUNK_BIN.to_le_bytes())?;
+        writer.write_all(&bin)?;
+    }
+
+    Ok(())
+}
+
+/// Build the glTF JSON document and its binary buffer
+fn build_document(layers: &[(&str, &[Triangle])]) -> (Value, Vec<u8>) {
+    let mut bin: Vec<u8> = Vec::new();
+    let mut buffer_views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut materials = Vec::new();
+    let mut primitives = Vec::new();
+
+    for (name, triangles) in layers {
+        if triangles.is_empty() {
+            continue;
+        }
+        let mesh = IndexedMesh::from_triangles(triangles);
+        let Some((min, max)) = mesh.bounds() else {
+            continue;
+        };
+
+        // Positions
+        let position_offset = bin.len();
+        for vertex in &mesh.vertices {
+            for &c in vertex {
+                bin.extend_from_slice(&c.to_le_bytes());
+            }
+        }
+        buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": position_offset,
+            "byteLength": bin.len() - position_offset,
+            "target": TARGET_ARRAY_BUFFER,
+        }));
+        let position_accessor = accessors.len();
+        accessors.push(json!({
+            "bufferView": buffer_views.len() - 1,
+            "componentType": COMPONENT_FLOAT,
+            "count": mesh.vertices.len(),
+            "type": "VEC3",
+            "min": min,
+            "max": max,
+        }));
+
+        // Indices (positions are 12-byte aligned, so no padding is needed)
+        let index_offset = bin.len();
+        for face in &mesh.faces {
+            for &i in face {
+                bin.extend_from_slice(&i.to_le_bytes());
+            }
+        }
+        buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": index_offset,
+            "byteLength": bin.len() - index_offset,
+            "target": TARGET_ELEMENT_ARRAY_BUFFER,
+        }));
+        let index_accessor = accessors.len();
+        accessors.push(json!({
+            "bufferView": buffer_views.len() - 1,
+            "componentType": COMPONENT_UNSIGNED_INT,
+            "count": mesh.faces.len() * 3,
+            "type": "SCALAR",
+        }));
+
+        let [r, g, b] = layer_color(name).map(srgb_to_linear);
+        materials.push(json!({
+            "name": name,
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [r, g, b, 1.0],
+                "metallicFactor": 0.0,
+                "roughnessFactor": 0.8,
+            },
+        }));
+
+        primitives.push(json!({
+            "attributes": { "POSITION": position_accessor },
+            "indices": index_accessor,
+            "material": materials.len() - 1,
+        }));
+    }
+
+    // glTF arrays may not be empty, so a map with nothing in it is a bare
+    // scene rather than a mesh without primitives
+    if primitives.is_empty() {
+        let document = json!({
+            "asset": { "version": "2.0", "generator": "mapto3d" },
+            "scene": 0,
+            "scenes": [{}],
+        });
+        return (document, bin);
+    }
+
+    let half_sqrt2 = std::f32::consts::FRAC_1_SQRT_2;
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "mapto3d" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{
+            "name": "map",
+            "mesh": 0,
+            // -90 degrees about X: model Z-up -> glTF Y-up
+            "rotation": [-half_sqrt2, 0.0, 0.0, half_sqrt2],
+            // Model units are mm, glTF units are meters
+            "scale": [0.001, 0.001, 0.001],
+        }],
+        "meshes": [{ "name": "map", "primitives": primitives }],
+        "materials": materials,
+        "accessors": accessors,
+        "bufferViews": buffer_views,
+    });
+
+    if !bin.is_empty() {
+        document["buffers"] = json!([{ "byteLength": bin.len() }]);
+    }
+
+    (document, bin)
+}
+
+/// Convert an 8-bit sRGB channel to the linear factor glTF expects
+fn srgb_to_linear(channel: u8) -> f32 {
+    let c = channel as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn pad_to_four(bytes: &mut Vec<u8>, fill: u8) {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(fill);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_glb_header_and_json() {
+        let base = vec![
+            Triangle::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]),
+            Triangle::new([0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]),
+        ];
+        let roads = vec![Triangle::new(
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+        )];
+
+        let mut buffer: Vec<u8> = Vec::new();
+        write_glb_to(
+            &mut buffer,
+            &[("base", &base), ("water", &[]), ("roads", &roads)],
+        )
+        .unwrap();
+
+        let read_u32 = |at: usize| u32::from_le_bytes(buffer[at..at + 4].try_into().unwrap());
+        assert_eq!(&buffer[0..4], b"glTF");
+        assert_eq!(read_u32(4), 2);
+        assert_eq!(read_u32(8) as usize, buffer.len());
+
+        let json_len = read_u32(12) as usize;
+        assert_eq!(read_u32(16), CHUNK_JSON);
+        let document: Value = serde_json::from_slice(&buffer[20..20 + json_len]).unwrap();
+
+        assert_eq!(document["asset"]["version"], "2.0");
+        assert_eq!(
+            document["meshes"][0]["primitives"]
+                .as_array()
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(document["materials"][1]["name"], "roads");
+        assert_eq!(document["accessors"][0]["count"], 4);
+
+        let bin_len = read_u32(20 + json_len) as usize;
+        assert_eq!(read_u32(24 + json_len), CHUNK_BIN);
+        assert_eq!(document["buffers"][0]["byteLength"], bin_len);
+    }
+
+    #[test]
+    fn test_write_glb_all_layers_empty() {
+        let mut buffer: Vec<u8> = Vec::new();
+        write_glb_to(&mut buffer, &[("base", &[]), ("roads", &[])]).unwrap();
+
+        let json_len = u32::from_le_bytes(buffer[12..16].try_into().unwrap()) as usize;
+        let document: Value = serde_json::from_slice(&buffer[20..20 + json_len]).unwrap();
+        assert_eq!(document["scenes"][0], json!({}));
+        for key in [
+            "nodes",
+            "meshes",
+            "materials",
+            "accessors",
+            "bufferViews",
+            "buffers",
+        ] {
+            assert!(document.get(key).is_none(), "{key} should be left out");
+        }
+        assert_eq!(buffer.len(), 20 + json_len);
+    }
+
+    #[test]
+    fn test_srgb_to_linear() {
+        assert_eq!(srgb_to_linear(0), 0.0);
+        assert!((srgb_to_linear(255) - 1.0).abs() < 1e-6);
+        assert!((srgb_to_linear(128) - 0.2158).abs() < 0.001);
+    }
+}
@@ -0,0 +1,121 @@
+//! Indexed (welded) mesh representation
+//!
+//! STL stores every triangle with its own copy of each vertex. Formats like
+//! OBJ and glTF reference a shared vertex list instead, so coincident vertices
+//! are welded together by their exact bit pattern.
+
+use std::collections::HashMap;
+
+use super::Triangle;
+
+/// A mesh with a shared, deduplicated vertex list
+#[derive(Debug, Default, Clone)]
+pub struct IndexedMesh {
+    /// Unique vertices [x, y, z]
+    pub vertices: Vec<[f32; 3]>,
+    /// Triangle faces as indices into `vertices`
+    pub faces: Vec<[u32; 3]>,
+}
+
+impl IndexedMesh {
+    /// Weld the vertices of a triangle soup into an indexed mesh
+    pub fn from_triangles(triangles: &[Triangle]) -> Self {
+        let mut mesh = Self::default();
+        let mut lookup: HashMap<[u32; 3], u32> = HashMap::new();
+
+        for tri in triangles {
+            let mut face = [0u32; 3];
+            for (slot, vertex) in face.iter_mut().zip(tri.vertices.iter()) {
+                *slot = mesh.insert_vertex(&mut lookup, *vertex);
+            }
+            mesh.faces.push(face);
+        }
+
+        mesh
+    }
+
+    fn insert_vertex(&mut self, lookup: &mut HashMap<[u32; 3], u32>, vertex: [f32; 3]) -> u32 {
+        let key = vertex_key(vertex);
+        *lookup.entry(key).or_insert_with(|| {
+            self.vertices.push(vertex);
+            (self.vertices.len() - 1) as u32
+        })
+    }
+
+    /// Expand back into a triangle soup, with normals from the winding
+    pub fn to_triangles(&self) -> Vec<Triangle> {
+        self.faces
+            .iter()
+            .map(|face| {
+                let [a, b, c] = face.map(|i| self.vertices[i as usize]);
+                Triangle::new(a, b, c)
+            })
+            .collect()
+    }
+
+    /// Axis-aligned bounds as (min, max), or `None` for an empty mesh
+    pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        let first = *self.vertices.first()?;
+        let mut min = first;
+        let mut max = first;
+        for v in &self.vertices {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(v[axis]);
+                max[axis] = max[axis].max(v[axis]);
+            }
+        }
+        Some((min, max))
+    }
+}
+
+/// Hashable key for a vertex; `-0.0` is folded into `0.0` so they weld together
+fn vertex_key(vertex: [f32; 3]) -> [u32; 3] {
+    vertex.map(|c| {
+        if c == 0.0 {
+            0.0f32.to_bits()
+        } else {
+            c.to_bits()
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weld_shared_vertices() {
+        let triangles = vec![
+            Triangle::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]),
+            Triangle::new([0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]),
+        ];
+        let mesh = IndexedMesh::from_triangles(&triangles);
+
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.faces, vec![[0, 1, 2], [0, 2, 3]]);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let triangles = vec![Triangle::new(
+            [0.0, -1.0, 0.0],
+            [2.0, 0.0, 0.5],
+            [0.0, 1.0, 3.0],
+        )];
+        let mesh = IndexedMesh::from_triangles(&triangles);
+
+        assert_eq!(mesh.bounds(), Some(([0.0, -1.0, 0.0], [2.0, 1.0, 3.0])));
+        assert_eq!(IndexedMesh::default().bounds(), None);
+    }
+
+    #[test]
+    fn test_negative_zero_welds() {
+        let triangles = vec![
+            Triangle::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            Triangle::new([-0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [-1.0, 0.0, 0.0]),
+        ];
+        let mesh = IndexedMesh::from_triangles(&triangles);
+
+        assert_eq!(mesh.vertices.len(), 4);
+    }
+}
@@ -1,12 +1,26 @@
 pub mod builder;
+pub mod clip;
+pub mod decimate;
 pub mod extrusion;
+pub mod gltf;
+pub mod indexed;
+pub mod obj;
+pub mod palette;
 pub mod ribbon;
 pub mod stl;
+#[cfg(test)]
+pub(crate) mod test_util;
+pub mod threemf;
+pub mod tiles;
 pub mod triangulation;
 pub mod validation;
 
-pub use builder::Triangle;
-pub use extrusion::{extrude_polygon, extrude_polygon_ex};
-pub use ribbon::extrude_ribbon_ex;
-pub use stl::write_stl;
-pub use validation::validate_and_fix;
+pub use builder::{Triangle, sort_triangles};
+pub use decimate::decimate_triangles;
+pub use extrusion::{extrude_multipolygon, extrude_polygon, extrude_polygon_ex};
+pub use gltf::write_glb;
+pub use obj::write_obj;
+pub use ribbon::{RibbonOptions, RibbonProfile, extrude_ribbon_ex, ribbon_footprint};
+pub use stl::{StlFormat, write_stl, write_stl_ascii, write_stl_gz};
+pub use threemf::write_3mf;
+pub use validation::{DEFAULT_MIN_TRIANGLE_AREA, min_triangle_area, validate_and_fix};
@@ -0,0 +1,94 @@
+use super::Triangle;
+use super::indexed::IndexedMesh;
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Write named triangle layers to a Wavefront OBJ file
+///
+/// Vertices are welded into a single shared `v` list and each layer is
+/// emitted as its own `g <name>` group, so features stay separable when the
+/// file is opened in Blender or MeshLab.
+///
+/// # Arguments
+/// * `path` - Output file path
+/// * `layers` - (group name, triangles) pairs, written in order
+pub fn write_obj(path: &Path, layers: &[(&str, &[Triangle])]) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create OBJ file: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    let all_triangles: Vec<Triangle> = layers
+        .iter()
+        .flat_map(|(_, triangles)| triangles.iter().cloned())
+        .collect();
+    let mesh = IndexedMesh::from_triangles(&all_triangles);
+
+    writeln!(writer, "# mapto3d - City Map OBJ Export")?;
+    writeln!(
+        writer,
+        "# {} vertices, {} faces",
+        mesh.vertices.len(),
+        mesh.faces.len()
+    )?;
+
+    for [x, y, z] in &mesh.vertices {
+        writeln!(writer, "v {} {} {}", x, y, z)?;
+    }
+
+    // Faces were welded in layer order, so each layer owns a contiguous run
+    let mut face_offset = 0;
+    for (name, triangles) in layers {
+        if triangles.is_empty() {
+            continue;
+        }
+        writeln!(writer, "g {}", name)?;
+        for face in &mesh.faces[face_offset..face_offset + triangles.len()] {
+            // OBJ indices are 1-based
+            writeln!(writer, "f {} {} {}", face[0] + 1, face[1] + 1, face[2] + 1)?;
+        }
+        face_offset += triangles.len();
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_write_obj_groups() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.obj");
+
+        let base = vec![
+            Triangle::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]),
+            Triangle::new([0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]),
+        ];
+        let roads = vec![Triangle::new(
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+        )];
+
+        write_obj(&path, &[("base", &base), ("water", &[]), ("roads", &roads)]).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let vertex_count = contents.lines().filter(|l| l.starts_with("v ")).count();
+        let face_count = contents.lines().filter(|l| l.starts_with("f ")).count();
+        let groups: Vec<&str> = contents
+            .lines()
+            .filter_map(|l| l.strip_prefix("g "))
+            .collect();
+
+        assert_eq!(vertex_count, 5);
+        assert_eq!(face_count, 3);
+        assert_eq!(groups, vec!["base", "roads"]);
+    }
+}
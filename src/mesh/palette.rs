@@ -0,0 +1,55 @@
+//! Default per-layer colors for formats that carry color information
+
+/// sRGB color for a feature layer, matching the "Classic" palette from the
+/// color change guide: white base and terrain, blue water, green parks, gray
+/// roads, black text. Buildings get a warm sand tone, railways a rust brown,
+/// contours an earthy brown and aeroways a dark slate; the frame, boundary and
+/// back text match the text. Unknown layers fall back to a neutral light gray.
+pub fn layer_color(layer: &str) -> [u8; 3] {
+    match layer {
+        "base" | "terrain" => [0xF5, 0xF5, 0xF5],
+        "water" => [0x3A, 0x7C, 0xC9],
+        "parks" | "custom" => [0x4C, 0xA6, 0x4C],
+        "buildings" => [0xD9, 0xC7, 0xA7],
+        "railways" => [0x8B, 0x4A, 0x2B],
+        "aeroways" => [0x4A, 0x50, 0x58],
+        "contours" => [0x9C, 0x6B, 0x3E],
+        "roads" => [0x80, 0x80, 0x80],
+        "text" | "back_text" | "frame" | "grid" | "boundary" => [0x1A, 0x1A, 0x1A],
+        _ => [0xC0, 0xC0, 0xC0],
+    }
+}
+
+/// Format a color as an uppercase `#RRGGBB` hex string
+pub fn to_hex(color: [u8; 3]) -> String {
+    format!("#{:02X}{:02X}{:02X}", color[0], color[1], color[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_hex() {
+        assert_eq!(to_hex([0xFF, 0x00, 0x80]), "#FF0080");
+    }
+
+    #[test]
+    fn test_layer_colors_distinct() {
+        let names = [
+            "base",
+            "water",
+            "parks",
+            "buildings",
+            "railways",
+            "aeroways",
+            "roads",
+            "text",
+        ];
+        for (i, a) in names.iter().enumerate() {
+            for b in &names[i + 1..] {
+                assert_ne!(layer_color(a), layer_color(b));
+            }
+        }
+    }
+}
@@ -1,4 +1,105 @@
 use super::Triangle;
+use serde::Deserialize;
+
+/// Number of edges in the half circle of a rounded end cap
+const ROUND_CAP_SEGMENTS: usize = 8;
+
+/// Top width of a trapezoid profile as a fraction of the base width
+const TRAPEZOID_TOP_RATIO: f32 = 0.5;
+
+/// Height of the vertical walls under a rounded top, as a fraction of the
+/// ribbon height
+const ROUNDED_WALL_RATIO: f32 = 0.5;
+
+/// Number of edges across a rounded top; even, so the crown has a vertex
+const ROUNDED_TOP_SEGMENTS: usize = 6;
+
+/// Cross-section shape of an extruded ribbon
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RibbonProfile {
+    /// Vertical walls and a flat top
+    #[default]
+    Flat,
+    /// Walls sloping in to a top narrower than the base
+    #[serde(alias = "trapezoidal")]
+    Trapezoid,
+    /// Short vertical walls under a half-ellipse top
+    #[serde(alias = "round")]
+    Rounded,
+}
+
+impl std::str::FromStr for RibbonProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "flat" => Ok(RibbonProfile::Flat),
+            "trapezoid" | "trapezoidal" => Ok(RibbonProfile::Trapezoid),
+            "rounded" | "round" => Ok(RibbonProfile::Rounded),
+            _ => Err(format!(
+                "Invalid profile '{}'. Valid options: flat, trapezoid, rounded",
+                s
+            )),
+        }
+    }
+}
+
+impl RibbonProfile {
+    /// Cross-section outline from the left base corner over the top to the
+    /// right base corner, as (offset, height) fractions of the half width
+    /// and the ribbon height
+    fn outline(self) -> Vec<(f32, f32)> {
+        match self {
+            RibbonProfile::Flat => vec![(-1.0, 0.0), (-1.0, 1.0), (1.0, 1.0), (1.0, 0.0)],
+            RibbonProfile::Trapezoid => vec![
+                (-1.0, 0.0),
+                (-TRAPEZOID_TOP_RATIO, 1.0),
+                (TRAPEZOID_TOP_RATIO, 1.0),
+                (1.0, 0.0),
+            ],
+            RibbonProfile::Rounded => {
+                let mut outline = vec![(-1.0, 0.0), (-1.0, ROUNDED_WALL_RATIO)];
+                outline.extend((1..ROUNDED_TOP_SEGMENTS).map(|k| {
+                    if 2 * k == ROUNDED_TOP_SEGMENTS {
+                        return (0.0, 1.0);
+                    }
+                    let angle =
+                        std::f32::consts::PI * (1.0 - k as f32 / ROUNDED_TOP_SEGMENTS as f32);
+                    let (sin, cos) = angle.sin_cos();
+                    (cos, ROUNDED_WALL_RATIO + (1.0 - ROUNDED_WALL_RATIO) * sin)
+                }));
+                outline.push((1.0, ROUNDED_WALL_RATIO));
+                outline.push((1.0, 0.0));
+                outline
+            }
+        }
+    }
+}
+
+/// Faces and shape of an extruded ribbon
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RibbonOptions {
+    /// Generate bottom faces; if false, create an open-bottom shell
+    pub include_bottom: bool,
+    /// Close the ribbon at both ends
+    pub include_end_caps: bool,
+    /// End caps are half circles of the ribbon width instead of flat faces
+    pub round_caps: bool,
+    /// Cross-section shape
+    pub profile: RibbonProfile,
+}
+
+impl Default for RibbonOptions {
+    fn default() -> Self {
+        Self {
+            include_bottom: true,
+            include_end_caps: true,
+            round_caps: false,
+            profile: RibbonProfile::Flat,
+        }
+    }
+}
 
 /// Extrude a 2D polyline into a 3D ribbon mesh
 ///
@@ -20,18 +121,17 @@ pub fn extrude_ribbon(
     height: f32,
     base_z: f32,
 ) -> Vec<Triangle> {
-    extrude_ribbon_ex(points, width, height, base_z, true, true)
+    extrude_ribbon_ex(points, width, height, base_z, &RibbonOptions::default())
 }
 
 /// Extrude a 2D polyline into a 3D ribbon mesh with control over faces
 ///
 /// # Arguments
 /// * `points` - 2D points in mm [(x, y), ...]
-/// * `width` - Ribbon width in mm
+/// * `width` - Ribbon width in mm, at the base for sloped profiles
 /// * `height` - Ribbon height in mm
 /// * `base_z` - Base Z level in mm
-/// * `include_bottom` - If true, generate bottom faces; if false, create open-bottom shell
-/// * `include_end_caps` - If true, generate end cap faces
+/// * `options` - Which faces to generate and the cross-section shape
 ///
 /// # Returns
 /// Vector of triangles forming the ribbon mesh
@@ -40,8 +140,7 @@ pub fn extrude_ribbon_ex(
     width: f32,
     height: f32,
     base_z: f32,
-    include_bottom: bool,
-    include_end_caps: bool,
+    options: &RibbonOptions,
 ) -> Vec<Triangle> {
     if points.len() < 2 {
         return Vec::new();
@@ -49,14 +148,131 @@ pub fn extrude_ribbon_ex(
 
     let mut triangles = Vec::new();
     let half_width = width / 2.0;
-    let top_z = base_z + height;
+    let outline = options.profile.outline();
 
-    // Generate left and right edge points for each input point
-    let edges: Vec<([f32; 2], [f32; 2])> = points
+    // Cross-section vertices for each input point
+    let sections: Vec<Vec<[f32; 3]>> = points
         .iter()
-        .enumerate()
-        .map(|(i, &(x, y))| {
-            // Calculate direction at this point
+        .zip(perpendiculars(points))
+        .map(|(&(x, y), (px, py))| {
+            outline
+                .iter()
+                .map(|&(offset, z)| {
+                    [
+                        x + px * half_width * offset,
+                        y + py * half_width * offset,
+                        base_z + height * z,
+                    ]
+                })
+                .collect()
+        })
+        .collect();
+
+    // Walk the outline edges, closing it along the base if there is a bottom
+    let mut outline_edges: Vec<(usize, usize)> =
+        (0..outline.len() - 1).map(|j| (j, j + 1)).collect();
+    if options.include_bottom {
+        outline_edges.push((outline.len() - 1, 0));
+    }
+
+    // Generate mesh for each segment
+    for pair in sections.windows(2) {
+        let (s0, s1) = (&pair[0], &pair[1]);
+        for &(a, b) in &outline_edges {
+            triangles.push(Triangle::new(s0[a], s1[b], s0[b]));
+            triangles.push(Triangle::new(s0[a], s1[a], s1[b]));
+        }
+    }
+
+    if options.include_end_caps && options.round_caps {
+        let n = points.len();
+        let start_outward = direction(points[1], points[0]);
+        let end_outward = direction(points[n - 2], points[n - 1]);
+        let section: Vec<(f32, f32)> = outline
+            .iter()
+            .map(|&(offset, z)| (offset * half_width, base_z + height * z))
+            .collect();
+        for (center, outward) in [(points[0], start_outward), (points[n - 1], end_outward)] {
+            add_round_cap(
+                &mut triangles,
+                center,
+                outward,
+                &section,
+                options.include_bottom,
+            );
+        }
+    } else if options.include_end_caps {
+        let n = points.len();
+        let start_outward = direction(points[1], points[0]);
+        let end_outward = direction(points[n - 2], points[n - 1]);
+        for (section, (ox, oy)) in [
+            (&sections[0], start_outward),
+            (&sections[n - 1], end_outward),
+        ] {
+            // The outlines are convex, so a fan closes them
+            for k in 1..section.len() - 1 {
+                triangles.push(oriented(
+                    section[0],
+                    section[k],
+                    section[k + 1],
+                    [ox, oy, 0.0],
+                ));
+            }
+        }
+    }
+
+    triangles
+}
+
+/// Outline of the ground a flat ribbon along `points` covers, as one ring
+///
+/// Runs up the left side and back down the right, through a half circle
+/// at each end with `round_caps`, matching the sides and caps of
+/// [`extrude_ribbon_ex`]. Sharp turns can fold the inner side over itself.
+pub fn ribbon_footprint(points: &[(f32, f32)], width: f32, round_caps: bool) -> Vec<(f32, f32)> {
+    if points.len() < 2 {
+        return Vec::new();
+    }
+
+    let half_width = width / 2.0;
+    let perpendiculars = perpendiculars(points);
+    let side = |sign: f32| -> Vec<(f32, f32)> {
+        points
+            .iter()
+            .zip(&perpendiculars)
+            .map(|(&(x, y), &(px, py))| (x + px * half_width * sign, y + py * half_width * sign))
+            .collect()
+    };
+    // Points between the two sides of an end facing `outward`, sweeping
+    // from the side on its left through the tip
+    let cap = |(cx, cy): (f32, f32), (ox, oy): (f32, f32)| {
+        let (lx, ly) = (-oy, ox);
+        (1..ROUND_CAP_SEGMENTS).map(move |k| {
+            let angle = std::f32::consts::PI * k as f32 / ROUND_CAP_SEGMENTS as f32;
+            let (sin, cos) = angle.sin_cos();
+            (
+                cx + half_width * (lx * cos + ox * sin),
+                cy + half_width * (ly * cos + oy * sin),
+            )
+        })
+    };
+
+    let n = points.len();
+    let mut ring = side(1.0);
+    if round_caps {
+        ring.extend(cap(points[n - 1], direction(points[n - 2], points[n - 1])));
+    }
+    ring.extend(side(-1.0).into_iter().rev());
+    if round_caps {
+        ring.extend(cap(points[0], direction(points[1], points[0])));
+    }
+    ring
+}
+
+/// Unit vector to the left of the path at each point, mitered at joints
+fn perpendiculars(points: &[(f32, f32)]) -> Vec<(f32, f32)> {
+    (0..points.len())
+        .map(|i| {
             let (dx, dy) = if i == 0 {
                 // First point: use direction to next point
                 direction(points[0], points[1])
@@ -70,67 +286,93 @@ pub fn extrude_ribbon_ex(
                 let avg = ((d1.0 + d2.0) / 2.0, (d1.1 + d2.1) / 2.0);
                 normalize(avg)
             };
+            // Rotate 90 degrees; negative outline offsets are on the right
+            (-dy, dx)
+        })
+        .collect()
+}
 
-            // Perpendicular vector (rotate 90 degrees)
-            let (px, py) = (-dy, dx);
-
-            // Left and right points
-            let left = [x - px * half_width, y - py * half_width];
-            let right = [x + px * half_width, y + py * half_width];
-
-            (left, right)
+/// Add a half dome bulging out of a ribbon end along `outward`
+///
+/// The right half of the cross-section, given as (offset, z) pairs in mm,
+/// is swept through a half turn starting and ending on the ribbon's end
+/// section, so the cap closes the ribbon without a flat end face.
+fn add_round_cap(
+    triangles: &mut Vec<Triangle>,
+    (cx, cy): (f32, f32),
+    (ox, oy): (f32, f32),
+    section: &[(f32, f32)],
+    include_bottom: bool,
+) {
+    // Sweep from one side edge through the outward direction to the other
+    let (px, py) = (-oy, ox);
+    let directions: Vec<(f32, f32)> = (0..=ROUND_CAP_SEGMENTS)
+        .map(|k| {
+            let angle = std::f32::consts::PI * k as f32 / ROUND_CAP_SEGMENTS as f32;
+            let (sin, cos) = angle.sin_cos();
+            (px * cos + ox * sin, py * cos + oy * sin)
         })
         .collect();
 
-    // Generate mesh for each segment
-    for i in 0..edges.len() - 1 {
-        let (l0, r0) = edges[i];
-        let (l1, r1) = edges[i + 1];
-
-        let tl0 = [l0[0], l0[1], top_z];
-        let tr0 = [r0[0], r0[1], top_z];
-        let tl1 = [l1[0], l1[1], top_z];
-        let tr1 = [r1[0], r1[1], top_z];
-
-        triangles.push(Triangle::new(tl0, tr0, tr1));
-        triangles.push(Triangle::new(tl0, tr1, tl1));
-
-        let bl0 = [l0[0], l0[1], base_z];
-        let br0 = [r0[0], r0[1], base_z];
-        let bl1 = [l1[0], l1[1], base_z];
-        let br1 = [r1[0], r1[1], base_z];
-
-        if include_bottom {
-            triangles.push(Triangle::new(bl0, br1, br0));
-            triangles.push(Triangle::new(bl0, bl1, br1));
-        }
+    // Crown to base, matching the ribbon's right side at the first
+    // direction and its left side at the last
+    let half: Vec<(f32, f32)> = section.iter().copied().filter(|&(s, _)| s >= 0.0).collect();
+    let rings: Vec<Vec<[f32; 3]>> = half
+        .iter()
+        .map(|&(offset, z)| {
+            directions
+                .iter()
+                .map(|&(dx, dy)| [cx + dx * offset, cy + dy * offset, z])
+                .collect()
+        })
+        .collect();
 
-        triangles.push(Triangle::new(bl0, tl0, tl1));
-        triangles.push(Triangle::new(bl0, tl1, bl1));
+    // A flat crown is a disc; fan it from the first ring point so it shares
+    // the ribbon's top edge exactly
+    let crown = &rings[0];
+    if half[0].0 > 0.0 {
+        fan(triangles, crown, [0.0, 0.0, 1.0]);
+    }
 
-        triangles.push(Triangle::new(br0, tr1, tr0));
-        triangles.push(Triangle::new(br0, br1, tr1));
+    for j in 0..half.len() - 1 {
+        let (inner, outer) = (&rings[j], &rings[j + 1]);
+        let ds = half[j + 1].0 - half[j].0;
+        let dz = half[j + 1].1 - half[j].1;
+        for k in 0..ROUND_CAP_SEGMENTS {
+            let (mx, my) = normalize((
+                directions[k].0 + directions[k + 1].0,
+                directions[k].1 + directions[k + 1].1,
+            ));
+            let facing = [-dz * mx, -dz * my, ds];
+            triangles.push(oriented(outer[k], outer[k + 1], inner[k + 1], facing));
+            // On the crown center the inner ring collapses to a point
+            if half[j].0 > 0.0 {
+                triangles.push(oriented(outer[k], inner[k + 1], inner[k], facing));
+            }
+        }
     }
 
-    if include_end_caps && !edges.is_empty() {
-        let (l0, r0) = edges[0];
-        let bl = [l0[0], l0[1], base_z];
-        let br = [r0[0], r0[1], base_z];
-        let tl = [l0[0], l0[1], top_z];
-        let tr = [r0[0], r0[1], top_z];
-        triangles.push(Triangle::new(bl, tl, tr));
-        triangles.push(Triangle::new(bl, tr, br));
+    if include_bottom {
+        fan(triangles, &rings[rings.len() - 1], [0.0, 0.0, -1.0]);
+    }
+}
 
-        let (l1, r1) = edges[edges.len() - 1];
-        let bl = [l1[0], l1[1], base_z];
-        let br = [r1[0], r1[1], base_z];
-        let tl = [l1[0], l1[1], top_z];
-        let tr = [r1[0], r1[1], top_z];
-        triangles.push(Triangle::new(bl, tr, tl));
-        triangles.push(Triangle::new(bl, br, tr));
+/// Fan a flat convex ring from its first point
+fn fan(triangles: &mut Vec<Triangle>, ring: &[[f32; 3]], facing: [f32; 3]) {
+    for pair in ring[1..].windows(2) {
+        triangles.push(oriented(ring[0], pair[0], pair[1], facing));
     }
+}
 
-    triangles
+/// Triangle with its winding chosen so the normal faces along `facing`
+fn oriented(v0: [f32; 3], v1: [f32; 3], v2: [f32; 3], facing: [f32; 3]) -> Triangle {
+    let triangle = Triangle::new(v0, v1, v2);
+    let n = triangle.normal;
+    if n[0] * facing[0] + n[1] * facing[1] + n[2] * facing[2] < 0.0 {
+        Triangle::new(v0, v2, v1)
+    } else {
+        triangle
+    }
 }
 
 fn direction(p1: (f32, f32), p2: (f32, f32)) -> (f32, f32) {
@@ -162,10 +404,129 @@ mod tests {
     #[test]
     fn test_extrude_open_bottom() {
         let points = vec![(0.0, 0.0), (10.0, 0.0)];
-        let triangles = extrude_ribbon_ex(&points, 2.0, 1.0, 0.0, false, true);
+        let options = RibbonOptions {
+            include_bottom: false,
+            ..Default::default()
+        };
+        let triangles = extrude_ribbon_ex(&points, 2.0, 1.0, 0.0, &options);
         assert_eq!(triangles.len(), 10);
     }
 
+    #[test]
+    fn test_extrude_round_caps() {
+        let points = vec![(0.0, 0.0), (10.0, 0.0)];
+        let options = RibbonOptions {
+            round_caps: true,
+            ..Default::default()
+        };
+        let triangles = extrude_ribbon_ex(&points, 2.0, 1.0, 0.0, &options);
+        // 8 for the segment; each cap fans its top and bottom and walls every arc edge
+        let per_cap = 2 * (ROUND_CAP_SEGMENTS - 1) + 2 * ROUND_CAP_SEGMENTS;
+        assert_eq!(triangles.len(), 8 + 2 * per_cap);
+
+        let xs = triangles.iter().flat_map(|t| t.vertices).map(|v| v[0]);
+        let (min_x, max_x) = xs.fold((f32::MAX, f32::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)));
+        assert!((min_x + 1.0).abs() < 1e-5);
+        assert!((max_x - 11.0).abs() < 1e-5);
+
+        // Cap walls face away from the ribbon, caps face up or down
+        for t in &triangles[8..] {
+            let centroid_x = (t.vertices[0][0] + t.vertices[1][0] + t.vertices[2][0]) / 3.0;
+            let outward = if centroid_x < 5.0 { -1.0 } else { 1.0 };
+            assert!(t.normal[2].abs() > 0.99 || t.normal[0] * outward > 0.0);
+        }
+
+        // Closed: every edge is shared by exactly two triangles
+        let key = |v: [f32; 3]| v.map(|c| (c * 1e4).round() as i64);
+        let mut edges = std::collections::HashMap::new();
+        for t in &triangles {
+            for i in 0..3 {
+                let (a, b) = (key(t.vertices[i]), key(t.vertices[(i + 1) % 3]));
+                *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+        assert!(edges.values().all(|&count| count == 2));
+    }
+
+    #[test]
+    fn test_extrude_profiles_are_closed() {
+        let points = vec![(0.0, 0.0), (10.0, 0.0), (15.0, 5.0)];
+        let key = |v: [f32; 3]| v.map(|c| (c * 1e4).round() as i64);
+        for profile in [
+            RibbonProfile::Flat,
+            RibbonProfile::Trapezoid,
+            RibbonProfile::Rounded,
+        ] {
+            for round_caps in [false, true] {
+                let options = RibbonOptions {
+                    round_caps,
+                    profile,
+                    ..Default::default()
+                };
+                let triangles = extrude_ribbon_ex(&points, 2.0, 1.0, 0.0, &options);
+
+                // Consistently wound: every directed edge is matched by its reverse
+                let mut edges = std::collections::HashMap::new();
+                for t in &triangles {
+                    for i in 0..3 {
+                        let (a, b) = (key(t.vertices[i]), key(t.vertices[(i + 1) % 3]));
+                        *edges.entry((a, b)).or_insert(0) += 1;
+                    }
+                }
+                for (&(a, b), &count) in &edges {
+                    assert_eq!(edges.get(&(b, a)), Some(&count), "{:?}", profile);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_extrude_profile_shapes() {
+        let points = vec![(0.0, 0.0), (10.0, 0.0)];
+        let top_half_width = |profile| {
+            let options = RibbonOptions {
+                profile,
+                ..Default::default()
+            };
+            extrude_ribbon_ex(&points, 2.0, 1.0, 0.0, &options)
+                .iter()
+                .flat_map(|t| t.vertices)
+                .filter(|v| (v[2] - 1.0).abs() < 1e-5)
+                .map(|v| v[1].abs())
+                .fold(0.0f32, f32::max)
+        };
+        assert!((top_half_width(RibbonProfile::Flat) - 1.0).abs() < 1e-5);
+        assert!((top_half_width(RibbonProfile::Trapezoid) - 0.5).abs() < 1e-5);
+        // Only the crown reaches full height
+        assert!(top_half_width(RibbonProfile::Rounded) < 1e-5);
+
+        assert_eq!("trapezoid".parse(), Ok(RibbonProfile::Trapezoid));
+        assert_eq!("Rounded".parse(), Ok(RibbonProfile::Rounded));
+        assert!("square".parse::<RibbonProfile>().is_err());
+    }
+
+    #[test]
+    fn test_ribbon_footprint() {
+        let points = vec![(0.0, 0.0), (10.0, 0.0)];
+        let ring = ribbon_footprint(&points, 2.0, false);
+        assert_eq!(
+            ring,
+            vec![(0.0, 1.0), (10.0, 1.0), (10.0, -1.0), (0.0, -1.0)]
+        );
+
+        let ring = ribbon_footprint(&points, 2.0, true);
+        assert_eq!(ring.len(), 4 + 2 * (ROUND_CAP_SEGMENTS - 1));
+        // The caps bulge out to the ribbon's round ends, on their own side
+        assert!((ring[2 + ROUND_CAP_SEGMENTS / 2 - 1].0 - 11.0).abs() < 1e-5);
+        assert!((ring.last().unwrap().1 - 1.0).abs() > 1e-3);
+        let x = |p: &(f32, f32)| p.0;
+        let (min_x, max_x) = ring.iter().fold((f32::MAX, f32::MIN), |(lo, hi), p| {
+            (lo.min(x(p)), hi.max(x(p)))
+        });
+        assert!((min_x + 1.0).abs() < 1e-5 && (max_x - 11.0).abs() < 1e-5);
+        assert!(ribbon_footprint(&points[..1], 2.0, true).is_empty());
+    }
+
     #[test]
     fn test_extrude_empty() {
         let points: Vec<(f32, f32)> = vec![];
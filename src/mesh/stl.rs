@@ -1,11 +1,54 @@
 use super::Triangle;
 use anyhow::{Context, Result};
+use flate2::Compression;
+use flate2::write::GzEncoder;
 use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// STL output encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StlFormat {
+    #[default]
+    Binary,
+    Ascii,
+}
+
+impl std::str::FromStr for StlFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "binary" => Ok(StlFormat::Binary),
+            "ascii" => Ok(StlFormat::Ascii),
+            _ => Err(format!(
+                "Invalid STL format '{}'. Valid options: binary, ascii",
+                s
+            )),
+        }
+    }
+}
 
 /// Write triangles to a binary STL file
 ///
+/// Opens `path` and delegates to [`write_stl_to`].
+///
+/// # Arguments
+/// * `path` - Output file path
+/// * `triangles` - Triangles to write
+pub fn write_stl(path: &Path, triangles: &[Triangle]) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create STL file: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    write_stl_to(&mut writer, triangles)?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Write triangles as binary STL to any writer (e.g. a `Vec<u8>` or HTTP body)
+///
 /// Binary STL format:
 /// - 80 byte header
 /// - 4 byte u32 triangle count (little endian)
@@ -15,13 +58,9 @@ use std::path::Path;
 ///   - 2 byte attribute (usually 0)
 ///
 /// # Arguments
-/// * `path` - Output file path
+/// * `writer` - Destination; wrap unbuffered writers in a `BufWriter`
 /// * `triangles` - Triangles to write
-pub fn write_stl(path: &Path, triangles: &[Triangle]) -> Result<()> {
-    let file = File::create(path)
-        .with_context(|| format!("Failed to create STL file: {}", path.display()))?;
-    let mut writer = BufWriter::new(file);
-
+pub fn write_stl_to<W: Write>(writer: &mut W, triangles: &[Triangle]) -> Result<()> {
     let header: [u8; 80] =
         *b"mapto3d - City Map STL Generator                                                ";
     writer.write_all(&header)?;
@@ -48,11 +87,110 @@ pub fn write_stl(path: &Path, triangles: &[Triangle]) -> Result<()> {
         writer.write_all(&[0u8, 0u8])?;
     }
 
+    Ok(())
+}
+
+/// Write triangles to an ASCII STL file
+///
+/// ASCII STL format:
+/// ```text
+/// solid mapto3d
+///   facet normal nx ny nz
+///     outer loop
+///       vertex x y z
+///       vertex x y z
+///       vertex x y z
+///     endloop
+///   endfacet
+/// endsolid mapto3d
+/// ```
+///
+/// Much larger than binary STL, but easy to diff and inspect.
+///
+/// # Arguments
+/// * `path` - Output file path
+/// * `triangles` - Triangles to write
+pub fn write_stl_ascii(path: &Path, triangles: &[Triangle]) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create STL file: {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+
+    write_stl_ascii_to(&mut writer, triangles)?;
     writer.flush()?;
 
     Ok(())
 }
 
+/// Write triangles as ASCII STL to any writer
+pub fn write_stl_ascii_to<W: Write>(writer: &mut W, triangles: &[Triangle]) -> Result<()> {
+    writeln!(writer, "solid mapto3d")?;
+
+    for tri in triangles {
+        let [nx, ny, nz] = tri.normal;
+        writeln!(writer, "  facet normal {:e} {:e} {:e}", nx, ny, nz)?;
+        writeln!(writer, "    outer loop")?;
+        for [x, y, z] in &tri.vertices {
+            writeln!(writer, "      vertex {:e} {:e} {:e}", x, y, z)?;
+        }
+        writeln!(writer, "    endloop")?;
+        writeln!(writer, "  endfacet")?;
+    }
+
+    writeln!(writer, "endsolid mapto3d")?;
+
+    Ok(())
+}
+
+/// Write triangles to a gzip-compressed STL file (`.stl.gz`)
+///
+/// # Arguments
+/// * `path` - Output file path
+/// * `triangles` - Triangles to write
+/// * `format` - STL encoding inside the gzip stream
+pub fn write_stl_gz(path: &Path, triangles: &[Triangle], format: StlFormat) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create STL file: {}", path.display()))?;
+    let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+
+    match format {
+        StlFormat::Binary => write_stl_to(&mut encoder, triangles)?,
+        StlFormat::Ascii => write_stl_ascii_to(&mut encoder, triangles)?,
+    }
+
+    encoder
+        .finish()
+        .context("Failed to finish gzip stream")?
+        .flush()?;
+
+    Ok(())
+}
+
+/// Size of the data inside a gzip file, read from the length gzip records
+/// at its end
+///
+/// gzip keeps only the low 32 bits, so this wraps past 4 GiB.
+pub fn gz_uncompressed_size(path: &Path) -> Result<usize> {
+    let mut file = File::open(path)
+        .with_context(|| format!("Failed to open gzip file: {}", path.display()))?;
+    let mut length = [0u8; 4];
+    file.seek(SeekFrom::End(-4))?;
+    file.read_exact(&mut length)?;
+    Ok(u32::from_le_bytes(length) as usize)
+}
+
+/// Append `.gz` to a path unless it already ends in `.gz`
+pub fn gz_path(path: &Path) -> PathBuf {
+    if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("gz"))
+    {
+        return path.to_path_buf();
+    }
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
 /// Get the file size of an STL with the given number of triangles
 pub fn estimate_stl_size(triangle_count: usize) -> usize {
     // 80 (header) + 4 (count) + triangles * (12 normal + 36 vertices + 2 attribute)
@@ -82,6 +220,116 @@ mod tests {
         assert_eq!(metadata.len(), estimate_stl_size(2) as u64);
     }
 
+    #[test]
+    fn test_write_stl_to_buffer() {
+        let triangles = vec![Triangle::new(
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+        )];
+
+        let mut buffer: Vec<u8> = Vec::new();
+        write_stl_to(&mut buffer, &triangles).unwrap();
+
+        assert_eq!(buffer.len(), estimate_stl_size(1));
+        assert!(buffer.starts_with(b"mapto3d"));
+        assert_eq!(u32::from_le_bytes(buffer[80..84].try_into().unwrap()), 1);
+        // First vertex x of the second vertex (after 12-byte normal + 12-byte v0)
+        assert_eq!(
+            f32::from_le_bytes(buffer[108..112].try_into().unwrap()),
+            1.0
+        );
+    }
+
+    #[test]
+    fn test_write_stl_ascii() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test_ascii.stl");
+
+        let triangles = vec![
+            Triangle::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            Triangle::new([0.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+        ];
+
+        write_stl_ascii(&path, &triangles).unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("solid mapto3d"));
+        assert!(contents.trim_end().ends_with("endsolid mapto3d"));
+
+        let facets = contents
+            .lines()
+            .filter(|l| l.trim_start().starts_with("facet normal"))
+            .count();
+        let vertices: Vec<[f32; 3]> = contents
+            .lines()
+            .filter_map(|l| l.trim_start().strip_prefix("vertex "))
+            .map(|rest| {
+                let coords: Vec<f32> = rest
+                    .split_whitespace()
+                    .map(|c| c.parse().unwrap())
+                    .collect();
+                [coords[0], coords[1], coords[2]]
+            })
+            .collect();
+
+        assert_eq!(facets, 2);
+        assert_eq!(vertices.len(), 6);
+        assert_eq!(vertices[1], [1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_write_stl_gz_round_trip() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.stl.gz");
+
+        let triangles: Vec<Triangle> = (0..100)
+            .map(|i| {
+                let x = i as f32;
+                Triangle::new([x, 0.0, 0.0], [x + 1.0, 0.0, 0.0], [x, 1.0, 0.0])
+            })
+            .collect();
+
+        write_stl_gz(&path, &triangles, StlFormat::Binary).unwrap();
+
+        let compressed = fs::read(&path).unwrap();
+        assert!(compressed.len() < estimate_stl_size(triangles.len()));
+
+        let mut decoded = Vec::new();
+        GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decoded)
+            .unwrap();
+        let mut expected = Vec::new();
+        write_stl_to(&mut expected, &triangles).unwrap();
+        assert_eq!(decoded, expected);
+        assert_eq!(gz_uncompressed_size(&path).unwrap(), expected.len());
+
+        // ASCII is far bigger than the binary estimate
+        write_stl_gz(&path, &triangles, StlFormat::Ascii).unwrap();
+        let mut ascii = Vec::new();
+        write_stl_ascii_to(&mut ascii, &triangles).unwrap();
+        assert_eq!(gz_uncompressed_size(&path).unwrap(), ascii.len());
+    }
+
+    #[test]
+    fn test_gz_path() {
+        assert_eq!(gz_path(Path::new("map.stl")), PathBuf::from("map.stl.gz"));
+        assert_eq!(
+            gz_path(Path::new("map.stl.gz")),
+            PathBuf::from("map.stl.gz")
+        );
+    }
+
+    #[test]
+    fn test_stl_format_from_str() {
+        assert_eq!("ascii".parse::<StlFormat>(), Ok(StlFormat::Ascii));
+        assert_eq!("BINARY".parse::<StlFormat>(), Ok(StlFormat::Binary));
+        assert!("obj".parse::<StlFormat>().is_err());
+    }
+
     #[test]
     fn test_estimate_size() {
         // Empty STL: 80 + 4 = 84 bytes
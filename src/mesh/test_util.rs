@@ -0,0 +1,44 @@
+//! Helpers shared by the mesh tests
+
+use super::Triangle;
+
+/// Volume enclosed by a closed mesh, positive when every face points outward
+pub fn signed_volume(triangles: &[Triangle]) -> f32 {
+    triangles
+        .iter()
+        .map(|t| {
+            let [a, b, c] = t.vertices;
+            (a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+                + a[2] * (b[0] * c[1] - b[1] * c[0]))
+                / 6.0
+        })
+        .sum()
+}
+
+/// Closed, outward-facing box from `min` to `max`
+pub fn cuboid(min: [f32; 3], max: [f32; 3]) -> Vec<Triangle> {
+    let c = |i: usize| {
+        [
+            if i & 1 == 0 { min[0] } else { max[0] },
+            if i & 2 == 0 { min[1] } else { max[1] },
+            if i & 4 == 0 { min[2] } else { max[2] },
+        ]
+    };
+    let faces = [
+        [0, 2, 3, 1],
+        [4, 5, 7, 6],
+        [0, 1, 5, 4],
+        [2, 6, 7, 3],
+        [0, 4, 6, 2],
+        [1, 3, 7, 5],
+    ];
+    faces
+        .iter()
+        .flat_map(|f| {
+            [
+                Triangle::new(c(f[0]), c(f[1]), c(f[2])),
+                Triangle::new(c(f[0]), c(f[2]), c(f[3])),
+            ]
+        })
+        .collect()
+}
@@ -0,0 +1,206 @@
+//! 3MF export with per-layer materials
+//!
+//! A 3MF file is a zip archive holding an XML model. Each feature layer is
+//! written as its own mesh object tagged with a base material, so slicers
+//! like PrusaSlicer and Bambu Studio load the map already color-separated.
+
+use anyhow::{Context, Result};
+use std::fs::File;
+use std::io::{Seek, Write};
+use std::path::Path;
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+use super::Triangle;
+use super::indexed::IndexedMesh;
+use super::palette::{layer_color, to_hex};
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+  <Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+  <Default Extension="model" ContentType="application/vnd.ms-package.3dmanufacturing-3dmodel+xml"/>
+</Types>
+"#;
+
+const RELS: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Target="/3D/3dmodel.model" Id="rel0" Type="http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel"/>
+</Relationships>
+"#;
+
+/// Resource id of the shared `<basematerials>` group
+const MATERIALS_ID: usize = 1;
+
+/// Write named triangle layers to a 3MF file
+///
+/// Layer colors come from the default palette (see [`layer_color`]).
+/// Empty layers are skipped.
+///
+/// # Arguments
+/// * `path` - Output file path
+/// * `layers` - (layer name, triangles) pairs
+pub fn write_3mf(path: &Path, layers: &[(&str, &[Triangle])]) -> Result<()> {
+    let file = File::create(path)
+        .with_context(|| format!("Failed to create 3MF file: {}", path.display()))?;
+    write_3mf_to(file, layers)
+}
+
+/// Write a 3MF archive to any seekable writer
+pub fn write_3mf_to<W: Write + Seek>(writer: W, layers: &[(&str, &[Triangle])]) -> Result<()> {
+    let mut zip = ZipWriter::new(writer);
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    zip.start_file("[Content_Types].xml", options)?;
+    zip.write_all(CONTENT_TYPES.as_bytes())?;
+
+    zip.start_file("_rels/.rels", options)?;
+    zip.write_all(RELS.as_bytes())?;
+
+    zip.start_file("3D/3dmodel.model", options)?;
+    write_model(&mut zip, layers)?;
+
+    zip.finish().context("Failed to finalize 3MF archive")?;
+
+    Ok(())
+}
+
+/// Write the 3D model XML part
+fn write_model<W: Write>(w: &mut W, layers: &[(&str, &[Triangle])]) -> Result<()> {
+    let layers: Vec<&(&str, &[Triangle])> = layers.iter().filter(|(_, t)| !t.is_empty()).collect();
+
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        w,
+        r#"<model unit="millimeter" xml:lang="en-US" xmlns="http://schemas.microsoft.com/3dmanufacturing/core/2015/02">"#
+    )?;
+    writeln!(w, r#"  <metadata name="Application">mapto3d</metadata>"#)?;
+    writeln!(w, "  <resources>")?;
+
+    writeln!(w, r#"    <basematerials id="{}">"#, MATERIALS_ID)?;
+    for (name, _) in &layers {
+        writeln!(
+            w,
+            r#"      <base name="{}" displaycolor="{}"/>"#,
+            escape_attr(name),
+            to_hex(layer_color(name))
+        )?;
+    }
+    writeln!(w, "    </basematerials>")?;
+
+    for (index, (name, triangles)) in layers.iter().enumerate() {
+        let mesh = IndexedMesh::from_triangles(triangles);
+        writeln!(
+            w,
+            r#"    <object id="{}" name="{}" type="model" pid="{}" pindex="{}">"#,
+            object_id(index),
+            escape_attr(name),
+            MATERIALS_ID,
+            index
+        )?;
+        writeln!(w, "      <mesh>")?;
+        writeln!(w, "        <vertices>")?;
+        for [x, y, z] in &mesh.vertices {
+            writeln!(w, r#"          <vertex x="{}" y="{}" z="{}"/>"#, x, y, z)?;
+        }
+        writeln!(w, "        </vertices>")?;
+        writeln!(w, "        <triangles>")?;
+        for [v1, v2, v3] in &mesh.faces {
+            writeln!(
+                w,
+                r#"          <triangle v1="{}" v2="{}" v3="{}"/>"#,
+                v1, v2, v3
+            )?;
+        }
+        writeln!(w, "        </triangles>")?;
+        writeln!(w, "      </mesh>")?;
+        writeln!(w, "    </object>")?;
+    }
+
+    writeln!(w, "  </resources>")?;
+    writeln!(w, "  <build>")?;
+    for index in 0..layers.len() {
+        writeln!(w, r#"    <item objectid="{}"/>"#, object_id(index))?;
+    }
+    writeln!(w, "  </build>")?;
+    writeln!(w, "</model>")?;
+
+    Ok(())
+}
+
+/// Escape a value for a double-quoted XML attribute
+fn escape_attr(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Object ids follow the material group id
+fn object_id(layer_index: usize) -> usize {
+    MATERIALS_ID + 1 + layer_index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+
+    #[test]
+    fn test_write_3mf_archive() {
+        let base = vec![
+            Triangle::new([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [1.0, 1.0, 0.0]),
+            Triangle::new([0.0, 0.0, 0.0], [1.0, 1.0, 0.0], [0.0, 1.0, 0.0]),
+        ];
+        let roads = vec![Triangle::new(
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+        )];
+
+        let mut buffer = Cursor::new(Vec::new());
+        write_3mf_to(
+            &mut buffer,
+            &[("base", &base), ("water", &[]), ("roads", &roads)],
+        )
+        .unwrap();
+
+        let mut archive = zip::ZipArchive::new(buffer).unwrap();
+        assert!(archive.by_name("[Content_Types].xml").is_ok());
+        assert!(archive.by_name("_rels/.rels").is_ok());
+
+        let mut model = String::new();
+        archive
+            .by_name("3D/3dmodel.model")
+            .unwrap()
+            .read_to_string(&mut model)
+            .unwrap();
+
+        assert_eq!(model.matches("<base ").count(), 2);
+        assert_eq!(model.matches("<object ").count(), 2);
+        assert_eq!(model.matches("<triangle ").count(), 3);
+        assert!(model.contains(r#"name="roads" type="model" pid="1" pindex="1""#));
+        assert!(!model.contains(r#"name="water""#));
+    }
+
+    #[test]
+    fn test_write_3mf_escapes_names() {
+        let roads = vec![Triangle::new(
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0],
+        )];
+        let mut model = Vec::new();
+        write_model(&mut model, &[("roads & \"rails\" <1>", &roads)]).unwrap();
+        let model = String::from_utf8(model).unwrap();
+        assert!(model.contains(r#"name="roads &amp; &quot;rails&quot; &lt;1&gt;""#));
+        assert!(!model.contains("& "));
+    }
+}
@@ -0,0 +1,451 @@
+//! Splitting a finished model into printable tiles
+//!
+//! Tiles meet in half-lap joints. Below the joint height each seam sits
+//! `TILE_LAP_MM` further right (or up) than above it, so every tile carries
+//! a thin tab that slides under the overhang of its right and upper
+//! neighbors, holding the glued pieces level and in line. Each tile is cut
+//! as two closed shells, under and over the joint height, that meet on the
+//! joint plane; slicers merge them into one solid.
+//!
+//! Optional registration pins stand on the tabs and drop into sockets cut
+//! into the underside of the neighbor's overhang, so the tiles only fit
+//! together one way. Pins go only where both the tab and the overhang are
+//! solid, which leaves out spots over water cutouts or off a round plate.
+
+use rayon::prelude::*;
+
+use super::Triangle;
+use super::clip::{Axis, clip_half_space};
+use super::extrusion::extrude_polygon;
+
+/// Width of the lap joint along every seam, in mm
+pub const TILE_LAP_MM: f32 = 6.0;
+
+/// Diameter of a registration pin, in mm
+pub const TILE_PIN_DIAMETER_MM: f32 = 3.0;
+
+/// Gap between a pin and its socket on every side, in mm
+pub const TILE_PIN_CLEARANCE_MM: f32 = 0.2;
+
+/// Share of the overhang's thickness a socket cuts into
+const SOCKET_DEPTH_RATIO: f32 = 0.6;
+
+const PIN_SEGMENTS: usize = 24;
+
+/// Registration pin size, derived from the base plate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TilePins {
+    pub diameter: f32,
+    pub height: f32,
+    pub clearance: f32,
+}
+
+impl TilePins {
+    /// Pins for an overhang `thickness` mm thick above the joint
+    ///
+    /// Sockets take most of the overhang and leave a ceiling over the pin.
+    pub fn for_overhang(thickness: f32) -> Self {
+        Self {
+            diameter: TILE_PIN_DIAMETER_MM,
+            height: thickness * SOCKET_DEPTH_RATIO - TILE_PIN_CLEARANCE_MM,
+            clearance: TILE_PIN_CLEARANCE_MM,
+        }
+    }
+
+    /// Overhang thickness that gives pins of `height`
+    pub fn overhang_for_height(height: f32) -> f32 {
+        (height + TILE_PIN_CLEARANCE_MM) / SOCKET_DEPTH_RATIO
+    }
+
+    fn socket_depth(&self) -> f32 {
+        self.height + self.clearance
+    }
+
+    /// Overhang left above a socket
+    fn ceiling(&self) -> f32 {
+        self.socket_depth() / SOCKET_DEPTH_RATIO - self.socket_depth()
+    }
+
+    /// Half the side of the square socket; the round pin centers itself in it
+    fn socket_half_width(&self) -> f32 {
+        self.diameter / 2.0 + self.clearance
+    }
+}
+
+/// Number of tiles across and down the plate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileGrid {
+    pub columns: usize,
+    pub rows: usize,
+}
+
+impl std::str::FromStr for TileGrid {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "Invalid tile grid '{}'. Expected COLUMNSxROWS, e.g. 2x2 or 3x1",
+                s
+            )
+        };
+        let (columns, rows) = s
+            .to_lowercase()
+            .split_once('x')
+            .ok_or_else(invalid)
+            .and_then(|(columns, rows)| {
+                let columns: usize = columns.trim().parse().map_err(|_| invalid())?;
+                let rows: usize = rows.trim().parse().map_err(|_| invalid())?;
+                Ok((columns, rows))
+            })?;
+        if columns == 0 || rows == 0 || columns * rows == 1 {
+            return Err(format!(
+                "Invalid tile grid '{}'. Use at least two tiles, e.g. 2x1",
+                s
+            ));
+        }
+        Ok(TileGrid { columns, rows })
+    }
+}
+
+/// One printable piece of a tiled model
+#[derive(Debug)]
+pub struct Tile<'a> {
+    /// Row from the top edge of the plate
+    pub row: usize,
+    /// Column from the left edge of the plate
+    pub column: usize,
+    /// Layers with anything left in this tile, in their original order
+    pub layers: Vec<(&'a str, Vec<Triangle>)>,
+    /// Width and height of the tile's footprint in mm, tabs included
+    pub size_mm: (f32, f32),
+}
+
+/// Cut layers into a grid of tiles joined by laps at height `joint_z`
+///
+/// Row 0 is the top edge of the plate, as the map is read. The grid spans
+/// the XY bounds of all layers, which must be closed and outward-facing.
+///
+/// With `pins`, each seam gets two registration pins at a quarter and three
+/// quarters of its length, standing on the tab and sunk into the neighbor.
+pub fn split_into_tiles<'a>(
+    layers: &[(&'a str, &[Triangle])],
+    grid: TileGrid,
+    joint_z: f32,
+    pins: Option<TilePins>,
+) -> Vec<Tile<'a>> {
+    let mut min = [f32::INFINITY; 2];
+    let mut max = [f32::NEG_INFINITY; 2];
+    for v in layers
+        .iter()
+        .flat_map(|(_, triangles)| triangles.iter().flat_map(|t| t.vertices))
+    {
+        for axis in 0..2 {
+            min[axis] = min[axis].min(v[axis]);
+            max[axis] = max[axis].max(v[axis]);
+        }
+    }
+    let tile_w = (max[0] - min[0]) / grid.columns as f32;
+    let tile_h = (max[1] - min[1]) / grid.rows as f32;
+
+    let pin_sites: Vec<PinSite> = pins
+        .map(|pins| {
+            // Pins a quarter of the way along a seam must clear the lap
+            // strip that runs across the tab's far end
+            let fits = |length: f32| length * 0.25 - pins.socket_half_width() >= TILE_LAP_MM;
+            let mut sites = Vec::new();
+            for row in 0..grid.rows {
+                for column in 0..grid.columns {
+                    let x0 = min[0] + column as f32 * tile_w;
+                    let y0 = max[1] - (row + 1) as f32 * tile_h;
+                    // Tab reaching right into the next column
+                    if column + 1 < grid.columns && fits(tile_h) {
+                        for share in [0.25, 0.75] {
+                            sites.push(PinSite {
+                                x: x0 + tile_w + TILE_LAP_MM / 2.0,
+                                y: y0 + tile_h * share,
+                                tab: (row, column),
+                                socket: (row, column + 1),
+                            });
+                        }
+                    }
+                    // Tab reaching up into the row above
+                    if row > 0 && fits(tile_w) {
+                        for share in [0.25, 0.75] {
+                            sites.push(PinSite {
+                                x: x0 + tile_w * share,
+                                y: y0 + tile_h + TILE_LAP_MM / 2.0,
+                                tab: (row, column),
+                                socket: (row - 1, column),
+                            });
+                        }
+                    }
+                }
+            }
+            sites.retain(|site| site.is_supported(layers, joint_z, &pins));
+            sites
+        })
+        .unwrap_or_default();
+
+    (0..grid.rows * grid.columns)
+        .into_par_iter()
+        .map(|i| {
+            let (row, column) = (i / grid.columns, i % grid.columns);
+            let x0 = min[0] + column as f32 * tile_w;
+            let y1 = max[1] - row as f32 * tile_h;
+            let seams = Seams {
+                left: (column > 0).then_some(x0),
+                right: (column + 1 < grid.columns).then_some(x0 + tile_w),
+                bottom: (row + 1 < grid.rows).then_some(y1 - tile_h),
+                top: (row > 0).then_some(y1),
+            };
+
+            let sockets: Vec<&PinSite> = pin_sites
+                .iter()
+                .filter(|site| site.socket == (row, column))
+                .collect();
+            let mut layers: Vec<(&str, Vec<Triangle>)> = layers
+                .iter()
+                .filter_map(|&(name, triangles)| {
+                    let under = clip_half_space(triangles, Axis::Z, joint_z, true);
+                    let over = clip_half_space(triangles, Axis::Z, joint_z, false);
+                    let mut piece = seams.shifted(TILE_LAP_MM).clip(&under);
+                    piece.extend(seams.clip(&over));
+                    if let Some(pins) = pins {
+                        for site in &sockets {
+                            piece = site.cut_socket(piece, joint_z, &pins);
+                        }
+                    }
+                    (!piece.is_empty()).then_some((name, piece))
+                })
+                .collect();
+            if let (Some(pins), Some((_, first))) = (pins, layers.first_mut()) {
+                for site in pin_sites.iter().filter(|site| site.tab == (row, column)) {
+                    first.extend(site.pin(joint_z, &pins));
+                }
+            }
+
+            // Tabs stick out past the right and top seams
+            let tab = |seam: Option<f32>| if seam.is_some() { TILE_LAP_MM } else { 0.0 };
+            let size_mm = (tile_w + tab(seams.right), tile_h + tab(seams.top));
+            Tile {
+                row,
+                column,
+                layers,
+                size_mm,
+            }
+        })
+        .collect()
+}
+
+/// Cut positions around one tile; `None` on the outer edges of the plate
+#[derive(Debug, Clone, Copy)]
+struct Seams {
+    left: Option<f32>,
+    right: Option<f32>,
+    bottom: Option<f32>,
+    top: Option<f32>,
+}
+
+impl Seams {
+    /// Every seam moved right and up by `offset`
+    fn shifted(self, offset: f32) -> Self {
+        Self {
+            left: self.left.map(|x| x + offset),
+            right: self.right.map(|x| x + offset),
+            bottom: self.bottom.map(|y| y + offset),
+            top: self.top.map(|y| y + offset),
+        }
+    }
+
+    fn clip(&self, triangles: &[Triangle]) -> Vec<Triangle> {
+        let cuts = [
+            (Axis::X, self.left, false),
+            (Axis::X, self.right, true),
+            (Axis::Y, self.bottom, false),
+            (Axis::Y, self.top, true),
+        ];
+        let mut piece = triangles.to_vec();
+        for (axis, value, keep_below) in cuts {
+            if let Some(value) = value {
+                piece = clip_half_space(&piece, axis, value, keep_below);
+            }
+        }
+        piece
+    }
+}
+
+/// Where a pin stands, and which tiles carry it and its socket
+#[derive(Debug, Clone, Copy)]
+struct PinSite {
+    x: f32,
+    y: f32,
+    tab: (usize, usize),
+    socket: (usize, usize),
+}
+
+impl PinSite {
+    /// Whether the model is solid both under the pin and over its socket
+    fn is_supported(&self, layers: &[(&str, &[Triangle])], joint_z: f32, pins: &TilePins) -> bool {
+        let under = joint_z / 2.0;
+        let over = joint_z + pins.socket_depth() + pins.ceiling() / 2.0;
+        let h = pins.socket_half_width();
+        let corners = [(0.0, 0.0), (-h, -h), (h, -h), (h, h), (-h, h)];
+        corners.iter().all(|&(dx, dy)| {
+            [under, over].iter().all(|&z| {
+                let point = [self.x + dx, self.y + dy, z];
+                layers
+                    .iter()
+                    .any(|(_, triangles)| contains_point(triangles, point))
+            })
+        })
+    }
+
+    /// Cylinder standing on the tab, sunk slightly into it so it fuses
+    fn pin(&self, joint_z: f32, pins: &TilePins) -> Vec<Triangle> {
+        let radius = pins.diameter / 2.0;
+        let ring: Vec<(f32, f32)> = (0..PIN_SEGMENTS)
+            .map(|i| {
+                let angle = std::f32::consts::TAU * i as f32 / PIN_SEGMENTS as f32;
+                (self.x + radius * angle.cos(), self.y + radius * angle.sin())
+            })
+            .collect();
+        extrude_polygon(&ring, &[], joint_z - pins.clearance, joint_z + pins.height)
+    }
+
+    /// Remove the socket's box from a closed mesh
+    ///
+    /// The rest is peeled off in closed slices: beside the box along x,
+    /// then along y, then above and below it.
+    fn cut_socket(&self, triangles: Vec<Triangle>, joint_z: f32, pins: &TilePins) -> Vec<Triangle> {
+        let h = pins.socket_half_width();
+        let cuts = [
+            (Axis::X, self.x - h, self.x + h),
+            (Axis::Y, self.y - h, self.y + h),
+            (Axis::Z, joint_z, joint_z + pins.socket_depth()),
+        ];
+        let mut rest = triangles;
+        let mut kept = Vec::new();
+        for (axis, low, high) in cuts {
+            kept.extend(clip_half_space(&rest, axis, low, true));
+            kept.extend(clip_half_space(&rest, axis, high, false));
+            rest = clip_half_space(&clip_half_space(&rest, axis, low, false), axis, high, true);
+        }
+        kept
+    }
+}
+
+/// Whether a point lies inside a closed mesh, by counting the surface
+/// crossings of a ray straight up from it
+fn contains_point(triangles: &[Triangle], [x, y, z]: [f32; 3]) -> bool {
+    let mut crossings = 0;
+    for t in triangles {
+        let [a, b, c] = t.vertices;
+        let det = (b[1] - c[1]) * (a[0] - c[0]) + (c[0] - b[0]) * (a[1] - c[1]);
+        if det == 0.0 {
+            continue;
+        }
+        let u = ((b[1] - c[1]) * (x - c[0]) + (c[0] - b[0]) * (y - c[1])) / det;
+        let v = ((c[1] - a[1]) * (x - c[0]) + (a[0] - c[0]) * (y - c[1])) / det;
+        let w = 1.0 - u - v;
+        if u < 0.0 || v < 0.0 || w < 0.0 {
+            continue;
+        }
+        if u * a[2] + v * b[2] + w * c[2] > z {
+            crossings += 1;
+        }
+    }
+    crossings % 2 == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::test_util::{cuboid, signed_volume};
+
+    /// Closed, outward-facing plate from the origin to `size`
+    fn plate(size: [f32; 3]) -> Vec<Triangle> {
+        cuboid([0.0; 3], size)
+    }
+
+    #[test]
+    fn test_parse_tile_grid() {
+        assert_eq!(
+            "3x2".parse(),
+            Ok(TileGrid {
+                columns: 3,
+                rows: 2
+            })
+        );
+        assert!("1x1".parse::<TileGrid>().is_err());
+        assert!("0x2".parse::<TileGrid>().is_err());
+        assert!("two".parse::<TileGrid>().is_err());
+    }
+
+    #[test]
+    fn test_tiles_share_the_plate() {
+        let base = plate([100.0, 60.0, 2.0]);
+        let grid = TileGrid {
+            columns: 2,
+            rows: 2,
+        };
+        let tiles = split_into_tiles(&[("base", &base)], grid, 1.0, None);
+        assert_eq!(tiles.len(), 4);
+
+        // Nothing is lost or doubled at the seams
+        let total: f32 = tiles.iter().map(|t| signed_volume(&t.layers[0].1)).sum();
+        assert!((total - 12000.0).abs() < 0.5, "{}", total);
+
+        // The top-left tile carries tabs to the right and down into row 1
+        let top_left = &tiles[0];
+        assert_eq!((top_left.row, top_left.column), (0, 0));
+        assert_eq!(top_left.size_mm, (50.0 + TILE_LAP_MM, 30.0));
+        let max_x = top_left.layers[0]
+            .1
+            .iter()
+            .flat_map(|t| t.vertices)
+            .map(|v| v[0])
+            .fold(f32::MIN, f32::max);
+        assert_eq!(max_x, 50.0 + TILE_LAP_MM);
+    }
+
+    #[test]
+    fn test_pins_match_sockets() {
+        let base = plate([100.0, 60.0, 2.0]);
+        let grid = TileGrid {
+            columns: 2,
+            rows: 1,
+        };
+        let pins = TilePins::for_overhang(1.0);
+        let tiles = split_into_tiles(&[("base", &base)], grid, 1.0, Some(pins));
+
+        // Past the tab, the left tile gains the pins and the right tile
+        // loses the sockets
+        let pin = PinSite {
+            x: 0.0,
+            y: 0.0,
+            tab: (0, 0),
+            socket: (0, 1),
+        }
+        .pin(1.0, &pins);
+        let pin_volume = signed_volume(&pin);
+        let socket_side = 2.0 * pins.socket_half_width();
+        let socket_volume = socket_side * socket_side * pins.socket_depth();
+        assert!(pin_volume > 0.0);
+        let left = signed_volume(&tiles[0].layers[0].1);
+        let right = signed_volume(&tiles[1].layers[0].1);
+        assert!(
+            (left - (6000.0 + TILE_LAP_MM * 60.0 + 2.0 * pin_volume)).abs() < 0.1,
+            "{}",
+            left
+        );
+        assert!(
+            (right - (6000.0 - TILE_LAP_MM * 60.0 - 2.0 * socket_volume)).abs() < 0.1,
+            "{}",
+            right
+        );
+
+        // Sockets never open onto the top of the plate
+        assert!(pins.ceiling() > 0.3);
+    }
+}
@@ -4,9 +4,12 @@
 //! - Detect degenerate triangles (zero area)
 //! - Check for NaN/Inf coordinates
 //! - Verify and fix normal orientation
+//! - Count overlapping coplanar faces, which slicers may show as z-fighting
 //! - Remove invalid geometry
 
 use super::Triangle;
+use serde::Serialize;
+use std::collections::HashMap;
 
 /// Result of mesh validation
 #[derive(Debug, Default)]
@@ -20,6 +23,14 @@ pub struct ValidationResult {
     pub invalid_coords: usize,
     /// Number of triangles with incorrect normals (fixed during validation)
     pub invalid_normal: usize,
+    /// Edge sharing; open or over-shared edges are reported, not fixed
+    pub manifold: ManifoldReport,
+    /// Pairs of triangles lying in the same plane, facing the same way and
+    /// overlapping with a positive area, such as the tops of crossing roads
+    ///
+    /// Reported for information only: every map with crossing roads has
+    /// some, so they do not count as issues.
+    pub coplanar_overlaps: usize,
     /// Warning messages for issues found
     pub warnings: Vec<String>,
 }
@@ -34,7 +45,10 @@ impl ValidationResult {
     /// Check if the mesh has any issues at all
     #[allow(dead_code)]
     pub fn has_issues(&self) -> bool {
-        self.degenerate > 0 || self.invalid_coords > 0 || self.invalid_normal > 0
+        self.degenerate > 0
+            || self.invalid_coords > 0
+            || self.invalid_normal > 0
+            || !self.manifold.is_watertight()
     }
 
     /// Get a summary string
@@ -44,15 +58,53 @@ impl ValidationResult {
             format!("Mesh valid: {} triangles, no issues", self.total)
         } else {
             format!(
-                "Mesh issues: {} total, {} degenerate, {} invalid coords, {} bad normals",
-                self.total, self.degenerate, self.invalid_coords, self.invalid_normal
+                "Mesh issues: {} total, {} degenerate, {} invalid coords, {} bad normals, {} boundary edges, {} non-manifold edges, {} coplanar overlaps",
+                self.total,
+                self.degenerate,
+                self.invalid_coords,
+                self.invalid_normal,
+                self.manifold.boundary_edges,
+                self.manifold.non_manifold_edges,
+                self.coplanar_overlaps
             )
         }
     }
 }
 
-/// Minimum area threshold for non-degenerate triangles (in square mm)
-const MIN_TRIANGLE_AREA: f32 = 1e-10;
+/// Edge sharing found by [`check_manifold`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ManifoldReport {
+    /// Edges used by a single triangle: holes and open ends
+    pub boundary_edges: usize,
+    /// Edges shared by more than two triangles
+    pub non_manifold_edges: usize,
+}
+
+impl ManifoldReport {
+    /// Every edge is shared by exactly two triangles
+    pub fn is_watertight(&self) -> bool {
+        self.boundary_edges == 0 && self.non_manifold_edges == 0
+    }
+}
+
+/// Minimum area threshold for non-degenerate triangles (in square mm) on a
+/// plate of [`REFERENCE_PLATE_MM`]
+pub const DEFAULT_MIN_TRIANGLE_AREA: f32 = 1e-10;
+
+/// Plate size the default area threshold was tuned for
+const REFERENCE_PLATE_MM: f32 = 220.0;
+
+/// Vertices closer than this (in mm) are treated as the same point when
+/// matching edges
+const WELD_TOLERANCE_MM: f32 = 1e-4;
+
+/// Faces whose unit normals differ by less than this in every component,
+/// and whose planes lie within [`COPLANAR_DISTANCE_MM`], share a plane
+const COPLANAR_NORMAL_TOLERANCE: f32 = 1e-3;
+const COPLANAR_DISTANCE_MM: f32 = 1e-3;
+
+/// Side of the grid cells used to find neighboring faces within a plane
+const OVERLAP_CELL_MM: f32 = 2.0;
 
 /// Validate a mesh and return a detailed report
 ///
@@ -60,9 +112,19 @@ const MIN_TRIANGLE_AREA: f32 = 1e-10;
 /// - Degenerate triangles (zero or near-zero area)
 /// - Invalid coordinates (NaN, Inf)
 /// - Normal vector validity
+/// - Edges not shared by exactly two triangles
+/// - Overlapping coplanar faces
 pub fn validate_mesh(triangles: &[Triangle]) -> ValidationResult {
+    validate_mesh_with_area(triangles, DEFAULT_MIN_TRIANGLE_AREA)
+}
+
+/// Validate a mesh, counting triangles under `min_area` square mm as
+/// degenerate
+pub fn validate_mesh_with_area(triangles: &[Triangle], min_area: f32) -> ValidationResult {
     let mut result = ValidationResult {
         total: triangles.len(),
+        manifold: check_manifold(triangles),
+        coplanar_overlaps: count_coplanar_overlaps(triangles),
         ..Default::default()
     };
 
@@ -75,7 +137,7 @@ pub fn validate_mesh(triangles: &[Triangle]) -> ValidationResult {
             continue;
         }
 
-        if is_degenerate(tri) {
+        if triangle_area(&tri.vertices) < min_area {
             result.degenerate += 1;
         }
 
@@ -96,10 +158,169 @@ pub fn validate_mesh(triangles: &[Triangle]) -> ValidationResult {
             result.invalid_normal
         ));
     }
+    if result.manifold.boundary_edges > 0 {
+        result.warnings.push(format!(
+            "{} boundary edges detected (mesh is not closed)",
+            result.manifold.boundary_edges
+        ));
+    }
+    if result.manifold.non_manifold_edges > 0 {
+        result.warnings.push(format!(
+            "{} edges shared by more than two triangles",
+            result.manifold.non_manifold_edges
+        ));
+    }
 
     result
 }
 
+/// Count edges that are not shared by exactly two triangles
+///
+/// Vertices are welded on a [`WELD_TOLERANCE_MM`] grid so that separately
+/// computed copies of the same point match. Edge direction is ignored, and
+/// edges that weld down to a single point are skipped.
+pub fn check_manifold(triangles: &[Triangle]) -> ManifoldReport {
+    let mut edges: HashMap<([i64; 3], [i64; 3]), u32> = HashMap::new();
+    for tri in triangles.iter().filter(|tri| !has_invalid_coords(tri)) {
+        for i in 0..3 {
+            let a = weld_key(tri.vertices[i]);
+            let b = weld_key(tri.vertices[(i + 1) % 3]);
+            if a != b {
+                *edges.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut report = ManifoldReport::default();
+    for &count in edges.values() {
+        match count {
+            1 => report.boundary_edges += 1,
+            2 => {}
+            _ => report.non_manifold_edges += 1,
+        }
+    }
+    report
+}
+
+/// Count pairs of triangles that overlap within a shared plane
+///
+/// Triangles are grouped by their plane, quantized to
+/// [`COPLANAR_NORMAL_TOLERANCE`] and [`COPLANAR_DISTANCE_MM`], so faces
+/// on opposite sides of a thin wall are not compared. Within a plane the
+/// faces are flattened along the dominant normal axis and binned on an
+/// [`OVERLAP_CELL_MM`] grid. Faces that only share an edge or a corner do
+/// not count.
+pub fn count_coplanar_overlaps(triangles: &[Triangle]) -> usize {
+    let mut planes: HashMap<([i32; 3], i64), Vec<usize>> = HashMap::new();
+    for (t, tri) in triangles.iter().enumerate() {
+        if has_invalid_coords(tri) || is_degenerate(tri) {
+            continue;
+        }
+        let normal = calculate_normal(&tri.vertices);
+        let v = tri.vertices[0];
+        let distance = normal[0] * v[0] + normal[1] * v[1] + normal[2] * v[2];
+        let key = (
+            normal.map(|c| (c / COPLANAR_NORMAL_TOLERANCE).round() as i32),
+            (distance / COPLANAR_DISTANCE_MM).round() as i64,
+        );
+        planes.entry(key).or_default().push(t);
+    }
+
+    let mut overlaps = 0;
+    for (&(normal, _), faces) in planes.iter().filter(|(_, faces)| faces.len() > 1) {
+        // Drop the axis the plane faces most, keeping the other two
+        let axis = (0..3).max_by_key(|&i| normal[i].abs()).unwrap_or(2);
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let flat: Vec<[(f32, f32); 3]> = faces
+            .iter()
+            .map(|&t| triangles[t].vertices.map(|p| (p[u], p[v])))
+            .collect();
+        let cell = |x: f32, y: f32| {
+            (
+                (x / OVERLAP_CELL_MM).floor() as i64,
+                (y / OVERLAP_CELL_MM).floor() as i64,
+            )
+        };
+        let bounds: Vec<((f32, f32), (f32, f32))> = flat
+            .iter()
+            .map(|tri| {
+                tri.iter().fold(
+                    ((f32::MAX, f32::MAX), (f32::MIN, f32::MIN)),
+                    |(min, max), &(x, y)| {
+                        ((min.0.min(x), min.1.min(y)), (max.0.max(x), max.1.max(y)))
+                    },
+                )
+            })
+            .collect();
+
+        let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (i, &(min, max)) in bounds.iter().enumerate() {
+            let (x0, y0) = cell(min.0, min.1);
+            let (x1, y1) = cell(max.0, max.1);
+            for x in x0..=x1 {
+                for y in y0..=y1 {
+                    grid.entry((x, y)).or_default().push(i);
+                }
+            }
+        }
+
+        for (&here, members) in &grid {
+            for (k, &i) in members.iter().enumerate() {
+                for &j in &members[k + 1..] {
+                    let (min_i, max_i) = bounds[i];
+                    let (min_j, max_j) = bounds[j];
+                    let corner = (min_i.0.max(min_j.0), min_i.1.max(min_j.1));
+                    // Each pair is tested only in the cell holding the low
+                    // corner of their shared box
+                    if corner.0 > max_i.0.min(max_j.0) || corner.1 > max_i.1.min(max_j.1) {
+                        continue;
+                    }
+                    if cell(corner.0, corner.1) == here
+                        && flat_triangles_overlap(&flat[i], &flat[j])
+                    {
+                        overlaps += 1;
+                    }
+                }
+            }
+        }
+    }
+    overlaps
+}
+
+/// Whether two flat triangles share more than an edge or a point
+///
+/// Separating axis test over the six edge normals; projections that meet
+/// by no more than [`WELD_TOLERANCE_MM`] count as apart.
+fn flat_triangles_overlap(a: &[(f32, f32); 3], b: &[(f32, f32); 3]) -> bool {
+    let project = |tri: &[(f32, f32); 3], (nx, ny): (f32, f32)| {
+        tri.iter()
+            .map(|&(x, y)| x * nx + y * ny)
+            .fold((f32::MAX, f32::MIN), |(lo, hi), d| (lo.min(d), hi.max(d)))
+    };
+    for tri in [a, b] {
+        for i in 0..3 {
+            let (x0, y0) = tri[i];
+            let (x1, y1) = tri[(i + 1) % 3];
+            let length = (x1 - x0).hypot(y1 - y0);
+            if length <= f32::EPSILON {
+                continue;
+            }
+            let axis = ((y0 - y1) / length, (x1 - x0) / length);
+            let (lo_a, hi_a) = project(a, axis);
+            let (lo_b, hi_b) = project(b, axis);
+            if hi_a.min(hi_b) - lo_a.max(lo_b) <= WELD_TOLERANCE_MM {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Grid cell of a vertex for matching edges within [`WELD_TOLERANCE_MM`]
+fn weld_key(vertex: [f32; 3]) -> [i64; 3] {
+    vertex.map(|c| (c / WELD_TOLERANCE_MM).round() as i64)
+}
+
 /// Check if a triangle has any invalid (NaN/Inf) coordinates
 fn has_invalid_coords(tri: &Triangle) -> bool {
     for vertex in &tri.vertices {
@@ -120,7 +341,16 @@ fn has_invalid_coords(tri: &Triangle) -> bool {
 /// Check if a triangle is degenerate (zero or near-zero area)
 fn is_degenerate(tri: &Triangle) -> bool {
     let area = triangle_area(&tri.vertices);
-    area < MIN_TRIANGLE_AREA
+    area < DEFAULT_MIN_TRIANGLE_AREA
+}
+
+/// Degenerate-area threshold in square mm for a plate whose larger side is
+/// `plate_mm`
+///
+/// Triangle areas grow with the square of the plate size, so the threshold
+/// does too and a map keeps the same triangles at any size.
+pub fn min_triangle_area(plate_mm: f32) -> f32 {
+    DEFAULT_MIN_TRIANGLE_AREA * (plate_mm / REFERENCE_PLATE_MM).powi(2)
 }
 
 /// Calculate the area of a triangle from its vertices
@@ -154,6 +384,127 @@ pub fn fix_normals(triangles: &mut [Triangle]) {
     }
 }
 
+/// Triangles meeting along one welded edge, with whether each runs it from
+/// the lower to the higher vertex key
+#[derive(Default)]
+struct EdgeUse {
+    count: u32,
+    users: [(usize, bool); 2],
+}
+
+/// Rewind triangles so every connected solid faces outward
+///
+/// Triangles joined by an edge shared with exactly one other triangle form
+/// a component. Within each component the winding is made consistent by
+/// walking across shared edges (neighbors must run a shared edge in
+/// opposite directions), then the whole component is flipped if its signed
+/// volume is negative. Open shells use their centroid as the reference
+/// point, which keeps the result stable for nearly closed pieces.
+/// Normals of flipped triangles are recalculated.
+pub fn make_normals_outward(triangles: &mut [Triangle]) {
+    let usable: Vec<bool> = triangles
+        .iter()
+        .map(|tri| !has_invalid_coords(tri) && !is_degenerate(tri))
+        .collect();
+
+    let mut edges: HashMap<([i64; 3], [i64; 3]), EdgeUse> = HashMap::new();
+    for (t, tri) in triangles.iter().enumerate().filter(|&(t, _)| usable[t]) {
+        for i in 0..3 {
+            let a = weld_key(tri.vertices[i]);
+            let b = weld_key(tri.vertices[(i + 1) % 3]);
+            if a == b {
+                continue;
+            }
+            let entry = edges.entry((a.min(b), a.max(b))).or_default();
+            if entry.count < 2 {
+                entry.users[entry.count as usize] = (t, a < b);
+            }
+            entry.count += 1;
+        }
+    }
+
+    let mut neighbors: Vec<Vec<(usize, bool, bool)>> = vec![Vec::new(); triangles.len()];
+    for edge in edges.values().filter(|e| e.count == 2) {
+        let [(t, t_forward), (u, u_forward)] = edge.users;
+        neighbors[t].push((u, t_forward, u_forward));
+        neighbors[u].push((t, u_forward, t_forward));
+    }
+    // Hash order varies between runs; walking neighbors in index order keeps
+    // the result the same where a component cannot be oriented consistently
+    for list in &mut neighbors {
+        list.sort_unstable_by_key(|&(u, _, _)| u);
+    }
+
+    let mut flip: Vec<Option<bool>> = vec![None; triangles.len()];
+    for start in 0..triangles.len() {
+        if !usable[start] || flip[start].is_some() {
+            continue;
+        }
+
+        flip[start] = Some(false);
+        let mut component = vec![start];
+        let mut stack = vec![start];
+        while let Some(t) = stack.pop() {
+            let t_flip = flip[t].unwrap_or(false);
+            for &(u, t_forward, u_forward) in &neighbors[t] {
+                if flip[u].is_none() {
+                    // Consistent neighbors run the shared edge in opposite directions
+                    flip[u] = Some((t_forward ^ t_flip) == u_forward);
+                    component.push(u);
+                    stack.push(u);
+                }
+            }
+        }
+
+        let oriented = |t: usize| {
+            let [v0, v1, v2] = triangles[t].vertices;
+            if flip[t] == Some(true) {
+                [v0, v2, v1]
+            } else {
+                [v0, v1, v2]
+            }
+        };
+        let count = (component.len() * 3) as f64;
+        let centroid =
+            component
+                .iter()
+                .flat_map(|&t| triangles[t].vertices)
+                .fold([0.0f64; 3], |acc, v| {
+                    [
+                        acc[0] + v[0] as f64 / count,
+                        acc[1] + v[1] as f64 / count,
+                        acc[2] + v[2] as f64 / count,
+                    ]
+                });
+        let volume: f64 = component
+            .iter()
+            .map(|&t| {
+                let [a, b, c] = oriented(t).map(|v| {
+                    [
+                        v[0] as f64 - centroid[0],
+                        v[1] as f64 - centroid[1],
+                        v[2] as f64 - centroid[2],
+                    ]
+                });
+                a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0])
+                    + a[2] * (b[0] * c[1] - b[1] * c[0])
+            })
+            .sum();
+        if volume < 0.0 {
+            for &t in &component {
+                flip[t] = flip[t].map(|f| !f);
+            }
+        }
+    }
+
+    for (tri, flip) in triangles.iter_mut().zip(flip) {
+        if flip == Some(true) {
+            tri.vertices.swap(1, 2);
+            tri.normal = calculate_normal(&tri.vertices);
+        }
+    }
+}
+
 /// Calculate the normal vector for a triangle using the cross product
 fn calculate_normal(vertices: &[[f32; 3]; 3]) -> [f32; 3] {
     let v0 = vertices[0];
@@ -177,25 +528,32 @@ fn calculate_normal(vertices: &[[f32; 3]; 3]) -> [f32; 3] {
 
 /// Remove degenerate and invalid triangles from a mesh
 ///
-/// Returns a new vector containing only valid triangles
-pub fn remove_degenerate(triangles: Vec<Triangle>) -> Vec<Triangle> {
+/// Returns a new vector containing only valid triangles of at least
+/// `min_area` square mm
+pub fn remove_degenerate(triangles: Vec<Triangle>, min_area: f32) -> Vec<Triangle> {
     triangles
         .into_iter()
-        .filter(|tri| !has_invalid_coords(tri) && !is_degenerate(tri))
+        .filter(|tri| !has_invalid_coords(tri) && triangle_area(&tri.vertices) >= min_area)
         .collect()
 }
 
 /// Validate, fix, and clean a mesh in one pass
 ///
-/// 1. Validates the mesh and reports issues
-/// 2. Fixes normals on all triangles
-/// 3. Removes degenerate/invalid triangles
+/// 1. Validates the mesh and reports issues, including open and
+///    non-manifold edges
+/// 2. Rewinds each solid to face outward and fixes normals on all triangles
+/// 3. Removes invalid triangles and those under `min_area` square mm, such
+///    as [`DEFAULT_MIN_TRIANGLE_AREA`] or [`min_triangle_area`] for the plate
 ///
 /// Returns the cleaned mesh and validation report
-pub fn validate_and_fix(mut triangles: Vec<Triangle>) -> (Vec<Triangle>, ValidationResult) {
-    let report = validate_mesh(&triangles);
+pub fn validate_and_fix(
+    mut triangles: Vec<Triangle>,
+    min_area: f32,
+) -> (Vec<Triangle>, ValidationResult) {
+    let report = validate_mesh_with_area(&triangles, min_area);
+    make_normals_outward(&mut triangles);
     fix_normals(&mut triangles);
-    let cleaned = remove_degenerate(triangles);
+    let cleaned = remove_degenerate(triangles, min_area);
     (cleaned, report)
 }
 
@@ -275,9 +633,12 @@ mod tests {
         let another_valid = make_triangle([0.0, 0.0, 0.0], [2.0, 0.0, 0.0], [0.0, 2.0, 0.0]);
         let triangles = vec![valid_tri, degenerate_tri, another_valid];
 
-        let cleaned = remove_degenerate(triangles);
+        let cleaned = remove_degenerate(triangles.clone(), DEFAULT_MIN_TRIANGLE_AREA);
 
         assert_eq!(cleaned.len(), 2);
+        // A larger threshold also drops the half square mm triangle
+        assert_eq!(remove_degenerate(triangles, 1.0).len(), 1);
+        assert!((min_triangle_area(440.0) - 4.0 * DEFAULT_MIN_TRIANGLE_AREA).abs() < 1e-15);
     }
 
     #[test]
@@ -298,13 +659,136 @@ mod tests {
         let degenerate_tri = make_triangle([0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [2.0, 0.0, 0.0]);
         let triangles = vec![valid_tri, degenerate_tri];
 
-        let (cleaned, report) = validate_and_fix(triangles);
+        let (cleaned, report) = validate_and_fix(triangles, DEFAULT_MIN_TRIANGLE_AREA);
 
         assert_eq!(report.total, 2);
         assert_eq!(report.degenerate, 1);
         assert_eq!(cleaned.len(), 1);
     }
 
+    /// Closed cube of side `size` at `offset`, wound outward
+    fn make_box(offset: [f32; 3], size: f32) -> Vec<Triangle> {
+        let p = |x: f32, y: f32, z: f32| {
+            [
+                offset[0] + x * size,
+                offset[1] + y * size,
+                offset[2] + z * size,
+            ]
+        };
+        let quads = [
+            [p(0., 0., 0.), p(0., 1., 0.), p(1., 1., 0.), p(1., 0., 0.)],
+            [p(0., 0., 1.), p(1., 0., 1.), p(1., 1., 1.), p(0., 1., 1.)],
+            [p(0., 0., 0.), p(1., 0., 0.), p(1., 0., 1.), p(0., 0., 1.)],
+            [p(0., 1., 0.), p(0., 1., 1.), p(1., 1., 1.), p(1., 1., 0.)],
+            [p(0., 0., 0.), p(0., 0., 1.), p(0., 1., 1.), p(0., 1., 0.)],
+            [p(1., 0., 0.), p(1., 1., 0.), p(1., 1., 1.), p(1., 0., 1.)],
+        ];
+        quads
+            .iter()
+            .flat_map(|q| {
+                [
+                    make_triangle(q[0], q[1], q[2]),
+                    make_triangle(q[0], q[2], q[3]),
+                ]
+            })
+            .collect()
+    }
+
+    fn faces_outward(triangles: &[Triangle], center: [f32; 3]) -> bool {
+        triangles.iter().all(|t| {
+            let c =
+                [0, 1, 2].map(|i| (t.vertices[0][i] + t.vertices[1][i] + t.vertices[2][i]) / 3.0);
+            (0..3)
+                .map(|i| (c[i] - center[i]) * t.normal[i])
+                .sum::<f32>()
+                > 0.0
+        })
+    }
+
+    #[test]
+    fn test_make_normals_outward() {
+        let reference = make_box([0.0; 3], 2.0);
+        assert!(faces_outward(&reference, [1.0; 3]));
+
+        // Whole box inside out, next to a second box with one stray facet
+        let mut triangles: Vec<Triangle> = reference
+            .iter()
+            .map(|t| make_triangle(t.vertices[0], t.vertices[2], t.vertices[1]))
+            .collect();
+        let mut other = make_box([5.0, 0.0, 0.0], 2.0);
+        other[7] = make_triangle(
+            other[7].vertices[0],
+            other[7].vertices[2],
+            other[7].vertices[1],
+        );
+        triangles.extend(other);
+        assert!(!faces_outward(&triangles[..12], [1.0; 3]));
+
+        make_normals_outward(&mut triangles);
+        assert!(faces_outward(&triangles[..12], [1.0; 3]));
+        assert!(faces_outward(&triangles[12..], [6.0, 1.0, 1.0]));
+    }
+
+    #[test]
+    fn test_check_manifold() {
+        // Tetrahedron: closed, every edge shared twice
+        let p = [
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ];
+        let mut tetra = vec![
+            make_triangle(p[0], p[2], p[1]),
+            make_triangle(p[0], p[1], p[3]),
+            make_triangle(p[1], p[2], p[3]),
+            make_triangle(p[2], p[0], p[3]),
+        ];
+        assert!(check_manifold(&tetra).is_watertight());
+
+        // A vertex off by less than the weld tolerance still matches
+        tetra[3].vertices[2][2] += 1e-6;
+        assert!(check_manifold(&tetra).is_watertight());
+
+        // Missing a face leaves its three edges open
+        let open = check_manifold(&tetra[..3]);
+        assert_eq!(open.boundary_edges, 3);
+
+        // A fin on an existing edge makes it non-manifold
+        tetra.push(make_triangle(p[0], p[1], [0.5, -1.0, 0.0]));
+        let fin = check_manifold(&tetra);
+        assert_eq!(fin.non_manifold_edges, 1);
+        assert_eq!(fin.boundary_edges, 2);
+
+        let report = validate_mesh(&tetra);
+        assert_eq!(report.manifold, fin);
+        assert!(report.has_issues());
+    }
+
+    #[test]
+    fn test_count_coplanar_overlaps() {
+        // Two boxes crossing like roads: their tops and bottoms overlap,
+        // their walls only meet at lines
+        let mut crossing = make_box([0.0; 3], 2.0);
+        crossing.extend(make_box([1.0, 1.0, 0.0], 2.0));
+        assert_eq!(count_coplanar_overlaps(&crossing), 4);
+        let report = validate_mesh(&crossing);
+        assert_eq!(report.coplanar_overlaps, 4);
+        // Expected wherever roads cross, so not an issue
+        assert!(!report.has_issues());
+        assert!(report.warnings.is_empty());
+
+        // Boxes side by side share a plane but no area
+        let mut touching = make_box([0.0; 3], 2.0);
+        touching.extend(make_box([2.0, 0.0, 0.0], 2.0));
+        assert_eq!(count_coplanar_overlaps(&touching), 0);
+
+        // A face stacked on another facing the other way is not z-fighting
+        let mut stacked = make_box([0.0; 3], 2.0);
+        stacked.extend(make_box([0.0, 0.0, 2.0], 2.0));
+        assert_eq!(count_coplanar_overlaps(&stacked), 0);
+    }
+
     #[test]
     fn test_triangle_area() {
         let vertices = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
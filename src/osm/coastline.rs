@@ -0,0 +1,337 @@
+//! Sea polygons from `natural=coastline` ways
+//!
+//! OSM coastlines are open linestrings drawn with land on the left and sea on
+//! the right. This module joins them end-to-end, clips the joined chains to
+//! the map bounding box, and closes them along the box edges to produce sea
+//! polygons. Closed rings that stay inside the box are islands and become
+//! holes in the sea.
+
+use super::parser::{point_in_ring, stitch_ways};
+use crate::api::OverpassResponse;
+use crate::domain::WaterPolygon;
+use crate::geometry::bbox::{
+    BBox, clip_polyline, contains, crosses_antimeridian, unwrap_antimeridian,
+};
+use crate::geometry::signed_area;
+use std::collections::HashMap;
+
+/// Tolerance in degrees for deciding a point lies on the bounding box edge
+const EDGE_EPSILON: f64 = 1e-9;
+
+/// Whether a way's tags mark it as a coastline
+pub fn is_coastline_way(tags: &HashMap<String, String>) -> bool {
+    tags.get("natural").is_some_and(|v| v == "coastline")
+}
+
+/// Build sea polygons from the coastline ways in an Overpass response
+///
+/// # Algorithm
+/// 1. Join coastline ways sharing end nodes into chains
+/// 2. Closed chains fully inside the box are islands (counter-clockwise) or
+///    enclosed sea (clockwise)
+/// 3. Other chains are clipped to the box into pieces that enter and exit
+///    through its edges
+/// 4. Pieces are linked by walking clockwise along the box edges from each
+///    exit to the next entry, which keeps the sea on the inside
+/// 5. Islands become holes of the sea polygon containing them; with no
+///    crossing coastline, islands imply the whole box is sea
+pub fn parse_coastline(response: &OverpassResponse, bbox: BBox) -> Vec<WaterPolygon> {
+    let nodes: HashMap<u64, (f64, f64)> = response
+        .elements
+        .iter()
+        .filter(|e| e.type_ == "node")
+        .filter_map(|e| Some((e.id, (e.lat?, e.lon?))))
+        .collect();
+
+    let ways: Vec<Vec<u64>> = response
+        .elements
+        .iter()
+        .filter(|e| e.type_ == "way")
+        .filter(|e| e.tags.as_ref().is_some_and(is_coastline_way))
+        .filter_map(|e| e.nodes.clone())
+        .filter(|n| n.len() >= 2)
+        .collect();
+
+    if ways.is_empty() {
+        return Vec::new();
+    }
+
+    let chains: Vec<Vec<(f64, f64)>> = stitch_ways(ways)
+        .into_iter()
+        .map(|chain| {
+            chain
+                .iter()
+                .filter_map(|id| nodes.get(id).copied())
+                .collect::<Vec<_>>()
+        })
+        .filter(|points| points.len() >= 2)
+        .collect();
+
+    // Across the antimeridian, work east of `west` so the box stays
+    // contiguous; the projector wraps longitudes past 180 back
+    if crosses_antimeridian(bbox) {
+        let bbox = unwrap_antimeridian(bbox);
+        let (_, west, _, east) = bbox;
+        let shift_below = (west + east) / 2.0 - 180.0;
+        let chains: Vec<Vec<(f64, f64)>> = chains
+            .into_iter()
+            .map(|chain| {
+                chain
+                    .into_iter()
+                    .map(|(lat, lon)| (lat, if lon < shift_below { lon + 360.0 } else { lon }))
+                    .collect()
+            })
+            .collect();
+        return build_sea_polygons(&chains, bbox);
+    }
+    build_sea_polygons(&chains, bbox)
+}
+
+fn build_sea_polygons(chains: &[Vec<(f64, f64)>], bbox: BBox) -> Vec<WaterPolygon> {
+    let mut islands = Vec::new();
+    let mut enclosed_sea = Vec::new();
+    let mut pieces = Vec::new();
+
+    for chain in chains {
+        let closed = chain.len() >= 4 && chain[0] == chain[chain.len() - 1];
+        if closed && chain.iter().all(|&p| contains(p, bbox)) {
+            // (lat, lon) points are (y, x), so counter-clockwise on the map
+            // comes out negative
+            if signed_area(chain) < 0.0 {
+                islands.push(chain.clone());
+            } else {
+                enclosed_sea.push(chain.clone());
+            }
+            continue;
+        }
+        pieces.extend(clip_to_bbox(chain, bbox));
+    }
+
+    let mut seas = link_pieces(pieces, bbox);
+    if seas.is_empty() && !islands.is_empty() {
+        seas.push(bbox_ring(bbox));
+    }
+
+    let mut holes: Vec<Vec<Vec<(f64, f64)>>> = vec![Vec::new(); seas.len()];
+    for island in islands {
+        if let Some(i) = seas.iter().position(|sea| point_in_ring(island[0], sea)) {
+            holes[i].push(island);
+        }
+    }
+
+    seas.into_iter()
+        .zip(holes)
+        .map(|(outer, holes)| WaterPolygon::with_holes(outer, holes))
+        .chain(enclosed_sea.into_iter().map(WaterPolygon::new))
+        .filter(|p| p.is_valid())
+        .collect()
+}
+
+/// Split a chain into pieces lying inside the box, each starting and ending
+/// on a box edge. Chain ends that stop inside the box (truncated data) are
+/// extended to the nearest edge.
+fn clip_to_bbox(chain: &[(f64, f64)], bbox: BBox) -> Vec<Vec<(f64, f64)>> {
+    clip_polyline(chain, bbox)
+        .into_iter()
+        .map(|mut piece| {
+            if !on_edge(piece[0], bbox) {
+                piece.insert(0, nearest_edge_point(piece[0], bbox));
+            }
+            let last = piece[piece.len() - 1];
+            if !on_edge(last, bbox) {
+                piece.push(nearest_edge_point(last, bbox));
+            }
+            piece
+        })
+        .collect()
+}
+
+/// Close clipped pieces into rings by walking clockwise along the box edges
+fn link_pieces(pieces: Vec<Vec<(f64, f64)>>, bbox: BBox) -> Vec<Vec<(f64, f64)>> {
+    let entries: Vec<f64> = pieces
+        .iter()
+        .map(|p| perimeter_position(p[0], bbox))
+        .collect();
+    let mut used = vec![false; pieces.len()];
+    let mut rings = Vec::new();
+
+    for start in 0..pieces.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let mut ring = pieces[start].clone();
+
+        // Each piece is visited at most once, which bounds the walk
+        for _ in 0..pieces.len() {
+            let exit = *ring.last().unwrap();
+            let exit_pos = perimeter_position(exit, bbox);
+
+            let next = (0..pieces.len())
+                .filter(|&i| i == start || !used[i])
+                .min_by(|&a, &b| {
+                    let da = clockwise_distance(exit_pos, entries[a]);
+                    let db = clockwise_distance(exit_pos, entries[b]);
+                    da.total_cmp(&db)
+                })
+                .unwrap_or(start);
+
+            let distance = clockwise_distance(exit_pos, entries[next]);
+            for corner in 1..=4 {
+                let corner_pos = (exit_pos.floor() + corner as f64) % 4.0;
+                let corner_distance = clockwise_distance(exit_pos, corner_pos);
+                if corner_distance > 0.0 && corner_distance < distance {
+                    ring.push(corner_point(corner_pos as usize, bbox));
+                }
+            }
+
+            if next == start {
+                ring.push(ring[0]);
+                break;
+            }
+            used[next] = true;
+            ring.extend_from_slice(&pieces[next]);
+        }
+
+        if ring.len() >= 4 {
+            rings.push(ring);
+        }
+    }
+
+    rings
+}
+
+/// Position along the box perimeter in [0, 4), clockwise from the NW corner:
+/// north edge, east edge, south edge, west edge
+fn perimeter_position((lat, lon): (f64, f64), (south, west, north, east): BBox) -> f64 {
+    let width = east - west;
+    let height = north - south;
+    if (lat - north).abs() < EDGE_EPSILON && lon < east {
+        (lon - west) / width
+    } else if (lon - east).abs() < EDGE_EPSILON && lat > south {
+        1.0 + (north - lat) / height
+    } else if (lat - south).abs() < EDGE_EPSILON && lon > west {
+        2.0 + (east - lon) / width
+    } else {
+        3.0 + (lat - south) / height
+    }
+}
+
+fn clockwise_distance(from: f64, to: f64) -> f64 {
+    (to - from).rem_euclid(4.0)
+}
+
+/// Corner at perimeter position 0 (NW), 1 (NE), 2 (SE) or 3 (SW)
+fn corner_point(index: usize, (south, west, north, east): BBox) -> (f64, f64) {
+    match index % 4 {
+        0 => (north, west),
+        1 => (north, east),
+        2 => (south, east),
+        _ => (south, west),
+    }
+}
+
+fn bbox_ring(bbox: BBox) -> Vec<(f64, f64)> {
+    (0..=4).map(|i| corner_point(i, bbox)).collect()
+}
+
+fn on_edge((lat, lon): (f64, f64), (south, west, north, east): BBox) -> bool {
+    (lat - south).abs() < EDGE_EPSILON
+        || (lat - north).abs() < EDGE_EPSILON
+        || (lon - west).abs() < EDGE_EPSILON
+        || (lon - east).abs() < EDGE_EPSILON
+}
+
+fn nearest_edge_point((lat, lon): (f64, f64), (south, west, north, east): BBox) -> (f64, f64) {
+    let candidates = [
+        (lat - south, (south, lon)),
+        (north - lat, (north, lon)),
+        (lon - west, (lat, west)),
+        (east - lon, (lat, east)),
+    ];
+    candidates
+        .into_iter()
+        .min_by(|a, b| a.0.abs().total_cmp(&b.0.abs()))
+        .map(|(_, p)| p)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BBOX: BBox = (0.0, 0.0, 1.0, 1.0);
+
+    fn response(json: &str) -> OverpassResponse {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_stitch_ways_joins_end_to_end() {
+        let chains = stitch_ways(vec![vec![3, 4, 5], vec![1, 2, 3], vec![7, 8]]);
+        assert_eq!(chains.len(), 2);
+        assert!(chains.contains(&vec![1, 2, 3, 4, 5]));
+    }
+
+    #[test]
+    fn test_coastline_sea_on_right() {
+        // Two ways crossing the box west to east at lat 0.5: land north, sea south
+        let response = response(
+            r#"{"elements": [
+                {"type": "node", "id": 1, "lat": 0.5, "lon": -0.5},
+                {"type": "node", "id": 2, "lat": 0.5, "lon": 0.5},
+                {"type": "node", "id": 3, "lat": 0.5, "lon": 1.5},
+                {"type": "way", "id": 10, "nodes": [2, 3], "tags": {"natural": "coastline"}},
+                {"type": "way", "id": 11, "nodes": [1, 2], "tags": {"natural": "coastline"}}
+            ]}"#,
+        );
+
+        let seas = parse_coastline(&response, BBOX);
+        assert_eq!(seas.len(), 1);
+        let sea = &seas[0];
+        assert!(sea.outer.iter().all(|&(lat, _)| lat <= 0.5 + 1e-9));
+        assert!((signed_area(&sea.outer).abs() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_coastline_across_antimeridian() {
+        // Same shore as above, but the box spans 179.5E to 179.5W
+        let response = response(
+            r#"{"elements": [
+                {"type": "node", "id": 1, "lat": 0.5, "lon": 179.0},
+                {"type": "node", "id": 2, "lat": 0.5, "lon": 179.9},
+                {"type": "node", "id": 3, "lat": 0.5, "lon": -179.0},
+                {"type": "way", "id": 10, "nodes": [1, 2, 3], "tags": {"natural": "coastline"}}
+            ]}"#,
+        );
+
+        let seas = parse_coastline(&response, (0.0, 179.5, 1.0, -179.5));
+        assert_eq!(seas.len(), 1);
+        assert!((signed_area(&seas[0].outer).abs() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_island_only_makes_whole_box_sea() {
+        // Counter-clockwise ring (land on the left) fully inside the box
+        let response = response(
+            r#"{"elements": [
+                {"type": "node", "id": 1, "lat": 0.4, "lon": 0.4},
+                {"type": "node", "id": 2, "lat": 0.4, "lon": 0.6},
+                {"type": "node", "id": 3, "lat": 0.6, "lon": 0.6},
+                {"type": "node", "id": 4, "lat": 0.6, "lon": 0.4},
+                {"type": "way", "id": 10, "nodes": [1, 2, 3, 4, 1], "tags": {"natural": "coastline"}}
+            ]}"#,
+        );
+
+        let seas = parse_coastline(&response, BBOX);
+        assert_eq!(seas.len(), 1);
+        assert_eq!(seas[0].outer.len(), 5);
+        assert_eq!(seas[0].holes.len(), 1);
+    }
+
+    #[test]
+    fn test_no_coastline() {
+        let response = response(r#"{"elements": []}"#);
+        assert!(parse_coastline(&response, BBOX).is_empty());
+    }
+}
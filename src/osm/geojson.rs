@@ -0,0 +1,470 @@
+//! Parsed features as a GeoJSON FeatureCollection
+//!
+//! `--dump-geojson <path>` writes every feature after parsing and clipping,
+//! so the geometry that is about to be meshed can be checked in a GIS. Each
+//! feature carries a `layer` property and, where it has one, a `class`.
+//! `--geojson-input <path>` reads such a file back, or one drawn by hand.
+
+use anyhow::{Context, Result, bail};
+use serde_json::{Map, Value, json};
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use super::parser::is_water_way;
+
+use crate::domain::{
+    AerowayClass, Aeroways, BuildingPolygon, CustomFeatures, ParkPolygon, RoadClass, RoadSegment,
+    WaterPolygon,
+};
+
+/// Features collected layer by layer, in GeoJSON (lon, lat) order
+#[derive(Debug, Default)]
+pub struct GeoJsonWriter {
+    features: Vec<Value>,
+}
+
+impl GeoJsonWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Roads or railways as LineStrings with their class and tags
+    pub fn add_segments(&mut self, layer: &str, segments: &[RoadSegment]) {
+        for segment in segments {
+            let mut properties = Map::new();
+            properties.insert("class".into(), road_class_name(segment.class).into());
+            properties.insert("bridge".into(), segment.is_bridge.into());
+            properties.insert("tunnel".into(), segment.is_tunnel.into());
+            properties.insert("oneway".into(), segment.is_oneway.into());
+            if let Some(name) = &segment.name {
+                properties.insert("name".into(), name.as_str().into());
+            }
+            if let Some(lanes) = segment.lanes {
+                properties.insert("lanes".into(), lanes.into());
+            }
+            if let Some(width) = segment.width_m {
+                properties.insert("width".into(), width.into());
+            }
+            self.push(layer, line_string(&segment.points), properties);
+        }
+    }
+
+    pub fn add_water(&mut self, water: &[WaterPolygon]) {
+        for polygon in water {
+            self.push(
+                "water",
+                polygon_geometry(&polygon.outer, &polygon.holes),
+                Map::new(),
+            );
+        }
+    }
+
+    /// Parks, or the areas of another layer that reuses the park polygon
+    pub fn add_areas(&mut self, layer: &str, areas: &[ParkPolygon]) {
+        for polygon in areas {
+            self.push(
+                layer,
+                polygon_geometry(&polygon.outer, &polygon.holes),
+                Map::new(),
+            );
+        }
+    }
+
+    pub fn add_buildings(&mut self, buildings: &[BuildingPolygon]) {
+        for building in buildings {
+            let mut properties = Map::new();
+            if let Some(height) = building.height_m {
+                properties.insert("height".into(), height.into());
+            }
+            self.push(
+                "buildings",
+                polygon_geometry(&building.outer, &[]),
+                properties,
+            );
+        }
+    }
+
+    pub fn add_aeroways(&mut self, aeroways: &Aeroways) {
+        for line in &aeroways.lines {
+            let class = match line.class {
+                AerowayClass::Runway => "runway",
+                AerowayClass::Taxiway => "taxiway",
+            };
+            let mut properties = Map::new();
+            properties.insert("class".into(), class.into());
+            properties.insert("width".into(), line.width_m().into());
+            self.push("aeroways", line_string(&line.points), properties);
+        }
+        for apron in &aeroways.aprons {
+            let mut properties = Map::new();
+            properties.insert("class".into(), "apron".into());
+            self.push(
+                "aeroways",
+                polygon_geometry(&apron.outer, &apron.holes),
+                properties,
+            );
+        }
+    }
+
+    pub fn add_custom(&mut self, custom: &CustomFeatures) {
+        self.add_areas("custom", &custom.areas);
+        for line in &custom.lines {
+            self.push("custom", line_string(line), Map::new());
+        }
+    }
+
+    /// Number of features added so far
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    /// Write the FeatureCollection to `path`
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create GeoJSON file: {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        let collection = json!({
+            "type": "FeatureCollection",
+            "features": self.features,
+        });
+        serde_json::to_writer(&mut writer, &collection).context("Failed to write GeoJSON")?;
+        writer.flush().context("Failed to write GeoJSON")
+    }
+
+    fn push(&mut self, layer: &str, geometry: Value, mut properties: Map<String, Value>) {
+        properties.insert("layer".into(), layer.into());
+        self.features.push(json!({
+            "type": "Feature",
+            "geometry": geometry,
+            "properties": properties,
+        }));
+    }
+}
+
+/// Features read from a GeoJSON FeatureCollection
+#[derive(Debug, Default)]
+pub struct GeoJsonFeatures {
+    pub roads: Vec<RoadSegment>,
+    pub railways: Vec<RoadSegment>,
+    pub water: Vec<WaterPolygon>,
+    pub parks: Vec<ParkPolygon>,
+    pub buildings: Vec<BuildingPolygon>,
+    /// Count of features left out, by reason
+    pub skipped: BTreeMap<String, usize>,
+}
+
+impl GeoJsonFeatures {
+    /// Center of all points as (lat, lon), if there are any
+    pub fn center(&self) -> Option<(f64, f64)> {
+        let lines = self.roads.iter().chain(&self.railways).map(|r| &r.points);
+        let rings = self.water.iter().map(|w| &w.outer);
+        let rings = rings
+            .chain(self.parks.iter().map(|p| &p.outer))
+            .chain(self.buildings.iter().map(|b| &b.outer));
+        let (min, max) = lines.chain(rings).flatten().fold(
+            ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN)),
+            |(min, max), &(lat, lon)| {
+                (
+                    (min.0.min(lat), min.1.min(lon)),
+                    (max.0.max(lat), max.1.max(lon)),
+                )
+            },
+        );
+        (min.0 <= max.0).then(|| ((min.0 + max.0) / 2.0, (min.1 + max.1) / 2.0))
+    }
+
+    fn skip(&mut self, reason: String) {
+        *self.skipped.entry(reason).or_default() += 1;
+    }
+}
+
+/// Read a FeatureCollection into map features
+///
+/// LineStrings become roads, classed by a `class` or `highway` property
+/// (residential when there is neither), or railways when `layer` is
+/// `railways` or the class is `rail`. Polygons become water when `layer` is
+/// `water` or their tags mark water, buildings when `layer` is `buildings`
+/// or they have a `building` property, and parks otherwise. Multi-part
+/// geometries split into one feature per part; other geometry types are
+/// skipped.
+pub fn read_geojson(path: &Path) -> Result<GeoJsonFeatures> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read GeoJSON file: {}", path.display()))?;
+    let value: Value = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse GeoJSON: {}", path.display()))?;
+    if value["type"] != "FeatureCollection" {
+        bail!(
+            "GeoJSON input must be a FeatureCollection: {}",
+            path.display()
+        );
+    }
+
+    let mut features = GeoJsonFeatures::default();
+    for feature in value["features"].as_array().into_iter().flatten() {
+        let properties = feature["properties"].as_object();
+        let tags: HashMap<String, String> = properties
+            .into_iter()
+            .flatten()
+            .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+            .collect();
+        let geometry = &feature["geometry"];
+        let coordinates = &geometry["coordinates"];
+        match geometry["type"].as_str() {
+            Some("LineString") => add_line(&mut features, &tags, properties, coordinates),
+            Some("MultiLineString") => {
+                for line in coordinates.as_array().into_iter().flatten() {
+                    add_line(&mut features, &tags, properties, line);
+                }
+            }
+            Some("Polygon") => add_polygon(&mut features, &tags, properties, coordinates),
+            Some("MultiPolygon") => {
+                for polygon in coordinates.as_array().into_iter().flatten() {
+                    add_polygon(&mut features, &tags, properties, polygon);
+                }
+            }
+            Some(other) => features.skip(format!("{} geometry", other)),
+            None => features.skip("no geometry".to_string()),
+        }
+    }
+    Ok(features)
+}
+
+fn add_line(
+    features: &mut GeoJsonFeatures,
+    tags: &HashMap<String, String>,
+    properties: Option<&Map<String, Value>>,
+    coordinates: &Value,
+) {
+    let points = points(coordinates);
+    if points.len() < 2 {
+        features.skip("LineString with fewer than 2 points".to_string());
+        return;
+    }
+    let class_tag = tags.get("class").or(tags.get("highway"));
+    let railway = tags.get("layer").is_some_and(|l| l == "railways")
+        || class_tag.is_some_and(|c| c == "rail")
+        || tags.contains_key("railway");
+    if railway {
+        features.railways.push(segment_properties(
+            RoadSegment::new(points, RoadClass::Rail),
+            properties,
+        ));
+        return;
+    }
+    let class = match class_tag {
+        Some(tag) => match road_class_from_name(tag).or(RoadClass::from_highway_tag(tag)) {
+            Some(class) => class,
+            None => {
+                features.skip(format!("road class '{}'", tag));
+                return;
+            }
+        },
+        None => RoadClass::Residential,
+    };
+    features.roads.push(segment_properties(
+        RoadSegment::new(points, class),
+        properties,
+    ));
+}
+
+/// Carry the properties written by [`GeoJsonWriter::add_segments`] over
+fn segment_properties(
+    segment: RoadSegment,
+    properties: Option<&Map<String, Value>>,
+) -> RoadSegment {
+    let flag = |key: &str| {
+        properties
+            .and_then(|p| p.get(key)?.as_bool())
+            .unwrap_or(false)
+    };
+    let number = |key: &str| properties.and_then(|p| p.get(key)?.as_f64());
+    segment
+        .with_bridge(flag("bridge"))
+        .with_tunnel(flag("tunnel"))
+        .with_oneway(flag("oneway"))
+        .with_width_m(number("width").map(|w| w as f32))
+        .with_lanes(number("lanes").map(|l| l as u8).filter(|&l| l > 0))
+        .with_name(properties.and_then(|p| Some(p.get("name")?.as_str()?.to_string())))
+}
+
+fn add_polygon(
+    features: &mut GeoJsonFeatures,
+    tags: &HashMap<String, String>,
+    properties: Option<&Map<String, Value>>,
+    coordinates: &Value,
+) {
+    let mut rings = coordinates.as_array().into_iter().flatten().map(points);
+    let outer = match rings.next() {
+        Some(outer) if outer.len() >= 3 => outer,
+        _ => {
+            features.skip("Polygon with fewer than 3 points".to_string());
+            return;
+        }
+    };
+    let holes: Vec<Vec<(f64, f64)>> = rings.filter(|hole| hole.len() >= 3).collect();
+    let layer = tags.get("layer").map(String::as_str);
+    if layer == Some("water") || is_water_way(tags) {
+        features.water.push(WaterPolygon::with_holes(outer, holes));
+    } else if layer == Some("buildings") || tags.contains_key("building") {
+        let height = properties.and_then(|p| p.get("height")?.as_f64());
+        features
+            .buildings
+            .push(BuildingPolygon::new(outer).with_height(height));
+    } else {
+        features.parks.push(ParkPolygon::with_holes(outer, holes));
+    }
+}
+
+/// GeoJSON [lon, lat] positions as (lat, lon) points
+fn points(coordinates: &Value) -> Vec<(f64, f64)> {
+    coordinates
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|position| Some((position.get(1)?.as_f64()?, position.get(0)?.as_f64()?)))
+        .collect()
+}
+
+fn road_class_from_name(name: &str) -> Option<RoadClass> {
+    [
+        RoadClass::Motorway,
+        RoadClass::Primary,
+        RoadClass::Secondary,
+        RoadClass::Tertiary,
+        RoadClass::Residential,
+        RoadClass::Path,
+    ]
+    .into_iter()
+    .find(|&class| road_class_name(class) == name)
+}
+
+fn road_class_name(class: RoadClass) -> &'static str {
+    match class {
+        RoadClass::Motorway => "motorway",
+        RoadClass::Primary => "primary",
+        RoadClass::Secondary => "secondary",
+        RoadClass::Tertiary => "tertiary",
+        RoadClass::Residential => "residential",
+        RoadClass::Path => "path",
+        RoadClass::Rail => "rail",
+    }
+}
+
+/// (lat, lon) points as GeoJSON [lon, lat] positions
+fn positions(points: &[(f64, f64)]) -> Vec<[f64; 2]> {
+    points.iter().map(|&(lat, lon)| [lon, lat]).collect()
+}
+
+fn line_string(points: &[(f64, f64)]) -> Value {
+    json!({ "type": "LineString", "coordinates": positions(points) })
+}
+
+/// Polygon geometry; clipped rings may come open, and GeoJSON wants them
+/// closed
+fn polygon_geometry(outer: &[(f64, f64)], holes: &[Vec<(f64, f64)>]) -> Value {
+    let ring = |points: &[(f64, f64)]| {
+        let mut ring = positions(points);
+        if let (Some(&first), Some(&last)) = (ring.first(), ring.last())
+            && first != last
+        {
+            ring.push(first);
+        }
+        ring
+    };
+    let rings: Vec<Vec<[f64; 2]>> = std::iter::once(ring(outer))
+        .chain(holes.iter().map(|hole| ring(hole)))
+        .collect();
+    json!({ "type": "Polygon", "coordinates": rings })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_geojson_lon_lat_order_and_closed_rings() {
+        let mut writer = GeoJsonWriter::new();
+        writer.add_segments(
+            "roads",
+            &[
+                RoadSegment::new(vec![(10.0, 20.0), (10.5, 20.5)], RoadClass::Primary)
+                    .with_name(Some("Main Street".into())),
+            ],
+        );
+        // An open ring, as clipping leaves them
+        writer.add_water(&[WaterPolygon::new(vec![(1.0, 2.0), (1.0, 3.0), (2.0, 3.0)])]);
+        assert_eq!(writer.len(), 2);
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("features.geojson");
+        writer.write(&path).unwrap();
+        let value: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+        assert_eq!(value["type"], "FeatureCollection");
+        let road = &value["features"][0];
+        assert_eq!(road["geometry"]["coordinates"][0], json!([20.0, 10.0]));
+        assert_eq!(road["properties"]["class"], "primary");
+        assert_eq!(road["properties"]["name"], "Main Street");
+        assert_eq!(road["properties"]["layer"], "roads");
+        let ring = &value["features"][1]["geometry"]["coordinates"][0];
+        assert_eq!(ring.as_array().unwrap().len(), 4);
+        assert_eq!(ring[0], ring[3]);
+
+        // What was written reads back as the same features
+        let features = read_geojson(&path).unwrap();
+        assert_eq!(features.roads.len(), 1);
+        assert_eq!(features.roads[0].class, RoadClass::Primary);
+        assert_eq!(features.roads[0].points[0], (10.0, 20.0));
+        assert_eq!(features.roads[0].name.as_deref(), Some("Main Street"));
+        assert_eq!(features.water.len(), 1);
+    }
+
+    #[test]
+    fn test_read_geojson_classes_and_skips() {
+        let json = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "properties": {"highway": "trunk"},
+                 "geometry": {"type": "LineString", "coordinates": [[0, 0], [1, 1]]}},
+                {"type": "Feature", "properties": {},
+                 "geometry": {"type": "MultiLineString", "coordinates": [[[0, 0], [1, 0]], [[0, 1], [1, 1]]]}},
+                {"type": "Feature", "properties": {"class": "rail"},
+                 "geometry": {"type": "LineString", "coordinates": [[0, 0], [0, 1]]}},
+                {"type": "Feature", "properties": {"highway": "bridleway"},
+                 "geometry": {"type": "LineString", "coordinates": [[0, 0], [0, 1]]}},
+                {"type": "Feature", "properties": {"natural": "water"},
+                 "geometry": {"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 0]]]}},
+                {"type": "Feature", "properties": {"leisure": "park"},
+                 "geometry": {"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 0]]]}},
+                {"type": "Feature", "properties": {"amenity": "cafe"},
+                 "geometry": {"type": "Point", "coordinates": [0, 0]}}
+            ]
+        }"#;
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("input.geojson");
+        std::fs::write(&path, json).unwrap();
+
+        let features = read_geojson(&path).unwrap();
+        let classes: Vec<RoadClass> = features.roads.iter().map(|r| r.class).collect();
+        assert_eq!(
+            classes,
+            vec![
+                RoadClass::Primary,
+                RoadClass::Residential,
+                RoadClass::Residential
+            ]
+        );
+        assert_eq!(features.railways.len(), 1);
+        assert_eq!((features.water.len(), features.parks.len()), (1, 1));
+        assert_eq!(features.skipped.get("Point geometry"), Some(&1));
+        assert_eq!(features.skipped.get("road class 'bridleway'"), Some(&1));
+        assert_eq!(features.center(), Some((0.5, 0.5)));
+    }
+}
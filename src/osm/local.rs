@@ -0,0 +1,290 @@
+//! Saved Overpass responses for offline and reproducible runs
+//!
+//! `--dump-osm <dir>` writes one JSON file per fetched layer; `--osm-file` reads
+//! either such a directory or a single Overpass JSON file containing any mix of
+//! features, which is split into layers by tag.
+
+use anyhow::{Context, Result, bail};
+use std::fs;
+use std::path::Path;
+
+use super::parser::{is_aeroway_way, is_building_way, is_park_way, is_railway_way, is_water_way};
+use crate::api::OverpassResponse;
+use crate::api::overpass::Element;
+
+pub const ROADS_FILE: &str = "roads.json";
+pub const WATER_FILE: &str = "water.json";
+pub const PARKS_FILE: &str = "parks.json";
+pub const BUILDINGS_FILE: &str = "buildings.json";
+pub const RAILWAYS_FILE: &str = "railways.json";
+pub const AEROWAYS_FILE: &str = "aeroways.json";
+pub const CUSTOM_FILE: &str = "custom.json";
+
+/// Which optional feature layers are enabled (roads are always included)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FeatureLayers {
+    pub water: bool,
+    pub parks: bool,
+    pub buildings: bool,
+    pub railways: bool,
+    pub aeroways: bool,
+    /// Features from a custom Overpass query
+    pub custom: bool,
+}
+
+/// Raw Overpass responses for each feature layer
+#[derive(Debug)]
+pub struct OsmData {
+    pub roads: OverpassResponse,
+    pub water: Option<OverpassResponse>,
+    pub parks: Option<OverpassResponse>,
+    pub buildings: Option<OverpassResponse>,
+    pub railways: Option<OverpassResponse>,
+    pub aeroways: Option<OverpassResponse>,
+    pub custom: Option<OverpassResponse>,
+}
+
+impl OsmData {
+    /// Center of all node coordinates as (lat, lon), if any nodes are present
+    pub fn center(&self) -> Option<(f64, f64)> {
+        let mut min = (f64::MAX, f64::MAX);
+        let mut max = (f64::MIN, f64::MIN);
+        let mut any = false;
+
+        let responses = std::iter::once(&self.roads)
+            .chain(self.water.as_ref())
+            .chain(self.parks.as_ref())
+            .chain(self.buildings.as_ref())
+            .chain(self.railways.as_ref())
+            .chain(self.aeroways.as_ref())
+            .chain(self.custom.as_ref());
+        for element in responses.flat_map(|r| &r.elements) {
+            if let (Some(lat), Some(lon)) = (element.lat, element.lon) {
+                min = (min.0.min(lat), min.1.min(lon));
+                max = (max.0.max(lat), max.1.max(lon));
+                any = true;
+            }
+        }
+
+        any.then(|| ((min.0 + max.0) / 2.0, (min.1 + max.1) / 2.0))
+    }
+}
+
+/// Load a single Overpass JSON response from disk
+pub fn load_response(path: &Path) -> Result<OverpassResponse> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read OSM file: {}", path.display()))?;
+    if contents.trim_start().starts_with('<') {
+        bail!(
+            "OSM XML is not supported: {} (save the Overpass response with [out:json])",
+            path.display()
+        );
+    }
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse Overpass JSON: {}", path.display()))
+}
+
+/// Load OSM input from a `--dump-osm` directory or a single JSON file
+///
+/// Optional layers are only loaded when enabled in `layers`.
+pub fn load_osm_input(path: &Path, layers: FeatureLayers) -> Result<OsmData> {
+    if path.is_dir() {
+        let load_optional = |enabled: bool, name: &str| -> Result<Option<OverpassResponse>> {
+            let file = path.join(name);
+            if enabled && file.exists() {
+                load_response(&file).map(Some)
+            } else {
+                Ok(None)
+            }
+        };
+        return Ok(OsmData {
+            roads: load_response(&path.join(ROADS_FILE))?,
+            water: load_optional(layers.water, WATER_FILE)?,
+            parks: load_optional(layers.parks, PARKS_FILE)?,
+            buildings: load_optional(layers.buildings, BUILDINGS_FILE)?,
+            railways: load_optional(layers.railways, RAILWAYS_FILE)?,
+            aeroways: load_optional(layers.aeroways, AEROWAYS_FILE)?,
+            custom: load_optional(layers.custom, CUSTOM_FILE)?,
+        });
+    }
+
+    let response = load_response(path)?;
+    let water = layers.water.then(|| filter_ways(&response, is_water_way));
+    let parks = layers.parks.then(|| filter_ways(&response, is_park_way));
+    let buildings = layers
+        .buildings
+        .then(|| filter_ways(&response, is_building_way));
+    let railways = layers
+        .railways
+        .then(|| filter_ways(&response, is_railway_way));
+    let aeroways = layers
+        .aeroways
+        .then(|| filter_ways(&response, is_aeroway_way));
+    Ok(OsmData {
+        roads: response,
+        water,
+        parks,
+        buildings,
+        railways,
+        aeroways,
+        // Nothing in a single file marks which features a custom query
+        // asked for
+        custom: None,
+    })
+}
+
+/// Save each layer's response into `dir` so the run can be replayed with `--osm-file`
+pub fn save_osm_data(dir: &Path, data: &OsmData) -> Result<()> {
+    fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create dump directory: {}", dir.display()))?;
+
+    save_response(&dir.join(ROADS_FILE), &data.roads)?;
+    if let Some(water) = &data.water {
+        save_response(&dir.join(WATER_FILE), water)?;
+    }
+    if let Some(parks) = &data.parks {
+        save_response(&dir.join(PARKS_FILE), parks)?;
+    }
+    if let Some(buildings) = &data.buildings {
+        save_response(&dir.join(BUILDINGS_FILE), buildings)?;
+    }
+    if let Some(railways) = &data.railways {
+        save_response(&dir.join(RAILWAYS_FILE), railways)?;
+    }
+    if let Some(aeroways) = &data.aeroways {
+        save_response(&dir.join(AEROWAYS_FILE), aeroways)?;
+    }
+    if let Some(custom) = &data.custom {
+        save_response(&dir.join(CUSTOM_FILE), custom)?;
+    }
+
+    Ok(())
+}
+
+fn save_response(path: &Path, response: &OverpassResponse) -> Result<()> {
+    let json = serde_json::to_string(response).context("Failed to serialize Overpass response")?;
+    fs::write(path, json).with_context(|| format!("Failed to write OSM file: {}", path.display()))
+}
+
+/// Keep all nodes plus the ways and relations whose tags match `predicate`,
+/// along with the member ways of kept relations
+fn filter_ways(
+    response: &OverpassResponse,
+    predicate: fn(&std::collections::HashMap<String, String>) -> bool,
+) -> OverpassResponse {
+    let matches = |e: &Element| e.tags.as_ref().is_some_and(predicate);
+    let member_ways: std::collections::HashSet<u64> = response
+        .elements
+        .iter()
+        .filter(|e| e.type_ == "relation" && matches(e))
+        .flat_map(|e| e.members.iter().flatten())
+        .filter(|m| m.type_ == "way")
+        .map(|m| m.ref_)
+        .collect();
+    let elements: Vec<Element> = response
+        .elements
+        .iter()
+        .filter(|e| match e.type_.as_str() {
+            "way" => matches(e) || member_ways.contains(&e.id),
+            "relation" => matches(e),
+            _ => true,
+        })
+        .cloned()
+        .collect();
+    OverpassResponse { elements }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    const MIXED: &str = r#"{
+        "elements": [
+            {"type": "node", "id": 1, "lat": 10.0, "lon": 20.0},
+            {"type": "node", "id": 2, "lat": 10.2, "lon": 20.4},
+            {"type": "way", "id": 10, "nodes": [1, 2], "tags": {"highway": "primary"}},
+            {"type": "way", "id": 11, "nodes": [1, 2], "tags": {"natural": "water"}},
+            {"type": "way", "id": 12, "nodes": [1, 2], "tags": {"leisure": "park"}},
+            {"type": "way", "id": 13, "nodes": [1, 2], "tags": {"building": "yes"}},
+            {"type": "way", "id": 14, "nodes": [1, 2], "tags": {"railway": "tram"}},
+            {"type": "way", "id": 15, "nodes": [1, 2], "tags": {"aeroway": "runway"}}
+        ]
+    }"#;
+
+    fn way_ids(response: &OverpassResponse) -> Vec<u64> {
+        response
+            .elements
+            .iter()
+            .filter(|e| e.type_ == "way")
+            .map(|e| e.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_load_single_file_splits_layers() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mixed.json");
+        fs::write(&path, MIXED).unwrap();
+
+        let layers = FeatureLayers {
+            water: true,
+            parks: true,
+            buildings: true,
+            railways: true,
+            aeroways: true,
+            custom: true,
+        };
+        let data = load_osm_input(&path, layers).unwrap();
+        assert_eq!(way_ids(&data.roads), vec![10, 11, 12, 13, 14, 15]);
+        assert_eq!(way_ids(data.water.as_ref().unwrap()), vec![11]);
+        assert_eq!(way_ids(data.parks.as_ref().unwrap()), vec![12]);
+        assert_eq!(way_ids(data.buildings.as_ref().unwrap()), vec![13]);
+        assert_eq!(way_ids(data.railways.as_ref().unwrap()), vec![14]);
+        assert_eq!(way_ids(data.aeroways.as_ref().unwrap()), vec![15]);
+
+        let (lat, lon) = data.center().unwrap();
+        assert!((lat - 10.1).abs() < 1e-9);
+        assert!((lon - 20.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_dump_and_reload_directory() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("mixed.json");
+        fs::write(&path, MIXED).unwrap();
+        let water_only = FeatureLayers {
+            water: true,
+            ..Default::default()
+        };
+        let data = load_osm_input(&path, water_only).unwrap();
+
+        let dump_dir = dir.path().join("dump");
+        save_osm_data(&dump_dir, &data).unwrap();
+        assert!(dump_dir.join(ROADS_FILE).exists());
+        assert!(dump_dir.join(WATER_FILE).exists());
+        assert!(!dump_dir.join(PARKS_FILE).exists());
+
+        let all = FeatureLayers {
+            water: true,
+            parks: true,
+            buildings: true,
+            railways: true,
+            aeroways: true,
+            custom: true,
+        };
+        let reloaded = load_osm_input(&dump_dir, all).unwrap();
+        assert_eq!(reloaded.roads.elements.len(), data.roads.elements.len());
+        assert_eq!(way_ids(reloaded.water.as_ref().unwrap()), vec![11]);
+        assert!(reloaded.parks.is_none());
+    }
+
+    #[test]
+    fn test_rejects_osm_xml() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("map.osm");
+        fs::write(&path, "<?xml version='1.0'?><osm></osm>").unwrap();
+
+        assert!(load_response(&path).is_err());
+    }
+}
@@ -1,3 +1,12 @@
+pub mod coastline;
+pub mod geojson;
+pub mod local;
 pub mod parser;
 
-pub use parser::{parse_parks, parse_roads, parse_water};
+pub use coastline::parse_coastline;
+pub use geojson::{GeoJsonFeatures, GeoJsonWriter, read_geojson};
+pub use local::{FeatureLayers, OsmData, load_osm_input, save_osm_data};
+pub use parser::{
+    parse_aeroways, parse_boundary, parse_buildings, parse_custom, parse_parks, parse_railways,
+    parse_roads, parse_water,
+};
@@ -1,6 +1,13 @@
+use super::coastline::is_coastline_way;
 use crate::api::OverpassResponse;
-use crate::domain::{ParkPolygon, RoadClass, RoadSegment, WaterPolygon};
-use std::collections::HashMap;
+use crate::api::overpass::Element;
+use crate::domain::building::{parse_height_tag, parse_levels_tag};
+use crate::domain::road::parse_lanes_tag;
+use crate::domain::{
+    AerowayClass, AerowayLine, Aeroways, BuildingPolygon, CustomFeatures, ParkPolygon, RoadClass,
+    RoadSegment, WaterPolygon,
+};
+use std::collections::{HashMap, HashSet};
 
 /// Parse Overpass response into domain road segments
 ///
@@ -9,7 +16,12 @@ use std::collections::HashMap;
 /// 2. For each way element with highway tag:
 ///    - Resolve node refs to coordinates
 ///    - Classify road type from highway tag
-pub fn parse_roads(response: &OverpassResponse) -> Vec<RoadSegment> {
+///    - Flag bridges and tunnels from their tags
+///    - Read `width` (same units as `height`) and `lanes` when tagged
+///
+/// Footways and other paths are skipped unless `include_paths` is set, so
+/// a response fetched at depth `all` keeps to streets by default.
+pub fn parse_roads(response: &OverpassResponse, include_paths: bool) -> Vec<RoadSegment> {
     // Step 1: Build node lookup map
     let nodes: HashMap<u64, (f64, f64)> = response
         .elements
@@ -25,11 +37,7 @@ pub fn parse_roads(response: &OverpassResponse) -> Vec<RoadSegment> {
     // Step 2: Process ways into road segments
     let mut roads = Vec::new();
 
-    for element in &response.elements {
-        if element.type_ != "way" {
-            continue;
-        }
-
+    for element in unique_elements(response, "way") {
         // Get highway tag
         let tags = match &element.tags {
             Some(t) => t,
@@ -43,6 +51,7 @@ pub fn parse_roads(response: &OverpassResponse) -> Vec<RoadSegment> {
 
         // Classify road type
         let class = match RoadClass::from_highway_tag(highway) {
+            Some(RoadClass::Path) if !include_paths => continue,
             Some(c) => c,
             None => continue, // Skip unknown road types
         };
@@ -53,7 +62,7 @@ pub fn parse_roads(response: &OverpassResponse) -> Vec<RoadSegment> {
             None => continue,
         };
 
-        let points: Vec<(f64, f64)> = node_refs
+        let mut points: Vec<(f64, f64)> = node_refs
             .iter()
             .filter_map(|id| nodes.get(id).copied())
             .collect();
@@ -63,12 +72,178 @@ pub fn parse_roads(response: &OverpassResponse) -> Vec<RoadSegment> {
             continue;
         }
 
-        roads.push(RoadSegment::new(points, class));
+        let direction = oneway_direction(tags);
+        if direction == Some(Oneway::Reverse) {
+            points.reverse();
+        }
+
+        roads.push(
+            RoadSegment::new(points, class)
+                .with_oneway(direction.is_some())
+                .with_bridge(is_bridge(tags))
+                .with_tunnel(is_underground(tags))
+                .with_width_m(
+                    tags.get("width")
+                        .and_then(|w| parse_height_tag(w))
+                        .map(|w| w as f32),
+                )
+                .with_lanes(tags.get("lanes").and_then(|l| parse_lanes_tag(l)))
+                .with_name(tags.get("name").cloned()),
+        );
     }
 
     roads
 }
 
+/// Parse Overpass response into rail line segments
+///
+/// Ways tagged as underground (`layer` below 0 or `tunnel=yes`) are skipped
+/// unless `include_underground` is set, since they are not visible on a
+/// surface map.
+pub fn parse_railways(response: &OverpassResponse, include_underground: bool) -> Vec<RoadSegment> {
+    let nodes = build_node_lookup(response);
+    let mut railways = Vec::new();
+
+    for element in unique_elements(response, "way") {
+        let tags = match &element.tags {
+            Some(t) => t,
+            None => continue,
+        };
+
+        let class = match tags
+            .get("railway")
+            .and_then(|r| RoadClass::from_railway_tag(r))
+        {
+            Some(c) => c,
+            None => continue,
+        };
+
+        if !include_underground && is_underground(tags) {
+            continue;
+        }
+
+        let node_refs = match &element.nodes {
+            Some(n) => n,
+            None => continue,
+        };
+
+        let points = resolve_way_to_points(node_refs, &nodes);
+        if points.len() < 2 {
+            continue;
+        }
+
+        railways.push(
+            RoadSegment::new(points, class)
+                .with_bridge(is_bridge(tags))
+                .with_tunnel(is_underground(tags)),
+        );
+    }
+
+    railways
+}
+
+/// Whether a way is tagged as running below ground
+fn is_underground(tags: &HashMap<String, String>) -> bool {
+    let below_ground = tags
+        .get("layer")
+        .and_then(|l| l.trim().parse::<i32>().ok())
+        .is_some_and(|layer| layer < 0);
+    below_ground || tags.get("tunnel").is_some_and(|t| t == "yes")
+}
+
+/// Direction of travel of a one-way road relative to its nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Oneway {
+    Forward,
+    Reverse,
+}
+
+/// Read the `oneway` tag, which roundabouts and motorways imply unless it
+/// says `no`
+fn oneway_direction(tags: &HashMap<String, String>) -> Option<Oneway> {
+    match tags.get("oneway").map(String::as_str) {
+        Some("yes" | "true" | "1") => Some(Oneway::Forward),
+        Some("-1" | "reverse") => Some(Oneway::Reverse),
+        Some(_) => None,
+        None => {
+            let implied = tags.get("junction").is_some_and(|j| j == "roundabout")
+                || tags
+                    .get("highway")
+                    .is_some_and(|h| h == "motorway" || h == "motorway_link");
+            implied.then_some(Oneway::Forward)
+        }
+    }
+}
+
+/// Whether a way is carried on a bridge (`bridge=yes`, `viaduct`, ...)
+fn is_bridge(tags: &HashMap<String, String>) -> bool {
+    tags.get("bridge").is_some_and(|b| b != "no")
+}
+
+/// Join ways end-to-end wherever one ends on the node where another starts
+///
+/// Ways keep their direction, which coastlines rely on to tell land from sea.
+pub(super) fn stitch_ways(remaining: Vec<Vec<u64>>) -> Vec<Vec<u64>> {
+    stitch(remaining, false)
+}
+
+/// Join relation member ways into rings, turning around those that run
+/// against the chain they join
+fn stitch_rings(remaining: Vec<Vec<u64>>) -> Vec<Vec<u64>> {
+    stitch(remaining, true)
+}
+
+fn stitch(mut remaining: Vec<Vec<u64>>, reversible: bool) -> Vec<Vec<u64>> {
+    let mut chains = Vec::new();
+
+    while let Some(mut chain) = remaining.pop() {
+        loop {
+            let first = chain[0];
+            let last = chain[chain.len() - 1];
+            if first == last {
+                break;
+            }
+            if let Some(pos) = remaining.iter().position(|w| w[0] == last) {
+                let next = remaining.swap_remove(pos);
+                chain.extend_from_slice(&next[1..]);
+            } else if reversible
+                && let Some(pos) = remaining.iter().position(|w| w[w.len() - 1] == last)
+            {
+                let next = remaining.swap_remove(pos);
+                chain.extend(next.iter().rev().skip(1));
+            } else if let Some(pos) = remaining.iter().position(|w| w[w.len() - 1] == first) {
+                let mut prev = remaining.swap_remove(pos);
+                prev.extend_from_slice(&chain[1..]);
+                chain = prev;
+            } else if reversible && let Some(pos) = remaining.iter().position(|w| w[0] == first) {
+                let mut prev = remaining.swap_remove(pos);
+                prev.reverse();
+                prev.extend_from_slice(&chain[1..]);
+                chain = prev;
+            } else {
+                break;
+            }
+        }
+        chains.push(chain);
+    }
+
+    chains
+}
+
+/// Ray-casting point-in-polygon test on (lat, lon) rings
+pub(super) fn point_in_ring((lat, lon): (f64, f64), ring: &[(f64, f64)]) -> bool {
+    let mut inside = false;
+    for w in ring.windows(2) {
+        let ((lat_a, lon_a), (lat_b, lon_b)) = (w[0], w[1]);
+        if (lat_a > lat) != (lat_b > lat)
+            && lon < lon_a + (lat - lat_a) / (lat_b - lat_a) * (lon_b - lon_a)
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
 fn build_node_lookup(response: &OverpassResponse) -> HashMap<u64, (f64, f64)> {
     response
         .elements
@@ -98,12 +273,50 @@ fn is_closed_way(points: &[(f64, f64)]) -> bool {
     (first.0 - last.0).abs() < 1e-9 && (first.1 - last.1).abs() < 1e-9
 }
 
+/// Whether a way's tags mark it as a water feature (mirrors the `fetch_water` query)
+pub fn is_water_way(tags: &HashMap<String, String>) -> bool {
+    let tag_is =
+        |key: &str, values: &[&str]| tags.get(key).is_some_and(|v| values.contains(&v.as_str()));
+    tags.contains_key("water")
+        || tag_is("natural", &["water", "coastline"])
+        || tag_is("waterway", &["riverbank", "river"])
+        || tag_is("landuse", &["reservoir"])
+}
+
+/// Whether a way's tags mark it as a park feature (mirrors the `fetch_parks` query)
+pub fn is_park_way(tags: &HashMap<String, String>) -> bool {
+    let tag_is =
+        |key: &str, values: &[&str]| tags.get(key).is_some_and(|v| values.contains(&v.as_str()));
+    tag_is("leisure", &["park", "garden", "nature_reserve"])
+        || tag_is("landuse", &["grass", "meadow", "forest"])
+        || tag_is("natural", &["wood"])
+}
+
+/// Whether a way's tags mark it as a rail line (mirrors the `fetch_railways` query)
+pub fn is_railway_way(tags: &HashMap<String, String>) -> bool {
+    tags.get("railway")
+        .is_some_and(|r| RoadClass::from_railway_tag(r).is_some())
+}
+
+/// Whether a way's tags mark it as a runway, taxiway or apron (mirrors the
+/// `fetch_aeroways` query)
+pub fn is_aeroway_way(tags: &HashMap<String, String>) -> bool {
+    tags.get("aeroway")
+        .is_some_and(|a| is_apron(tags) || AerowayClass::from_aeroway_tag(a).is_some())
+}
+
+/// Whether a way's tags mark it as a building (mirrors the `fetch_buildings` query)
+pub fn is_building_way(tags: &HashMap<String, String>) -> bool {
+    tags.contains_key("building")
+}
+
 pub fn parse_water(response: &OverpassResponse) -> Vec<WaterPolygon> {
     let nodes = build_node_lookup(response);
     let mut water_polygons = Vec::new();
 
-    for element in &response.elements {
-        if element.type_ != "way" {
+    for element in unique_elements(response, "way") {
+        // Coastlines are stitched against the map bounds by `parse_coastline`
+        if element.tags.as_ref().is_some_and(is_coastline_way) {
             continue;
         }
 
@@ -128,15 +341,216 @@ pub fn parse_water(response: &OverpassResponse) -> Vec<WaterPolygon> {
     water_polygons
 }
 
+/// Parse Overpass response into park polygons
+///
+/// Closed ways become hole-free parks. Multipolygon relations are assembled
+/// from their member ways: `outer` rings become parks and `inner` rings
+/// (ponds, buildings) become holes of the outer ring containing them. Member
+/// ways are not emitted again as standalone parks.
 pub fn parse_parks(response: &OverpassResponse) -> Vec<ParkPolygon> {
+    let (mut park_polygons, member_ways) = parse_multipolygons(response, is_park_way);
+    park_polygons.extend(closed_ways(response, &member_ways).map(ParkPolygon::new));
+    park_polygons
+}
+
+/// Parse Overpass response into runways, taxiways and aprons
+///
+/// Runway and taxiway ways become centerlines with their `width` tag. Aprons
+/// are assembled like parks from closed ways and multipolygon relations.
+pub fn parse_aeroways(response: &OverpassResponse) -> Aeroways {
+    let (mut aprons, member_ways) = parse_multipolygons(response, is_apron);
     let nodes = build_node_lookup(response);
-    let mut park_polygons = Vec::new();
+    let mut lines = Vec::new();
 
-    for element in &response.elements {
-        if element.type_ != "way" {
+    for element in unique_elements(response, "way") {
+        if member_ways.contains(&element.id) {
+            continue;
+        }
+        let (Some(tags), Some(node_refs)) = (&element.tags, &element.nodes) else {
             continue;
+        };
+        let points = resolve_way_to_points(node_refs, &nodes);
+
+        if is_apron(tags) {
+            if is_closed_way(&points) && points.len() >= 4 {
+                aprons.push(ParkPolygon::new(points));
+            }
+            continue;
+        }
+        let Some(class) = tags
+            .get("aeroway")
+            .and_then(|a| AerowayClass::from_aeroway_tag(a))
+        else {
+            continue;
+        };
+        if points.len() < 2 {
+            continue;
+        }
+        lines.push(
+            AerowayLine::new(points, class).with_width_m(
+                tags.get("width")
+                    .and_then(|w| parse_height_tag(w))
+                    .map(|w| w as f32),
+            ),
+        );
+    }
+
+    Aeroways { lines, aprons }
+}
+
+fn is_apron(tags: &HashMap<String, String>) -> bool {
+    tags.get("aeroway").is_some_and(|a| a == "apron")
+}
+
+/// Parse the response of a custom Overpass query, keeping every feature
+///
+/// Multipolygon relations and closed ways become areas as in
+/// [`parse_parks`]; the remaining open ways become lines. Lone nodes are
+/// ignored.
+pub fn parse_custom(response: &OverpassResponse) -> CustomFeatures {
+    let (mut areas, member_ways) = parse_multipolygons(response, |_| true);
+    areas.extend(closed_ways(response, &member_ways).map(ParkPolygon::new));
+
+    let nodes = build_node_lookup(response);
+    let lines = unique_elements(response, "way")
+        .filter(|e| !member_ways.contains(&e.id))
+        .filter_map(|e| e.nodes.as_ref())
+        .map(|refs| resolve_way_to_points(refs, &nodes))
+        .filter(|points| points.len() >= 2 && !is_closed_way(points))
+        .collect();
+
+    CustomFeatures { areas, lines }
+}
+
+/// Outline of a boundary relation, such as a city or district
+///
+/// Assembles every `type=boundary` or `type=multipolygon` relation in the
+/// response from its outer and inner ways; an exclave becomes a polygon of
+/// its own.
+pub fn parse_boundary(response: &OverpassResponse) -> Vec<ParkPolygon> {
+    assemble_relations(response, |tags| {
+        matches!(
+            tags.get("type").map(String::as_str),
+            Some("boundary" | "multipolygon")
+        )
+    })
+    .0
+}
+
+/// Assemble the multipolygon relations whose tags match `predicate`, along
+/// with the ids of their member ways
+fn parse_multipolygons(
+    response: &OverpassResponse,
+    predicate: fn(&HashMap<String, String>) -> bool,
+) -> (Vec<ParkPolygon>, HashSet<u64>) {
+    assemble_relations(response, |tags| {
+        tags.get("type").is_some_and(|v| v == "multipolygon") && predicate(tags)
+    })
+}
+
+/// Assemble the relations whose tags are `selected` into polygons from
+/// their outer and inner ways, along with the ids of those ways
+fn assemble_relations(
+    response: &OverpassResponse,
+    selected: impl Fn(&HashMap<String, String>) -> bool,
+) -> (Vec<ParkPolygon>, HashSet<u64>) {
+    let nodes = build_node_lookup(response);
+    let way_nodes: HashMap<u64, &Vec<u64>> = response
+        .elements
+        .iter()
+        .filter(|e| e.type_ == "way")
+        .filter_map(|e| Some((e.id, e.nodes.as_ref()?)))
+        .collect();
+
+    let mut polygons = Vec::new();
+    let mut member_ways = HashSet::new();
+
+    for element in unique_elements(response, "relation") {
+        let is_selected = element.tags.as_ref().is_some_and(&selected);
+        let members = match &element.members {
+            Some(m) if is_selected => m,
+            _ => continue,
+        };
+
+        let mut outer_ways = Vec::new();
+        let mut inner_ways = Vec::new();
+        for member in members.iter().filter(|m| m.type_ == "way") {
+            let Some(refs) = way_nodes.get(&member.ref_) else {
+                continue;
+            };
+            member_ways.insert(member.ref_);
+            if member.role == "inner" {
+                inner_ways.push((*refs).clone());
+            } else {
+                outer_ways.push((*refs).clone());
+            }
         }
 
+        let closed_rings = |ways: Vec<Vec<u64>>| -> Vec<Vec<(f64, f64)>> {
+            stitch_rings(ways)
+                .into_iter()
+                .map(|chain| resolve_way_to_points(&chain, &nodes))
+                .filter(|points| is_closed_way(points) && points.len() >= 4)
+                .collect()
+        };
+        let outers = closed_rings(outer_ways);
+        let mut inners = closed_rings(inner_ways);
+
+        for outer in outers {
+            let (holes, rest): (Vec<_>, Vec<_>) = inners
+                .into_iter()
+                .partition(|inner| point_in_ring(inner[0], &outer));
+            inners = rest;
+            polygons.push(ParkPolygon::with_holes(outer, holes));
+        }
+    }
+
+    (polygons, member_ways)
+}
+
+/// Rings of the closed ways that are not in `skip`
+fn closed_ways<'a>(
+    response: &'a OverpassResponse,
+    skip: &'a HashSet<u64>,
+) -> impl Iterator<Item = Vec<(f64, f64)>> + 'a {
+    let nodes = build_node_lookup(response);
+    unique_elements(response, "way")
+        .filter(move |e| !skip.contains(&e.id))
+        .filter_map(|e| e.nodes.as_ref())
+        .map(move |refs| resolve_way_to_points(refs, &nodes))
+        .filter(|points| is_closed_way(points) && points.len() >= 4)
+}
+
+/// Elements of type `kind`, each id once
+///
+/// Overlapping queries can return the same way twice, which would otherwise
+/// be meshed twice on top of itself.
+fn unique_elements<'a>(
+    response: &'a OverpassResponse,
+    kind: &'a str,
+) -> impl Iterator<Item = &'a Element> + 'a {
+    let mut seen = HashSet::new();
+    response
+        .elements
+        .iter()
+        .filter(move |e| e.type_ == kind && seen.insert(e.id))
+}
+
+/// Real-world building height in meters from `height`, falling back to `building:levels`
+fn building_height_from_tags(tags: &HashMap<String, String>) -> Option<f64> {
+    tags.get("height")
+        .and_then(|h| parse_height_tag(h))
+        .or_else(|| {
+            tags.get("building:levels")
+                .and_then(|l| parse_levels_tag(l))
+        })
+}
+
+pub fn parse_buildings(response: &OverpassResponse) -> Vec<BuildingPolygon> {
+    let nodes = build_node_lookup(response);
+    let mut building_polygons = Vec::new();
+
+    for element in unique_elements(response, "way") {
         let node_refs = match &element.nodes {
             Some(n) => n,
             None => continue,
@@ -152,16 +566,18 @@ pub fn parse_parks(response: &OverpassResponse) -> Vec<ParkPolygon> {
             continue;
         }
 
-        park_polygons.push(ParkPolygon::new(points));
+        let height_m = element.tags.as_ref().and_then(building_height_from_tags);
+        building_polygons.push(BuildingPolygon::new(points).with_height(height_m));
     }
 
-    park_polygons
+    building_polygons
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::api::overpass::Element;
+    use crate::geometry::{Area, Bounds, Projector, Scaler};
+    use crate::layers::generate_boundary;
 
     #[test]
     fn test_parse_roads() {
@@ -173,6 +589,7 @@ mod tests {
                     lat: Some(37.77),
                     lon: Some(-122.42),
                     nodes: None,
+                    members: None,
                     tags: None,
                 },
                 Element {
@@ -181,6 +598,7 @@ mod tests {
                     lat: Some(37.78),
                     lon: Some(-122.43),
                     nodes: None,
+                    members: None,
                     tags: None,
                 },
                 Element {
@@ -189,6 +607,7 @@ mod tests {
                     lat: None,
                     lon: None,
                     nodes: Some(vec![1, 2]),
+                    members: None,
                     tags: Some({
                         let mut m = HashMap::new();
                         m.insert("highway".to_string(), "primary".to_string());
@@ -198,9 +617,342 @@ mod tests {
             ],
         };
 
-        let roads = parse_roads(&response);
+        let roads = parse_roads(&response, false);
         assert_eq!(roads.len(), 1);
         assert_eq!(roads[0].class, RoadClass::Primary);
         assert_eq!(roads[0].points.len(), 2);
+        assert!(!roads[0].is_bridge && !roads[0].is_tunnel);
+    }
+
+    #[test]
+    fn test_parse_roads_structure_and_width_tags() {
+        let json = r#"{
+            "elements": [
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0},
+                {"type": "node", "id": 2, "lat": 0.0, "lon": 0.001},
+                {"type": "way", "id": 10, "nodes": [1, 2], "tags": {"highway": "primary", "bridge": "viaduct", "layer": "1"}},
+                {"type": "way", "id": 11, "nodes": [1, 2], "tags": {"highway": "primary", "tunnel": "yes", "layer": "-1"}},
+                {"type": "way", "id": 12, "nodes": [1, 2], "tags": {"highway": "primary", "bridge": "no", "lanes": "6", "width": "21 m"}}
+            ]
+        }"#;
+        let response: OverpassResponse = serde_json::from_str(json).unwrap();
+
+        let roads = parse_roads(&response, false);
+        let flags: Vec<(bool, bool)> = roads.iter().map(|r| (r.is_bridge, r.is_tunnel)).collect();
+        assert_eq!(flags, vec![(true, false), (false, true), (false, false)]);
+        assert_eq!((roads[0].lanes, roads[0].width_m), (None, None));
+        assert_eq!((roads[2].lanes, roads[2].width_m), (Some(6), Some(21.0)));
+    }
+
+    #[test]
+    fn test_parse_skips_duplicate_ways() {
+        let json = r#"{
+            "elements": [
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0},
+                {"type": "node", "id": 2, "lat": 0.0, "lon": 0.001},
+                {"type": "node", "id": 3, "lat": 0.001, "lon": 0.001},
+                {"type": "way", "id": 10, "nodes": [1, 2], "tags": {"highway": "primary"}},
+                {"type": "way", "id": 10, "nodes": [1, 2], "tags": {"highway": "primary"}},
+                {"type": "way", "id": 11, "nodes": [1, 2, 3, 1], "tags": {"leisure": "park"}},
+                {"type": "way", "id": 11, "nodes": [1, 2, 3, 1], "tags": {"leisure": "park"}},
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0}
+            ]
+        }"#;
+        let response: OverpassResponse = serde_json::from_str(json).unwrap();
+
+        assert_eq!(parse_roads(&response, false).len(), 1);
+        assert_eq!(parse_parks(&response).len(), 1);
+        // The water parser takes every closed way of its own response
+        assert_eq!(parse_water(&response).len(), 1);
+    }
+
+    #[test]
+    fn test_parse_roads_paths_opt_in() {
+        let json = r#"{
+            "elements": [
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0},
+                {"type": "node", "id": 2, "lat": 0.0, "lon": 0.001},
+                {"type": "way", "id": 10, "nodes": [1, 2], "tags": {"highway": "residential"}},
+                {"type": "way", "id": 11, "nodes": [1, 2], "tags": {"highway": "footway"}},
+                {"type": "way", "id": 12, "nodes": [1, 2], "tags": {"highway": "steps"}}
+            ]
+        }"#;
+        let response: OverpassResponse = serde_json::from_str(json).unwrap();
+
+        assert_eq!(parse_roads(&response, false).len(), 1);
+        let roads = parse_roads(&response, true);
+        assert_eq!(roads.len(), 3);
+        assert!(roads[1..].iter().all(|r| r.class == RoadClass::Path));
+    }
+
+    #[test]
+    fn test_parse_roads_oneway() {
+        let json = r#"{
+            "elements": [
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0},
+                {"type": "node", "id": 2, "lat": 0.0, "lon": 0.001},
+                {"type": "way", "id": 10, "nodes": [1, 2], "tags": {"highway": "primary", "oneway": "yes"}},
+                {"type": "way", "id": 11, "nodes": [1, 2], "tags": {"highway": "primary", "oneway": "-1"}},
+                {"type": "way", "id": 12, "nodes": [1, 2], "tags": {"highway": "residential", "junction": "roundabout"}},
+                {"type": "way", "id": 13, "nodes": [1, 2], "tags": {"highway": "motorway", "oneway": "no"}},
+                {"type": "way", "id": 14, "nodes": [1, 2], "tags": {"highway": "primary"}}
+            ]
+        }"#;
+        let response: OverpassResponse = serde_json::from_str(json).unwrap();
+
+        let roads = parse_roads(&response, false);
+        let oneway: Vec<bool> = roads.iter().map(|r| r.is_oneway).collect();
+        assert_eq!(oneway, vec![true, true, true, false, false]);
+        // oneway=-1 runs against the nodes, so its points are flipped
+        assert_eq!(roads[0].points[0], (0.0, 0.0));
+        assert_eq!(roads[1].points[0], (0.0, 0.001));
+    }
+
+    #[test]
+    fn test_parse_aeroways() {
+        let json = r#"{
+            "elements": [
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0},
+                {"type": "node", "id": 2, "lat": 0.0, "lon": 0.01},
+                {"type": "node", "id": 3, "lat": 0.005, "lon": 0.01},
+                {"type": "way", "id": 10, "nodes": [1, 2], "tags": {"aeroway": "runway", "width": "60"}},
+                {"type": "way", "id": 11, "nodes": [2, 3], "tags": {"aeroway": "taxiway"}},
+                {"type": "way", "id": 12, "nodes": [1, 2, 3, 1], "tags": {"aeroway": "apron"}},
+                {"type": "way", "id": 13, "nodes": [1, 3], "tags": {"aeroway": "windsock"}}
+            ]
+        }"#;
+        let response: OverpassResponse = serde_json::from_str(json).unwrap();
+
+        let aeroways = parse_aeroways(&response);
+        let lines: Vec<(AerowayClass, f32)> = aeroways
+            .lines
+            .iter()
+            .map(|l| (l.class, l.width_m()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![(AerowayClass::Runway, 60.0), (AerowayClass::Taxiway, 23.0)]
+        );
+        assert_eq!(aeroways.aprons.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_custom_areas_and_lines() {
+        let json = r#"{
+            "elements": [
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0},
+                {"type": "node", "id": 2, "lat": 0.0, "lon": 0.001},
+                {"type": "node", "id": 3, "lat": 0.001, "lon": 0.001},
+                {"type": "way", "id": 10, "nodes": [1, 2, 3, 1], "tags": {"amenity": "parking"}},
+                {"type": "way", "id": 11, "nodes": [1, 2, 3], "tags": {"aeroway": "runway"}},
+                {"type": "way", "id": 12, "nodes": [1, 2, 3]},
+                {"type": "way", "id": 13, "nodes": [3, 1]},
+                {"type": "relation", "id": 20, "members": [
+                    {"type": "way", "ref": 12, "role": "outer"},
+                    {"type": "way", "ref": 13, "role": "outer"}
+                ], "tags": {"type": "multipolygon", "aeroway": "apron"}}
+            ]
+        }"#;
+        let response: OverpassResponse = serde_json::from_str(json).unwrap();
+
+        let custom = parse_custom(&response);
+        // The relation and the closed way, whatever their tags
+        assert_eq!(custom.areas.len(), 2);
+        // Only the open way outside the relation is a line
+        assert_eq!(
+            custom.lines,
+            vec![vec![(0.0, 0.0), (0.0, 0.001), (0.001, 0.001)]]
+        );
+    }
+
+    #[test]
+    fn test_parse_railways_skips_underground() {
+        let json = r#"{
+            "elements": [
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0},
+                {"type": "node", "id": 2, "lat": 0.0, "lon": 0.001},
+                {"type": "way", "id": 10, "nodes": [1, 2], "tags": {"railway": "rail"}},
+                {"type": "way", "id": 11, "nodes": [1, 2], "tags": {"railway": "subway", "layer": "-2"}},
+                {"type": "way", "id": 12, "nodes": [1, 2], "tags": {"railway": "tram", "tunnel": "yes"}},
+                {"type": "way", "id": 13, "nodes": [1, 2], "tags": {"railway": "platform"}}
+            ]
+        }"#;
+        let response: OverpassResponse = serde_json::from_str(json).unwrap();
+
+        let surface = parse_railways(&response, false);
+        assert_eq!(surface.len(), 1);
+        assert_eq!(surface[0].class, RoadClass::Rail);
+
+        let all = parse_railways(&response, true);
+        assert_eq!(all.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_parks_multipolygon_holes() {
+        let json = r#"{
+            "elements": [
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0},
+                {"type": "node", "id": 2, "lat": 0.0, "lon": 1.0},
+                {"type": "node", "id": 3, "lat": 1.0, "lon": 1.0},
+                {"type": "node", "id": 4, "lat": 1.0, "lon": 0.0},
+                {"type": "node", "id": 5, "lat": 0.4, "lon": 0.4},
+                {"type": "node", "id": 6, "lat": 0.4, "lon": 0.6},
+                {"type": "node", "id": 7, "lat": 0.6, "lon": 0.5},
+                {"type": "way", "id": 10, "nodes": [1, 2, 3]},
+                {"type": "way", "id": 11, "nodes": [3, 4, 1]},
+                {"type": "way", "id": 12, "nodes": [5, 6, 7, 5], "tags": {"natural": "water"}},
+                {"type": "relation", "id": 20,
+                 "members": [
+                    {"type": "way", "ref": 10, "role": "outer"},
+                    {"type": "way", "ref": 11, "role": "outer"},
+                    {"type": "way", "ref": 12, "role": "inner"}
+                 ],
+                 "tags": {"type": "multipolygon", "leisure": "park"}}
+            ]
+        }"#;
+        let response: OverpassResponse = serde_json::from_str(json).unwrap();
+
+        let parks = parse_parks(&response);
+        assert_eq!(parks.len(), 1);
+        assert_eq!(parks[0].outer.len(), 5);
+        assert_eq!(parks[0].holes.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_parks_multipolygon_reversed_ways() {
+        // Outer ways meeting head to head and an inner ring split over two
+        // ways meeting tail to tail
+        let json = r#"{
+            "elements": [
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0},
+                {"type": "node", "id": 2, "lat": 0.0, "lon": 1.0},
+                {"type": "node", "id": 3, "lat": 1.0, "lon": 1.0},
+                {"type": "node", "id": 4, "lat": 1.0, "lon": 0.0},
+                {"type": "node", "id": 5, "lat": 0.4, "lon": 0.4},
+                {"type": "node", "id": 6, "lat": 0.4, "lon": 0.6},
+                {"type": "node", "id": 7, "lat": 0.6, "lon": 0.5},
+                {"type": "way", "id": 10, "nodes": [1, 2, 3]},
+                {"type": "way", "id": 11, "nodes": [1, 4, 3]},
+                {"type": "way", "id": 12, "nodes": [5, 6]},
+                {"type": "way", "id": 13, "nodes": [5, 7, 6]},
+                {"type": "relation", "id": 20,
+                 "members": [
+                    {"type": "way", "ref": 10, "role": "outer"},
+                    {"type": "way", "ref": 11, "role": "outer"},
+                    {"type": "way", "ref": 12, "role": "inner"},
+                    {"type": "way", "ref": 13, "role": "inner"}
+                 ],
+                 "tags": {"type": "multipolygon", "leisure": "park"}}
+            ]
+        }"#;
+        let response: OverpassResponse = serde_json::from_str(json).unwrap();
+
+        let parks = parse_parks(&response);
+        assert_eq!(parks.len(), 1);
+        assert_eq!(parks[0].outer.len(), 5);
+        assert_eq!(parks[0].holes.len(), 1);
+        assert_eq!(parks[0].holes[0].len(), 4);
+    }
+
+    #[test]
+    fn test_parse_boundary() {
+        // A square district split over two ways, with an inner enclave
+        // and the admin centre node as a member
+        let json = r#"{
+            "elements": [
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0},
+                {"type": "node", "id": 2, "lat": 0.0, "lon": 1.0},
+                {"type": "node", "id": 3, "lat": 1.0, "lon": 1.0},
+                {"type": "node", "id": 4, "lat": 1.0, "lon": 0.0},
+                {"type": "node", "id": 5, "lat": 0.4, "lon": 0.4},
+                {"type": "node", "id": 6, "lat": 0.4, "lon": 0.6},
+                {"type": "node", "id": 7, "lat": 0.6, "lon": 0.5},
+                {"type": "way", "id": 10, "nodes": [1, 2, 3]},
+                {"type": "way", "id": 11, "nodes": [3, 4, 1]},
+                {"type": "way", "id": 12, "nodes": [5, 6, 7, 5]},
+                {"type": "relation", "id": 20,
+                 "members": [
+                    {"type": "way", "ref": 10, "role": "outer"},
+                    {"type": "way", "ref": 11, "role": "outer"},
+                    {"type": "way", "ref": 12, "role": "inner"},
+                    {"type": "node", "ref": 5, "role": "admin_centre"}
+                 ],
+                 "tags": {"type": "boundary", "boundary": "administrative", "admin_level": "8"}}
+            ]
+        }"#;
+        let response: OverpassResponse = serde_json::from_str(json).unwrap();
+
+        let boundary = parse_boundary(&response);
+        assert_eq!(boundary.len(), 1);
+        assert_eq!(boundary[0].outer.len(), 5);
+        assert_eq!(boundary[0].holes.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_boundary_mixed_directions() {
+        // Admin borders are shared with neighbors, so their ways point
+        // every which way around the ring
+        let json = r#"{
+            "elements": [
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0},
+                {"type": "node", "id": 2, "lat": 0.0, "lon": 1.0},
+                {"type": "node", "id": 3, "lat": 1.0, "lon": 1.0},
+                {"type": "node", "id": 4, "lat": 1.0, "lon": 0.0},
+                {"type": "way", "id": 10, "nodes": [1, 2]},
+                {"type": "way", "id": 11, "nodes": [3, 2]},
+                {"type": "way", "id": 12, "nodes": [3, 4]},
+                {"type": "way", "id": 13, "nodes": [1, 4]},
+                {"type": "relation", "id": 20,
+                 "members": [
+                    {"type": "way", "ref": 10, "role": "outer"},
+                    {"type": "way", "ref": 11, "role": "outer"},
+                    {"type": "way", "ref": 12, "role": "outer"},
+                    {"type": "way", "ref": 13, "role": "outer"}
+                 ],
+                 "tags": {"type": "boundary", "boundary": "administrative", "admin_level": "8"}}
+            ]
+        }"#;
+        let response: OverpassResponse = serde_json::from_str(json).unwrap();
+
+        let boundary = parse_boundary(&response);
+        assert_eq!(boundary.len(), 1);
+        assert_eq!(boundary[0].outer.len(), 5);
+        // What --boundary raises around the map
+        let projector = Projector::new((0.5, 0.5));
+        let bounds = Bounds::from_points(&[(-1e6, -1e6), (1e6, 1e6)]).unwrap();
+        let scaler = Scaler::from_bounds(&bounds, 100.0);
+        let rings = [boundary[0].outer.clone()];
+        let outline = generate_boundary(&rings, &projector, &scaler, &bounds, None, 3.0);
+        assert!(!outline.is_empty());
+        // What --area builds from the boundary
+        let polygons: Vec<_> = boundary
+            .into_iter()
+            .map(|polygon| (polygon.outer, polygon.holes))
+            .collect();
+        let area = Area::new(&polygons).unwrap();
+        assert!(area.contains((0.5, 0.5)));
+    }
+
+    #[test]
+    fn test_parse_buildings_closed_ways_only() {
+        let json = r#"{
+            "elements": [
+                {"type": "node", "id": 1, "lat": 0.0, "lon": 0.0},
+                {"type": "node", "id": 2, "lat": 0.0, "lon": 0.001},
+                {"type": "node", "id": 3, "lat": 0.001, "lon": 0.001},
+                {"type": "way", "id": 10, "nodes": [1, 2, 3, 1], "tags": {"building": "yes", "building:levels": "5"}},
+                {"type": "way", "id": 11, "nodes": [1, 2, 3], "tags": {"building": "yes"}},
+                {"type": "way", "id": 12, "nodes": [3, 2, 1, 3], "tags": {"building": "yes", "height": "42 m", "building:levels": "5"}},
+                {"type": "way", "id": 13, "nodes": [2, 3, 1, 2], "tags": {"building": "yes"}}
+            ]
+        }"#;
+        let response: OverpassResponse = serde_json::from_str(json).unwrap();
+
+        let buildings = parse_buildings(&response);
+        assert_eq!(buildings.len(), 3);
+        assert_eq!(buildings[0].outer.len(), 4);
+        assert_eq!(buildings[0].height_m, Some(15.0));
+        assert_eq!(buildings[1].height_m, Some(42.0));
+        assert_eq!(buildings[2].height_m, None);
     }
 }
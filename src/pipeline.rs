@@ -0,0 +1,3686 @@
+//! End-to-end map generation
+//!
+//! [`generate_map`] runs the whole pipeline — geocode, fetch, parse, project,
+//! scale, mesh and validate — and hands back the finished layers. The CLI is a
+//! thin wrapper that resolves its arguments into [`MapParams`] and writes the
+//! result; embedders can do the same without going through `main`.
+
+use anyhow::{Context, Result, bail};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::api::{
+    BBOX_TOKEN, RoadDepth, calculate_bbox, fetch_aeroways, fetch_boundary, fetch_buildings,
+    fetch_custom, fetch_parks, fetch_railways, fetch_roads_with_depth, fetch_water,
+    geocode_area_cached, geocode_city_cached, reverse_geocode_cached,
+};
+use crate::config::{
+    FeatureHeights, HeightOverrides, NominatimConfig, OverpassConfig, RoadOverrides, Theme,
+};
+use crate::domain::{
+    AerowayLine, Aeroways, BuildingPolygon, CustomFeatures, ParkPolygon, RoadClass, RoadSegment,
+    WaterPolygon,
+};
+use crate::geometry::bbox::{
+    BBox, bbox_center, bbox_radius_m, clip_polyline, clip_ring, validate_bbox, validate_center,
+};
+use crate::geometry::circle::Circle;
+use crate::geometry::hexagon::{Hexagon, Outline};
+use crate::geometry::{
+    Area, Bounds, Dem, MarginSide, POLAR_LAT_LIMIT, ProjectionKind, Projector, Scaler,
+    SimplifyAlgorithm,
+};
+use crate::layers::terrain::TERRAIN_CELL_MM;
+use crate::layers::text::DEFAULT_CURVE_SUBDIVISIONS;
+use crate::layers::{
+    BED_MARGIN_MM, BedSize, BuildingConfig, CIRCULAR_BASE_SEGMENTS, CompassCorner,
+    DEFAULT_HANG_HOLE_DIAMETER_MM, HOLLOW_MIN_CAVITY_MM, HangHole, MapShape, MissingGlyph,
+    OnewayArrows, PlateCorner, PlateFootprint, RoadConfig, Terrain, TextRenderer, TunnelMode,
+    WaterStyle, generate_aeroway_meshes, generate_base_plate, generate_boundary,
+    generate_building_meshes, generate_circular_base, generate_compass, generate_contours,
+    generate_custom_meshes, generate_frame, generate_grid, generate_hex_base,
+    generate_oneway_arrows, generate_park_meshes, generate_recessed_water, generate_road_meshes,
+    generate_road_union, generate_scale_bar, generate_street_names, generate_water_meshes,
+    round_scale_length,
+};
+use crate::mesh::stl::estimate_stl_size;
+use crate::mesh::tiles::{TILE_LAP_MM, Tile, TileGrid, TilePins, split_into_tiles};
+use crate::mesh::validation::ManifoldReport;
+use crate::mesh::{
+    RibbonProfile, Triangle, decimate_triangles, min_triangle_area, sort_triangles,
+    validate_and_fix, write_stl,
+};
+use crate::osm::{
+    FeatureLayers, GeoJsonWriter, OsmData, load_osm_input, parse_aeroways, parse_boundary,
+    parse_buildings, parse_coastline, parse_custom, parse_parks, parse_railways, parse_roads,
+    parse_water, read_geojson, save_osm_data,
+};
+
+/// Height of the strip below the map reserved for the labels
+pub const TEXT_MARGIN_MM: f32 = 20.0;
+
+/// Map radius in meters when neither --radius nor a city extent is available
+pub const DEFAULT_RADIUS_M: u32 = 10000;
+
+/// Smallest radius fitted to a geocoded city
+const MIN_FITTED_RADIUS_M: u32 = 1000;
+
+/// Largest radius fitted to a geocoded city, so a sprawling metro area
+/// does not trigger a huge Overpass download
+const MAX_FITTED_RADIUS_M: u32 = 25000;
+
+/// Estimated projection error at the map edge, as a share of the radius,
+/// above which the local projection is worth trading for UTM
+const PROJECTION_ERROR_WARN_RATIO: f64 = 0.005;
+
+/// Share of roads widened to the minimum printable width above which the
+/// lost detail is worth a warning
+const CLAMPED_ROADS_WARN_RATIO: f64 = 0.25;
+
+/// Triangles per square kilometer of a typical city, by road depth and for
+/// each optional layer; only good for an order-of-magnitude dry run estimate
+const ROAD_TRIANGLES_PER_KM2: [(RoadDepth, f64); 5] = [
+    (RoadDepth::Motorway, 150.0),
+    (RoadDepth::Primary, 600.0),
+    (RoadDepth::Secondary, 1500.0),
+    (RoadDepth::Tertiary, 2500.0),
+    (RoadDepth::All, 6000.0),
+];
+const WATER_TRIANGLES_PER_KM2: f64 = 400.0;
+const PARK_TRIANGLES_PER_KM2: f64 = 800.0;
+const BUILDING_TRIANGLES_PER_KM2: f64 = 15000.0;
+const RAILWAY_TRIANGLES_PER_KM2: f64 = 300.0;
+const AEROWAY_TRIANGLES_PER_KM2: f64 = 20.0;
+const PATH_TRIANGLES_PER_KM2: f64 = 5000.0;
+
+/// Triangles for the base plate, labels and decorations, whatever the area
+const FIXED_TRIANGLES_ESTIMATE: usize = 4000;
+
+/// Gap kept between stacked label blocks and the top of the text margin
+const TEXT_BLOCK_GAP_MM: f32 = 1.0;
+
+/// How deep the back text reaches up into the base, in mm: a few layers,
+/// enough for a filament change without weakening the plate
+const BACK_TEXT_DEPTH_MM: f32 = 0.6;
+
+/// Largest cap height of the back text, in mm
+const BACK_TEXT_MAX_CAP_MM: f32 = 8.0;
+
+/// Widest a line of back text may be, as a fraction of the plate's shorter side
+const BACK_TEXT_WIDTH_FRACTION: f32 = 0.7;
+
+/// Longest the scale bar may be, as a fraction of the plate width
+const SCALE_BAR_MAX_WIDTH_FRACTION: f32 = 0.2;
+
+/// Space between the scale bar and the plate edge
+const SCALE_BAR_EDGE_PADDING_MM: f32 = 4.0;
+
+/// Everything that shapes a generated map, mirroring the CLI options
+///
+/// At least one of `city`/`country`, `lat`/`lon`, `bbox` or `osm_file` must
+/// be set. The defaults match the CLI defaults.
+#[derive(Debug, Clone)]
+pub struct MapParams {
+    /// City to geocode; requires `country`
+    pub city: Option<String>,
+    pub country: Option<String>,
+    /// State or region, to tell apart cities sharing a name
+    pub state: Option<String>,
+    /// Look the city up again instead of using the cached coordinates
+    pub refresh_geocode: bool,
+    /// Without a city or primary text, label the map with the place name
+    /// Nominatim finds at the center
+    pub reverse_geocode: bool,
+    /// Map center; both must be set to take effect
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    /// Exact map extent; replaces the center and `radius`
+    pub bbox: Option<BBox>,
+    /// Named area, such as a city or district, printed to its OSM boundary;
+    /// replaces the center, `radius` and `bbox`
+    pub area: Option<String>,
+    /// Map radius in meters; `None` fits the geocoded city, or falls back
+    /// to `DEFAULT_RADIUS_M`
+    pub radius: Option<u32>,
+    /// Plate width in mm
+    pub width: f32,
+    /// Plate height in mm, including the text margin
+    pub height: f32,
+    /// Base plate thickness in mm
+    pub base_height: f32,
+    /// Base plate corner radius in mm (square shape only)
+    pub corner_radius: f32,
+    /// Hanging hole at an explicit position
+    pub hang_hole: Option<HangHole>,
+    /// Hanging hole near a corner; ignored if `hang_hole` is set
+    pub hang_hole_corner: Option<PlateCorner>,
+    /// Rib and skin thickness of a pocketed base plate, in mm
+    pub hollow_base: Option<f32>,
+    /// Printer bed the plate is checked against
+    pub bed: Option<BedSize>,
+    /// Shrink a plate too large for `bed` until it fits
+    pub auto_fit_bed: bool,
+    pub road_scale: f32,
+    pub road_depth: RoadDepth,
+    /// Add footways, paths, pedestrian streets and steps as thin ribbons
+    pub paths: bool,
+    /// Large label; defaults to the city name
+    pub primary_text: Option<String>,
+    /// Cap height of the primary label in mm instead of fitting its width
+    pub primary_text_height: Option<f32>,
+    /// Small label; defaults to the center coordinates
+    pub secondary_text: Option<String>,
+    /// Cap height of the secondary label in mm instead of fitting its width
+    pub secondary_text_height: Option<f32>,
+    /// Multiplier on the font's line height for multi-line labels
+    pub line_spacing: f32,
+    /// Message inlaid into the underside of the base, mirrored to read from
+    /// below
+    pub back_text: Option<String>,
+    /// Road simplification level, 0 (off) to 3
+    pub simplify: u8,
+    pub simplify_algorithm: SimplifyAlgorithm,
+    /// Decimate the finished layers to at most this many triangles
+    pub max_triangles: Option<usize>,
+    /// Triangles under this many square mm are dropped as degenerate;
+    /// scaled with the plate size when unset
+    pub min_triangle_area: Option<f32>,
+    pub round_caps: bool,
+    /// Merge roads of the same height into one solid where they cross
+    pub union_roads: bool,
+    pub road_profile: RibbonProfile,
+    pub tunnels: TunnelMode,
+    /// TTF font for labels; the bundled font is used if unset
+    pub font: Option<PathBuf>,
+    /// TTF font for the secondary (coordinate) line; `font` is used if unset
+    pub secondary_font: Option<PathBuf>,
+    /// Fonts tried in order for characters `font` has no glyph for
+    pub fallback_fonts: Vec<PathBuf>,
+    /// Line segments per TTF glyph curve: higher is smoother text and more
+    /// triangles
+    pub text_quality: u8,
+    /// Placeholder for characters no font has a glyph for
+    pub missing_glyph: MissingGlyph,
+    pub projection: ProjectionKind,
+    /// Bearing in degrees clockwise from north that points up
+    pub rotate: f64,
+    pub shape: MapShape,
+    pub compass: Option<CompassCorner>,
+    pub scale_bar: bool,
+    /// Leave out the labels and their margin, so the map fills the plate
+    pub no_text: bool,
+    /// Plate edge the labels and their margin sit along
+    pub text_position: MarginSide,
+    /// Print the names of major roads at least this long in mm along them
+    pub street_names: Option<f32>,
+    /// Arrowheads along one-way roads, with the roads or with the text
+    pub oneway_arrows: Option<OnewayArrows>,
+    /// Spacing in meters of a graticule of raised lines over the map
+    pub grid: Option<f64>,
+    /// Raise the outline of the city's or area's administrative boundary
+    pub boundary: bool,
+    /// Sort each layer's triangles so identical inputs write identical files
+    pub deterministic: bool,
+    pub frame: bool,
+    /// Frame bar width in mm
+    pub frame_width: f32,
+    /// Frame height above the base plate in mm (defaults to the text height)
+    pub frame_height: Option<f32>,
+    /// Fit the map inside the frame instead of under it
+    pub frame_inset: bool,
+    pub water: bool,
+    /// Water as a raised band or as pockets in the base
+    pub water_style: WaterStyle,
+    pub parks: bool,
+    /// Runways, taxiways and aprons
+    pub aeroways: bool,
+    pub buildings: bool,
+    /// Height in meters for buildings without height tags
+    pub building_height: f32,
+    pub railways: bool,
+    /// Railway line width in mm, before `road_scale`
+    pub rail_width: f32,
+    /// Include underground railway segments
+    pub subways: bool,
+    /// Overpass QL query with a `{{bbox}}` token, whose features print in
+    /// the parks band
+    pub overpass_query: Option<String>,
+    /// Per-class road widths and heights from the `[roads]` config section
+    pub road_overrides: RoadOverrides,
+    /// Layer height and feature offsets from the `[heights]` config section
+    pub heights: HeightOverrides,
+    pub overpass: OverpassConfig,
+    pub nominatim: NominatimConfig,
+    /// Load OSM data from a saved response or dump directory instead of fetching
+    pub osm_file: Option<PathBuf>,
+    /// Save the fetched responses into this directory
+    pub dump_osm: Option<PathBuf>,
+    /// Write the parsed and clipped features to this GeoJSON file
+    pub dump_geojson: Option<PathBuf>,
+    /// Read features from this GeoJSON file instead of OSM data
+    pub geojson_input: Option<PathBuf>,
+    /// Elevation model (SRTM `.hgt` or GeoTIFF) to raise the map into terrain
+    pub dem: Option<PathBuf>,
+    /// Vertical exaggeration of the terrain relief over the map scale
+    pub terrain_scale: f32,
+    /// Trace the elevation model into contour lines this many meters apart
+    /// on a flat map, instead of raising the relief
+    pub contours: Option<f32>,
+}
+
+impl Default for MapParams {
+    fn default() -> Self {
+        Self {
+            city: None,
+            country: None,
+            state: None,
+            refresh_geocode: false,
+            reverse_geocode: false,
+            lat: None,
+            lon: None,
+            bbox: None,
+            area: None,
+            radius: None,
+            width: 220.0,
+            height: 220.0,
+            base_height: 2.0,
+            corner_radius: 0.0,
+            hang_hole: None,
+            hang_hole_corner: None,
+            hollow_base: None,
+            bed: None,
+            auto_fit_bed: false,
+            road_scale: 1.0,
+            road_depth: RoadDepth::Primary,
+            paths: false,
+            primary_text: None,
+            primary_text_height: None,
+            secondary_text: None,
+            secondary_text_height: None,
+            line_spacing: 1.2,
+            back_text: None,
+            simplify: 0,
+            simplify_algorithm: SimplifyAlgorithm::default(),
+            max_triangles: None,
+            min_triangle_area: None,
+            round_caps: false,
+            union_roads: false,
+            road_profile: RibbonProfile::default(),
+            tunnels: TunnelMode::default(),
+            font: None,
+            secondary_font: None,
+            fallback_fonts: Vec::new(),
+            text_quality: DEFAULT_CURVE_SUBDIVISIONS,
+            missing_glyph: MissingGlyph::default(),
+            projection: ProjectionKind::default(),
+            rotate: 0.0,
+            shape: MapShape::default(),
+            compass: None,
+            scale_bar: false,
+            no_text: false,
+            text_position: MarginSide::default(),
+            street_names: None,
+            oneway_arrows: None,
+            grid: None,
+            boundary: false,
+            deterministic: false,
+            frame: false,
+            frame_width: 2.0,
+            frame_height: None,
+            frame_inset: false,
+            water: false,
+            water_style: WaterStyle::default(),
+            parks: false,
+            aeroways: false,
+            buildings: false,
+            building_height: 10.0,
+            railways: false,
+            rail_width: 0.6,
+            subways: false,
+            overpass_query: None,
+            road_overrides: RoadOverrides::default(),
+            heights: HeightOverrides::default(),
+            overpass: OverpassConfig::default(),
+            nominatim: NominatimConfig::default(),
+            osm_file: None,
+            dump_osm: None,
+            dump_geojson: None,
+            geojson_input: None,
+            dem: None,
+            terrain_scale: 1.0,
+            contours: None,
+        }
+    }
+}
+
+impl MapParams {
+    /// Check the parameters for combinations that cannot be generated
+    pub fn validate(&self) -> Result<()> {
+        if self.city.is_none()
+            && self.lat.is_none()
+            && self.bbox.is_none()
+            && self.area.is_none()
+            && self.osm_file.is_none()
+            && self.geojson_input.is_none()
+        {
+            bail!(
+                "Must provide either --city/-c and --country/-C, --lat and --lon, --bbox, --area, --osm-file or --geojson-input"
+            );
+        }
+        if self.city.is_some() && self.country.is_none() {
+            bail!("--city requires --country");
+        }
+        if self.state.is_some() && self.city.is_none() {
+            bail!("--state requires --city");
+        }
+        if let Some(b) = self.bbox {
+            validate_bbox(b)?;
+        } else if self.radius == Some(0) {
+            bail!("--radius must be greater than 0");
+        }
+        if let (Some(lat), Some(lon)) = (self.lat, self.lon) {
+            validate_center(lat, lon)?;
+        }
+        // Rules for the round plate hold for the hexagon too
+        let circle = self.shape != MapShape::Square;
+        if self.compass.is_some() && circle {
+            bail!("--compass requires --shape square");
+        }
+        if self.scale_bar && circle {
+            bail!("--scale-bar requires --shape square");
+        }
+        // Labels grow up from their baseline, into a round plate's narrowing
+        // top rather than away from it
+        if self.text_position == MarginSide::Top && circle {
+            bail!("--text-position top requires --shape square");
+        }
+        if self.no_text && (self.compass.is_some() || self.scale_bar) {
+            bail!("--compass and --scale-bar sit in the text margin, which --no-text removes");
+        }
+        for (flag, height) in [
+            ("--primary-text-height-mm", self.primary_text_height),
+            ("--secondary-text-height-mm", self.secondary_text_height),
+        ] {
+            if let Some(height) = height
+                && !(height > 0.0 && height < TEXT_MARGIN_MM)
+            {
+                bail!(
+                    "{} must be greater than 0 and less than the {}mm text margin",
+                    flag,
+                    TEXT_MARGIN_MM
+                );
+            }
+        }
+        if let Some(path) = self.fallback_fonts.iter().find(|path| !path.is_file()) {
+            bail!("Fallback font not found: {}", path.display());
+        }
+        if self.text_quality == 0 {
+            bail!("--text-quality must be at least 1");
+        }
+        if self.street_names.is_some_and(|length| length <= 0.0) {
+            bail!("--street-names minimum length must be greater than 0");
+        }
+        if self.boundary && self.city.is_none() && self.area.is_none() {
+            bail!("--boundary needs --city or --area to know whose boundary to draw");
+        }
+        if self
+            .min_triangle_area
+            .is_some_and(|area| !(area.is_finite() && area >= 0.0))
+        {
+            bail!("--min-triangle-area must be zero or a positive area in square mm");
+        }
+        if self.grid.is_some_and(|spacing| spacing <= 0.0) {
+            bail!("--grid spacing must be greater than 0");
+        }
+        if self.geojson_input.is_some()
+            && (self.osm_file.is_some() || self.dump_osm.is_some() || self.overpass_query.is_some())
+        {
+            bail!(
+                "--geojson-input replaces OSM data and can't be combined with --osm-file, --dump-osm or --overpass-query-file"
+            );
+        }
+        if let Some(query) = &self.overpass_query
+            && !query.contains(BBOX_TOKEN)
+        {
+            bail!(
+                "--overpass-query-file must contain {} to limit the query to the map",
+                BBOX_TOKEN
+            );
+        }
+        if self.area.is_some() {
+            if self.city.is_some() || self.lat.is_some() || self.bbox.is_some() {
+                bail!("--area replaces --city, --lat/--lon and --bbox; give only one of them");
+            }
+            if self.osm_file.is_some() || self.geojson_input.is_some() {
+                bail!(
+                    "--area fetches its boundary from OpenStreetMap and cannot be combined with --osm-file or --geojson-input"
+                );
+            }
+            if circle {
+                bail!("--area requires --shape square");
+            }
+        }
+        if self.frame && circle {
+            bail!("--frame requires --shape square");
+        }
+        if self.auto_fit_bed && self.bed.is_none() {
+            bail!("--auto-fit-bed requires --bed");
+        }
+        if let Some(wall) = self.hollow_base {
+            if circle {
+                bail!("--hollow-base requires --shape square");
+            }
+            if wall <= 0.0 || self.base_height - wall < HOLLOW_MIN_CAVITY_MM {
+                bail!(
+                    "--hollow-base {}mm leaves no room for a cavity in a {}mm base; use a thinner wall or a thicker --base-height",
+                    wall,
+                    self.base_height
+                );
+            }
+        }
+        if self.back_text.is_some() {
+            if self.hollow_base.is_some() {
+                bail!(
+                    "--back-text needs a solid underside and cannot be combined with --hollow-base"
+                );
+            }
+            if self.base_height <= BACK_TEXT_DEPTH_MM {
+                bail!(
+                    "--back-text is {}mm deep and needs a thicker --base-height",
+                    BACK_TEXT_DEPTH_MM
+                );
+            }
+        }
+        if self.water && self.water_style == WaterStyle::Recessed && self.hollow_base.is_some() {
+            bail!("--water-style recessed cannot be combined with --hollow-base");
+        }
+        if (self.hang_hole.is_some() || self.hang_hole_corner.is_some()) && circle {
+            bail!("--hang-hole and --hang-hole-corner require --shape square");
+        }
+        if self.dem.is_some() {
+            if circle {
+                bail!("--dem requires --shape square");
+            }
+            // The terrain solid stands on the bed and would fill the pockets
+            if self.contours.is_none()
+                && (self.hollow_base.is_some()
+                    || self.water && self.water_style == WaterStyle::Recessed)
+            {
+                bail!(
+                    "--dem relief cannot be combined with --hollow-base or --water-style recessed; use --contours"
+                );
+            }
+        }
+        if let Some(interval) = self.contours {
+            if self.dem.is_none() {
+                bail!("--contours requires --dem for the elevations");
+            }
+            if !(interval > 0.0 && interval.is_finite()) {
+                bail!("--contours interval must be greater than 0");
+            }
+        }
+        if !(self.terrain_scale > 0.0 && self.terrain_scale.is_finite()) {
+            bail!("--terrain-scale must be greater than 0");
+        }
+        if self.union_roads {
+            if self.road_profile != RibbonProfile::Flat {
+                bail!("--union-roads requires --road-profile flat");
+            }
+            // Merged tops span whole junctions and cannot follow the ground
+            if self.dem.is_some() && self.contours.is_none() {
+                bail!("--union-roads cannot be combined with --dem relief; use --contours");
+            }
+        }
+        self.road_overrides.validate()?;
+        self.heights.validate()?;
+        self.feature_heights().check_order()
+    }
+
+    /// Plate width and height in mm, shrunk to the bed with `auto_fit_bed`
+    pub fn plate_size(&self) -> (f32, f32) {
+        let (width, height) = self.shape_plate_size();
+        match self.bed {
+            Some(bed) if self.auto_fit_bed => {
+                let scale = bed.fit_scale(width, height);
+                (width * scale, height * scale)
+            }
+            _ => (width, height),
+        }
+    }
+
+    /// Plate width and height in mm before fitting to the bed; a round
+    /// plate is as wide as it is tall, and a hexagon as tall as its flat
+    /// edges are apart
+    fn shape_plate_size(&self) -> (f32, f32) {
+        match self.shape {
+            MapShape::Circle => {
+                let diameter = self.width.min(self.height);
+                (diameter, diameter)
+            }
+            MapShape::Hexagon => {
+                let circumradius = (self.width / 2.0).min(self.height / 3.0_f32.sqrt());
+                (2.0 * circumradius, 3.0_f32.sqrt() * circumradius)
+            }
+            MapShape::Square => (self.width, self.height),
+        }
+    }
+
+    /// Outline features are clipped to in the projected plane, for a map of
+    /// `radius_m` that is not square
+    fn outline(&self, radius_m: f64) -> Option<Outline> {
+        match self.shape {
+            MapShape::Square => None,
+            MapShape::Circle => Some(Outline::Circle(Circle::new((0.0, 0.0), radius_m))),
+            MapShape::Hexagon => Some(Outline::Hexagon(Hexagon::new((0.0, 0.0), radius_m))),
+        }
+    }
+
+    /// Projected corners bounding a map of `radius_m` that is not square
+    fn outline_corners(&self, radius_m: f64) -> Option<[(f64, f64); 2]> {
+        match self.shape {
+            MapShape::Square => None,
+            MapShape::Circle => Some([(-radius_m, -radius_m), (radius_m, radius_m)]),
+            MapShape::Hexagon => {
+                let apothem = Hexagon::new((0.0, 0.0), radius_m).apothem();
+                Some([(-radius_m, -apothem), (radius_m, apothem)])
+            }
+        }
+    }
+
+    /// Strip below the map kept for the labels: none with `no_text`
+    pub fn text_margin_mm(&self) -> f32 {
+        if self.no_text { 0.0 } else { TEXT_MARGIN_MM }
+    }
+
+    /// Top of each feature band for the enabled layers
+    pub fn feature_heights(&self) -> FeatureHeights {
+        FeatureHeights::new(
+            self.base_height,
+            self.water.then_some(self.water_style),
+            // Custom features share the parks band
+            self.parks || self.overpass_query.is_some(),
+            self.aeroways,
+            self.buildings,
+            self.railways,
+            &self.heights,
+        )
+        .with_road_overrides(&self.road_overrides)
+    }
+
+    /// Map radius in meters, derived from the bounding box if there is one
+    ///
+    /// Without an explicit radius this is `DEFAULT_RADIUS_M`; the pipeline
+    /// fits it to the geocoded city instead when it can.
+    pub fn effective_radius(&self) -> u32 {
+        self.bbox
+            .map(bbox_radius_m)
+            .unwrap_or(self.radius.unwrap_or(DEFAULT_RADIUS_M))
+    }
+
+    /// Width of the frame bars in mm, or 0 without a frame
+    fn frame_width_mm(&self) -> f32 {
+        if self.frame { self.frame_width } else { 0.0 }
+    }
+
+    /// Road meshing settings for a map of `radius` meters; roads on terrain
+    /// are split finely enough to follow the relief
+    fn road_config(&self, radius: u32, relief: bool) -> RoadConfig {
+        let (width, height) = self.plate_size();
+        let heights = self.feature_heights();
+        let config = RoadConfig::default()
+            .with_scale(self.road_scale)
+            .with_map_radius(radius, width.max(height))
+            .with_simplify_level(self.simplify)
+            .with_simplify_algorithm(self.simplify_algorithm)
+            .with_round_caps(self.round_caps)
+            .with_profile(self.road_profile)
+            .with_tunnels(self.tunnels)
+            .with_base_z_top(heights.base_height)
+            .with_z_top(heights.road_z_top)
+            .with_overrides(&self.road_overrides);
+        if relief {
+            config.with_max_segment(TERRAIN_CELL_MM)
+        } else {
+            config
+        }
+    }
+
+    /// Hanging hole position, fitted inside the plate
+    pub fn fitted_hang_hole(&self) -> Result<Option<HangHole>> {
+        let (width, height) = self.plate_size();
+        let hole = self.hang_hole.or_else(|| {
+            self.hang_hole_corner.map(|corner| {
+                HangHole::in_corner(
+                    corner,
+                    width,
+                    height,
+                    DEFAULT_HANG_HOLE_DIAMETER_MM,
+                    self.corner_radius,
+                )
+            })
+        });
+        hole.map(|hole| hole.fit(width, height, self.corner_radius))
+            .transpose()
+    }
+
+    fn feature_layers(&self) -> FeatureLayers {
+        FeatureLayers {
+            water: self.water,
+            parks: self.parks,
+            buildings: self.buildings,
+            railways: self.railways,
+            aeroways: self.aeroways,
+            custom: self.overpass_query.is_some(),
+        }
+    }
+}
+
+/// Fluent construction of a map
+///
+/// ```no_run
+/// use mapto3d::api::RoadDepth;
+/// use mapto3d::pipeline::MapBuilder;
+///
+/// let triangles = MapBuilder::new()
+///     .center(45.4408, 12.3155)
+///     .radius(4000)
+///     .with_water(true)
+///     .road_depth(RoadDepth::Secondary)
+///     .build()?;
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MapBuilder {
+    params: MapParams,
+}
+
+impl MapBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Geocode the map center from a city and country
+    pub fn city(mut self, city: &str, country: &str) -> Self {
+        self.params.city = Some(city.to_string());
+        self.params.country = Some(country.to_string());
+        self
+    }
+
+    /// Narrow the city lookup to a state or region
+    pub fn with_state(mut self, state: &str) -> Self {
+        self.params.state = Some(state.to_string());
+        self
+    }
+
+    pub fn with_refresh_geocode(mut self, refresh: bool) -> Self {
+        self.params.refresh_geocode = refresh;
+        self
+    }
+
+    /// Name an unnamed map after the place at its center
+    pub fn with_reverse_geocode(mut self, enabled: bool) -> Self {
+        self.params.reverse_geocode = enabled;
+        self
+    }
+
+    pub fn center(mut self, lat: f64, lon: f64) -> Self {
+        self.params.lat = Some(lat);
+        self.params.lon = Some(lon);
+        self
+    }
+
+    /// Use an exact (south, west, north, east) extent instead of center and radius
+    pub fn bbox(mut self, bbox: BBox) -> Self {
+        self.params.bbox = Some(bbox);
+        self
+    }
+
+    /// Print a named area, such as a city or district, trimmed to its boundary
+    pub fn area(mut self, name: &str) -> Self {
+        self.params.area = Some(name.to_string());
+        self
+    }
+
+    pub fn radius(mut self, radius_m: u32) -> Self {
+        self.params.radius = Some(radius_m);
+        self
+    }
+
+    pub fn road_depth(mut self, depth: RoadDepth) -> Self {
+        self.params.road_depth = depth;
+        self
+    }
+
+    pub fn with_size(mut self, width_mm: f32, height_mm: f32) -> Self {
+        self.params.width = width_mm;
+        self.params.height = height_mm;
+        self
+    }
+
+    pub fn with_base_height(mut self, height_mm: f32) -> Self {
+        self.params.base_height = height_mm;
+        self
+    }
+
+    pub fn with_corner_radius(mut self, radius_mm: f32) -> Self {
+        self.params.corner_radius = radius_mm;
+        self
+    }
+
+    pub fn with_hang_hole(mut self, hole: HangHole) -> Self {
+        self.params.hang_hole = Some(hole);
+        self
+    }
+
+    pub fn with_hang_hole_corner(mut self, corner: PlateCorner) -> Self {
+        self.params.hang_hole_corner = Some(corner);
+        self
+    }
+
+    /// Check the plate against a printer bed, shrinking it to fit when
+    /// `auto_fit` is set
+    pub fn with_bed(mut self, bed: BedSize, auto_fit: bool) -> Self {
+        self.params.bed = Some(bed);
+        self.params.auto_fit_bed = auto_fit;
+        self
+    }
+
+    pub fn with_hollow_base(mut self, wall_mm: f32) -> Self {
+        self.params.hollow_base = Some(wall_mm);
+        self
+    }
+
+    pub fn with_road_scale(mut self, scale: f32) -> Self {
+        self.params.road_scale = scale;
+        self
+    }
+
+    pub fn with_primary_text(mut self, text: &str) -> Self {
+        self.params.primary_text = Some(text.to_string());
+        self
+    }
+
+    pub fn with_secondary_text(mut self, text: &str) -> Self {
+        self.params.secondary_text = Some(text.to_string());
+        self
+    }
+
+    /// Fixed cap heights in mm for the primary and secondary labels; `None`
+    /// fits that label to the plate width
+    pub fn with_text_heights(mut self, primary: Option<f32>, secondary: Option<f32>) -> Self {
+        self.params.primary_text_height = primary;
+        self.params.secondary_text_height = secondary;
+        self
+    }
+
+    /// Inlay a message, such as a date or dedication, into the underside
+    pub fn with_back_text(mut self, text: &str) -> Self {
+        self.params.back_text = Some(text.to_string());
+        self
+    }
+
+    pub fn with_line_spacing(mut self, spacing: f32) -> Self {
+        self.params.line_spacing = spacing;
+        self
+    }
+
+    pub fn with_simplify_level(mut self, level: u8) -> Self {
+        self.params.simplify = level.min(3);
+        self
+    }
+
+    pub fn with_simplify_algorithm(mut self, algorithm: SimplifyAlgorithm) -> Self {
+        self.params.simplify_algorithm = algorithm;
+        self
+    }
+
+    pub fn with_max_triangles(mut self, max_triangles: usize) -> Self {
+        self.params.max_triangles = Some(max_triangles);
+        self
+    }
+
+    /// Drop triangles under `area` square mm instead of the size-scaled default
+    pub fn with_min_triangle_area(mut self, area: f32) -> Self {
+        self.params.min_triangle_area = Some(area);
+        self
+    }
+
+    pub fn with_round_caps(mut self, round_caps: bool) -> Self {
+        self.params.round_caps = round_caps;
+        self
+    }
+
+    pub fn with_union_roads(mut self, union_roads: bool) -> Self {
+        self.params.union_roads = union_roads;
+        self
+    }
+
+    pub fn with_road_profile(mut self, profile: RibbonProfile) -> Self {
+        self.params.road_profile = profile;
+        self
+    }
+
+    pub fn with_tunnels(mut self, tunnels: TunnelMode) -> Self {
+        self.params.tunnels = tunnels;
+        self
+    }
+
+    pub fn with_font(mut self, path: impl Into<PathBuf>) -> Self {
+        self.params.font = Some(path.into());
+        self
+    }
+
+    /// Font for the secondary (coordinate) line only
+    pub fn with_secondary_font(mut self, path: impl Into<PathBuf>) -> Self {
+        self.params.secondary_font = Some(path.into());
+        self
+    }
+
+    pub fn with_projection(mut self, projection: ProjectionKind) -> Self {
+        self.params.projection = projection;
+        self
+    }
+
+    pub fn with_rotation(mut self, bearing_deg: f64) -> Self {
+        self.params.rotate = bearing_deg;
+        self
+    }
+
+    pub fn with_shape(mut self, shape: MapShape) -> Self {
+        self.params.shape = shape;
+        self
+    }
+
+    pub fn with_compass(mut self, corner: CompassCorner) -> Self {
+        self.params.compass = Some(corner);
+        self
+    }
+
+    /// Raised graticule lines about `spacing_m` apart
+    pub fn with_grid(mut self, spacing_m: f64) -> Self {
+        self.params.grid = Some(spacing_m);
+        self
+    }
+
+    /// Raised outline of the place's boundary, at the text height
+    pub fn with_boundary(mut self, boundary: bool) -> Self {
+        self.params.boundary = boundary;
+        self
+    }
+
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.params.deterministic = deterministic;
+        self
+    }
+
+    pub fn with_scale_bar(mut self, scale_bar: bool) -> Self {
+        self.params.scale_bar = scale_bar;
+        self
+    }
+
+    /// Leave out the labels and let the map fill their margin
+    pub fn with_no_text(mut self, no_text: bool) -> Self {
+        self.params.no_text = no_text;
+        self
+    }
+
+    /// Put the labels along the top or bottom edge
+    pub fn with_text_position(mut self, side: MarginSide) -> Self {
+        self.params.text_position = side;
+        self
+    }
+
+    /// Add a font to try for characters the main font lacks, such as a CJK
+    /// font for a Latin main font
+    pub fn with_fallback_font(mut self, path: impl Into<PathBuf>) -> Self {
+        self.params.fallback_fonts.push(path.into());
+        self
+    }
+
+    /// Line segments per TTF glyph curve
+    pub fn with_text_quality(mut self, subdivisions: u8) -> Self {
+        self.params.text_quality = subdivisions;
+        self
+    }
+
+    /// Placeholder for characters no font has a glyph for
+    pub fn with_missing_glyph(mut self, missing_glyph: MissingGlyph) -> Self {
+        self.params.missing_glyph = missing_glyph;
+        self
+    }
+
+    /// Names along motorways and primary roads at least `min_length_mm` long
+    pub fn with_street_names(mut self, min_length_mm: f32) -> Self {
+        self.params.street_names = Some(min_length_mm);
+        self
+    }
+
+    /// Arrowheads pointing the way along one-way roads
+    pub fn with_oneway_arrows(mut self, placement: OnewayArrows) -> Self {
+        self.params.oneway_arrows = Some(placement);
+        self
+    }
+
+    /// Raised frame of `width_mm` bars; `inset` fits the map inside it
+    pub fn with_frame(mut self, width_mm: f32, inset: bool) -> Self {
+        self.params.frame = true;
+        self.params.frame_width = width_mm;
+        self.params.frame_inset = inset;
+        self
+    }
+
+    pub fn with_water(mut self, water: bool) -> Self {
+        self.params.water = water;
+        self
+    }
+
+    pub fn with_water_style(mut self, style: WaterStyle) -> Self {
+        self.params.water_style = style;
+        self
+    }
+
+    pub fn with_parks(mut self, parks: bool) -> Self {
+        self.params.parks = parks;
+        self
+    }
+
+    pub fn with_buildings(mut self, buildings: bool) -> Self {
+        self.params.buildings = buildings;
+        self
+    }
+
+    pub fn with_building_height(mut self, height_m: f32) -> Self {
+        self.params.building_height = height_m;
+        self
+    }
+
+    pub fn with_aeroways(mut self, aeroways: bool) -> Self {
+        self.params.aeroways = aeroways;
+        self
+    }
+
+    /// Footways, paths, pedestrian streets and steps, for walkable city
+    /// centers; they add many triangles
+    pub fn with_paths(mut self, paths: bool) -> Self {
+        self.params.paths = paths;
+        self
+    }
+
+    pub fn with_railways(mut self, railways: bool) -> Self {
+        self.params.railways = railways;
+        self
+    }
+
+    pub fn with_rail_width(mut self, width_mm: f32) -> Self {
+        self.params.rail_width = width_mm;
+        self
+    }
+
+    pub fn with_subways(mut self, subways: bool) -> Self {
+        self.params.subways = subways;
+        self
+    }
+
+    /// Also draw the features of a raw Overpass QL query, which must limit
+    /// itself to the map with a `{{bbox}}` token
+    pub fn with_overpass_query(mut self, query: impl Into<String>) -> Self {
+        self.params.overpass_query = Some(query.into());
+        self
+    }
+
+    /// Turn on the theme's layers and use its band heights; later calls
+    /// still override either
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        let preset = theme.preset();
+        self.params.water |= preset.water;
+        self.params.parks |= preset.parks;
+        self.params.heights = self.params.heights.or(preset.heights);
+        self
+    }
+
+    /// Layer height and feature offsets above the base
+    pub fn with_heights(mut self, heights: HeightOverrides) -> Self {
+        self.params.heights = heights;
+        self
+    }
+
+    /// Per-class road widths and heights
+    pub fn with_road_overrides(mut self, overrides: RoadOverrides) -> Self {
+        self.params.road_overrides = overrides;
+        self
+    }
+
+    pub fn with_overpass(mut self, config: OverpassConfig) -> Self {
+        self.params.overpass = config;
+        self
+    }
+
+    pub fn with_nominatim(mut self, config: NominatimConfig) -> Self {
+        self.params.nominatim = config;
+        self
+    }
+
+    /// Identify as `agent` to both Overpass and Nominatim
+    pub fn with_user_agent(mut self, agent: impl Into<String>) -> Self {
+        let agent = agent.into();
+        self.params.overpass.user_agent = Some(agent.clone());
+        self.params.nominatim.user_agent = Some(agent);
+        self
+    }
+
+    /// Send both Overpass and Nominatim requests through the proxy at `url`
+    pub fn with_proxy(mut self, url: impl Into<String>) -> Self {
+        let url = url.into();
+        self.params.overpass.proxy = Some(url.clone());
+        self.params.nominatim.proxy = Some(url);
+        self
+    }
+
+    /// Read OSM data from a saved response or dump directory instead of fetching
+    pub fn with_osm_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.params.osm_file = Some(path.into());
+        self
+    }
+
+    /// Read roads, railways, water, parks and buildings from a GeoJSON
+    /// FeatureCollection instead of OSM data
+    pub fn with_geojson_input(mut self, path: impl Into<PathBuf>) -> Self {
+        self.params.geojson_input = Some(path.into());
+        self
+    }
+
+    /// Raise the map into terrain from an SRTM `.hgt` tile or GeoTIFF
+    pub fn with_dem(mut self, path: impl Into<PathBuf>) -> Self {
+        self.params.dem = Some(path.into());
+        self
+    }
+
+    pub fn with_terrain_scale(mut self, exaggeration: f32) -> Self {
+        self.params.terrain_scale = exaggeration;
+        self
+    }
+
+    /// Draw contour lines every `interval_m` meters from the `with_dem`
+    /// model on a flat map instead of raising the relief
+    pub fn with_contours(mut self, interval_m: f32) -> Self {
+        self.params.contours = Some(interval_m);
+        self
+    }
+
+    /// Write the parsed features, trimmed to the map, as GeoJSON
+    pub fn with_dump_geojson(mut self, path: impl Into<PathBuf>) -> Self {
+        self.params.dump_geojson = Some(path.into());
+        self
+    }
+
+    pub fn params(&self) -> &MapParams {
+        &self.params
+    }
+
+    pub fn into_params(self) -> MapParams {
+        self.params
+    }
+
+    /// Generate the map and return its layers and report
+    pub fn generate(self) -> Result<MapModel> {
+        generate_map(self.params)
+    }
+
+    /// Generate the map, reporting each step to `progress`
+    pub fn generate_with_progress(self, progress: &dyn Progress) -> Result<MapModel> {
+        generate_map_with_progress(self.params, progress)
+    }
+
+    /// Generate the map and return all layers as one triangle list
+    pub fn build(self) -> Result<Vec<Triangle>> {
+        Ok(self.generate()?.triangles())
+    }
+
+    /// Generate the map and write it as a binary STL
+    pub fn write_stl(self, path: impl AsRef<Path>) -> Result<MapReport> {
+        self.write_stl_with_progress(path, &Silent)
+    }
+
+    /// Generate the map and write it as a binary STL, reporting each step
+    pub fn write_stl_with_progress(
+        self,
+        path: impl AsRef<Path>,
+        progress: &dyn Progress,
+    ) -> Result<MapReport> {
+        let model = self.generate_with_progress(progress)?;
+        progress.stage(Stage::Writing, 0.0);
+        write_stl(path.as_ref(), &model.triangles()).context("Failed to write STL file")?;
+        progress.stage(Stage::Writing, 1.0);
+        Ok(model.report)
+    }
+}
+
+/// Receives progress from [`generate_map_with_progress`]
+///
+/// Every method does nothing by default, so implementors only pick the
+/// events they care about.
+pub trait Progress {
+    /// A slow step is starting
+    fn start(&self, _message: &str) {}
+    /// The step from the last `start` finished
+    fn finish(&self, _message: &str) {}
+    /// A line worth showing in normal output
+    fn message(&self, _message: &str) {}
+    /// Extra detail for verbose output
+    fn detail(&self, _message: &str) {}
+    /// Something was adjusted or ignored
+    fn warn(&self, _message: &str) {}
+    /// Structured progress: `fraction` of `stage` is done, from 0.0 to 1.0
+    fn stage(&self, _stage: Stage, _fraction: f32) {}
+}
+
+/// A closure receives only the structured stage updates
+impl<F: Fn(Stage, f32)> Progress for F {
+    fn stage(&self, stage: Stage, fraction: f32) {
+        self(stage, fraction)
+    }
+}
+
+/// Pipeline steps reported through [`Progress::stage`]
+///
+/// Stages a run skips, such as geocoding when coordinates are given, are
+/// not reported at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// Looking the city up with Nominatim
+    Geocoding,
+    /// Downloading roads and other features from Overpass
+    FetchingRoads,
+    /// Turning OSM elements into projected geometry
+    Parsing,
+    /// Building, decimating and validating the mesh layers
+    MeshingRoads,
+    /// Writing the output file
+    Writing,
+}
+
+/// Progress sink that drops everything
+pub struct Silent;
+
+impl Progress for Silent {}
+
+/// One named, validated mesh layer of a generated map
+#[derive(Debug)]
+pub struct MapLayer {
+    pub name: &'static str,
+    pub triangles: Vec<Triangle>,
+    /// Open or over-shared edges left after validation
+    pub manifold: ManifoldReport,
+    /// Zero-area triangles found during validation
+    pub degenerate: usize,
+    /// Pairs of overlapping faces in one plane, which may show as
+    /// z-fighting in slicers
+    pub coplanar_overlaps: usize,
+    /// Triangles dropped as degenerate or with invalid coordinates
+    pub removed: usize,
+}
+
+/// Summary of a generation run
+#[derive(Debug, Clone)]
+pub struct MapReport {
+    /// Map center as (lat, lon)
+    pub center: (f64, f64),
+    /// Covered area in meters as (width, height)
+    pub area_m: (f64, f64),
+    /// Triangles generated before decimation and validation
+    pub generated_triangles: usize,
+    /// Triangles in the finished layers
+    pub triangles: usize,
+}
+
+/// Finished map: layers in print order plus what it took to make them
+#[derive(Debug)]
+pub struct MapModel {
+    pub layers: Vec<MapLayer>,
+    /// Heights the layers were built with, for color change planning
+    pub heights: FeatureHeights,
+    pub report: MapReport,
+}
+
+impl MapModel {
+    /// Corners of the box around every layer, in mm
+    pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.triangles.iter().flat_map(|t| t.vertices))
+            .fold(None, |bounds, v| {
+                let (mut min, mut max) = bounds.unwrap_or((v, v));
+                for i in 0..3 {
+                    min[i] = min[i].min(v[i]);
+                    max[i] = max[i].max(v[i]);
+                }
+                Some((min, max))
+            })
+    }
+
+    /// All layers merged into one triangle list
+    pub fn triangles(&self) -> Vec<Triangle> {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.triangles.iter().cloned())
+            .collect()
+    }
+
+    /// Layers cut into a grid of lap-jointed tiles
+    ///
+    /// The joint sits at half the base plate thickness, so every tile's tabs
+    /// stay inside the plate and features are split only at the seams. With
+    /// `pins`, the tabs carry registration pins as tall as the upper half of
+    /// the base allows, which must be at least two print layers.
+    pub fn tiles(&self, grid: TileGrid, pins: bool) -> Result<Vec<Tile<'static>>> {
+        let joint_z = self.heights.base_height / 2.0;
+        let pins = if pins {
+            let pins = TilePins::for_overhang(self.heights.base_height - joint_z);
+            let min_height = 2.0 * self.heights.layer_height;
+            if pins.height < min_height - 1e-3 {
+                bail!(
+                    "A {}mm base is too thin for tile pins; use --base-height {:.1} or more",
+                    self.heights.base_height,
+                    2.0 * TilePins::overhang_for_height(min_height)
+                );
+            }
+            Some(pins)
+        } else {
+            None
+        };
+        let layers: Vec<(&'static str, &[Triangle])> = self
+            .layers
+            .iter()
+            .map(|layer| (layer.name, layer.triangles.as_slice()))
+            .collect();
+        let tiles = split_into_tiles(&layers, grid, joint_z, pins);
+        if let Some(tile) = tiles
+            .iter()
+            .find(|t| t.size_mm.0.min(t.size_mm.1) < 3.0 * TILE_LAP_MM)
+        {
+            bail!(
+                "Tiles of {:.0}x{:.0}mm are too small for {}mm lap joints; use fewer tiles",
+                tile.size_mm.0,
+                tile.size_mm.1,
+                TILE_LAP_MM
+            );
+        }
+        Ok(tiles)
+    }
+}
+
+/// Radius that covers a geocoded place's extent, kept to a printable range
+///
+/// Nominatim extents for large administrative areas can span a whole
+/// region, and for small towns can be a few hundred meters across.
+pub fn fitted_radius(extent: BBox) -> u32 {
+    bbox_radius_m(extent).clamp(MIN_FITTED_RADIUS_M, MAX_FITTED_RADIUS_M)
+}
+
+/// Map center and radius: from the bbox, coordinates, OSM data or geocoding
+///
+/// Without an explicit `--radius`, a geocoded city's extent sets the radius.
+fn locate_map(
+    params: &MapParams,
+    data_center: Option<(f64, f64)>,
+    progress: &dyn Progress,
+) -> Result<((f64, f64), u32)> {
+    let mut place_extent = None;
+    let center = if let Some(b) = params.bbox {
+        let c = bbox_center(b);
+        progress.message(&format!(
+            "Using bounding box centered at ({:.4}, {:.4})",
+            c.0, c.1
+        ));
+        c
+    } else if let (Some(lt), Some(ln)) = (params.lat, params.lon) {
+        progress.message(&format!(
+            "Using provided coordinates: ({:.4}, {:.4})",
+            lt, ln
+        ));
+        (lt, ln)
+    } else if params.city.is_none()
+        && let Some(data_center) = data_center
+    {
+        progress.message(&format!(
+            "Using center of the input data: ({:.4}, {:.4})",
+            data_center.0, data_center.1
+        ));
+        data_center
+    } else {
+        let (Some(c), Some(co)) = (params.city.as_ref(), params.country.as_ref()) else {
+            bail!(
+                "No map center: the input data has no points and no city or coordinates were given"
+            );
+        };
+        progress.start("Geocoding city...");
+        progress.stage(Stage::Geocoding, 0.0);
+        let start = Instant::now();
+        let place = geocode_city_cached(
+            c,
+            params.state.as_deref(),
+            co,
+            params.refresh_geocode,
+            &params.nominatim,
+        )
+        .context("Failed to geocode city")?;
+        progress.finish(&format!(
+            "Geocoded: {} -> ({:.4}, {:.4}) [{:.1}s]",
+            [Some(c.as_str()), params.state.as_deref(), Some(co.as_str())]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(", "),
+            place.lat,
+            place.lon,
+            start.elapsed().as_secs_f32()
+        ));
+        progress.stage(Stage::Geocoding, 1.0);
+        place_extent = place.bbox;
+        place.center()
+    };
+
+    let radius = match place_extent {
+        Some(extent) if params.radius.is_none() => {
+            let fitted = fitted_radius(extent);
+            progress.message(&format!(
+                "Fitted radius to the city extent: {}m (set --radius to override)",
+                fitted
+            ));
+            fitted
+        }
+        _ => params.effective_radius(),
+    };
+
+    if center.0.abs() > POLAR_LAT_LIMIT {
+        progress.warn(&format!(
+            "latitude {:.2} is beyond {}°, so east-west distances are only approximate",
+            center.0, POLAR_LAT_LIMIT
+        ));
+    }
+
+    Ok((center, radius))
+}
+
+/// Warn when the plate overhangs the printer bed, or say how far
+/// `auto_fit_bed` shrank it
+fn report_bed(params: &MapParams, progress: &dyn Progress) {
+    let Some(bed) = params.bed else {
+        return;
+    };
+    let (width, height) = params.shape_plate_size();
+    if params.auto_fit_bed {
+        let (fitted_width, fitted_height) = params.plate_size();
+        if fitted_width < width {
+            progress.message(&format!(
+                "Shrinking the {:.0}mm x {:.0}mm plate to {:.0}mm x {:.0}mm to fit the {}mm x {}mm bed",
+                width, height, fitted_width, fitted_height, bed.width, bed.height
+            ));
+        }
+    } else if let Some((over_x, over_y)) = bed.overflow(width, height) {
+        progress.warn(&format!(
+            "the {:.0}mm x {:.0}mm plate overhangs the {}mm x {}mm bed (less {}mm along each edge) \
+             by {:.1}mm in x and {:.1}mm in y; use --auto-fit-bed to shrink it or --tiles to split it",
+            width, height, bed.width, bed.height, BED_MARGIN_MM, over_x, over_y
+        ));
+    }
+}
+
+/// Look up a named place and fetch the polygons of its OSM boundary
+fn fetch_place_boundary(
+    name: &str,
+    params: &MapParams,
+    progress: &dyn Progress,
+) -> Result<Vec<(Ring, Vec<Ring>)>> {
+    progress.start("Looking up the boundary...");
+    progress.stage(Stage::Geocoding, 0.0);
+    let start = Instant::now();
+    let place = geocode_area_cached(name, params.refresh_geocode, &params.nominatim)
+        .context("Failed to look up the boundary")?;
+    let Some(relation) = place.relation else {
+        bail!("{} has no boundary in OpenStreetMap", name);
+    };
+    let response =
+        fetch_boundary(relation, &params.overpass).context("Failed to fetch the boundary")?;
+    let polygons: Vec<(Ring, Vec<Ring>)> = parse_boundary(&response)
+        .into_iter()
+        .map(|polygon| (polygon.outer, polygon.holes))
+        .collect();
+    if polygons.is_empty() {
+        bail!(
+            "The boundary of {} (relation {}) is not closed",
+            name,
+            relation
+        );
+    }
+    progress.finish(&format!(
+        "Boundary: {} -> relation {} [{:.1}s]",
+        name,
+        relation,
+        start.elapsed().as_secs_f32()
+    ));
+    progress.stage(Stage::Geocoding, 1.0);
+    Ok(polygons)
+}
+
+/// Rough size of a map, worked out without downloading any OSM data
+#[derive(Debug, Clone)]
+pub struct MapEstimate {
+    /// Map center as (lat, lon)
+    pub center: (f64, f64),
+    pub radius_m: u32,
+    /// Area that would be fetched
+    pub bbox: BBox,
+    /// Covered area in meters as (width, height)
+    pub area_m: (f64, f64),
+    /// Plate width and height in mm, including the text margin
+    pub plate_mm: (f32, f32),
+    /// Plate millimeters per kilometer on the ground
+    pub mm_per_km: f64,
+    pub triangles: usize,
+    /// Binary STL size for `triangles`, in bytes
+    pub stl_bytes: usize,
+}
+
+/// Locate the map and estimate its size without fetching or meshing
+///
+/// Geocoding still runs for a city. The triangle count assumes typical city
+/// density for the road depth and enabled layers, so treat it as a guide to
+/// choosing `--radius` and `--simplify` rather than a prediction.
+pub fn estimate_map(params: &MapParams, progress: &dyn Progress) -> Result<MapEstimate> {
+    params.validate()?;
+    // Nominatim's extent of the area stands in for its boundary
+    let area_bbox = match params.area {
+        Some(ref name) => {
+            let place = geocode_area_cached(name, params.refresh_geocode, &params.nominatim)
+                .context("Failed to look up the area")?;
+            Some(
+                place
+                    .bbox
+                    .with_context(|| format!("No extent known for {}", name))?,
+            )
+        }
+        None => None,
+    };
+    let params = &MapParams {
+        bbox: area_bbox.or(params.bbox),
+        ..params.clone()
+    };
+    report_bed(params, progress);
+    let data_center = match (&params.geojson_input, &params.osm_file) {
+        (Some(path), _) => read_geojson(path)
+            .context("Failed to load GeoJSON input")?
+            .center(),
+        (None, Some(path)) => load_osm_input(path, params.feature_layers())
+            .context("Failed to load local OSM data")?
+            .center(),
+        (None, None) => None,
+    };
+    let (center, radius) = locate_map(params, data_center, progress)?;
+    let bbox = params
+        .bbox
+        .unwrap_or_else(|| calculate_bbox(center, radius));
+
+    let projector = Projector::with_kind(center, params.projection).with_rotation(params.rotate);
+    let corners = if let Some(corners) = params.outline_corners(radius as f64) {
+        corners.to_vec()
+    } else {
+        let (south, west, north, east) = bbox;
+        projector.project_points(&[(south, west), (south, east), (north, west), (north, east)])
+    };
+    let bounds = Bounds::from_points(&corners).context("Failed to compute map bounds")?;
+    let (width, height) = params.plate_size();
+    let scaler = Scaler::from_bounds_in_rect(
+        &bounds,
+        width as f64,
+        height as f64,
+        params.text_margin_mm() as f64,
+        params.text_position,
+    );
+
+    let mut area_km2 = bounds.width() * bounds.height() / 1e6;
+    match params.shape {
+        MapShape::Square => {}
+        MapShape::Circle => area_km2 *= std::f64::consts::FRAC_PI_4,
+        // Three quarters of the box around it
+        MapShape::Hexagon => area_km2 *= 0.75,
+    }
+    let triangles = estimate_triangles(params, area_km2);
+
+    Ok(MapEstimate {
+        center,
+        radius_m: radius,
+        bbox,
+        area_m: (bounds.width(), bounds.height()),
+        plate_mm: (width, height),
+        mm_per_km: scaler.scale_factor() * 1000.0,
+        triangles,
+        stl_bytes: estimate_stl_size(triangles),
+    })
+}
+
+/// Order-of-magnitude triangle count for `area_km2` of map
+fn estimate_triangles(params: &MapParams, area_km2: f64) -> usize {
+    let roads = ROAD_TRIANGLES_PER_KM2
+        .iter()
+        .find(|(depth, _)| *depth == params.road_depth)
+        .map_or(0.0, |(_, density)| *density);
+    // Simplification removes a growing share of road vertices
+    let simplify = [1.0, 0.7, 0.5, 0.35][params.simplify.min(3) as usize];
+    let layers = [
+        (params.water, WATER_TRIANGLES_PER_KM2),
+        (params.parks, PARK_TRIANGLES_PER_KM2),
+        (params.buildings, BUILDING_TRIANGLES_PER_KM2),
+        (params.railways, RAILWAY_TRIANGLES_PER_KM2),
+        (params.aeroways, AEROWAY_TRIANGLES_PER_KM2),
+        (params.paths, PATH_TRIANGLES_PER_KM2),
+    ]
+    .iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, density)| density)
+    .sum::<f64>();
+
+    let estimate = ((roads * simplify + layers) * area_km2) as usize + FIXED_TRIANGLES_ESTIMATE;
+    params
+        .max_triangles
+        .map_or(estimate, |budget| estimate.min(budget))
+}
+
+/// Generate a map without reporting progress
+pub fn generate_map(params: MapParams) -> Result<MapModel> {
+    generate_map_with_progress(params, &Silent)
+}
+
+/// Generate a map, reporting each step to `progress`
+pub fn generate_map_with_progress(params: MapParams, progress: &dyn Progress) -> Result<MapModel> {
+    params.validate()?;
+    let mut params = params;
+    let area_boundary = match params.area {
+        Some(ref name) => Some(fetch_place_boundary(name, &params, progress)?),
+        None => None,
+    };
+    let area = match area_boundary {
+        Some(ref polygons) => Some(Area::new(polygons).context("The area boundary is empty")?),
+        None => None,
+    };
+    // The map is the box around the area, trimmed to its outline below
+    if let Some(ref area) = area {
+        params.bbox = Some(area.bbox());
+    }
+
+    report_bed(&params, progress);
+    let (width, height) = params.shape_plate_size();
+    match params.shape {
+        MapShape::Circle if (width, height) != (params.width, params.height) => {
+            progress.warn(&format!(
+                "--shape circle uses a {:.0}mm diameter plate, ignoring the longer side",
+                width
+            ));
+        }
+        // A hexagon is always wider than tall, so only a narrowed plate is news
+        MapShape::Hexagon if width < params.width => {
+            progress.warn(&format!(
+                "--shape hexagon uses a {:.0}mm x {:.0}mm plate, too short for the full width",
+                width, height
+            ));
+        }
+        _ => {}
+    }
+    let (width, height) = params.plate_size();
+    let hang_hole = params.fitted_hang_hole()?;
+    let feature_layers = params.feature_layers();
+    let dem = params.dem.as_deref().map(Dem::load).transpose()?;
+
+    let geojson = match params.geojson_input {
+        Some(ref path) => {
+            let features = read_geojson(path).context("Failed to load GeoJSON input")?;
+            progress.message(&format!(
+                "Loaded {} roads from {}",
+                features.roads.len(),
+                path.display()
+            ));
+            for (reason, count) in &features.skipped {
+                progress.warn(&format!("skipped {} GeoJSON features: {}", count, reason));
+            }
+            Some(features)
+        }
+        None => None,
+    };
+
+    let local_data = match params.osm_file {
+        Some(ref path) => {
+            let data =
+                load_osm_input(path, feature_layers).context("Failed to load local OSM data")?;
+            progress.message(&format!(
+                "Loaded {} road elements from {}",
+                data.roads.elements.len(),
+                path.display()
+            ));
+            Some(data)
+        }
+        None => None,
+    };
+
+    let data_center = match geojson {
+        Some(ref features) => features.center(),
+        None => local_data.as_ref().and_then(OsmData::center),
+    };
+    let (center, radius) = locate_map(&params, data_center, progress)?;
+
+    let fetch_bbox = params
+        .bbox
+        .unwrap_or_else(|| calculate_bbox(center, radius));
+
+    let features = match geojson {
+        Some(features) => {
+            if features.roads.is_empty() {
+                bail!("No roads found in the GeoJSON input; roads are LineString features");
+            }
+            let mut roads = features.roads;
+            if !params.paths {
+                roads.retain(|road| road.class != RoadClass::Path);
+            }
+            MapFeatures {
+                roads,
+                railways: features.railways,
+                water: features.water,
+                parks: features.parks,
+                buildings: features.buildings,
+                aeroways: Aeroways::default(),
+                custom: CustomFeatures::default(),
+            }
+        }
+        None => {
+            let osm_data = match local_data {
+                Some(data) => data,
+                None => fetch_osm_data(
+                    fetch_bbox,
+                    params.road_depth,
+                    params.paths,
+                    feature_layers,
+                    params.overpass_query.as_deref(),
+                    &params.overpass,
+                    progress,
+                )?,
+            };
+
+            if let Some(ref dir) = params.dump_osm {
+                save_osm_data(dir, &osm_data).context("Failed to save OSM data")?;
+                progress.message(&format!("Saved Overpass responses to {}", dir.display()));
+            }
+
+            parse_osm_data(&params, &osm_data, fetch_bbox, progress)?
+        }
+    };
+
+    let feature_heights = params.feature_heights();
+
+    progress.start("Setting up coordinate projection...");
+    let projector = Projector::with_kind(center, params.projection).with_rotation(params.rotate);
+    if projector.kind() == ProjectionKind::Utm {
+        progress.detail(&format!(
+            "  UTM zone {} (central meridian {:.0})",
+            projector.utm_zone(),
+            projector.central_meridian()
+        ));
+    }
+    let edge_error = projector.estimate_error(radius as f64);
+    if projector.kind() == ProjectionKind::Local
+        && edge_error > radius as f64 * PROJECTION_ERROR_WARN_RATIO
+    {
+        progress.warn(&format!(
+            "the local projection may be off by about {:.0}m at the map edge ({:.1}% of the radius); \
+             use --projection utm for an accurate large map",
+            edge_error,
+            100.0 * edge_error / radius as f64
+        ));
+    } else {
+        progress.detail(&format!(
+            "  Estimated projection error at the map edge: {:.1}m",
+            edge_error
+        ));
+    }
+
+    let mut features = features.clip_to_map(&params, &projector, radius, area.as_ref());
+    let outline = params.outline(radius as f64);
+    let dropped = features.drop_invalid_polygons();
+    if dropped > 0 {
+        progress.warn(&format!(
+            "skipped {} self-intersecting or degenerate area polygons",
+            dropped
+        ));
+    }
+    let MapFeatures {
+        roads,
+        railways,
+        water,
+        parks,
+        buildings,
+        aeroways,
+        custom,
+    } = features;
+
+    if let Some(ref path) = params.dump_geojson {
+        let mut geojson = GeoJsonWriter::new();
+        geojson.add_segments("roads", &roads);
+        geojson.add_segments("railways", &railways);
+        geojson.add_water(&water);
+        geojson.add_areas("parks", &parks);
+        geojson.add_buildings(&buildings);
+        geojson.add_aeroways(&aeroways);
+        geojson.add_custom(&custom);
+        geojson.write(path).context("Failed to save GeoJSON")?;
+        progress.message(&format!(
+            "Saved {} features to {}",
+            geojson.len(),
+            path.display()
+        ));
+    }
+
+    let (bounds, scaler) = fit_to_plate(&params, &projector, &roads, radius)?;
+    let text_margin_mm = params.text_margin_mm() as f64;
+    // Everything in the text margin moves up and in by the frame width so the
+    // bars never cover it
+    let frame_width = params.frame_width_mm();
+    // Bottom and top of the label strip, inside the frame
+    let text_band = match params.text_position {
+        MarginSide::Bottom => (frame_width, TEXT_MARGIN_MM),
+        MarginSide::Top => (height - TEXT_MARGIN_MM, height - frame_width),
+    };
+    progress.finish(&format!(
+        "Map area: {:.0}m x {:.0}m -> {:.0}mm x {:.0}mm (with {:.0}mm text margin)",
+        bounds.width(),
+        bounds.height(),
+        width,
+        height - text_margin_mm as f32,
+        text_margin_mm
+    ));
+    progress.stage(Stage::Parsing, 1.0);
+
+    let terrain = match dem {
+        Some(ref dem) => {
+            progress.start("Sampling terrain...");
+            let area = (
+                scaler.scale(bounds.min_x, bounds.min_y),
+                scaler.scale(bounds.max_x, bounds.max_y),
+            );
+            let terrain = Terrain::sample(dem, &projector, &scaler, area, params.terrain_scale)?;
+            let (lowest, highest) = terrain.elevation_range();
+            if params.contours.is_some() {
+                progress.finish(&format!(
+                    "Elevations from {:.0}m to {:.0}m",
+                    lowest, highest
+                ));
+            } else {
+                progress.finish(&format!(
+                    "Terrain relief: {:.1}mm at {}x vertical scale",
+                    terrain.max_relief(),
+                    params.terrain_scale
+                ));
+            }
+            let missing = terrain.missing_fraction();
+            if missing > 0.0 {
+                progress.warn(&format!(
+                    "the elevation model covers only {:.0}% of the map; the rest is flat at its lowest point",
+                    100.0 * (1.0 - missing)
+                ));
+            }
+            Some(terrain)
+        }
+        None => None,
+    };
+    // Contour lines draw the terrain on a flat map rather than raising it
+    let relief = terrain.as_ref().filter(|_| params.contours.is_none());
+
+    progress.start("Generating mesh layers...");
+    progress.stage(Stage::MeshingRoads, 0.0);
+    let start = Instant::now();
+
+    let (base_triangles, water_triangles) =
+        generate_plate(&params, &water, &projector, &scaler, hang_hole);
+    if params.water {
+        let recessed = params.water_style == WaterStyle::Recessed;
+        progress.detail(&format!(
+            "  Water{}: {} triangles",
+            if recessed { " (recessed)" } else { "" },
+            water_triangles.len()
+        ));
+    }
+    progress.detail(&format!("  Base plate: {} triangles", base_triangles.len()));
+
+    let park_triangles = generate_parks(&params, &parks, &projector, &scaler);
+    if params.parks {
+        progress.detail(&format!("  Parks: {} triangles", park_triangles.len()));
+    }
+
+    let building_triangles = if params.buildings {
+        let building_config = BuildingConfig::default()
+            .with_z_base(params.base_height)
+            .with_default_height(params.building_height);
+        let triangles = generate_building_meshes(&buildings, &projector, &scaler, &building_config);
+        progress.detail(&format!("  Buildings: {} triangles", triangles.len()));
+        triangles
+    } else {
+        Vec::new()
+    };
+
+    let aeroway_triangles = if params.aeroways {
+        let triangles = generate_aeroway_meshes(
+            &aeroways,
+            &projector,
+            &scaler,
+            feature_heights.aeroway_z_top,
+        );
+        progress.detail(&format!("  Aeroways: {} triangles", triangles.len()));
+        triangles
+    } else {
+        Vec::new()
+    };
+
+    let custom_triangles = if params.overpass_query.is_some() {
+        let triangles =
+            generate_custom_meshes(&custom, &projector, &scaler, feature_heights.park_z_top);
+        progress.detail(&format!("  Custom: {} triangles", triangles.len()));
+        triangles
+    } else {
+        Vec::new()
+    };
+
+    let contour_triangles = match (&terrain, params.contours) {
+        (Some(terrain), Some(interval)) => {
+            let triangles = generate_contours(terrain, interval, feature_heights.park_z_top);
+            progress.detail(&format!("  Contours: {} triangles", triangles.len()));
+            triangles
+        }
+        _ => Vec::new(),
+    };
+
+    let road_config = params.road_config(radius, relief.is_some());
+    let rail_triangles = if params.railways {
+        let rail_config = road_config
+            .clone()
+            .with_rail_width(params.rail_width)
+            .with_z_top(feature_heights.rail_z_top);
+        let triangles = generate_road_meshes(&railways, &projector, &scaler, &rail_config);
+        progress.detail(&format!("  Railways: {} triangles", triangles.len()));
+        triangles
+    } else {
+        Vec::new()
+    };
+
+    let mut road_triangles = generate_roads(&params, &roads, &projector, &scaler, &road_config);
+    progress.detail(&format!("  Roads: {} triangles", road_triangles.len()));
+    let clamped = roads
+        .iter()
+        .filter(|road| road_config.is_width_clamped(road))
+        .count();
+    if clamped as f64 > roads.len() as f64 * CLAMPED_ROADS_WARN_RATIO {
+        progress.warn(&format!(
+            "{} of {} roads are below the {:.1}mm printable width and print wider than to scale; \
+             consider a larger --road-scale or wider road overrides",
+            clamped,
+            roads.len(),
+            road_config.min_width_mm
+        ));
+    } else if clamped > 0 {
+        progress.detail(&format!(
+            "  {} roads widened to the {:.1}mm minimum width",
+            clamped, road_config.min_width_mm
+        ));
+    }
+
+    let mut arrow_triangles = Vec::new();
+    if let Some(placement) = params.oneway_arrows {
+        arrow_triangles = generate_oneway_arrows(
+            &roads,
+            &projector,
+            &scaler,
+            &road_config,
+            placement,
+            feature_heights.text_z_top,
+        );
+        progress.detail(&format!(
+            "  One-way arrows: {} triangles",
+            arrow_triangles.len()
+        ));
+        if placement == OnewayArrows::Roads {
+            road_triangles.append(&mut arrow_triangles);
+        }
+    }
+
+    let place_name = if params.reverse_geocode
+        && !params.no_text
+        && params.city.is_none()
+        && params.area.is_none()
+        && params.primary_text.is_none()
+    {
+        progress.start("Looking up the place name...");
+        match reverse_geocode_cached(center, params.refresh_geocode, &params.nominatim) {
+            Ok(Some(name)) => {
+                progress.finish(&format!("Place name: {}", name));
+                Some(name)
+            }
+            Ok(None) => {
+                progress.finish("No place found at the center");
+                None
+            }
+            Err(e) => {
+                progress.finish("Place name lookup failed");
+                progress.warn(&format!("could not look up the place name: {:#}", e));
+                None
+            }
+        }
+    } else {
+        None
+    };
+    let display_name = params
+        .city
+        .as_deref()
+        .or(params.area.as_deref())
+        .or(place_name.as_deref())
+        .unwrap_or("Custom Location");
+    // Loaded once; every label, name and marking shares the fonts
+    let load_renderer = |font: Option<&Path>| {
+        TextRenderer::new(font, feature_heights.text_z_top)
+            .with_fallback_fonts(&params.fallback_fonts)
+            .with_curve_subdivisions(params.text_quality)
+            .with_missing_glyph(params.missing_glyph)
+    };
+    let renderer = load_renderer(params.font.as_deref());
+    let secondary_renderer = params
+        .secondary_font
+        .as_deref()
+        .map(|path| load_renderer(Some(path)));
+    let mut text_triangles = if params.no_text {
+        Vec::new()
+    } else {
+        generate_text_layer(
+            display_name,
+            center,
+            (width, height),
+            &TextOptions {
+                primary: params.primary_text.as_deref(),
+                secondary: params.secondary_text.as_deref(),
+                primary_height: params.primary_text_height,
+                secondary_height: params.secondary_text_height,
+                renderer: &renderer,
+                secondary_renderer: secondary_renderer.as_ref(),
+                line_spacing: params.line_spacing,
+                band_mm: text_band,
+                anchor_top: params.text_position == MarginSide::Top,
+            },
+            params.shape,
+        )
+    };
+    progress.detail(&format!("  Text: {} triangles", text_triangles.len()));
+    // Left and right edges of the labels, which the compass must stay beside
+    let label_span = text_triangles
+        .iter()
+        .flat_map(|t| t.vertices)
+        .map(|v| v[0])
+        .fold(None, |span: Option<(f32, f32)>, x| {
+            Some(span.map_or((x, x), |(left, right)| (left.min(x), right.max(x))))
+        });
+    text_triangles.append(&mut arrow_triangles);
+
+    if let Some(min_length_mm) = params.street_names {
+        let name_triangles =
+            generate_street_names(&roads, &projector, &scaler, &renderer, min_length_mm);
+        progress.detail(&format!(
+            "  Street names: {} triangles",
+            name_triangles.len()
+        ));
+        text_triangles.extend(name_triangles);
+    }
+
+    if let Some(corner) = params.compass {
+        let (compass_size, (compass_x, compass_y)) =
+            corner.placement(width - 2.0 * frame_width, TEXT_MARGIN_MM - frame_width);
+        let compass_x = compass_x + frame_width;
+        let compass_y = match params.text_position {
+            MarginSide::Bottom => compass_y + frame_width,
+            MarginSide::Top => height - frame_width - compass_y,
+        };
+        let beside_labels = label_span.is_none_or(|(left, right)| {
+            compass_x + compass_size / 2.0 <= left || compass_x - compass_size / 2.0 >= right
+        });
+        if beside_labels {
+            let compass_triangles = generate_compass(
+                compass_x,
+                compass_y,
+                compass_size,
+                feature_heights.text_z_top,
+                params.rotate as f32,
+                &renderer,
+            );
+            progress.detail(&format!("  Compass: {} triangles", compass_triangles.len()));
+            text_triangles.extend(compass_triangles);
+        } else {
+            progress.warn(
+                "no room for the compass beside the labels; leaving it out \
+                 (try a wider plate or a smaller --primary-text-height-mm)",
+            );
+        }
+    }
+
+    if params.scale_bar {
+        // Longest round distance that fits in a fifth of the plate width
+        let max_length_mm = (width - 2.0 * frame_width) * SCALE_BAR_MAX_WIDTH_FRACTION;
+        let length_m = round_scale_length(max_length_mm as f64 / scaler.scale_factor());
+        let length_mm = (length_m * scaler.scale_factor()) as f32;
+        let edge_padding = SCALE_BAR_EDGE_PADDING_MM + frame_width;
+        let bar_x = match params.compass {
+            Some(CompassCorner::Left) => width - edge_padding - length_mm,
+            _ => edge_padding,
+        };
+        let scale_bar_triangles = generate_scale_bar(
+            &scaler,
+            length_m,
+            bar_x,
+            text_band.0 + 4.0 * (height / 220.0),
+            feature_heights.text_z_top,
+            &renderer,
+        );
+        progress.detail(&format!(
+            "  Scale bar: {}m = {:.1}mm, {} triangles",
+            length_m,
+            length_mm,
+            scale_bar_triangles.len()
+        ));
+        text_triangles.extend(scale_bar_triangles);
+    }
+
+    let back_text_triangles = match params.back_text {
+        Some(ref text) => {
+            let triangles = generate_back_text(
+                &unescape_newlines(text),
+                (width, height),
+                &renderer,
+                params.line_spacing,
+                feature_heights.text_z_top,
+            );
+            progress.detail(&format!("  Back text: {} triangles", triangles.len()));
+            triangles
+        }
+        None => Vec::new(),
+    };
+
+    let frame_triangles = if params.frame {
+        let frame_z_top = params
+            .frame_height
+            .map(|h| params.base_height + h)
+            .unwrap_or(feature_heights.text_z_top);
+        let triangles = generate_frame(width, height, frame_width, frame_z_top);
+        progress.detail(&format!("  Frame: {} triangles", triangles.len()));
+        triangles
+    } else {
+        Vec::new()
+    };
+
+    let grid_triangles = if let Some(spacing_m) = params.grid {
+        let triangles = generate_grid(
+            &projector,
+            &scaler,
+            &bounds,
+            outline,
+            spacing_m,
+            feature_heights.text_z_top,
+        );
+        progress.detail(&format!("  Grid: {} triangles", triangles.len()));
+        triangles
+    } else {
+        Vec::new()
+    };
+
+    let boundary_triangles = if params.boundary {
+        // The area's outline is already here; a city is looked up by name
+        let polygons = match area_boundary {
+            Some(polygons) => polygons,
+            None => {
+                let name = [
+                    params.city.as_deref(),
+                    params.state.as_deref(),
+                    params.country.as_deref(),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(", ");
+                fetch_place_boundary(&name, &params, progress)?
+            }
+        };
+        let rings: Vec<Ring> = polygons
+            .into_iter()
+            .flat_map(|(outer, holes)| std::iter::once(outer).chain(holes))
+            .collect();
+        let triangles = generate_boundary(
+            &rings,
+            &projector,
+            &scaler,
+            &bounds,
+            outline,
+            feature_heights.text_z_top,
+        );
+        progress.detail(&format!("  Boundary: {} triangles", triangles.len()));
+        triangles
+    } else {
+        Vec::new()
+    };
+
+    let terrain_triangles = match relief {
+        Some(terrain) => {
+            let triangles = terrain.mesh(params.base_height);
+            progress.detail(&format!("  Terrain: {} triangles", triangles.len()));
+            triangles
+        }
+        None => Vec::new(),
+    };
+
+    let mut layers: Vec<(&'static str, Vec<Triangle>)> = vec![
+        ("base", base_triangles),
+        ("terrain", terrain_triangles),
+        ("water", water_triangles),
+        ("parks", park_triangles),
+        ("custom", custom_triangles),
+        ("contours", contour_triangles),
+        ("aeroways", aeroway_triangles),
+        ("buildings", building_triangles),
+        ("railways", rail_triangles),
+        ("roads", road_triangles),
+        ("text", text_triangles),
+        ("back_text", back_text_triangles),
+        ("frame", frame_triangles),
+        ("grid", grid_triangles),
+        ("boundary", boundary_triangles),
+    ];
+    if let Some(terrain) = relief {
+        // The frame keeps to the flat plate edge, off the terrain, and the
+        // back text stays in the underside
+        for (_, triangles) in layers
+            .iter_mut()
+            .filter(|(name, _)| !matches!(*name, "base" | "terrain" | "frame" | "back_text"))
+        {
+            terrain.drape(triangles);
+        }
+    }
+    let total_triangles: usize = layers.iter().map(|(_, t)| t.len()).sum();
+
+    progress.finish(&format!(
+        "Generated {} triangles [{:.1}s]",
+        total_triangles,
+        start.elapsed().as_secs_f32()
+    ));
+    progress.stage(Stage::MeshingRoads, 0.7);
+
+    if let Some(budget) = params.max_triangles
+        && total_triangles > budget
+    {
+        progress.start(&format!("Decimating to {} triangles...", budget));
+        let start = Instant::now();
+        // The plate, frame and labels are small solids that collapse when
+        // decimated, so they stay whole and the rest of the budget is split
+        // between the other layers, each keeping its share so none vanishes
+        let kept = |name: &str| matches!(name, "base" | "frame" | "text" | "back_text");
+        let fixed: usize = layers
+            .iter()
+            .filter(|(name, _)| kept(name))
+            .map(|(_, t)| t.len())
+            .sum();
+        let budget = budget.saturating_sub(fixed);
+        let reducible = total_triangles - fixed;
+        layers = layers
+            .into_iter()
+            .map(|(name, triangles)| {
+                if kept(name) {
+                    return (name, triangles);
+                }
+                let share = (triangles.len() as f64 * budget as f64 / reducible as f64) as usize;
+                (name, decimate_triangles(triangles, share))
+            })
+            .collect();
+        let decimated: usize = layers.iter().map(|(_, t)| t.len()).sum();
+        progress.finish(&format!(
+            "Decimated {} -> {} triangles [{:.1}s]",
+            total_triangles,
+            decimated,
+            start.elapsed().as_secs_f32()
+        ));
+        progress.stage(Stage::MeshingRoads, 0.8);
+    }
+
+    progress.start("Validating meshes...");
+    let start = Instant::now();
+    let min_area = params
+        .min_triangle_area
+        .unwrap_or_else(|| min_triangle_area(width.max(height)));
+    let layers: Vec<MapLayer> = layers
+        .into_iter()
+        .map(|(name, triangles)| {
+            let generated = triangles.len();
+            let (mut triangles, report) = validate_and_fix(triangles, min_area);
+            if params.deterministic {
+                sort_triangles(&mut triangles);
+            }
+            if !report.manifold.is_watertight() {
+                progress.detail(&format!(
+                    "  {}: {} boundary edges, {} non-manifold edges",
+                    name, report.manifold.boundary_edges, report.manifold.non_manifold_edges
+                ));
+            }
+            if report.coplanar_overlaps > 0 {
+                progress.detail(&format!(
+                    "  {}: {} overlapping coplanar triangle pairs",
+                    name, report.coplanar_overlaps
+                ));
+            }
+            MapLayer {
+                name,
+                removed: generated - triangles.len(),
+                triangles,
+                manifold: report.manifold,
+                degenerate: report.degenerate,
+                coplanar_overlaps: report.coplanar_overlaps,
+            }
+        })
+        .collect();
+    let triangles: usize = layers.iter().map(|layer| layer.triangles.len()).sum();
+    progress.finish(&format!(
+        "Validated {} triangles [{:.1}s]",
+        triangles,
+        start.elapsed().as_secs_f32()
+    ));
+    progress.stage(Stage::MeshingRoads, 1.0);
+
+    Ok(MapModel {
+        layers,
+        heights: feature_heights,
+        report: MapReport {
+            center,
+            area_m: (bounds.width(), bounds.height()),
+            generated_triangles: total_triangles,
+            triangles,
+        },
+    })
+}
+
+/// Mesh hand-built roads, water and parks with no network, file or font
+/// access
+///
+/// Runs the same clipping, projection, scaling and meshing as
+/// [`generate_map`] for the base plate, water, parks and roads, so tests can
+/// pin down their triangle counts. The center is `lat`/`lon` or the middle of
+/// `bbox`, falling back to the middle of the roads; `params.water` and
+/// `params.parks` switch those layers on as usual. Labels and the other
+/// layers are left out.
+pub fn build_mesh(
+    roads: Vec<RoadSegment>,
+    water: Vec<WaterPolygon>,
+    parks: Vec<ParkPolygon>,
+    params: &MapParams,
+) -> Result<Vec<Triangle>> {
+    if params.city.is_some() || params.area.is_some() {
+        bail!("build_mesh cannot look up a place; give it --lat and --lon or --bbox");
+    }
+    let data_center = Bounds::from_points(
+        &roads
+            .iter()
+            .flat_map(|r| r.points.clone())
+            .collect::<Vec<_>>(),
+    )
+    .map(|b| ((b.min_x + b.max_x) / 2.0, (b.min_y + b.max_y) / 2.0));
+    let (center, radius) = locate_map(params, data_center, &Silent)?;
+    let hang_hole = params.fitted_hang_hole()?;
+    let projector = Projector::with_kind(center, params.projection).with_rotation(params.rotate);
+
+    let mut features = MapFeatures {
+        roads,
+        railways: Vec::new(),
+        water,
+        parks,
+        buildings: Vec::new(),
+        aeroways: Aeroways::default(),
+        custom: CustomFeatures::default(),
+    }
+    .clip_to_map(params, &projector, radius, None);
+    features.drop_invalid_polygons();
+    let (_, scaler) = fit_to_plate(params, &projector, &features.roads, radius)?;
+
+    let (mut triangles, water_triangles) =
+        generate_plate(params, &features.water, &projector, &scaler, hang_hole);
+    triangles.extend(water_triangles);
+    triangles.extend(generate_parks(params, &features.parks, &projector, &scaler));
+    let road_config = params.road_config(radius, false);
+    triangles.extend(generate_roads(
+        params,
+        &features.roads,
+        &projector,
+        &scaler,
+        &road_config,
+    ));
+
+    let (width, height) = params.plate_size();
+    let min_area = params
+        .min_triangle_area
+        .unwrap_or_else(|| min_triangle_area(width.max(height)));
+    let (triangles, _) = validate_and_fix(triangles, min_area);
+    Ok(triangles)
+}
+
+/// Projected extent of the map and its fit to the plate
+///
+/// The extent is the round or hexagonal outline, the explicit box, or else
+/// the extent of the roads.
+fn fit_to_plate(
+    params: &MapParams,
+    projector: &Projector,
+    roads: &[RoadSegment],
+    radius: u32,
+) -> Result<(Bounds, Scaler)> {
+    let (width, height) = params.plate_size();
+    let mut all_projected_points: Vec<(f64, f64)> = Vec::new();
+    if let Some(corners) = params.outline_corners(radius as f64) {
+        all_projected_points.extend(corners);
+    } else if let Some((south, west, north, east)) = params.bbox {
+        let corners = [(south, west), (south, east), (north, west), (north, east)];
+        all_projected_points.extend(projector.project_points(&corners));
+    } else {
+        for road in roads {
+            let projected = projector.project_points(&road.points);
+            all_projected_points.extend(projected);
+        }
+    }
+
+    let bounds = Bounds::from_points(&all_projected_points)
+        .context("Failed to compute bounds from road points")?;
+
+    let text_margin_mm = params.text_margin_mm() as f64;
+    // With frame inset the map fits between the bars
+    let scaler = if params.frame_inset {
+        // The bar along the label edge lies within the margin, or as much of
+        // it as the margin covers; with no text it all needs room
+        let inset = params.frame_width_mm() as f64;
+        let label_inset = (inset - text_margin_mm).max(0.0);
+        let dy = match params.text_position {
+            MarginSide::Bottom => label_inset,
+            MarginSide::Top => inset,
+        };
+        Scaler::from_bounds_in_rect(
+            &bounds,
+            width as f64 - 2.0 * inset,
+            height as f64 - inset - label_inset,
+            text_margin_mm,
+            params.text_position,
+        )
+        .with_offset(inset, dy)
+    } else {
+        Scaler::from_bounds_in_rect(
+            &bounds,
+            width as f64,
+            height as f64,
+            text_margin_mm,
+            params.text_position,
+        )
+    };
+    Ok((bounds, scaler))
+}
+
+/// Base plate and water, which recessed water cuts pockets into
+///
+/// The water is empty unless `params.water` is set.
+fn generate_plate(
+    params: &MapParams,
+    water: &[WaterPolygon],
+    projector: &Projector,
+    scaler: &Scaler,
+    hang_hole: Option<HangHole>,
+) -> (Vec<Triangle>, Vec<Triangle>) {
+    let (width, height) = params.plate_size();
+    let heights = params.feature_heights();
+    if params.water && params.water_style == WaterStyle::Recessed {
+        // The water columns fill the holes the pockets leave in the base
+        let plate = match params.shape {
+            MapShape::Square => {
+                PlateFootprint::square(width, height, params.corner_radius, hang_hole)
+            }
+            MapShape::Circle => PlateFootprint::circle(width.min(height), CIRCULAR_BASE_SEGMENTS),
+            MapShape::Hexagon => PlateFootprint::hexagon(width / 2.0),
+        };
+        let (water_triangles, land) = generate_recessed_water(
+            water,
+            projector,
+            scaler,
+            &plate,
+            heights.water_z_bottom,
+            heights.water_z_top,
+            params.base_height,
+        );
+        return (land, water_triangles);
+    }
+
+    let base_triangles = match params.shape {
+        MapShape::Square => generate_base_plate(
+            width,
+            height,
+            params.base_height,
+            params.corner_radius,
+            hang_hole,
+            params.hollow_base,
+        ),
+        // Circle maps are forced square, so the disc fills the plate
+        MapShape::Circle => generate_circular_base(
+            width.min(height),
+            params.base_height,
+            CIRCULAR_BASE_SEGMENTS,
+        ),
+        // Hexagon maps are sized so the prism fills the plate
+        MapShape::Hexagon => generate_hex_base(width / 2.0, params.base_height),
+    };
+    let water_triangles = if params.water {
+        generate_water_meshes(
+            water,
+            projector,
+            scaler,
+            heights.water_z_bottom,
+            heights.water_z_top,
+        )
+    } else {
+        Vec::new()
+    };
+    (base_triangles, water_triangles)
+}
+
+/// Park areas, or nothing unless `params.parks` is set
+fn generate_parks(
+    params: &MapParams,
+    parks: &[ParkPolygon],
+    projector: &Projector,
+    scaler: &Scaler,
+) -> Vec<Triangle> {
+    if params.parks {
+        generate_park_meshes(
+            parks,
+            projector,
+            scaler,
+            params.feature_heights().park_z_top,
+        )
+    } else {
+        Vec::new()
+    }
+}
+
+/// Road ribbons, merged into one solid with `params.union_roads`
+fn generate_roads(
+    params: &MapParams,
+    roads: &[RoadSegment],
+    projector: &Projector,
+    scaler: &Scaler,
+    road_config: &RoadConfig,
+) -> Vec<Triangle> {
+    if params.union_roads {
+        generate_road_union(roads, projector, scaler, road_config)
+    } else {
+        generate_road_meshes(roads, projector, scaler, road_config)
+    }
+}
+
+/// Label overrides for the text layer
+struct TextOptions<'a> {
+    primary: Option<&'a str>,
+    secondary: Option<&'a str>,
+    /// Fixed cap heights in mm, overriding the fit to the plate width
+    primary_height: Option<f32>,
+    secondary_height: Option<f32>,
+    renderer: &'a TextRenderer,
+    /// Typeface for the secondary line; `renderer` if unset
+    secondary_renderer: Option<&'a TextRenderer>,
+    /// Multiplier on the font's line height for multi-line labels
+    line_spacing: f32,
+    /// Bottom and top of the strip the labels fit in, clear of any frame bar
+    band_mm: (f32, f32),
+    /// Hang the labels from the top of the strip rather than stacking them
+    /// up from its bottom, so a tall name cannot run off the plate
+    anchor_top: bool,
+}
+
+/// Text for the underside of the plate, centered and mirrored so it reads
+/// from below
+///
+/// The renderer's glyphs, `glyph_height` tall, are squashed to
+/// [`BACK_TEXT_DEPTH_MM`] and stand on the bed inside the base, so the
+/// first layers print them flush with the bottom. Mirroring in x turns
+/// each solid inside out, so every triangle is rewound to keep the glyphs
+/// closed and facing outward.
+fn generate_back_text(
+    text: &str,
+    (width_mm, height_mm): (f32, f32),
+    renderer: &TextRenderer,
+    line_spacing: f32,
+    glyph_height: f32,
+) -> Vec<Triangle> {
+    let scale = renderer
+        .calculate_scale_for_width(text, width_mm.min(height_mm) * BACK_TEXT_WIDTH_FRACTION)
+        .min(renderer.scale_for_cap_height(BACK_TEXT_MAX_CAP_MM));
+    let block_height = renderer.block_height(text, scale, line_spacing);
+    let baseline = (height_mm - block_height) / 2.0;
+    let squash = BACK_TEXT_DEPTH_MM / glyph_height;
+    let flip = |v: [f32; 3]| [width_mm - v[0], v[1], v[2] * squash];
+
+    renderer
+        .render_text_centered(text, width_mm / 2.0, baseline, 0.0, scale, line_spacing)
+        .into_iter()
+        .map(|tri| {
+            let [v0, v1, v2] = tri.vertices;
+            Triangle::new(flip(v0), flip(v2), flip(v1))
+        })
+        .collect()
+}
+
+/// Turn a literal `\n` typed on the command line into a line break
+fn unescape_newlines(text: &str) -> String {
+    text.replace("\\n", "\n")
+}
+
+fn generate_text_layer(
+    city: &str,
+    coords: (f64, f64),
+    (width_mm, height_mm): (f32, f32),
+    options: &TextOptions,
+    shape: MapShape,
+) -> Vec<Triangle> {
+    let mut triangles = Vec::new();
+
+    // Widest a label may be with its baseline at `y`; on a round base the
+    // chord at the baseline is the limit since glyphs extend upward from it
+    let max_width = |y: f32, fraction: f32| match shape {
+        MapShape::Square => width_mm * fraction,
+        MapShape::Circle => {
+            let r = width_mm.min(height_mm) / 2.0;
+            let dy = height_mm / 2.0 - y;
+            let chord = 2.0 * (r * r - dy * dy).max(0.0).sqrt();
+            (width_mm * fraction).min(chord * 0.9)
+        }
+        // The slanted sides narrow from the full width at mid-height to
+        // half of it at the flat bottom edge
+        MapShape::Hexagon => {
+            let r = width_mm / 2.0;
+            let dy = (height_mm / 2.0 - y).abs();
+            let chord = 2.0 * (r - dy / 3.0_f32.sqrt()).max(0.0);
+            (width_mm * fraction).min(chord * 0.9)
+        }
+    };
+
+    let text_z = 0.0;
+    let renderer = options.renderer;
+    let secondary_renderer = options.secondary_renderer.unwrap_or(renderer);
+
+    // Multi-line blocks stack upward from their bottom baseline; shrink them
+    // so the whole block fits below `top`
+    let fit_block = |renderer: &TextRenderer, text: &str, scale: f32, bottom: f32, top: f32| {
+        if text.lines().count() <= 1 {
+            return scale;
+        }
+        let block_height = renderer.block_height(text, scale, options.line_spacing);
+        let available = top - bottom;
+        if block_height > available && available > 0.0 {
+            scale * available / block_height
+        } else {
+            scale
+        }
+    };
+
+    let primary = options
+        .primary
+        .map(|s| unescape_newlines(s).to_uppercase())
+        .unwrap_or_else(|| city.to_uppercase());
+
+    let secondary = options.secondary.map(unescape_newlines).unwrap_or_else(|| {
+        let (lat, lon) = coords;
+        let lat_dir = if lat >= 0.0 { "N" } else { "S" };
+        let lon_dir = if lon >= 0.0 { "E" } else { "W" };
+        format!("{:.4}{} / {:.4}{}", lat.abs(), lat_dir, lon.abs(), lon_dir)
+    });
+
+    let (band_bottom, band_top) = options.band_mm;
+    let mut primary_y = band_bottom + 12.0 * (height_mm / 220.0);
+    let mut secondary_y = band_bottom + 4.0 * (height_mm / 220.0);
+
+    let secondary_scale = match options.secondary_height {
+        Some(height) => secondary_renderer.scale_for_cap_height(height),
+        None => fit_block(
+            secondary_renderer,
+            &secondary,
+            secondary_renderer.calculate_scale_for_width(&secondary, max_width(secondary_y, 0.40)),
+            secondary_y,
+            primary_y - TEXT_BLOCK_GAP_MM,
+        ),
+    };
+    let primary_scale = match options.primary_height {
+        Some(height) => renderer.scale_for_cap_height(height),
+        None => fit_block(
+            renderer,
+            &primary,
+            renderer.calculate_scale_for_width(&primary, max_width(primary_y, 0.75)),
+            primary_y,
+            band_top - TEXT_BLOCK_GAP_MM,
+        ),
+    };
+
+    if options.anchor_top {
+        // Lines above the last baseline, plus the cap height of the first
+        let line_count = primary.lines().count().max(1);
+        let primary_top = primary_y
+            + (line_count - 1) as f32 * renderer.line_height(primary_scale) * options.line_spacing
+            + primary_scale / renderer.scale_for_cap_height(1.0);
+        let overflow = primary_top - (band_top - TEXT_BLOCK_GAP_MM);
+        if overflow > 0.0 {
+            primary_y -= overflow;
+            secondary_y -= overflow;
+        }
+    }
+
+    triangles.extend(secondary_renderer.render_text_centered(
+        &secondary,
+        width_mm / 2.0,
+        secondary_y,
+        text_z,
+        secondary_scale,
+        options.line_spacing,
+    ));
+    triangles.extend(renderer.render_text_centered(
+        &primary,
+        width_mm / 2.0,
+        primary_y,
+        text_z,
+        primary_scale,
+        options.line_spacing,
+    ));
+
+    triangles
+}
+
+/// Parse each layer's Overpass response into map features
+fn parse_osm_data(
+    params: &MapParams,
+    osm_data: &OsmData,
+    fetch_bbox: BBox,
+    progress: &dyn Progress,
+) -> Result<MapFeatures> {
+    progress.start("Parsing road data...");
+    progress.stage(Stage::Parsing, 0.0);
+    let start = Instant::now();
+    let roads = parse_roads(&osm_data.roads, params.paths);
+    if roads.is_empty() {
+        bail!(
+            "No roads found in the specified area. Try increasing the radius or using --road-depth all"
+        );
+    }
+    progress.finish(&format!(
+        "Parsed {} road segments [{:.1}s]",
+        roads.len(),
+        start.elapsed().as_secs_f32()
+    ));
+
+    let water = match osm_data.water {
+        Some(ref response) => {
+            let mut parsed = parse_water(response);
+            let sea = parse_coastline(response, fetch_bbox);
+            progress.detail(&format!(
+                "  Parsed {} water polygons ({} from coastline)",
+                parsed.len() + sea.len(),
+                sea.len()
+            ));
+            parsed.extend(sea);
+            parsed
+        }
+        None => Vec::new(),
+    };
+
+    let parks = match osm_data.parks {
+        Some(ref response) => {
+            let parsed = parse_parks(response);
+            progress.detail(&format!("  Parsed {} park polygons", parsed.len()));
+            parsed
+        }
+        None => Vec::new(),
+    };
+
+    let buildings = match osm_data.buildings {
+        Some(ref response) => {
+            let parsed = parse_buildings(response);
+            progress.detail(&format!("  Parsed {} building footprints", parsed.len()));
+            parsed
+        }
+        None => Vec::new(),
+    };
+
+    let railways = match osm_data.railways {
+        Some(ref response) => {
+            let parsed = parse_railways(response, params.subways);
+            progress.detail(&format!("  Parsed {} railway segments", parsed.len()));
+            parsed
+        }
+        None => Vec::new(),
+    };
+
+    let aeroways = match osm_data.aeroways {
+        Some(ref response) => {
+            let parsed = parse_aeroways(response);
+            progress.detail(&format!(
+                "  Parsed {} runways and taxiways, {} aprons",
+                parsed.lines.len(),
+                parsed.aprons.len()
+            ));
+            parsed
+        }
+        None => Aeroways::default(),
+    };
+
+    let custom = match osm_data.custom {
+        Some(ref response) => {
+            let parsed = parse_custom(response);
+            progress.detail(&format!(
+                "  Parsed {} custom areas and {} lines",
+                parsed.areas.len(),
+                parsed.lines.len()
+            ));
+            if parsed.is_empty() {
+                progress.warn("the custom Overpass query returned no ways to draw");
+            }
+            parsed
+        }
+        None => CustomFeatures::default(),
+    };
+
+    Ok(MapFeatures {
+        roads,
+        railways,
+        water,
+        parks,
+        buildings,
+        aeroways,
+        custom,
+    })
+}
+
+/// Fetch roads and the enabled optional layers from Overpass
+///
+/// The queries are independent, so optional layers run on their own threads
+/// while roads fetch on the current one. Each query keeps its own retry and
+/// mirror fallback inside `execute_overpass_query`.
+fn fetch_osm_data(
+    bbox: BBox,
+    road_depth: RoadDepth,
+    paths: bool,
+    layers: FeatureLayers,
+    custom_query: Option<&str>,
+    overpass_config: &OverpassConfig,
+    progress: &dyn Progress,
+) -> Result<OsmData> {
+    progress.start("Fetching map data from OpenStreetMap...");
+    progress.stage(Stage::FetchingRoads, 0.0);
+    let start = Instant::now();
+    let (
+        roads_fetch,
+        water_fetch,
+        parks_fetch,
+        buildings_fetch,
+        railways_fetch,
+        aeroways_fetch,
+        custom_fetch,
+    ) = std::thread::scope(|s| {
+        let water_handle = layers
+            .water
+            .then(|| s.spawn(|| timed(|| fetch_water(bbox, overpass_config))));
+        let parks_handle = layers
+            .parks
+            .then(|| s.spawn(|| timed(|| fetch_parks(bbox, overpass_config))));
+        let buildings_handle = layers
+            .buildings
+            .then(|| s.spawn(|| timed(|| fetch_buildings(bbox, overpass_config))));
+        let railways_handle = layers
+            .railways
+            .then(|| s.spawn(|| timed(|| fetch_railways(bbox, overpass_config))));
+        let aeroways_handle = layers
+            .aeroways
+            .then(|| s.spawn(|| timed(|| fetch_aeroways(bbox, overpass_config))));
+        let custom_handle = custom_query
+            .map(|query| s.spawn(move || timed(|| fetch_custom(query, bbox, overpass_config))));
+
+        let roads_fetch =
+            timed(|| fetch_roads_with_depth(bbox, road_depth, paths, overpass_config));
+        let water_fetch = water_handle.map(|h| h.join().expect("Water fetch thread panicked"));
+        let parks_fetch = parks_handle.map(|h| h.join().expect("Park fetch thread panicked"));
+        let buildings_fetch =
+            buildings_handle.map(|h| h.join().expect("Building fetch thread panicked"));
+        let railways_fetch =
+            railways_handle.map(|h| h.join().expect("Railway fetch thread panicked"));
+        let aeroways_fetch =
+            aeroways_handle.map(|h| h.join().expect("Aeroway fetch thread panicked"));
+        let custom_fetch = custom_handle.map(|h| h.join().expect("Custom fetch thread panicked"));
+
+        (
+            roads_fetch,
+            water_fetch,
+            parks_fetch,
+            buildings_fetch,
+            railways_fetch,
+            aeroways_fetch,
+            custom_fetch,
+        )
+    });
+    progress.finish(&format!(
+        "Fetched map data [{:.1}s]",
+        start.elapsed().as_secs_f32()
+    ));
+    progress.stage(Stage::FetchingRoads, 1.0);
+
+    let (roads, secs) = roads_fetch;
+    let roads = roads.context("Failed to fetch roads from Overpass API")?;
+    progress.message(&format!(
+        "  Roads: {} elements [{:.1}s]",
+        roads.elements.len(),
+        secs
+    ));
+
+    let water = match water_fetch {
+        Some((response, secs)) => {
+            let response = response.context("Failed to fetch water data")?;
+            progress.message(&format!(
+                "  Water: {} elements [{:.1}s]",
+                response.elements.len(),
+                secs
+            ));
+            Some(response)
+        }
+        None => None,
+    };
+
+    let parks = match parks_fetch {
+        Some((response, secs)) => {
+            let response = response.context("Failed to fetch park data")?;
+            progress.message(&format!(
+                "  Parks: {} elements [{:.1}s]",
+                response.elements.len(),
+                secs
+            ));
+            Some(response)
+        }
+        None => None,
+    };
+
+    let buildings = match buildings_fetch {
+        Some((response, secs)) => {
+            let response = response.context("Failed to fetch building data")?;
+            progress.message(&format!(
+                "  Buildings: {} elements [{:.1}s]",
+                response.elements.len(),
+                secs
+            ));
+            Some(response)
+        }
+        None => None,
+    };
+
+    let railways = match railways_fetch {
+        Some((response, secs)) => {
+            let response = response.context("Failed to fetch railway data")?;
+            progress.message(&format!(
+                "  Railways: {} elements [{:.1}s]",
+                response.elements.len(),
+                secs
+            ));
+            Some(response)
+        }
+        None => None,
+    };
+
+    let aeroways = match aeroways_fetch {
+        Some((response, secs)) => {
+            let response = response.context("Failed to fetch aeroway data")?;
+            progress.message(&format!(
+                "  Aeroways: {} elements [{:.1}s]",
+                response.elements.len(),
+                secs
+            ));
+            Some(response)
+        }
+        None => None,
+    };
+
+    let custom = match custom_fetch {
+        Some((response, secs)) => {
+            let response = response.context("Failed to run the custom Overpass query")?;
+            progress.message(&format!(
+                "  Custom: {} elements [{:.1}s]",
+                response.elements.len(),
+                secs
+            ));
+            Some(response)
+        }
+        None => None,
+    };
+
+    Ok(OsmData {
+        roads,
+        water,
+        parks,
+        buildings,
+        railways,
+        aeroways,
+        custom,
+    })
+}
+
+/// Parsed map features in (lat, lon), grouped so they can be clipped together
+struct MapFeatures {
+    roads: Vec<RoadSegment>,
+    railways: Vec<RoadSegment>,
+    water: Vec<WaterPolygon>,
+    parks: Vec<ParkPolygon>,
+    buildings: Vec<BuildingPolygon>,
+    aeroways: Aeroways,
+    custom: CustomFeatures,
+}
+
+impl MapFeatures {
+    /// Trim every feature to the map: the explicit box, the named `area`
+    /// and the round or hexagonal outline
+    fn clip_to_map(
+        self,
+        params: &MapParams,
+        projector: &Projector,
+        radius: u32,
+        area: Option<&Area>,
+    ) -> Self {
+        let mut features = self;
+        // An explicit box is the exact map extent, so trim features to it
+        if let Some(b) = params.bbox {
+            features = features.clip(|line| clip_polyline(line, b), |ring| clip_ring(ring, b));
+        }
+        if let Some(area) = area {
+            features = features.clip_with(
+                |line| area.clip_polyline(line),
+                |outer, holes| area.clip_polygon(outer, holes),
+            );
+        }
+        if let Some(outline) = params.outline(radius as f64) {
+            features = features.clip(
+                |line| {
+                    outline
+                        .clip_polyline(&projector.project_points(line))
+                        .iter()
+                        .map(|piece| projector.unproject_points(piece))
+                        .collect()
+                },
+                |ring| {
+                    projector.unproject_points(&outline.clip_ring(&projector.project_points(ring)))
+                },
+            );
+        }
+        features
+    }
+
+    /// Trim every feature to a convex region
+    ///
+    /// Lines are split where they leave the region; polygon rings that end up
+    /// empty are dropped, and so are polygons whose outer ring is empty.
+    fn clip(
+        self,
+        clip_line: impl Fn(&[(f64, f64)]) -> Vec<Vec<(f64, f64)>>,
+        clip_ring: impl Fn(&[(f64, f64)]) -> Vec<(f64, f64)>,
+    ) -> Self {
+        self.clip_with(clip_line, |outer, holes| {
+            let outer = clip_ring(outer);
+            if outer.is_empty() {
+                return Vec::new();
+            }
+            let holes = holes
+                .iter()
+                .map(|hole| clip_ring(hole))
+                .filter(|hole| !hole.is_empty())
+                .collect();
+            vec![(outer, holes)]
+        })
+    }
+
+    /// Trim every feature to a region that may split a polygon in pieces
+    ///
+    /// `clip_polygon` turns an outer ring and its holes into the pieces
+    /// left inside, each an outer ring with holes.
+    fn clip_with(
+        self,
+        clip_line: impl Fn(&[(f64, f64)]) -> Vec<Vec<(f64, f64)>>,
+        clip_polygon: impl Fn(&[(f64, f64)], &[Ring]) -> Vec<(Ring, Vec<Ring>)>,
+    ) -> Self {
+        let clip_segments = |segments: Vec<RoadSegment>| -> Vec<RoadSegment> {
+            segments
+                .into_iter()
+                .flat_map(|segment| {
+                    clip_line(&segment.points)
+                        .into_iter()
+                        .map(move |points| RoadSegment {
+                            points,
+                            ..segment.clone()
+                        })
+                })
+                .collect()
+        };
+        Self {
+            roads: clip_segments(self.roads),
+            railways: clip_segments(self.railways),
+            water: self
+                .water
+                .into_iter()
+                .flat_map(|w| clip_polygon(&w.outer, &w.holes))
+                .map(|(outer, holes)| WaterPolygon { outer, holes })
+                .collect(),
+            parks: self
+                .parks
+                .into_iter()
+                .flat_map(|p| clip_polygon(&p.outer, &p.holes))
+                .map(|(outer, holes)| ParkPolygon { outer, holes })
+                .collect(),
+            buildings: self
+                .buildings
+                .into_iter()
+                .flat_map(|b| {
+                    clip_polygon(&b.outer, &[])
+                        .into_iter()
+                        .map(move |(outer, _)| BuildingPolygon { outer, ..b.clone() })
+                })
+                .collect(),
+            aeroways: Aeroways {
+                lines: self
+                    .aeroways
+                    .lines
+                    .into_iter()
+                    .flat_map(|line| {
+                        clip_line(&line.points)
+                            .into_iter()
+                            .map(move |points| AerowayLine {
+                                points,
+                                ..line.clone()
+                            })
+                    })
+                    .collect(),
+                aprons: self
+                    .aeroways
+                    .aprons
+                    .into_iter()
+                    .flat_map(|p| clip_polygon(&p.outer, &p.holes))
+                    .map(|(outer, holes)| ParkPolygon { outer, holes })
+                    .collect(),
+            },
+            custom: CustomFeatures {
+                areas: self
+                    .custom
+                    .areas
+                    .into_iter()
+                    .flat_map(|p| clip_polygon(&p.outer, &p.holes))
+                    .map(|(outer, holes)| ParkPolygon { outer, holes })
+                    .collect(),
+                lines: self
+                    .custom
+                    .lines
+                    .iter()
+                    .flat_map(|line| clip_line(line))
+                    .collect(),
+            },
+        }
+    }
+
+    /// Drop water and park-like polygons that are too small or cross
+    /// themselves, returning how many were dropped
+    fn drop_invalid_polygons(&mut self) -> usize {
+        let before = self.water.len()
+            + self.parks.len()
+            + self.aeroways.aprons.len()
+            + self.custom.areas.len();
+        self.water.retain(|w| w.is_valid());
+        self.parks.retain(|p| p.is_valid());
+        self.aeroways.aprons.retain(|p| p.is_valid());
+        self.custom.areas.retain(|p| p.is_valid());
+        before
+            - self.water.len()
+            - self.parks.len()
+            - self.aeroways.aprons.len()
+            - self.custom.areas.len()
+    }
+}
+
+/// Closed ring of (lat, lon) points
+type Ring = Vec<(f64, f64)>;
+
+/// Run `f` and return its result with the elapsed time in seconds
+fn timed<T>(f: impl FnOnce() -> T) -> (T, f32) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed().as_secs_f32())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layers::text::StrokeTextRenderer;
+    use crate::mesh::test_util::signed_volume;
+
+    #[test]
+    fn test_params_validate() {
+        assert!(MapParams::default().validate().is_err());
+
+        let coords = MapParams {
+            lat: Some(48.85),
+            lon: Some(2.35),
+            ..MapParams::default()
+        };
+        assert!(coords.validate().is_ok());
+
+        let city_only = MapParams {
+            city: Some("Paris".to_string()),
+            ..MapParams::default()
+        };
+        assert!(city_only.validate().is_err());
+
+        let round_frame = MapParams {
+            shape: MapShape::Circle,
+            frame: true,
+            ..coords.clone()
+        };
+        assert!(round_frame.validate().is_err());
+
+        let off_planet = MapParams {
+            lat: Some(500.0),
+            lon: Some(9999.0),
+            ..MapParams::default()
+        };
+        assert!(off_planet.validate().is_err());
+
+        let no_radius = MapParams {
+            radius: Some(0),
+            ..coords.clone()
+        };
+        assert!(no_radius.validate().is_err());
+
+        let no_text = MapParams {
+            no_text: true,
+            ..coords.clone()
+        };
+        assert!(no_text.validate().is_ok());
+        assert_eq!(no_text.text_margin_mm(), 0.0);
+        assert_eq!(coords.text_margin_mm(), TEXT_MARGIN_MM);
+        let bar_without_margin = MapParams {
+            scale_bar: true,
+            ..no_text
+        };
+        assert!(bar_without_margin.validate().is_err());
+
+        let round_terrain = MapParams {
+            dem: Some(PathBuf::from("N48E002.hgt")),
+            shape: MapShape::Circle,
+            ..coords.clone()
+        };
+        assert!(round_terrain.validate().is_err());
+        let contours_without_dem = MapParams {
+            contours: Some(50.0),
+            ..coords.clone()
+        };
+        assert!(contours_without_dem.validate().is_err());
+        // Contours leave the plate flat, so a pocketed base still works
+        let hollow_contours = MapParams {
+            dem: Some(PathBuf::from("N48E002.hgt")),
+            hollow_base: Some(1.2),
+            ..contours_without_dem
+        };
+        assert!(hollow_contours.validate().is_ok());
+        let union_relief = MapParams {
+            contours: None,
+            hollow_base: None,
+            union_roads: true,
+            ..hollow_contours
+        };
+        assert!(union_relief.validate().is_err());
+        let rounded_union = MapParams {
+            union_roads: true,
+            road_profile: RibbonProfile::Rounded,
+            ..coords.clone()
+        };
+        assert!(rounded_union.validate().is_err());
+
+        let area = MapParams {
+            area: Some("Manhattan".to_string()),
+            ..MapParams::default()
+        };
+        assert!(area.validate().is_ok());
+        let area_and_coords = MapParams {
+            area: Some("Manhattan".to_string()),
+            ..coords.clone()
+        };
+        assert!(area_and_coords.validate().is_err());
+        let round_area = MapParams {
+            shape: MapShape::Circle,
+            ..area
+        };
+        assert!(round_area.validate().is_err());
+        let coords_boundary = MapParams {
+            boundary: true,
+            ..coords.clone()
+        };
+        assert!(coords_boundary.validate().is_err());
+
+        let thick_wall = MapParams {
+            hollow_base: Some(1.8),
+            ..coords
+        };
+        assert!(thick_wall.validate().is_err());
+    }
+
+    #[test]
+    fn test_params_plate_size() {
+        let params = MapParams {
+            width: 200.0,
+            height: 150.0,
+            ..MapParams::default()
+        };
+        assert_eq!(params.plate_size(), (200.0, 150.0));
+        let round = MapParams {
+            shape: MapShape::Circle,
+            ..params
+        };
+        assert_eq!(round.plate_size(), (150.0, 150.0));
+        // The hexagon's flat edges are the height apart here
+        let hexagon = MapParams {
+            shape: MapShape::Hexagon,
+            ..round
+        };
+        let (width, height) = hexagon.plate_size();
+        assert!((height - 150.0).abs() < 1e-3);
+        assert!((width - 300.0 / 3.0_f32.sqrt()).abs() < 1e-3);
+
+        // 300 x 200 shrinks to the 246 x 246 usable on a 256mm bed
+        let oversized = MapParams {
+            width: 300.0,
+            height: 200.0,
+            bed: Some(BedSize {
+                width: 256.0,
+                height: 256.0,
+            }),
+            auto_fit_bed: true,
+            ..MapParams::default()
+        };
+        let (width, height) = oversized.plate_size();
+        assert!((width - 246.0).abs() < 1e-3 && (height - 164.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_build_mesh_without_io() {
+        // A crossroads and a park 1km across, on a 100mm plate
+        let roads = vec![
+            RoadSegment::new(vec![(44.996, 10.0), (45.004, 10.0)], RoadClass::Primary),
+            RoadSegment::new(vec![(45.0, 9.994), (45.0, 10.006)], RoadClass::Residential),
+        ];
+        let park = ParkPolygon::new(vec![
+            (44.998, 9.997),
+            (44.998, 9.999),
+            (44.999, 9.999),
+            (44.999, 9.997),
+            (44.998, 9.997),
+        ]);
+        let params = MapParams {
+            lat: Some(45.0),
+            lon: Some(10.0),
+            radius: Some(500),
+            width: 100.0,
+            height: 100.0,
+            ..MapParams::default()
+        };
+
+        let bare = build_mesh(roads.clone(), Vec::new(), vec![park.clone()], &params).unwrap();
+        // A box of 12 triangles each for the base and the two roads; parks
+        // are off by default
+        assert_eq!(bare.len(), 36);
+        let with_parks = MapParams {
+            parks: true,
+            ..params.clone()
+        };
+        let parked = build_mesh(roads.clone(), Vec::new(), vec![park], &with_parks).unwrap();
+        assert!(parked.len() > bare.len());
+        // The center falls back to the middle of the roads
+        let centered = MapParams {
+            lat: None,
+            lon: None,
+            ..params.clone()
+        };
+        assert_eq!(
+            build_mesh(roads, Vec::new(), Vec::new(), &centered)
+                .unwrap()
+                .len(),
+            bare.len()
+        );
+
+        let city = MapParams {
+            city: Some("Paris".to_string()),
+            ..params
+        };
+        assert!(build_mesh(Vec::new(), Vec::new(), Vec::new(), &city).is_err());
+    }
+
+    #[test]
+    fn test_secondary_font_sets_coordinate_line() {
+        let renderer = TextRenderer::new(None, 3.0);
+        let stroke = TextRenderer::Stroke(StrokeTextRenderer::new(3.0));
+        let render = |secondary_renderer| {
+            generate_text_layer(
+                "Paris",
+                (48.852, 2.354),
+                (100.0, 100.0),
+                &TextOptions {
+                    primary: None,
+                    secondary: None,
+                    primary_height: None,
+                    secondary_height: None,
+                    renderer: &renderer,
+                    secondary_renderer,
+                    line_spacing: 1.2,
+                    band_mm: (0.0, TEXT_MARGIN_MM),
+                    anchor_top: false,
+                },
+                MapShape::Square,
+            )
+        };
+        let shared = render(None);
+        assert!(!shared.is_empty());
+        assert_eq!(render(Some(&renderer)).len(), shared.len());
+        if renderer.is_ttf() {
+            assert_ne!(render(Some(&stroke)).len(), shared.len());
+        }
+    }
+
+    /// Generate a map of a crossroads 1km across from a GeoJSON file, with
+    /// no network access
+    /// Two crossing roads plus `extra`, a comma-led list of GeoJSON features
+    fn generate_crossroads_with(extra: &str, params: MapParams) -> MapModel {
+        let json = format!(
+            r#"{{
+            "type": "FeatureCollection",
+            "features": [
+                {{"type": "Feature", "properties": {{"highway": "primary"}},
+                 "geometry": {{"type": "LineString", "coordinates": [[10.0, 44.996], [10.0, 45.004]]}}}},
+                {{"type": "Feature", "properties": {{"highway": "residential"}},
+                 "geometry": {{"type": "LineString", "coordinates": [[9.994, 45.0], [10.006, 45.0]]}}}}
+                {extra}
+            ]
+        }}"#
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("crossroads.geojson");
+        std::fs::write(&path, json).unwrap();
+        generate_map(MapParams {
+            geojson_input: Some(path),
+            width: 100.0,
+            height: 100.0,
+            ..params
+        })
+        .unwrap()
+    }
+
+    fn generate_crossroads(params: MapParams) -> MapModel {
+        generate_crossroads_with("", params)
+    }
+
+    fn layer<'a>(model: &'a MapModel, name: &str) -> &'a [Triangle] {
+        &model
+            .layers
+            .iter()
+            .find(|layer| layer.name == name)
+            .unwrap()
+            .triangles
+    }
+
+    #[test]
+    fn test_no_text_drops_labels_and_margin() {
+        let lowest_road = |model: &MapModel| {
+            layer(model, "roads")
+                .iter()
+                .flat_map(|t| t.vertices)
+                .map(|v| v[1])
+                .fold(f32::MAX, f32::min)
+        };
+
+        let labeled = generate_crossroads(MapParams::default());
+        assert!(!layer(&labeled, "text").is_empty());
+        assert!(lowest_road(&labeled) > TEXT_MARGIN_MM - 1.0);
+
+        let bare = generate_crossroads(MapParams {
+            no_text: true,
+            ..MapParams::default()
+        });
+        assert!(layer(&bare, "text").is_empty());
+        // The map is centered on the whole plate, reaching into the old margin
+        assert!(lowest_road(&bare) < 5.0);
+    }
+
+    #[test]
+    fn test_compass_skipped_beside_wide_labels() {
+        let text = |primary_text_height: Option<f32>, compass: Option<CompassCorner>| {
+            let model = generate_crossroads(MapParams {
+                primary_text_height,
+                compass,
+                ..MapParams::default()
+            });
+            layer(&model, "text").len()
+        };
+        // Labels fitted to the plate leave room at either end
+        assert!(text(None, Some(CompassCorner::Left)) > text(None, None));
+        // Tall ones run the full width
+        assert_eq!(
+            text(Some(12.0), Some(CompassCorner::Right)),
+            text(Some(12.0), None)
+        );
+    }
+
+    #[test]
+    fn test_frame_inset_without_text() {
+        // A tall box, so the map reaches the bottom bar
+        let model = generate_crossroads(MapParams {
+            bbox: Some((44.996, 9.999, 45.004, 10.001)),
+            frame: true,
+            frame_inset: true,
+            no_text: true,
+            ..MapParams::default()
+        });
+        let frame_width = MapParams::default().frame_width;
+        let roads = layer(&model, "roads").iter().flat_map(|t| t.vertices);
+        let lowest = roads.map(|v| v[1]).fold(f32::MAX, f32::min);
+        assert!(
+            lowest >= frame_width - 1e-3,
+            "road at y={lowest} under the bar"
+        );
+    }
+
+    #[test]
+    fn test_concave_water_survives_clip() {
+        // A lake shaped like a backwards C, its spine east of the box edge,
+        // so the cut leaves two bars joined by an edge along the boundary
+        let lake = r#",
+            {"type": "Feature", "properties": {"natural": "water"},
+             "geometry": {"type": "Polygon", "coordinates": [[
+                [9.996, 44.997], [10.002, 44.997], [10.002, 45.003], [9.996, 45.003],
+                [9.996, 45.001], [10.0, 45.001], [10.0, 44.999], [9.996, 44.999],
+                [9.996, 44.997]
+             ]]}}"#;
+        let model = generate_crossroads_with(
+            lake,
+            MapParams {
+                bbox: Some((44.995, 9.99, 45.005, 9.998)),
+                water: true,
+                ..MapParams::default()
+            },
+        );
+        assert!(!layer(&model, "water").is_empty());
+    }
+
+    #[test]
+    fn test_decimation_keeps_plate_whole() {
+        let full = generate_crossroads(MapParams::default());
+        let model = generate_crossroads(MapParams {
+            max_triangles: Some(500),
+            ..MapParams::default()
+        });
+        assert!(layer(&model, "roads").len() < layer(&full, "roads").len());
+        assert_eq!(layer(&model, "text").len(), layer(&full, "text").len());
+
+        let base = layer(&model, "base");
+        assert_eq!(base.len(), 12);
+        assert!(crate::mesh::validation::check_manifold(base).is_watertight());
+        let top = base
+            .iter()
+            .flat_map(|t| t.vertices)
+            .map(|v| v[2])
+            .fold(f32::MIN, f32::max);
+        assert_eq!(top, model.heights.base_height);
+        assert!(
+            base.iter()
+                .flat_map(|t| t.vertices)
+                .all(|v| v[2] == 0.0 || v[2] == top)
+        );
+    }
+
+    #[test]
+    fn test_back_text_mirrored_into_base() {
+        let renderer = TextRenderer::new(None, 3.0);
+        // The same glyphs unmirrored, at the size the back text picks
+        let scale = renderer
+            .calculate_scale_for_width("MAP", 100.0 * BACK_TEXT_WIDTH_FRACTION)
+            .min(renderer.scale_for_cap_height(BACK_TEXT_MAX_CAP_MM));
+        let front = renderer.render_text_centered("MAP", 50.0, 40.0, 0.0, scale, 1.2);
+        let back = generate_back_text("MAP", (100.0, 100.0), &renderer, 1.2, 3.0);
+        assert!(!back.is_empty());
+        let vertices: Vec<[f32; 3]> = back.iter().flat_map(|t| t.vertices).collect();
+        assert!(
+            vertices
+                .iter()
+                .all(|v| (-1e-4..=BACK_TEXT_DEPTH_MM + 1e-4).contains(&v[2]))
+        );
+        assert!(
+            vertices
+                .iter()
+                .all(|v| (15.0..=85.0).contains(&v[0]) && (0.0..=100.0).contains(&v[1]))
+        );
+
+        // Rewinding keeps the glyphs facing outward, and mirroring opens no
+        // edges the font's own meshes don't have
+        assert!(signed_volume(&back) > 0.0);
+        assert_eq!(
+            crate::mesh::validation::check_manifold(&back),
+            crate::mesh::validation::check_manifold(&front)
+        );
+    }
+
+    #[test]
+    fn test_fitted_radius() {
+        // Paris proper is about 10km across east to west
+        let paris = (48.8155755, 2.224122, 48.902156, 2.4697602);
+        let r = fitted_radius(paris);
+        assert!((8000..10000).contains(&r), "{}", r);
+
+        // A village is padded out, a whole region is capped
+        assert_eq!(
+            fitted_radius((45.0, 7.0, 45.001, 7.001)),
+            MIN_FITTED_RADIUS_M
+        );
+        assert_eq!(fitted_radius((40.0, -5.0, 45.0, 3.0)), MAX_FITTED_RADIUS_M);
+
+        assert_eq!(MapParams::default().effective_radius(), DEFAULT_RADIUS_M);
+    }
+
+    #[test]
+    fn test_closure_progress() {
+        let seen = std::cell::RefCell::new(Vec::new());
+        let callback = |stage: Stage, fraction: f32| seen.borrow_mut().push((stage, fraction));
+        let progress: &dyn Progress = &callback;
+        progress.message("ignored");
+        progress.stage(Stage::Parsing, 0.5);
+        assert_eq!(*seen.borrow(), vec![(Stage::Parsing, 0.5)]);
+
+        // Invalid parameters fail before any stage starts
+        assert!(generate_map_with_progress(MapParams::default(), &callback).is_err());
+        assert_eq!(seen.borrow().len(), 1);
+    }
+
+    #[test]
+    fn test_estimate_map() {
+        let params = MapParams {
+            lat: Some(48.85),
+            lon: Some(2.35),
+            radius: Some(2000),
+            ..MapParams::default()
+        };
+        let estimate = estimate_map(&params, &Silent).unwrap();
+        assert_eq!(estimate.radius_m, 2000);
+        assert!((estimate.area_m.0 - 4000.0).abs() < 100.0);
+        // 4km fits in the 200mm left above the text margin
+        assert!(
+            (estimate.mm_per_km - 50.0).abs() < 1.5,
+            "{}",
+            estimate.mm_per_km
+        );
+
+        let detailed = MapParams {
+            road_depth: RoadDepth::All,
+            buildings: true,
+            ..params.clone()
+        };
+        let bigger = estimate_map(&detailed, &Silent).unwrap();
+        assert!(bigger.triangles > estimate.triangles);
+        assert!(bigger.stl_bytes > estimate.stl_bytes);
+
+        let capped = MapParams {
+            max_triangles: Some(5000),
+            ..detailed
+        };
+        assert_eq!(estimate_map(&capped, &Silent).unwrap().triangles, 5000);
+    }
+
+    #[test]
+    fn test_map_builder() {
+        let builder = MapBuilder::new()
+            .center(45.44, 12.32)
+            .radius(4000)
+            .with_water(true)
+            .road_depth(RoadDepth::Secondary)
+            .with_simplify_level(7);
+        let params = builder.params();
+        assert_eq!((params.lat, params.lon), (Some(45.44), Some(12.32)));
+        assert_eq!(params.radius, Some(4000));
+        assert!(params.water && !params.parks);
+        assert_eq!(params.road_depth, RoadDepth::Secondary);
+        assert_eq!(params.simplify, 3);
+        assert!(params.validate().is_ok());
+
+        let agent = MapBuilder::new().with_user_agent("atlas/2.0 (ops@example.com)");
+        assert_eq!(
+            agent.params().overpass.user_agent(),
+            "atlas/2.0 (ops@example.com)"
+        );
+        assert_eq!(
+            agent.params().nominatim.user_agent(),
+            "atlas/2.0 (ops@example.com)"
+        );
+        assert_eq!(
+            MapParams::default().nominatim.user_agent(),
+            crate::config::DEFAULT_USER_AGENT
+        );
+        let proxied = MapBuilder::new().with_proxy("socks5://localhost:1080");
+        assert_eq!(
+            proxied.params().overpass.proxy.as_deref(),
+            Some("socks5://localhost:1080")
+        );
+        assert_eq!(
+            proxied.params().nominatim.proxy,
+            proxied.params().overpass.proxy
+        );
+
+        // Missing location is reported before anything is fetched
+        assert!(MapBuilder::new().build().is_err());
+    }
+}